@@ -0,0 +1,45 @@
+use std::num::NonZeroUsize;
+use std::time::Duration;
+
+use bloom_cardano_agent::config::{ExecutionCap, MatchmakingConfig};
+use bloom_offchain::execution_engine::quarantine::QuarantineConfig;
+use cardano_chain_sync::client::Point;
+use cardano_explorer::CardanoNetworkConfig;
+use spectrum_cardano_lib::NetworkId;
+use spectrum_offchain_cardano::creds::OperatorRewardAddress;
+use spectrum_offchain_cardano::script_eval::SlotConfig;
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BacktestConfig {
+    pub source: BlockSource,
+    pub network_id: NetworkId,
+    /// Slot-to-time conversion of the network, see Shelley genesis.
+    pub slot_config: SlotConfig,
+    /// Explorer the deployment and collateral are resolved with.
+    pub explorer: CardanoNetworkConfig,
+    pub operator_key: String,
+    pub operator_reward_address: OperatorRewardAddress,
+    pub cardano_finalization_delay: Duration,
+    pub backlog_capacity: u32,
+    pub execution_cap: ExecutionCap,
+    pub max_in_flight_txs: NonZeroUsize,
+    pub quarantine: QuarantineConfig,
+    #[serde(default)]
+    pub matchmaking: MatchmakingConfig,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BlockSource {
+    /// Blocks cached by chain-sync starting from the given point,
+    /// including blocks pruned to the archive if it is set.
+    #[serde(rename_all = "camelCase")]
+    LedgerCache {
+        db_path: String,
+        from_point: Point,
+        archive_dir: Option<String>,
+    },
+    /// File of concatenated CBOR byte strings, each holding an era-tagged block.
+    BlockDump { path: String },
+}
@@ -0,0 +1,238 @@
+use std::pin::pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use clap::Parser;
+use futures::channel::mpsc;
+use futures::task::{waker, ArcWake};
+use futures::{Stream, StreamExt};
+use log::info;
+use tokio::sync::{broadcast, Mutex};
+
+use bloom_cardano_agent::context::ExecutionContext;
+use bloom_cardano_agent::upstream::merge_upstreams;
+use bloom_offchain::execution_engine::bundled::Bundled;
+use bloom_offchain::execution_engine::execution_part_stream;
+use bloom_offchain::execution_engine::liquidity_book::{SharedExecutionCap, TLB};
+use bloom_offchain::execution_engine::multi_pair::MultiPair;
+use bloom_offchain::execution_engine::snapshots::Snapshots;
+use bloom_offchain::execution_engine::storage::kv_store::InMemoryKvStore;
+use bloom_offchain::execution_engine::storage::{DeliveredPoint, InMemoryStateIndex};
+use bloom_offchain_cardano::bounds::{Bounds, SharedBounds};
+use bloom_offchain_cardano::event_sink::context::HandlerContextProto;
+use bloom_offchain_cardano::event_sink::entity_index::InMemoryEntityIndex;
+use bloom_offchain_cardano::event_sink::handler::{
+    PairUpdateHandler, ProcessingTransaction, SpecializedHandler,
+};
+use bloom_offchain_cardano::event_sink::order_bearers::InMemoryOrderBearers;
+use bloom_offchain_cardano::event_sink::order_index::InMemoryOrderIndex;
+use bloom_offchain_cardano::event_sink::{AtomicCardanoEntity, EvolvingCardanoEntity};
+use bloom_offchain_cardano::execution_engine::backlog::interpreter::SpecializedInterpreterViaRunOrder;
+use bloom_offchain_cardano::execution_engine::interpreter::CardanoRecipeInterpreter;
+use bloom_offchain_cardano::orders::AnyOrder;
+use cardano_chain_sync::data::LedgerTxEvent;
+use cardano_explorer::AnyCardanoNetwork;
+use spectrum_cardano_lib::chain_tip::ChainTip;
+use spectrum_cardano_lib::ex_units::ExUnits;
+use spectrum_cardano_lib::output::FinalizedTxOut;
+use spectrum_cardano_lib::protocol_params::{constant_tx_builder_config, SharedTxBuilderConfig};
+use spectrum_offchain::backlog::{BacklogCapacity, HotPriorityBacklog};
+use spectrum_offchain::data::event::{Channel, StateUpdate};
+use spectrum_offchain::data::order::OrderUpdate;
+use spectrum_offchain::event_sink::event_handler::EventHandler;
+use spectrum_offchain::event_sink::process_events;
+use spectrum_offchain::partitioning::Partitioned;
+use spectrum_offchain_cardano::collateral::pull_collateral;
+use spectrum_offchain_cardano::creds::operator_creds;
+use spectrum_offchain_cardano::data::order::ClassicalAMMOrder;
+use spectrum_offchain_cardano::data::pair::PairId;
+use spectrum_offchain_cardano::data::pool::AnyPool;
+use spectrum_offchain_cardano::deployment::{DeployedValidators, ProtocolDeployment, ProtocolScriptHashes};
+use spectrum_offchain_cardano::prover::operator::OperatorProver;
+use spectrum_offchain_cardano::tx_validation::CardanoTxValidator;
+
+use crate::config::BacktestConfig;
+use crate::network::SimulatedNetwork;
+use crate::source::{ledger_events, raw_blocks};
+use crate::stats::{BacktestStats, RecordingInterpreter};
+
+mod config;
+mod network;
+mod source;
+mod stats;
+
+/// Replays historical blocks through the same handlers and execution engine the agent runs.
+/// TXs built by the engine are confirmed right away, before the next historical event is applied.
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let args = AppArgs::parse();
+    let raw_config = std::fs::read_to_string(args.config_path).expect("Cannot load configuration file");
+    let config: BacktestConfig = serde_json::from_str(&raw_config).expect("Invalid configuration file");
+
+    let raw_deployment = std::fs::read_to_string(args.deployment_path).expect("Cannot load deployment file");
+    let deployment: DeployedValidators =
+        serde_json::from_str(&raw_deployment).expect("Invalid deployment file");
+
+    let raw_bounds = std::fs::read_to_string(args.bounds_path).expect("Cannot load bounds file");
+    let bounds: Bounds = serde_json::from_str(&raw_bounds).expect("Invalid bounds file");
+
+    log4rs::init_file(args.log4rs_path, Default::default()).unwrap();
+
+    info!("Starting backtest ..");
+
+    let explorer = AnyCardanoNetwork::new(config.explorer.clone(), config.network_id.into())
+        .await
+        .expect("Explorer instantiation failed");
+    let protocol_deployment = ProtocolDeployment::unsafe_pull(deployment, &explorer).await;
+
+    let (operator_sk, operator_pkh, operator_cred) = operator_creds(&config.operator_key);
+    let collateral = pull_collateral(operator_pkh, &explorer)
+        .await
+        .expect("Couldn't retrieve collateral");
+
+    let (pair_upd_snd, pair_upd_recv) =
+        mpsc::unbounded::<(PairId, Channel<StateUpdate<EvolvingCardanoEntity>>)>();
+    let (spec_upd_snd, spec_upd_recv) = mpsc::unbounded::<(
+        PairId,
+        Channel<OrderUpdate<AtomicCardanoEntity, AtomicCardanoEntity>>,
+    )>();
+    let entity_index = Arc::new(Mutex::new(InMemoryEntityIndex::new(
+        config.cardano_finalization_delay,
+    )));
+    let spec_order_index = Arc::new(Mutex::new(InMemoryOrderIndex::new(
+        config.cardano_finalization_delay,
+    )));
+    let handler_context = HandlerContextProto {
+        executor_cred: operator_cred,
+        scripts: ProtocolScriptHashes::from(&protocol_deployment),
+        bounds: SharedBounds::new(bounds),
+    };
+    let general_upd_handler = PairUpdateHandler::new(
+        Partitioned::new_unsafe(vec![pair_upd_snd]),
+        Arc::clone(&entity_index),
+        handler_context.clone(),
+    );
+    let spec_upd_handler = SpecializedHandler::new(
+        PairUpdateHandler::new(
+            Partitioned::new_unsafe(vec![spec_upd_snd]),
+            entity_index,
+            handler_context,
+        ),
+        spec_order_index,
+        Arc::new(Mutex::new(InMemoryOrderBearers::default())),
+    );
+    let handlers: Vec<Box<dyn EventHandler<LedgerTxEvent<ProcessingTransaction>>>> =
+        vec![Box::new(general_upd_handler), Box::new(spec_upd_handler)];
+
+    let stats = BacktestStats::new();
+    let chain_tip = ChainTip::new();
+    let (confirmations_snd, confirmations_recv) = mpsc::unbounded();
+    let context = ExecutionContext {
+        deployment: protocol_deployment,
+        execution_cap: SharedExecutionCap::new(config.execution_cap.into()),
+        reward_addr: config.operator_reward_address,
+        backlog_capacity: BacklogCapacity::from(config.backlog_capacity),
+        collateral,
+        network_id: config.network_id,
+        slot_config: config.slot_config,
+        chain_tip: chain_tip.clone(),
+        operator_cred,
+        tx_builder_config: SharedTxBuilderConfig::new(constant_tx_builder_config()),
+        matchmaking: config.matchmaking,
+        pair: None,
+    };
+    let (signal_tip_reached_snd, _) = broadcast::channel(1);
+    let execution_stream = execution_part_stream(
+        InMemoryStateIndex::new(),
+        InMemoryKvStore::new(),
+        MultiPair::new::<TLB<AnyOrder, AnyPool, ExUnits>>(context.clone(), "Book"),
+        MultiPair::new::<HotPriorityBacklog<Bundled<ClassicalAMMOrder, FinalizedTxOut>>>(
+            context.clone(),
+            "Backlog",
+        ),
+        Snapshots::new(),
+        context,
+        RecordingInterpreter::new(CardanoRecipeInterpreter, stats.clone()),
+        SpecializedInterpreterViaRunOrder,
+        OperatorProver::new(&operator_sk),
+        CardanoTxValidator,
+        merge_upstreams(pair_upd_recv, spec_upd_recv),
+        SimulatedNetwork::new(confirmations_snd, chain_tip.clone(), stats.clone()),
+        config.max_in_flight_txs,
+        config.quarantine,
+        DeliveredPoint::default(),
+        signal_tip_reached_snd.subscribe(),
+    );
+    // History is replayed from the very beginning, so the tip is considered reached.
+    signal_tip_reached_snd.send(true).unwrap();
+
+    let mut execution = pin!(execution_stream);
+    let (events_snd, events_recv) = mpsc::unbounded();
+    let mut replay = pin!(process_events(events_recv, handlers));
+    let mut history = pin!(ledger_events(
+        raw_blocks(config.source),
+        chain_tip,
+        config.slot_config
+    ));
+    // Each event is followed by execution run to completion, confirmations of TXs it produced
+    // are applied before the next historical event. Thus the engine is settled once history is over.
+    while let Some(historical_event) = history.next().await {
+        let mut next_event = Some(historical_event);
+        while let Some(event) = next_event {
+            events_snd.unbounded_send(event).unwrap();
+            replay.next().await;
+            settle(execution.as_mut());
+            next_event = confirmations_recv.try_next().ok().flatten();
+        }
+    }
+
+    let report = serde_json::to_string_pretty(&stats.get()).unwrap();
+    match args.output_path {
+        Some(path) => std::fs::write(path, report).expect("Cannot write report"),
+        None => println!("{}", report),
+    }
+}
+
+/// Poll the stream until it has nothing left to do.
+/// All of its inputs are fed synchronously during the replay, so once the stream is pending
+/// without having been woken up in the meantime, it is idle.
+fn settle<S: Stream + Unpin>(mut stream: S) {
+    let woken = Arc::new(WakeFlag(AtomicBool::new(true)));
+    let waker = waker(Arc::clone(&woken));
+    let mut cx = Context::from_waker(&waker);
+    while woken.0.swap(false, Ordering::SeqCst) {
+        while let Poll::Ready(Some(_)) = stream.poll_next_unpin(&mut cx) {}
+    }
+}
+
+struct WakeFlag(AtomicBool);
+
+impl ArcWake for WakeFlag {
+    fn wake_by_ref(arc_self: &Arc<Self>) {
+        arc_self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+#[derive(Parser)]
+#[command(name = "backtest")]
+#[command(author = "Spectrum Labs")]
+#[command(version = "1.0.0")]
+#[command(about = "Replay history through Bloom matchmaking", long_about = None)]
+struct AppArgs {
+    /// Path to the JSON backtest configuration file.
+    #[arg(long, short)]
+    config_path: String,
+    /// Path to the deployment JSON configuration file .
+    #[arg(long, short)]
+    deployment_path: String,
+    /// Path to the bounds JSON configuration file .
+    #[arg(long, short)]
+    bounds_path: String,
+    /// Path to the log4rs YAML configuration file.
+    #[arg(long, short)]
+    log4rs_path: String,
+    /// Where to write per-pair statistics, stdout by default.
+    #[arg(long, short)]
+    output_path: Option<String>,
+}
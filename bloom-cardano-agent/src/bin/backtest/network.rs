@@ -0,0 +1,49 @@
+use cml_chain::transaction::Transaction;
+use futures::channel::mpsc;
+
+use cardano_chain_sync::data::LedgerTxEvent;
+use spectrum_cardano_lib::chain_tip::ChainTip;
+use spectrum_cardano_lib::transaction::{OutboundTransaction, TxViewMut};
+use spectrum_offchain::network::Network;
+use spectrum_offchain::tx_hash::CanonicalHash;
+use spectrum_offchain_cardano::tx_submission::TxRejected;
+
+use crate::stats::BacktestStats;
+
+/// Network which accepts every TX and confirms it at the current slot of the replay.
+#[derive(Debug, Clone)]
+pub struct SimulatedNetwork {
+    confirmations: mpsc::UnboundedSender<LedgerTxEvent<TxViewMut>>,
+    tip: ChainTip,
+    stats: BacktestStats,
+}
+
+impl SimulatedNetwork {
+    pub fn new(
+        confirmations: mpsc::UnboundedSender<LedgerTxEvent<TxViewMut>>,
+        tip: ChainTip,
+        stats: BacktestStats,
+    ) -> Self {
+        Self {
+            confirmations,
+            tip,
+            stats,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Network<OutboundTransaction<Transaction>, TxRejected> for SimulatedNetwork {
+    async fn submit_tx(&mut self, tx: OutboundTransaction<Transaction>) -> Result<(), TxRejected> {
+        self.stats.commit(&tx.canonical_hash());
+        let confirmed = LedgerTxEvent::TxApplied {
+            tx: TxViewMut::from((*tx).clone()),
+            slot: self.tip.get(),
+            position: None,
+        };
+        self.confirmations
+            .unbounded_send(confirmed)
+            .expect("Replay is over");
+        Ok(())
+    }
+}
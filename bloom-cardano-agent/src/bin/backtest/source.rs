@@ -0,0 +1,72 @@
+use std::io::Cursor;
+
+use futures::stream::BoxStream;
+use futures::{stream, Stream, StreamExt};
+use log::info;
+
+use cardano_chain_sync::block::{block_info, decode_cached_block, unpack_positioned_transactions};
+use cardano_chain_sync::cache::{LedgerCache, LedgerCacheRocksDB, LinkedBlock};
+use cardano_chain_sync::data::LedgerTxEvent;
+use cardano_chain_sync::retention::BlockArchive;
+use spectrum_cardano_lib::chain_tip::ChainTip;
+use spectrum_cardano_lib::time::SlotConfig;
+use spectrum_cardano_lib::transaction::TxViewMut;
+
+use crate::config::BlockSource;
+
+/// Era-tagged blocks in the order they were applied to the ledger.
+pub fn raw_blocks(source: BlockSource) -> BoxStream<'static, Vec<u8>> {
+    match source {
+        BlockSource::LedgerCache {
+            db_path,
+            from_point,
+            archive_dir,
+        } => LedgerCacheRocksDB::new(db_path)
+            .with_archive(archive_dir.map(BlockArchive::new))
+            .replay(from_point)
+            .map(|LinkedBlock(raw_blk, _)| raw_blk)
+            .boxed(),
+        BlockSource::BlockDump { path } => {
+            let dump = std::fs::read(path).expect("Cannot read block dump");
+            let mut raw = cbor_event::de::Deserializer::from(Cursor::new(dump));
+            let mut blocks = vec![];
+            while let Ok(raw_blk) = raw.bytes() {
+                blocks.push(raw_blk);
+            }
+            info!("{} blocks loaded from dump", blocks.len());
+            stream::iter(blocks).boxed()
+        }
+    }
+}
+
+/// Transactions of replayed blocks, each block is followed by a completion marker.
+/// The chain tip is advanced as blocks go.
+pub fn ledger_events<S>(
+    blocks: S,
+    tip: ChainTip,
+    slot_config: SlotConfig,
+) -> impl Stream<Item = LedgerTxEvent<TxViewMut>>
+where
+    S: Stream<Item = Vec<u8>>,
+{
+    blocks.flat_map(move |raw_blk| {
+        let events =
+            match decode_cached_block(&raw_blk).and_then(|blk| Some((block_info(&blk, slot_config)?, blk))) {
+                Some((block, blk)) => {
+                    tip.set(block.slot);
+                    let mut events: Vec<_> = unpack_positioned_transactions(blk, slot_config)
+                        .into_iter()
+                        .map(|(tx, position)| LedgerTxEvent::TxApplied {
+                            tx,
+                            slot: block.slot,
+                            position: Some(position),
+                        })
+                        .collect();
+                    events.push(LedgerTxEvent::BlockCompleted(block));
+                    events
+                }
+                None => vec![],
+            };
+        stream::iter(events)
+    })
+}
@@ -0,0 +1,497 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Display;
+use std::sync::{Arc, Mutex};
+
+use cml_chain::builders::tx_builder::SignedTxBuilder;
+use cml_crypto::TransactionHash;
+use either::Either;
+
+use bloom_offchain::execution_engine::bundled::Bundled;
+use bloom_offchain::execution_engine::execution_effect::ExecutionEff;
+use bloom_offchain::execution_engine::liquidity_book::core::ExecutionRecipe;
+use bloom_offchain::execution_engine::liquidity_book::fragment::MarketTaker;
+use bloom_offchain::execution_engine::liquidity_book::interpreter::{RecipeInterpreter, RecipeRejection};
+use bloom_offchain::execution_engine::liquidity_book::market_maker::MarketMaker;
+use bloom_offchain::execution_engine::liquidity_book::routing::AssetPair;
+use bloom_offchain::execution_engine::liquidity_book::side::Side;
+use spectrum_cardano_lib::hash::hash_transaction_canonical;
+use spectrum_offchain::data::{Baked, Tradable};
+
+/// Amounts keyed by asset.
+pub type Volumes = BTreeMap<String, u64>;
+
+fn add_volume(volumes: &mut Volumes, asset: String, amount: u64) {
+    if amount > 0 {
+        *volumes.entry(asset).or_default() += amount;
+    }
+}
+
+/// Outcome of matchmaking in a single pair.
+/// Volumes are measured in input assets of takers.
+#[derive(Debug, Default, Clone, Eq, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PairStats {
+    pub recipes: u64,
+    /// Input of takers matched against other takers.
+    pub volume_taker_vs_taker: Volumes,
+    /// Input of takers matched against AMM pools.
+    pub volume_taker_vs_amm: Volumes,
+    /// Input of takers routed through AMM pools of other pairs.
+    pub volume_routed: Volumes,
+    pub operator_fees: u64,
+    /// Execution budget of takers spent on TX fees.
+    pub budget_consumed: u64,
+}
+
+impl PairStats {
+    fn add(&mut self, other: PairStats) {
+        self.recipes += other.recipes;
+        for (volumes, other_volumes) in [
+            (&mut self.volume_taker_vs_taker, other.volume_taker_vs_taker),
+            (&mut self.volume_taker_vs_amm, other.volume_taker_vs_amm),
+            (&mut self.volume_routed, other.volume_routed),
+        ] {
+            for (asset, amount) in other_volumes {
+                add_volume(volumes, asset, amount);
+            }
+        }
+        self.operator_fees += other.operator_fees;
+        self.budget_consumed += other.budget_consumed;
+    }
+}
+
+#[derive(Debug, Default)]
+struct Records {
+    /// Stats of recipes whose TXs were submitted.
+    submitted: BTreeMap<String, PairStats>,
+    /// Stats of interpreted recipes awaiting submission of their TXs.
+    pending: HashMap<TransactionHash, (String, PairStats)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct BacktestStats(Arc<Mutex<Records>>);
+
+impl BacktestStats {
+    pub fn new() -> Self {
+        Self(Arc::new(Mutex::new(Records::default())))
+    }
+
+    /// Hold stats of a recipe until its TX is submitted.
+    pub fn stage<Pair: Display>(&self, tx_hash: TransactionHash, pair: Pair, stats: PairStats) {
+        self.0
+            .lock()
+            .unwrap()
+            .pending
+            .insert(tx_hash, (pair.to_string(), stats));
+    }
+
+    /// TX passed validation and was submitted, stats of its recipe are accounted.
+    pub fn commit(&self, tx_hash: &TransactionHash) {
+        let mut records = self.0.lock().unwrap();
+        if let Some((pair, stats)) = records.pending.remove(tx_hash) {
+            records.submitted.entry(pair).or_default().add(stats);
+        }
+    }
+
+    pub fn get(&self) -> BTreeMap<String, PairStats> {
+        self.0.lock().unwrap().submitted.clone()
+    }
+}
+
+/// Summarize recipe before it is turned into a TX.
+fn summarize<Fr, Pl, B>(
+    ExecutionRecipe(instructions): &ExecutionRecipe<Fr, Pl, B>,
+) -> Option<(Fr::PairId, PairStats)>
+where
+    Fr: MarketTaker + Tradable,
+    Fr::PairId: AssetPair,
+    <Fr::PairId as AssetPair>::Asset: Display,
+    Pl: MarketMaker + Tradable<PairId = Fr::PairId>,
+{
+    let pair = instructions
+        .iter()
+        .find_map(|i| i.as_ref().left().map(|take| take.target.0.pair_id()))?;
+    let (base, quote) = pair.assets();
+    // Asks spend base asset, bids spend quote asset.
+    let input_asset = |side: Side| match side {
+        Side::Ask => base.to_string(),
+        Side::Bid => quote.to_string(),
+    };
+    let mut routed = false;
+    let mut taker_input = Volumes::new();
+    let mut stats = PairStats {
+        recipes: 1,
+        ..PairStats::default()
+    };
+    for instruction in instructions {
+        match instruction {
+            Either::Left(take) => {
+                add_volume(
+                    &mut taker_input,
+                    input_asset(take.target.0.side()),
+                    take.removed_input(),
+                );
+                stats.operator_fees += take.consumed_fee();
+                stats.budget_consumed += take.consumed_budget();
+            }
+            Either::Right(make) if make.target.0.pair_id() == pair => {
+                if let (Some(side), Some(gain)) = (make.trade_side(), make.gain()) {
+                    add_volume(&mut stats.volume_taker_vs_amm, input_asset(side), gain);
+                }
+            }
+            // Makers of other pairs are only touched by routed recipes.
+            Either::Right(_) => routed = true,
+        }
+    }
+    if routed {
+        stats.volume_routed = taker_input;
+    } else {
+        for (asset, input) in taker_input {
+            let vs_amm = stats.volume_taker_vs_amm.get(&asset).copied().unwrap_or(0);
+            add_volume(
+                &mut stats.volume_taker_vs_taker,
+                asset,
+                input.saturating_sub(vs_amm),
+            );
+        }
+    }
+    Some((pair, stats))
+}
+
+/// Summarizes recipes successfully interpreted by the underlying interpreter.
+/// Summaries are accounted once the resulting TX is submitted.
+#[derive(Debug, Clone)]
+pub struct RecordingInterpreter<I> {
+    inner: I,
+    stats: BacktestStats,
+}
+
+impl<I> RecordingInterpreter<I> {
+    pub fn new(inner: I, stats: BacktestStats) -> Self {
+        Self { inner, stats }
+    }
+}
+
+impl<I, Fr, Pl, Ctx, V, Bearer> RecipeInterpreter<Fr, Pl, Ctx, V, Bearer, SignedTxBuilder>
+    for RecordingInterpreter<I>
+where
+    I: RecipeInterpreter<Fr, Pl, Ctx, V, Bearer, SignedTxBuilder>,
+    Fr: MarketTaker + Tradable,
+    Fr::PairId: AssetPair,
+    <Fr::PairId as AssetPair>::Asset: Display,
+    Pl: MarketMaker + Tradable<PairId = Fr::PairId>,
+{
+    fn run(
+        &mut self,
+        recipe: ExecutionRecipe<Fr, Pl, Bearer>,
+        ctx: Ctx,
+    ) -> Result<
+        (
+            SignedTxBuilder,
+            Vec<
+                ExecutionEff<
+                    Bundled<Either<Baked<Fr, V>, Baked<Pl, V>>, Bearer>,
+                    Bundled<Either<Baked<Fr, V>, Baked<Pl, V>>, Bearer>,
+                >,
+            >,
+        ),
+        RecipeRejection<Fr, Pl, V>,
+    > {
+        let summary = summarize(&recipe);
+        let result = self.inner.run(recipe, ctx);
+        if let (Ok((txc, _)), Some((pair, stats))) = (&result, summary) {
+            self.stats
+                .stage(hash_transaction_canonical(&txc.body()), pair, stats);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::{Display, Formatter};
+
+    use either::Either;
+
+    use bloom_offchain::execution_engine::bundled::Bundled;
+    use bloom_offchain::execution_engine::liquidity_book::core::{
+        Execution, ExecutionRecipe, Next, TerminalTake, Trans,
+    };
+    use bloom_offchain::execution_engine::liquidity_book::fragment::MarketTaker;
+    use bloom_offchain::execution_engine::liquidity_book::market_maker::{
+        AbsoluteReserves, MarketMaker, PoolQuality, SpotPrice,
+    };
+    use bloom_offchain::execution_engine::liquidity_book::routing::AssetPair;
+    use bloom_offchain::execution_engine::liquidity_book::side::{OnSide, Side};
+    use bloom_offchain::execution_engine::liquidity_book::time::TimeBounds;
+    use bloom_offchain::execution_engine::liquidity_book::types::{
+        AbsolutePrice, FeeAsset, InputAsset, OutputAsset,
+    };
+    use spectrum_offchain::data::Tradable;
+
+    use crate::stats::{summarize, PairStats, Volumes};
+
+    #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+    struct Pair(&'static str, &'static str);
+
+    impl Display for Pair {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{}/{}", self.0, self.1)
+        }
+    }
+
+    impl AssetPair for Pair {
+        type Asset = &'static str;
+        fn assets(&self) -> (Self::Asset, Self::Asset) {
+            (self.0, self.1)
+        }
+        fn of(x: Self::Asset, y: Self::Asset) -> Self {
+            Pair(x, y)
+        }
+    }
+
+    const ADA_SPF: Pair = Pair("ada", "spf");
+    const ADA_SNEK: Pair = Pair("ada", "snek");
+
+    #[derive(Copy, Clone, Debug)]
+    struct Taker {
+        pair: Pair,
+        side: Side,
+        input: u64,
+        fee: u64,
+        budget: u64,
+    }
+
+    impl Tradable for Taker {
+        type PairId = Pair;
+        fn pair_id(&self) -> Self::PairId {
+            self.pair
+        }
+    }
+
+    impl MarketTaker for Taker {
+        type U = u64;
+        fn side(&self) -> Side {
+            self.side
+        }
+        fn input(&self) -> InputAsset<u64> {
+            self.input
+        }
+        fn output(&self) -> OutputAsset<u64> {
+            0
+        }
+        fn price(&self) -> AbsolutePrice {
+            AbsolutePrice::new_unsafe(1, 1)
+        }
+        fn operator_fee(&self, _: InputAsset<u64>) -> FeeAsset<u64> {
+            0
+        }
+        fn fee(&self) -> FeeAsset<u64> {
+            self.fee
+        }
+        fn budget(&self) -> FeeAsset<u64> {
+            self.budget
+        }
+        fn marginal_cost_hint(&self) -> Self::U {
+            0
+        }
+        fn min_marginal_output(&self) -> OutputAsset<u64> {
+            0
+        }
+        fn time_bounds(&self) -> TimeBounds<u64> {
+            TimeBounds::None
+        }
+    }
+
+    #[derive(Copy, Clone, Debug)]
+    struct Pool {
+        pair: Pair,
+        reserves_base: u64,
+        reserves_quote: u64,
+    }
+
+    impl Tradable for Pool {
+        type PairId = Pair;
+        fn pair_id(&self) -> Self::PairId {
+            self.pair
+        }
+    }
+
+    impl MarketMaker for Pool {
+        type U = u64;
+        fn static_price(&self) -> SpotPrice {
+            AbsolutePrice::new_unsafe(1, 1).into()
+        }
+        fn real_price(&self, _: OnSide<u64>) -> Option<AbsolutePrice> {
+            None
+        }
+        fn quality(&self) -> PoolQuality {
+            0.into()
+        }
+        fn marginal_cost_hint(&self) -> Self::U {
+            0
+        }
+        fn liquidity(&self) -> AbsoluteReserves {
+            AbsoluteReserves {
+                base: self.reserves_base,
+                quote: self.reserves_quote,
+            }
+        }
+        fn is_active(&self) -> bool {
+            true
+        }
+    }
+
+    fn taker(pair: Pair, side: Side) -> Taker {
+        Taker {
+            pair,
+            side,
+            input: 1000,
+            fee: 100,
+            budget: 100,
+        }
+    }
+
+    fn pool(pair: Pair) -> Pool {
+        Pool {
+            pair,
+            reserves_base: 1_000_000,
+            reserves_quote: 1_000_000,
+        }
+    }
+
+    fn take(
+        taker: Taker,
+        removed_input: u64,
+        consumed_fee: u64,
+        consumed_budget: u64,
+    ) -> Execution<Taker, Pool, ()> {
+        Either::Left(Trans::new(
+            Bundled(taker, ()),
+            Next::Term(TerminalTake {
+                remaining_input: taker.input - removed_input,
+                accumulated_output: 0,
+                remaining_budget: taker.budget - consumed_budget,
+                remaining_fee: taker.fee - consumed_fee,
+            }),
+        ))
+    }
+
+    fn make(pool: Pool, delta_base: i64, delta_quote: i64) -> Execution<Taker, Pool, ()> {
+        let next = Pool {
+            reserves_base: (pool.reserves_base as i64 + delta_base) as u64,
+            reserves_quote: (pool.reserves_quote as i64 + delta_quote) as u64,
+            ..pool
+        };
+        Either::Right(Trans::new(Bundled(pool, ()), Next::Succ(next)))
+    }
+
+    fn volumes<const N: usize>(entries: [(&str, u64); N]) -> Volumes {
+        entries
+            .into_iter()
+            .map(|(asset, amount)| (asset.to_string(), amount))
+            .collect()
+    }
+
+    #[test]
+    fn takers_matched_against_each_other() {
+        let recipe = ExecutionRecipe(vec![
+            take(taker(ADA_SPF, Side::Ask), 400, 10, 20),
+            take(taker(ADA_SPF, Side::Bid), 800, 15, 20),
+        ]);
+        assert_eq!(
+            summarize(&recipe),
+            Some((
+                ADA_SPF,
+                PairStats {
+                    recipes: 1,
+                    volume_taker_vs_taker: volumes([("ada", 400), ("spf", 800)]),
+                    operator_fees: 25,
+                    budget_consumed: 40,
+                    ..PairStats::default()
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn takers_matched_against_pool_and_each_other() {
+        // Bid is filled partially by the ask, and the rest of it is swapped in the pool.
+        let recipe = ExecutionRecipe(vec![
+            take(taker(ADA_SPF, Side::Ask), 200, 5, 10),
+            take(taker(ADA_SPF, Side::Bid), 1000, 10, 10),
+            make(pool(ADA_SPF), -500, 600),
+        ]);
+        assert_eq!(
+            summarize(&recipe),
+            Some((
+                ADA_SPF,
+                PairStats {
+                    recipes: 1,
+                    volume_taker_vs_taker: volumes([("ada", 200), ("spf", 400)]),
+                    volume_taker_vs_amm: volumes([("spf", 600)]),
+                    operator_fees: 15,
+                    budget_consumed: 20,
+                    ..PairStats::default()
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn routed_takers() {
+        let recipe = ExecutionRecipe(vec![
+            take(taker(ADA_SPF, Side::Ask), 1000, 10, 10),
+            make(pool(ADA_SPF), 1000, -900),
+            make(pool(ADA_SNEK), -900, 800),
+        ]);
+        assert_eq!(
+            summarize(&recipe),
+            Some((
+                ADA_SPF,
+                PairStats {
+                    recipes: 1,
+                    volume_taker_vs_amm: volumes([("ada", 1000)]),
+                    volume_routed: volumes([("ada", 1000)]),
+                    operator_fees: 10,
+                    budget_consumed: 10,
+                    ..PairStats::default()
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn arbitrage_is_not_summarized() {
+        let recipe = ExecutionRecipe(vec![
+            make(pool(ADA_SPF), 100, -90),
+            make(pool(ADA_SPF), -100, 110),
+        ]);
+        assert_eq!(summarize(&recipe), None);
+    }
+
+    #[test]
+    fn volumes_are_added_per_asset() {
+        let mut stats = PairStats {
+            recipes: 1,
+            volume_taker_vs_taker: volumes([("ada", 100)]),
+            operator_fees: 1,
+            ..PairStats::default()
+        };
+        stats.add(PairStats {
+            recipes: 1,
+            volume_taker_vs_taker: volumes([("ada", 50), ("spf", 70)]),
+            operator_fees: 2,
+            ..PairStats::default()
+        });
+        assert_eq!(
+            stats,
+            PairStats {
+                recipes: 2,
+                volume_taker_vs_taker: volumes([("ada", 150), ("spf", 70)]),
+                operator_fees: 3,
+                ..PairStats::default()
+            }
+        );
+    }
+}
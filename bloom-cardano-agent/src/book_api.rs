@@ -0,0 +1,277 @@
+use std::convert::Infallible;
+use std::net::SocketAddr;
+
+use cml_crypto::RawBytesEncoding;
+use futures::{stream, Stream};
+use hyper::header::CONTENT_TYPE;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use log::{error, info};
+
+use bloom_offchain::execution_engine::bundled::Bundled;
+use bloom_offchain::execution_engine::liquidity_book::market_maker::{
+    AbsoluteReserves, MarketMaker, PoolQuality, SpotPrice,
+};
+use bloom_offchain::execution_engine::liquidity_book::AllowedPriceRange;
+use bloom_offchain::execution_engine::snapshots::{PairSnapshot, Snapshots};
+use bloom_offchain_cardano::orders::AnyOrder;
+use spectrum_cardano_lib::connection::ConnectionState;
+use spectrum_cardano_lib::output::FinalizedTxOut;
+use spectrum_cardano_lib::OutputRef;
+use spectrum_offchain::data::order::SpecializedOrder;
+use spectrum_offchain_cardano::data::order::ClassicalAMMOrder;
+use spectrum_offchain_cardano::data::pair::PairId;
+use spectrum_offchain_cardano::data::pool::AnyPool;
+use spectrum_offchain_cardano::node::NodeConnections;
+
+pub type BookSnapshots = Snapshots<PairId, AnyOrder, AnyPool, Bundled<ClassicalAMMOrder, FinalizedTxOut>>;
+
+/// Serves snapshots of books and backlogs of all pairs at `GET /books`
+/// and states of connections to the node at `GET /node`.
+pub fn book_api_stream(
+    addr: SocketAddr,
+    snapshots: BookSnapshots,
+    node: NodeConnections,
+) -> impl Stream<Item = ()> {
+    stream::once(async move {
+        let make_svc = make_service_fn(move |_| {
+            let snapshots = snapshots.clone();
+            let node = node.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req| {
+                    handle(req, snapshots.clone(), node.clone())
+                }))
+            }
+        });
+        info!("Serving book API at {}", addr);
+        if let Err(err) = Server::bind(&addr).serve(make_svc).await {
+            error!("Book API server failed: {}", err);
+        }
+    })
+}
+
+async fn handle(
+    req: Request<Body>,
+    snapshots: BookSnapshots,
+    node: NodeConnections,
+) -> Result<Response<Body>, Infallible> {
+    if req.method() != Method::GET {
+        return Ok(not_found());
+    }
+    let body = match req.uri().path() {
+        "/books" => {
+            let books = snapshots
+                .all()
+                .into_iter()
+                .map(|(pair, snapshot)| PairBook::from_snapshot(pair, snapshot))
+                .collect::<Vec<_>>();
+            serde_json::to_vec(&books).unwrap()
+        }
+        "/node" => serde_json::to_vec(&NodeView::from(node)).unwrap(),
+        _ => return Ok(not_found()),
+    };
+    Ok(Response::builder()
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(body))
+        .unwrap())
+}
+
+fn not_found() -> Response<Body> {
+    let mut not_found = Response::new(Body::empty());
+    *not_found.status_mut() = StatusCode::NOT_FOUND;
+    not_found
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct NodeView {
+    chain_sync: ConnectionState,
+    mempool_sync: ConnectionState,
+    tx_submission: ConnectionState,
+    state_query: ConnectionState,
+}
+
+impl From<NodeConnections> for NodeView {
+    fn from(node: NodeConnections) -> Self {
+        Self {
+            chain_sync: node.chain_sync.get(),
+            mempool_sync: node.mempool_sync.get(),
+            tx_submission: node.tx_submission.get(),
+            state_query: node.state_query.get(),
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PairBook {
+    pair: PairId,
+    active_takers: Vec<AnyOrder>,
+    inactive_takers: Vec<AnyOrder>,
+    makers: Vec<MakerView>,
+    allowed_price_range: AllowedPriceRange,
+    backlog: Vec<BacklogOrderView>,
+}
+
+impl PairBook {
+    fn from_snapshot(
+        pair: PairId,
+        snapshot: PairSnapshot<AnyOrder, AnyPool, Bundled<ClassicalAMMOrder, FinalizedTxOut>>,
+    ) -> Self {
+        let PairSnapshot { book, backlog } = snapshot;
+        Self {
+            pair,
+            active_takers: book.active_takers,
+            inactive_takers: book.inactive_takers,
+            makers: book.makers.into_iter().map(MakerView::from).collect(),
+            allowed_price_range: book.allowed_price_range,
+            backlog: backlog
+                .into_iter()
+                .map(|Bundled(order, _)| BacklogOrderView::from(order))
+                .collect(),
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MakerView {
+    maker: AnyPool,
+    static_price: SpotPrice,
+    liquidity: AbsoluteReserves,
+    quality: PoolQuality,
+}
+
+impl From<AnyPool> for MakerView {
+    fn from(maker: AnyPool) -> Self {
+        Self {
+            static_price: maker.static_price(),
+            liquidity: maker.liquidity(),
+            quality: maker.quality(),
+            maker,
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BacklogOrderView {
+    order_ref: OutputRef,
+    pool_id: String,
+    kind: &'static str,
+}
+
+impl From<ClassicalAMMOrder> for BacklogOrderView {
+    fn from(order: ClassicalAMMOrder) -> Self {
+        let kind = match order {
+            ClassicalAMMOrder::Swap(_) => "swap",
+            ClassicalAMMOrder::Deposit(_) => "deposit",
+            ClassicalAMMOrder::Redeem(_) => "redeem",
+        };
+        Self {
+            order_ref: order.get_self_ref(),
+            pool_id: order.get_pool_ref().to_hex(),
+            kind,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::header::CONTENT_TYPE;
+    use hyper::{Body, Method, Request, StatusCode};
+    use serde_json::json;
+
+    use bloom_offchain::execution_engine::liquidity_book::{AllowedPriceRange, BookSnapshot};
+    use bloom_offchain::execution_engine::snapshots::PairSnapshot;
+    use spectrum_cardano_lib::connection::ConnectionState;
+    use spectrum_cardano_lib::AssetClass;
+    use spectrum_offchain_cardano::data::pair::PairId;
+    use spectrum_offchain_cardano::node::NodeConnections;
+
+    use crate::book_api::{handle, BookSnapshots, PairBook};
+
+    async fn get(
+        path: &str,
+        snapshots: BookSnapshots,
+        node: NodeConnections,
+    ) -> (StatusCode, serde_json::Value) {
+        let req = Request::builder()
+            .method(Method::GET)
+            .uri(path)
+            .body(Body::empty())
+            .unwrap();
+        let resp = handle(req, snapshots, node).await.unwrap();
+        let status = resp.status();
+        assert_eq!(resp.headers()[CONTENT_TYPE], "application/json");
+        let body = hyper::body::to_bytes(resp.into_body()).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn serve_books() {
+        let (status, body) = get("/books", BookSnapshots::new(), NodeConnections::default()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, json!([]));
+    }
+
+    #[tokio::test]
+    async fn serve_node_connection_states() {
+        let node = NodeConnections::default();
+        node.chain_sync.set(ConnectionState::Connected);
+        node.tx_submission.set(ConnectionState::Disconnected);
+        let (status, body) = get("/node", BookSnapshots::new(), node).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body,
+            json!({
+                "chainSync": "Connected",
+                "mempoolSync": "Connecting",
+                "txSubmission": "Disconnected",
+                "stateQuery": "Connecting",
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn unknown_requests_are_not_found() {
+        for (method, path) in [(Method::GET, "/orders"), (Method::POST, "/books")] {
+            let req = Request::builder()
+                .method(method)
+                .uri(path)
+                .body(Body::empty())
+                .unwrap();
+            let resp = handle(req, BookSnapshots::new(), NodeConnections::default())
+                .await
+                .unwrap();
+            assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        }
+    }
+
+    #[test]
+    fn serialize_pair_book() {
+        let pair = PairId::canonical(AssetClass::Native, AssetClass::Native);
+        let snapshot = PairSnapshot {
+            book: BookSnapshot {
+                active_takers: vec![],
+                inactive_takers: vec![],
+                makers: vec![],
+                allowed_price_range: AllowedPriceRange {
+                    max_ask_price: None,
+                    min_bid_price: None,
+                },
+            },
+            backlog: vec![],
+        };
+        let book = serde_json::to_value(PairBook::from_snapshot(pair, snapshot)).unwrap();
+        assert_eq!(book["pair"], serde_json::to_value(pair).unwrap());
+        assert_eq!(book["activeTakers"], json!([]));
+        assert_eq!(book["inactiveTakers"], json!([]));
+        assert_eq!(book["makers"], json!([]));
+        assert_eq!(
+            book["allowedPriceRange"],
+            json!({"max_ask_price": null, "min_bid_price": null})
+        );
+        assert_eq!(book["backlog"], json!([]));
+    }
+}
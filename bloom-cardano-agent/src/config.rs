@@ -1,14 +1,23 @@
+use std::net::SocketAddr;
+use std::num::NonZeroUsize;
 use std::time::Duration;
 
 use cml_core::Slot;
 
 use bloom_offchain::execution_engine::liquidity_book;
+use bloom_offchain::execution_engine::liquidity_book::arbitrage::ArbitrageConf;
+use bloom_offchain::execution_engine::liquidity_book::MatchmakingMode;
+use bloom_offchain::execution_engine::quarantine::QuarantineConfig;
 use bloom_offchain::partitioning::Partitioning;
 use cardano_chain_sync::client::Point;
+use cardano_chain_sync::retention::RetentionPolicy;
+use cardano_explorer::CardanoNetworkConfig;
 use spectrum_cardano_lib::ex_units::ExUnits;
 use spectrum_cardano_lib::NetworkId;
 use spectrum_offchain_cardano::creds::OperatorRewardAddress;
+use spectrum_offchain_cardano::data::pair::PairId;
 use spectrum_offchain_cardano::node::NodeConfig;
+use spectrum_offchain_cardano::script_eval::SlotConfig;
 
 #[derive(serde::Deserialize)]
 #[serde(bound = "'de: 'a")]
@@ -22,12 +31,29 @@ pub struct AppConfig<'a> {
     pub cardano_finalization_delay: Duration,
     pub backlog_capacity: u32,
     pub network_id: NetworkId,
-    pub maestro_key_path: &'a str,
+    /// Slot-to-time conversion of the network, see Shelley genesis.
+    pub slot_config: SlotConfig,
+    pub explorer: CardanoNetworkConfig,
     pub execution_cap: ExecutionCap,
     pub channel_buffer_size: usize,
+    /// Number of execution partitions run by this instance.
+    pub num_execution_partitions: NonZeroUsize,
+    /// Max number of TXs each partition may have submitted without knowing their outcome.
+    pub max_in_flight_txs: NonZeroUsize,
+    /// How long entities causing TXs to fail are kept out of execution.
+    pub quarantine: QuarantineConfig,
+    #[serde(default)]
+    pub matchmaking: MatchmakingConfig,
     pub mempool_buffering_duration: Duration,
     pub ledger_buffering_duration: Duration,
     pub partitioning: Partitioning,
+    pub protocol_params_poll_interval: Duration,
+    /// Address to serve Prometheus metrics at.
+    pub metrics_endpoint: SocketAddr,
+    /// Address to serve snapshots of books at.
+    pub book_api_endpoint: SocketAddr,
+    /// How often the configuration and bounds files are checked for changes.
+    pub config_watch_interval: Duration,
 }
 
 #[derive(serde::Deserialize)]
@@ -37,6 +63,38 @@ pub struct ChainSyncConfig<'a> {
     pub replay_from_point: Option<Point>,
     pub disable_rollbacks_until: Slot,
     pub db_path: &'a str,
+    /// Cached blocks are kept forever if not set.
+    pub retention: Option<RetentionPolicy>,
+}
+
+/// How takers are matched in each pair.
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchmakingConfig {
+    #[serde(default)]
+    pub default_mode: MatchmakingMode,
+    /// Pairs matched differently from the default.
+    #[serde(default)]
+    pub per_pair: Vec<PairMatchmakingMode>,
+    /// Arbitrage between pools of the same pair, profit is taken in ADA.
+    #[serde(default)]
+    pub arbitrage: ArbitrageConf,
+}
+
+impl MatchmakingConfig {
+    pub fn mode_of(&self, pair: &PairId) -> MatchmakingMode {
+        self.per_pair
+            .iter()
+            .find(|pm| pm.pair == *pair)
+            .map(|pm| pm.mode)
+            .unwrap_or(self.default_mode)
+    }
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct PairMatchmakingMode {
+    pub pair: PairId,
+    pub mode: MatchmakingMode,
 }
 
 #[derive(serde::Deserialize)]
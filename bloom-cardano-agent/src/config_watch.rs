@@ -0,0 +1,146 @@
+use std::fs;
+use std::time::{Duration, SystemTime};
+
+use async_stream::stream;
+use futures::Stream;
+use futures_timer::Delay;
+use log::{info, warn};
+use tokio::signal::unix::{signal, SignalKind};
+
+use bloom_offchain::execution_engine::liquidity_book::SharedExecutionCap;
+use bloom_offchain_cardano::bounds::{Bounds, SharedBounds};
+use spectrum_cardano_lib::ex_units::ExUnits;
+
+use crate::config::AppConfig;
+
+/// Reloads [Bounds] and the execution cap whenever the corresponding
+/// files change or SIGHUP is received.
+pub fn config_watch_stream(
+    config_path: String,
+    bounds_path: String,
+    poll_interval: Duration,
+    bounds: SharedBounds,
+    execution_cap: SharedExecutionCap<ExUnits>,
+) -> impl Stream<Item = ()> {
+    stream! {
+        let mut hangup = signal(SignalKind::hangup()).expect("Failed to install SIGHUP handler");
+        let mut config_modified_at = modified_at(&config_path);
+        let mut bounds_modified_at = modified_at(&bounds_path);
+        loop {
+            let forced = tokio::select! {
+                _ = hangup.recv() => true,
+                _ = Delay::new(poll_interval) => false,
+            };
+            let config_modified = modified_at(&config_path);
+            if forced || config_modified != config_modified_at {
+                config_modified_at = config_modified;
+                reload_execution_cap(&config_path, &execution_cap);
+            }
+            let bounds_modified = modified_at(&bounds_path);
+            if forced || bounds_modified != bounds_modified_at {
+                bounds_modified_at = bounds_modified;
+                reload_bounds(&bounds_path, &bounds);
+            }
+            yield ();
+        }
+    }
+}
+
+fn modified_at(path: &str) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+fn reload_execution_cap(config_path: &str, execution_cap: &SharedExecutionCap<ExUnits>) {
+    let raw_config = match fs::read_to_string(config_path) {
+        Ok(raw) => raw,
+        Err(err) => {
+            warn!("Cannot load configuration file: {}", err);
+            return;
+        }
+    };
+    match serde_json::from_str::<AppConfig>(&raw_config) {
+        Ok(config) => {
+            info!("Execution cap reloaded");
+            execution_cap.set(config.execution_cap.into());
+        }
+        Err(err) => warn!(
+            "Invalid configuration file, keeping current execution cap: {}",
+            err
+        ),
+    }
+}
+
+fn reload_bounds(bounds_path: &str, bounds: &SharedBounds) {
+    let raw_bounds = match fs::read_to_string(bounds_path) {
+        Ok(raw) => raw,
+        Err(err) => {
+            warn!("Cannot load bounds file: {}", err);
+            return;
+        }
+    };
+    match serde_json::from_str::<Bounds>(&raw_bounds) {
+        Ok(new_bounds) => {
+            info!("Bounds reloaded");
+            bounds.set(new_bounds);
+        }
+        Err(err) => warn!("Invalid bounds file, keeping current bounds: {}", err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use rand::RngCore;
+    use serde_json::json;
+
+    use bloom_offchain::execution_engine::liquidity_book::{ExecutionCap, SharedExecutionCap};
+    use bloom_offchain_cardano::bounds::{Bounds, SharedBounds};
+    use spectrum_cardano_lib::ex_units::ExUnits;
+
+    use crate::config_watch::{reload_bounds, reload_execution_cap};
+
+    const BOUNDS: &str = include_str!("../resources/bounds.json.template");
+    const CONFIG: &str = include_str!("../resources/preprod.config.json");
+
+    fn tmp_file(contents: String) -> String {
+        let path = std::env::temp_dir()
+            .join(format!("config-watch-{}.json", rand::thread_rng().next_u32()))
+            .to_str()
+            .unwrap()
+            .to_string();
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn reloaded_bounds_are_observed() {
+        let bounds = SharedBounds::new(serde_json::from_str::<Bounds>(BOUNDS).unwrap());
+        let mut raw_bounds: serde_json::Value = serde_json::from_str(BOUNDS).unwrap();
+        raw_bounds["pool"]["minN2tLovelace"] = json!(42);
+        let path = tmp_file(raw_bounds.to_string());
+        reload_bounds(&path, &bounds);
+        assert_eq!(bounds.get().pool.min_n2t_lovelace, 42);
+        fs::write(&path, "{}").unwrap();
+        reload_bounds(&path, &bounds);
+        assert_eq!(bounds.get().pool.min_n2t_lovelace, 42);
+    }
+
+    #[test]
+    fn reloaded_execution_cap_is_observed() {
+        let execution_cap = SharedExecutionCap::new(ExecutionCap {
+            soft: ExUnits { mem: 1, steps: 1 },
+            hard: ExUnits { mem: 2, steps: 2 },
+        });
+        let mut raw_config: serde_json::Value = serde_json::from_str(CONFIG).unwrap();
+        raw_config["operatorRewardAddress"] =
+            json!("addr1w98v2rexyjaxyppmaezyfz7fkwy059ewpde7l9xr4vhcp9qhtzss2");
+        raw_config["executionCap"]["soft"] = json!({"mem": 100, "steps": 200});
+        raw_config["executionCap"]["hard"] = json!({"mem": 300, "steps": 400});
+        let path = tmp_file(raw_config.to_string());
+        reload_execution_cap(&path, &execution_cap);
+        let cap = execution_cap.get();
+        assert_eq!(cap.soft, ExUnits { mem: 100, steps: 200 });
+        assert_eq!(cap.hard, ExUnits { mem: 300, steps: 400 });
+    }
+}
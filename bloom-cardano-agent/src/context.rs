@@ -1,13 +1,20 @@
+use cml_chain::builders::tx_builder::TransactionBuilderConfig;
 use type_equalities::IsEqual;
 
-use bloom_offchain::execution_engine::liquidity_book::ExecutionCap;
+use bloom_offchain::execution_engine::liquidity_book::arbitrage::ArbitrageConf;
+use bloom_offchain::execution_engine::liquidity_book::routing::AssetPair;
+use bloom_offchain::execution_engine::liquidity_book::{ExecutionCap, MatchmakingMode, SharedExecutionCap};
 use bloom_offchain::execution_engine::types::Time;
+use spectrum_cardano_lib::chain_tip::ChainTip;
 use spectrum_cardano_lib::collateral::Collateral;
 use spectrum_cardano_lib::ex_units::ExUnits;
-use spectrum_cardano_lib::NetworkId;
+use spectrum_cardano_lib::protocol_params::SharedTxBuilderConfig;
+use spectrum_cardano_lib::{AssetClass, NetworkId};
 use spectrum_offchain::backlog::BacklogCapacity;
 use spectrum_offchain::data::Has;
+use spectrum_offchain::maker::Scoped;
 use spectrum_offchain_cardano::creds::{OperatorCred, OperatorRewardAddress};
+use spectrum_offchain_cardano::data::pair::PairId;
 use spectrum_offchain_cardano::deployment::ProtocolValidator::{
     BalanceFnPoolDeposit, BalanceFnPoolRedeem, BalanceFnPoolV1, BalanceFnPoolV2, ConstFnFeeSwitchPoolDeposit,
     ConstFnFeeSwitchPoolRedeem, ConstFnFeeSwitchPoolSwap, ConstFnPoolDeposit, ConstFnPoolFeeSwitch,
@@ -16,17 +23,55 @@ use spectrum_offchain_cardano::deployment::ProtocolValidator::{
     StableFnPoolT2TDeposit, StableFnPoolT2TRedeem,
 };
 use spectrum_offchain_cardano::deployment::{DeployedValidator, ProtocolDeployment};
+use spectrum_offchain_cardano::script_eval::SlotConfig;
+
+use crate::config::MatchmakingConfig;
 
 #[derive(Debug, Clone)]
 pub struct ExecutionContext {
-    pub time: Time,
-    pub execution_cap: ExecutionCap<ExUnits>,
+    pub execution_cap: SharedExecutionCap<ExUnits>,
     pub deployment: ProtocolDeployment,
     pub collateral: Collateral,
     pub reward_addr: OperatorRewardAddress,
     pub backlog_capacity: BacklogCapacity,
     pub network_id: NetworkId,
+    pub slot_config: SlotConfig,
+    pub chain_tip: ChainTip,
     pub operator_cred: OperatorCred,
+    pub tx_builder_config: SharedTxBuilderConfig,
+    pub matchmaking: MatchmakingConfig,
+    /// Pair the context is narrowed down to.
+    pub pair: Option<PairId>,
+}
+
+impl Scoped<PairId> for ExecutionContext {
+    fn scoped(&self, pair: &PairId) -> Self {
+        Self {
+            pair: Some(*pair),
+            ..self.clone()
+        }
+    }
+}
+
+impl Has<MatchmakingMode> for ExecutionContext {
+    fn select<U: IsEqual<MatchmakingMode>>(&self) -> MatchmakingMode {
+        self.pair
+            .map(|pair| self.matchmaking.mode_of(&pair))
+            .unwrap_or(self.matchmaking.default_mode)
+    }
+}
+
+impl Has<ArbitrageConf> for ExecutionContext {
+    fn select<U: IsEqual<ArbitrageConf>>(&self) -> ArbitrageConf {
+        // TX fee is paid out of the profit, so only pairs with ADA as a base asset are arbitraged.
+        let base_is_ada = self
+            .pair
+            .map_or(false, |pair| pair.assets().0 == AssetClass::Native);
+        ArbitrageConf {
+            enabled: self.matchmaking.arbitrage.enabled && base_is_ada,
+            ..self.matchmaking.arbitrage
+        }
+    }
 }
 
 impl Has<NetworkId> for ExecutionContext {
@@ -35,6 +80,24 @@ impl Has<NetworkId> for ExecutionContext {
     }
 }
 
+impl Has<SlotConfig> for ExecutionContext {
+    fn select<U: IsEqual<SlotConfig>>(&self) -> SlotConfig {
+        self.slot_config
+    }
+}
+
+impl Has<ChainTip> for ExecutionContext {
+    fn select<U: IsEqual<ChainTip>>(&self) -> ChainTip {
+        self.chain_tip.clone()
+    }
+}
+
+impl Has<TransactionBuilderConfig> for ExecutionContext {
+    fn select<U: IsEqual<TransactionBuilderConfig>>(&self) -> TransactionBuilderConfig {
+        self.tx_builder_config.get()
+    }
+}
+
 impl Has<OperatorCred> for ExecutionContext {
     fn select<U: IsEqual<OperatorCred>>(&self) -> OperatorCred {
         self.operator_cred
@@ -47,15 +110,16 @@ impl Has<BacklogCapacity> for ExecutionContext {
     }
 }
 
+/// Time is measured in slots of the chain tip.
 impl Has<Time> for ExecutionContext {
     fn select<U: IsEqual<Time>>(&self) -> Time {
-        self.time
+        self.chain_tip.get().into()
     }
 }
 
 impl Has<ExecutionCap<ExUnits>> for ExecutionContext {
     fn select<U: IsEqual<ExecutionCap<ExUnits>>>(&self) -> ExecutionCap<ExUnits> {
-        self.execution_cap
+        self.execution_cap.get()
     }
 }
 
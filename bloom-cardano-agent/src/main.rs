@@ -3,30 +3,33 @@ use std::sync::Arc;
 
 use clap::Parser;
 use cml_chain::transaction::Transaction;
-use cml_multi_era::babbage::BabbageTransaction;
-use either::Either;
 use futures::channel::mpsc;
 use futures::stream::select_all;
-use futures::{stream_select, Stream, StreamExt};
+use futures::{FutureExt, StreamExt};
 use log::info;
 use tokio::sync::{broadcast, Mutex};
 use tracing_subscriber::fmt::Subscriber;
 
+use crate::book_api::{book_api_stream, BookSnapshots};
+use crate::config_watch::config_watch_stream;
+use crate::metrics::metrics_server_stream;
 use crate::partitioning::select_partition;
 use bloom_cardano_agent::config::AppConfig;
 use bloom_cardano_agent::context::ExecutionContext;
+use bloom_cardano_agent::upstream::merge_upstreams;
 use bloom_offchain::execution_engine::bundled::Bundled;
 use bloom_offchain::execution_engine::execution_part_stream;
-use bloom_offchain::execution_engine::liquidity_book::TLB;
+use bloom_offchain::execution_engine::liquidity_book::{SharedExecutionCap, TLB};
 use bloom_offchain::execution_engine::multi_pair::MultiPair;
-use bloom_offchain::execution_engine::storage::kv_store::InMemoryKvStore;
-use bloom_offchain::execution_engine::storage::{InMemoryStateIndex, StateIndexTracing};
-use bloom_offchain_cardano::bounds::Bounds;
+use bloom_offchain::execution_engine::storage::kv_store::KvStoreRocksDB;
+use bloom_offchain::execution_engine::storage::{DeliveredPoint, StateIndexRocksDB, StateIndexTracing};
+use bloom_offchain_cardano::bounds::{Bounds, SharedBounds};
 use bloom_offchain_cardano::event_sink::context::HandlerContextProto;
 use bloom_offchain_cardano::event_sink::entity_index::InMemoryEntityIndex;
 use bloom_offchain_cardano::event_sink::handler::{
-    PairUpdateHandler, ProcessingTransaction, SpecializedHandler,
+    ChainTipHandler, DeliveredPointHandler, PairUpdateHandler, ProcessingTransaction, SpecializedHandler,
 };
+use bloom_offchain_cardano::event_sink::order_bearers::OrderBearersRocksDB;
 use bloom_offchain_cardano::event_sink::order_index::InMemoryOrderIndex;
 use bloom_offchain_cardano::event_sink::{AtomicCardanoEntity, EvolvingCardanoEntity};
 use bloom_offchain_cardano::execution_engine::backlog::interpreter::SpecializedInterpreterViaRunOrder;
@@ -34,23 +37,27 @@ use bloom_offchain_cardano::execution_engine::interpreter::CardanoRecipeInterpre
 use bloom_offchain_cardano::orders::AnyOrder;
 use cardano_chain_sync::cache::LedgerCacheRocksDB;
 use cardano_chain_sync::chain_sync_stream;
-use cardano_chain_sync::client::ChainSyncClient;
+use cardano_chain_sync::client::Point;
 use cardano_chain_sync::data::LedgerTxEvent;
 use cardano_chain_sync::event_source::ledger_transactions;
-use cardano_explorer::Maestro;
-use cardano_mempool_sync::client::LocalTxMonitorClient;
+use cardano_chain_sync::retention::{pruning_stream, RetentionPolicy};
+use cardano_chain_sync::supervisor::ChainSyncConnector;
+use cardano_explorer::AnyCardanoNetwork;
 use cardano_mempool_sync::data::MempoolUpdate;
+use cardano_mempool_sync::ledger::LedgerObserver;
 use cardano_mempool_sync::mempool_stream;
-use spectrum_cardano_lib::constants::BABBAGE_ERA_ID;
+use cardano_mempool_sync::supervisor::LocalTxMonitorConnector;
+use cardano_state_query::supervisor::SupervisedLocalStateQueryClient;
+use cardano_state_query::{protocol_params_sync_stream, ProtocolParamsProvider};
+use spectrum_cardano_lib::chain_tip::ChainTip;
+use spectrum_cardano_lib::era::SharedEra;
 use spectrum_cardano_lib::ex_units::ExUnits;
-use spectrum_cardano_lib::hash::hash_transaction_canonical;
 use spectrum_cardano_lib::output::FinalizedTxOut;
+use spectrum_cardano_lib::protocol_params::SharedTxBuilderConfig;
 use spectrum_cardano_lib::transaction::OutboundTransaction;
-use spectrum_cardano_lib::OutputRef;
 use spectrum_offchain::backlog::{BacklogCapacity, HotPriorityBacklog};
 use spectrum_offchain::data::event::{Channel, StateUpdate};
 use spectrum_offchain::data::order::OrderUpdate;
-use spectrum_offchain::data::Baked;
 use spectrum_offchain::event_sink::event_handler::EventHandler;
 use spectrum_offchain::event_sink::process_events;
 use spectrum_offchain::partitioning::Partitioned;
@@ -61,12 +68,19 @@ use spectrum_offchain_cardano::data::order::ClassicalAMMOrder;
 use spectrum_offchain_cardano::data::pair::PairId;
 use spectrum_offchain_cardano::data::pool::AnyPool;
 use spectrum_offchain_cardano::deployment::{DeployedValidators, ProtocolDeployment, ProtocolScriptHashes};
+use spectrum_offchain_cardano::node::NodeConnections;
 use spectrum_offchain_cardano::prover::operator::OperatorProver;
-use spectrum_offchain_cardano::tx_submission::{tx_submission_agent_stream, TxSubmissionAgent};
+use spectrum_offchain_cardano::tx_submission::{
+    tx_submission_agent_stream, TxSubmissionAgent, TypedTxSubmissionChannel,
+};
+use spectrum_offchain_cardano::tx_validation::CardanoTxValidator;
 use spectrum_streaming::StreamExt as StreamExt1;
 
+mod book_api;
 mod config;
+mod config_watch;
 mod context;
+mod metrics;
 mod partitioning;
 
 #[tokio::main(flavor = "multi_thread", worker_threads = 10)]
@@ -74,15 +88,16 @@ async fn main() {
     let subscriber = Subscriber::new();
     tracing::subscriber::set_global_default(subscriber).expect("setting tracing default failed");
     let args = AppArgs::parse();
-    let raw_config = std::fs::read_to_string(args.config_path).expect("Cannot load configuration file");
+    let raw_config = std::fs::read_to_string(&args.config_path).expect("Cannot load configuration file");
     let config: AppConfig = serde_json::from_str(&raw_config).expect("Invalid configuration file");
 
     let raw_deployment = std::fs::read_to_string(args.deployment_path).expect("Cannot load deployment file");
     let deployment: DeployedValidators =
         serde_json::from_str(&raw_deployment).expect("Invalid deployment file");
 
-    let raw_bounds = std::fs::read_to_string(args.bounds_path).expect("Cannot load bounds file");
+    let raw_bounds = std::fs::read_to_string(&args.bounds_path).expect("Cannot load bounds file");
     let bounds: Bounds = serde_json::from_str(&raw_bounds).expect("Invalid bounds file");
+    let bounds = SharedBounds::new(bounds);
 
     log4rs::init_file(args.log4rs_path, Default::default()).unwrap();
 
@@ -90,34 +105,89 @@ async fn main() {
 
     let rollback_in_progress = Arc::new(AtomicBool::new(false));
 
-    let explorer = Maestro::new(config.maestro_key_path, config.network_id.into())
+    let explorer = AnyCardanoNetwork::new(config.explorer.clone(), config.network_id.into())
         .await
-        .expect("Maestro instantiation failed");
+        .expect("Explorer instantiation failed");
 
     let protocol_deployment = ProtocolDeployment::unsafe_pull(deployment, &explorer).await;
 
-    let chain_sync_cache = Arc::new(Mutex::new(LedgerCacheRocksDB::new(config.chain_sync.db_path)));
-    let chain_sync = ChainSyncClient::init(
+    let ledger_cache = LedgerCacheRocksDB::new(config.chain_sync.db_path).with_archive(
+        config
+            .chain_sync
+            .retention
+            .as_ref()
+            .and_then(RetentionPolicy::archive),
+    );
+    // Execution state lives next to the chain-sync cache. Each partition persists its states
+    // atomically with the point of the last block it has fully applied, on restart volatile
+    // states are dropped, confirmed ones are restored and the ledger is replayed from
+    // the earliest such point. The configured replay point only matters on the first start.
+    let num_execution_partitions = config.num_execution_partitions.get();
+    let state_index = StateIndexRocksDB::with_db(Arc::clone(&ledger_cache.db));
+    let execution_tip: Option<Point> = state_index.recover(num_execution_partitions);
+    let replay_from_point = execution_tip.or(config.chain_sync.replay_from_point);
+    let restored_states = state_index
+        .get_all_confirmed()
+        .into_iter()
+        .map(EvolvingCardanoEntity)
+        .collect::<Vec<_>>();
+    let order_bearers = Arc::new(Mutex::new(OrderBearersRocksDB::with_db(Arc::clone(
+        &ledger_cache.db,
+    ))));
+    let delivered = DeliveredPoint::default();
+    let chain_tip = ChainTip::new();
+    let state_cache = KvStoreRocksDB::with_db(Arc::clone(&ledger_cache.db));
+    let chain_sync_cache = Arc::new(Mutex::new(ledger_cache));
+    // Node clients reconnect on their own, their state is reported at the book API.
+    let node_connections = NodeConnections::default();
+    let reconnect_policy = config.node.reconnect;
+    let era = SharedEra::default();
+    let tx_builder_config = SharedTxBuilderConfig::default();
+    let chain_sync = ChainSyncConnector::new(
         Arc::clone(&chain_sync_cache),
-        config.node.path,
+        config.node.address(),
         config.node.magic,
         config.chain_sync.starting_point,
     )
-    .await
-    .expect("ChainSync initialization failed");
+    .with_era(era.clone());
+
+    // n2c clients, protocol parameters can only be queried from the local node:
+    let node_path = config
+        .node
+        .path
+        .expect("Local node socket is required to query protocol parameters");
+    let state_query = SupervisedLocalStateQueryClient::new(
+        node_path,
+        config.node.magic,
+        reconnect_policy,
+        node_connections.state_query.clone(),
+    );
+    let mut protocol_params_provider = ProtocolParamsProvider::new(state_query);
+    let (current_era, current_tx_builder_config) = protocol_params_provider
+        .current()
+        .await
+        .expect("Couldn't retrieve protocol parameters");
+    era.set(current_era);
+    tx_builder_config.set(current_tx_builder_config);
+    let protocol_params_stream = protocol_params_sync_stream(
+        protocol_params_provider,
+        tx_builder_config.clone(),
+        era.clone(),
+        config.protocol_params_poll_interval,
+    );
 
-    // n2c clients:
-    let mempool_sync =
-        LocalTxMonitorClient::<BabbageTransaction>::connect(config.node.path, config.node.magic)
-            .await
-            .expect("MempoolSync initialization failed");
+    // Mempool-sync consults the ledger to tell txs included in blocks from dropped ones,
+    // tx submission consults it on the fate of txs propagated through relays.
+    let ledger_observer = LedgerObserver::default();
+    let mempool_sync = LocalTxMonitorConnector::new(node_path, config.node.magic, ledger_observer.clone());
     let (tx_submission_agent, tx_submission_channel) =
-        TxSubmissionAgent::<BABBAGE_ERA_ID, OutboundTransaction<Transaction>, Transaction>::new(
+        TxSubmissionAgent::<OutboundTransaction<Transaction>, Transaction>::new(
             config.node,
             config.tx_submission_buffer_size,
-        )
-        .await
-        .expect("LocalTxSubmission initialization failed");
+            era,
+            node_connections.tx_submission.clone(),
+            ledger_observer.clone(),
+        );
 
     // prepare upstreams
     let tx_submission_stream = tx_submission_agent_stream(tx_submission_agent);
@@ -128,37 +198,25 @@ async fn main() {
         .await
         .expect("Couldn't retrieve collateral");
 
-    let (pair_upd_snd_p1, pair_upd_recv_p1) =
-        mpsc::channel::<(PairId, Channel<StateUpdate<EvolvingCardanoEntity>>)>(config.channel_buffer_size);
-    let (pair_upd_snd_p2, pair_upd_recv_p2) =
-        mpsc::channel::<(PairId, Channel<StateUpdate<EvolvingCardanoEntity>>)>(config.channel_buffer_size);
-    let (pair_upd_snd_p3, pair_upd_recv_p3) =
-        mpsc::channel::<(PairId, Channel<StateUpdate<EvolvingCardanoEntity>>)>(config.channel_buffer_size);
-    let (pair_upd_snd_p4, pair_upd_recv_p4) =
-        mpsc::channel::<(PairId, Channel<StateUpdate<EvolvingCardanoEntity>>)>(config.channel_buffer_size);
-
-    let partitioned_pair_upd_snd =
-        Partitioned::new([pair_upd_snd_p1, pair_upd_snd_p2, pair_upd_snd_p3, pair_upd_snd_p4]);
-
-    let (spec_upd_snd_p1, spec_upd_recv_p1) = mpsc::channel::<(
-        PairId,
-        Channel<OrderUpdate<AtomicCardanoEntity, AtomicCardanoEntity>>,
-    )>(config.channel_buffer_size);
-    let (spec_upd_snd_p2, spec_upd_recv_p2) = mpsc::channel::<(
-        PairId,
-        Channel<OrderUpdate<AtomicCardanoEntity, AtomicCardanoEntity>>,
-    )>(config.channel_buffer_size);
-    let (spec_upd_snd_p3, spec_upd_recv_p3) = mpsc::channel::<(
-        PairId,
-        Channel<OrderUpdate<AtomicCardanoEntity, AtomicCardanoEntity>>,
-    )>(config.channel_buffer_size);
-    let (spec_upd_snd_p4, spec_upd_recv_p4) = mpsc::channel::<(
-        PairId,
-        Channel<OrderUpdate<AtomicCardanoEntity, AtomicCardanoEntity>>,
-    )>(config.channel_buffer_size);
+    let mut pair_upd_snds = Vec::with_capacity(num_execution_partitions);
+    let mut spec_upd_snds = Vec::with_capacity(num_execution_partitions);
+    let mut partition_upstreams = Vec::with_capacity(num_execution_partitions);
+    for _ in 0..num_execution_partitions {
+        let (pair_upd_snd, pair_upd_recv) = mpsc::channel::<(
+            PairId,
+            Channel<StateUpdate<EvolvingCardanoEntity>>,
+        )>(config.channel_buffer_size);
+        let (spec_upd_snd, spec_upd_recv) = mpsc::channel::<(
+            PairId,
+            Channel<OrderUpdate<AtomicCardanoEntity, AtomicCardanoEntity>>,
+        )>(config.channel_buffer_size);
+        pair_upd_snds.push(pair_upd_snd);
+        spec_upd_snds.push(spec_upd_snd);
+        partition_upstreams.push(merge_upstreams(pair_upd_recv, spec_upd_recv));
+    }
 
-    let partitioned_spec_upd_snd =
-        Partitioned::new([spec_upd_snd_p1, spec_upd_snd_p2, spec_upd_snd_p3, spec_upd_snd_p4]);
+    let partitioned_pair_upd_snd = Partitioned::new_unsafe(pair_upd_snds);
+    let partitioned_spec_upd_snd = Partitioned::new_unsafe(spec_upd_snds);
 
     let entity_index = Arc::new(Mutex::new(InMemoryEntityIndex::new(
         config.cardano_finalization_delay,
@@ -169,190 +227,162 @@ async fn main() {
     let handler_context = HandlerContextProto {
         executor_cred: operator_cred,
         scripts: ProtocolScriptHashes::from(&protocol_deployment),
-        bounds,
+        bounds: bounds.clone(),
     };
     let general_upd_handler = PairUpdateHandler::new(
         partitioned_pair_upd_snd,
         Arc::clone(&entity_index),
-        handler_context,
+        handler_context.clone(),
     );
     let spec_upd_handler = SpecializedHandler::new(
         PairUpdateHandler::new(partitioned_spec_upd_snd, entity_index, handler_context),
         spec_order_index,
+        order_bearers,
     );
+    // Hand states confirmed before restart over to executors ahead of replayed blocks.
+    let restore_execution_state = {
+        let mut general_upd_handler = general_upd_handler.clone();
+        let mut spec_upd_handler = spec_upd_handler.clone();
+        async move {
+            info!("Restoring {} confirmed states", restored_states.len());
+            general_upd_handler.restore(restored_states).await;
+            spec_upd_handler.restore().await;
+        }
+    };
 
     let handlers_ledger: Vec<Box<dyn EventHandler<LedgerTxEvent<ProcessingTransaction>>>> = vec![
+        Box::new(ChainTipHandler::new(chain_tip.clone())),
         Box::new(general_upd_handler.clone()),
         Box::new(spec_upd_handler.clone()),
+        Box::new(DeliveredPointHandler::new(delivered.clone())),
     ];
 
     let handlers_mempool: Vec<Box<dyn EventHandler<MempoolUpdate<ProcessingTransaction>>>> =
         vec![Box::new(general_upd_handler), Box::new(spec_upd_handler.clone())];
 
     let prover = OperatorProver::new(&operator_sk);
+    let validator = CardanoTxValidator;
     let recipe_interpreter = CardanoRecipeInterpreter;
     let spec_interpreter = SpecializedInterpreterViaRunOrder;
+    let execution_cap = SharedExecutionCap::new(config.execution_cap.into());
     let context = ExecutionContext {
-        time: 0.into(),
         deployment: protocol_deployment,
-        execution_cap: config.execution_cap.into(),
+        execution_cap: execution_cap.clone(),
         reward_addr: config.operator_reward_address,
         backlog_capacity: BacklogCapacity::from(config.backlog_capacity),
         collateral,
         network_id: config.network_id,
+        slot_config: config.slot_config,
+        chain_tip,
         operator_cred,
+        tx_builder_config,
+        matchmaking: config.matchmaking,
+        pair: None,
     };
     let multi_book = MultiPair::new::<TLB<AnyOrder, AnyPool, ExUnits>>(context.clone(), "Book");
     let multi_backlog = MultiPair::new::<HotPriorityBacklog<Bundled<ClassicalAMMOrder, FinalizedTxOut>>>(
         context.clone(),
         "Backlog",
     );
-    let state_index = StateIndexTracing(InMemoryStateIndex::new());
-    let state_cache = InMemoryKvStore::new();
+    let book_snapshots = BookSnapshots::new();
 
     let (signal_tip_reached_snd, signal_tip_reached_recv) = broadcast::channel(1);
 
-    let execution_stream_p1 = execution_part_stream(
-        state_index.clone(),
-        state_cache.clone(),
-        multi_book.clone(),
-        multi_backlog.clone(),
-        context.clone(),
-        recipe_interpreter,
-        spec_interpreter,
-        prover,
-        select_partition(
-            merge_upstreams(pair_upd_recv_p1, spec_upd_recv_p1),
-            config.partitioning.clone(),
-        ),
-        tx_submission_channel.clone(),
-        signal_tip_reached_snd.subscribe(),
-    );
-    let execution_stream_p2 = execution_part_stream(
-        state_index.clone(),
-        state_cache.clone(),
-        multi_book.clone(),
-        multi_backlog.clone(),
-        context.clone(),
-        recipe_interpreter,
-        spec_interpreter,
-        prover,
-        select_partition(
-            merge_upstreams(pair_upd_recv_p2, spec_upd_recv_p2),
-            config.partitioning.clone(),
-        ),
-        tx_submission_channel.clone(),
-        signal_tip_reached_snd.subscribe(),
-    );
-    let execution_stream_p3 = execution_part_stream(
-        state_index.clone(),
-        state_cache.clone(),
-        multi_book.clone(),
-        multi_backlog.clone(),
-        context.clone(),
-        recipe_interpreter,
-        spec_interpreter,
-        prover,
-        select_partition(
-            merge_upstreams(pair_upd_recv_p3, spec_upd_recv_p3),
-            config.partitioning.clone(),
-        ),
-        tx_submission_channel.clone(),
-        signal_tip_reached_snd.subscribe(),
-    );
-    let execution_stream_p4 = execution_part_stream(
-        state_index,
-        state_cache,
-        multi_book,
-        multi_backlog,
-        context,
-        recipe_interpreter,
-        spec_interpreter,
-        prover,
-        select_partition(
-            merge_upstreams(pair_upd_recv_p4, spec_upd_recv_p4),
-            config.partitioning,
-        ),
-        tx_submission_channel,
-        signal_tip_reached_snd.subscribe(),
-    );
+    let execution_streams = partition_upstreams
+        .into_iter()
+        .enumerate()
+        .map(|(partition, upstream)| {
+            boxed(execution_part_stream(
+                StateIndexTracing(state_index.clone().partition(partition)),
+                state_cache.clone(),
+                multi_book.clone(),
+                multi_backlog.clone(),
+                book_snapshots.clone(),
+                context.clone(),
+                recipe_interpreter,
+                spec_interpreter,
+                prover,
+                validator,
+                select_partition(upstream, config.partitioning.clone()),
+                TypedTxSubmissionChannel(tx_submission_channel.clone()),
+                config.max_in_flight_txs,
+                config.quarantine,
+                delivered.clone(),
+                signal_tip_reached_snd.subscribe(),
+            ))
+        })
+        .collect::<Vec<_>>();
+
+    let ledger_cache_pruning_stream = config
+        .chain_sync
+        .retention
+        .map(|policy| boxed(pruning_stream(Arc::clone(&chain_sync_cache), policy)));
 
     let ledger_stream = Box::pin(ledger_transactions(
         chain_sync_cache,
-        chain_sync_stream(chain_sync, signal_tip_reached_snd),
+        chain_sync_stream(
+            chain_sync,
+            reconnect_policy,
+            node_connections.chain_sync.clone(),
+            signal_tip_reached_snd,
+        ),
         config.chain_sync.disable_rollbacks_until,
-        config.chain_sync.replay_from_point,
+        replay_from_point,
         rollback_in_progress,
+        config.slot_config,
     ))
     .await
-    .map(|ev| match ev {
-        LedgerTxEvent::TxApplied { tx, slot } => LedgerTxEvent::TxApplied {
-            tx: (hash_transaction_canonical(&tx.body), tx),
-            slot,
-        },
-        LedgerTxEvent::TxUnapplied(tx) => {
-            LedgerTxEvent::TxUnapplied((hash_transaction_canonical(&tx.body), tx))
-        }
-    });
-    let mempool_stream = mempool_stream(&mempool_sync, signal_tip_reached_recv).map(|ev| match ev {
-        MempoolUpdate::TxAccepted(tx) => {
-            MempoolUpdate::TxAccepted((hash_transaction_canonical(&tx.body), tx))
-        }
+    .inspect(move |ev| match ev {
+        LedgerTxEvent::TxApplied { tx, .. } => ledger_observer.on_tx_applied(tx.hash),
+        LedgerTxEvent::TxUnapplied(tx) => ledger_observer.on_tx_unapplied(tx.hash),
+        LedgerTxEvent::BlockCompleted(_) => ledger_observer.on_block_completed(),
+        LedgerTxEvent::BlockRolledBack(_) | LedgerTxEvent::RollbackIncomplete(_) => {}
     });
+    let mempool_stream = mempool_stream(
+        mempool_sync,
+        reconnect_policy,
+        node_connections.mempool_sync.clone(),
+        signal_tip_reached_recv,
+    );
 
-    let process_ledger_events_stream =
-        process_events(ledger_stream, handlers_ledger).buffered_within(config.ledger_buffering_duration);
+    let process_ledger_events_stream = restore_execution_state
+        .map(move |_| {
+            process_events(ledger_stream, handlers_ledger).buffered_within(config.ledger_buffering_duration)
+        })
+        .flatten_stream();
     let process_mempool_events_stream =
         process_events(mempool_stream, handlers_mempool).buffered_within(config.mempool_buffering_duration);
 
-    let mut app = select_all(vec![
-        boxed(process_ledger_events_stream),
-        boxed(process_mempool_events_stream),
-        boxed(execution_stream_p1),
-        boxed(execution_stream_p2),
-        boxed(execution_stream_p3),
-        boxed(execution_stream_p4),
-        boxed(tx_submission_stream),
-    ]);
+    let mut app = select_all(
+        vec![
+            boxed(process_ledger_events_stream),
+            boxed(process_mempool_events_stream),
+            boxed(tx_submission_stream),
+            boxed(protocol_params_stream),
+            boxed(metrics_server_stream(config.metrics_endpoint)),
+            boxed(book_api_stream(
+                config.book_api_endpoint,
+                book_snapshots,
+                node_connections,
+            )),
+            boxed(config_watch_stream(
+                args.config_path,
+                args.bounds_path,
+                config.config_watch_interval,
+                bounds,
+                execution_cap,
+            )),
+        ]
+        .into_iter()
+        .chain(execution_streams)
+        .chain(ledger_cache_pruning_stream),
+    );
 
     loop {
         app.select_next_some().await;
     }
 }
 
-fn merge_upstreams(
-    xs: impl Stream<Item = (PairId, Channel<StateUpdate<EvolvingCardanoEntity>>)> + Unpin,
-    ys: impl Stream<
-            Item = (
-                PairId,
-                Channel<OrderUpdate<AtomicCardanoEntity, AtomicCardanoEntity>>,
-            ),
-        > + Unpin,
-) -> impl Stream<
-    Item = (
-        PairId,
-        Either<
-            Channel<
-                StateUpdate<
-                    Bundled<Either<Baked<AnyOrder, OutputRef>, Baked<AnyPool, OutputRef>>, FinalizedTxOut>,
-                >,
-            >,
-            Channel<OrderUpdate<Bundled<ClassicalAMMOrder, FinalizedTxOut>, ClassicalAMMOrder>>,
-        >,
-    ),
-> {
-    stream_select!(
-        xs.map(|(p, m)| (p, Either::Left(m.map(|s| s.map(|EvolvingCardanoEntity(e)| e))))),
-        ys.map(|(p, m)| (
-            p,
-            Either::Right(m.map(|upd| match upd {
-                OrderUpdate::Created(AtomicCardanoEntity(i)) => OrderUpdate::Created(i),
-                OrderUpdate::Eliminated(AtomicCardanoEntity(Bundled(i, _))) => OrderUpdate::Eliminated(i),
-            }))
-        ))
-    )
-}
-
 #[derive(Parser)]
 #[command(name = "bloom-cardano-agent")]
 #[command(author = "Spectrum Labs")]
@@ -0,0 +1,37 @@
+use std::convert::Infallible;
+use std::net::SocketAddr;
+
+use futures::{stream, Stream};
+use hyper::header::CONTENT_TYPE;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use log::{error, info};
+use prometheus::{Encoder, TextEncoder};
+
+/// Serves metrics collected in the default registry at `GET /metrics`.
+pub fn metrics_server_stream(addr: SocketAddr) -> impl Stream<Item = ()> {
+    stream::once(async move {
+        let make_svc = make_service_fn(|_| async { Ok::<_, Infallible>(service_fn(handle)) });
+        info!("Serving metrics at {}", addr);
+        if let Err(err) = Server::bind(&addr).serve(make_svc).await {
+            error!("Metrics server failed: {}", err);
+        }
+    })
+}
+
+async fn handle(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    if req.method() != Method::GET || req.uri().path() != "/metrics" {
+        let mut not_found = Response::new(Body::empty());
+        *not_found.status_mut() = StatusCode::NOT_FOUND;
+        return Ok(not_found);
+    }
+    let encoder = TextEncoder::new();
+    let mut buffer = vec![];
+    if let Err(err) = encoder.encode(&prometheus::gather(), &mut buffer) {
+        error!("Failed to encode metrics: {}", err);
+    }
+    Ok(Response::builder()
+        .header(CONTENT_TYPE, encoder.format_type())
+        .body(Body::from(buffer))
+        .unwrap())
+}
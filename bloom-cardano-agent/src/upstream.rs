@@ -0,0 +1,48 @@
+use either::Either;
+use futures::{stream_select, Stream, StreamExt};
+
+use bloom_offchain::execution_engine::bundled::Bundled;
+use bloom_offchain_cardano::event_sink::{AtomicCardanoEntity, EvolvingCardanoEntity};
+use bloom_offchain_cardano::orders::AnyOrder;
+use spectrum_cardano_lib::output::FinalizedTxOut;
+use spectrum_cardano_lib::OutputRef;
+use spectrum_offchain::data::event::{Channel, StateUpdate};
+use spectrum_offchain::data::order::OrderUpdate;
+use spectrum_offchain::data::Baked;
+use spectrum_offchain_cardano::data::order::ClassicalAMMOrder;
+use spectrum_offchain_cardano::data::pair::PairId;
+use spectrum_offchain_cardano::data::pool::AnyPool;
+
+/// Merge updates of evolving and atomic entities into a single upstream of an execution partition.
+pub fn merge_upstreams(
+    xs: impl Stream<Item = (PairId, Channel<StateUpdate<EvolvingCardanoEntity>>)> + Unpin,
+    ys: impl Stream<
+            Item = (
+                PairId,
+                Channel<OrderUpdate<AtomicCardanoEntity, AtomicCardanoEntity>>,
+            ),
+        > + Unpin,
+) -> impl Stream<
+    Item = (
+        PairId,
+        Either<
+            Channel<
+                StateUpdate<
+                    Bundled<Either<Baked<AnyOrder, OutputRef>, Baked<AnyPool, OutputRef>>, FinalizedTxOut>,
+                >,
+            >,
+            Channel<OrderUpdate<Bundled<ClassicalAMMOrder, FinalizedTxOut>, ClassicalAMMOrder>>,
+        >,
+    ),
+> {
+    stream_select!(
+        xs.map(|(p, m)| (p, Either::Left(m.map(|s| s.map(|EvolvingCardanoEntity(e)| e))))),
+        ys.map(|(p, m)| (
+            p,
+            Either::Right(m.map(|upd| match upd {
+                OrderUpdate::Created(AtomicCardanoEntity(i)) => OrderUpdate::Created(i),
+                OrderUpdate::Eliminated(AtomicCardanoEntity(Bundled(i, _))) => OrderUpdate::Eliminated(i),
+            }))
+        ))
+    )
+}
@@ -1,3 +1,5 @@
+use std::sync::{Arc, RwLock};
+
 use crate::orders::limit::LimitOrderBounds;
 use spectrum_offchain::data::Has;
 use spectrum_offchain_cardano::data::deposit::DepositOrderBounds;
@@ -12,3 +14,23 @@ pub struct Bounds {
     pub redeem_order: RedeemOrderBounds,
     pub pool: PoolBounds,
 }
+
+/// [Bounds] which can be replaced at runtime.
+#[derive(Debug, Clone)]
+pub struct SharedBounds(Arc<RwLock<Bounds>>);
+
+impl SharedBounds {
+    pub fn new(bounds: Bounds) -> Self {
+        Self(Arc::new(RwLock::new(bounds)))
+    }
+
+    /// Get the latest bounds.
+    pub fn get(&self) -> Bounds {
+        *self.0.read().unwrap()
+    }
+
+    /// Replace current bounds.
+    pub fn set(&self, bounds: Bounds) {
+        *self.0.write().unwrap() = bounds;
+    }
+}
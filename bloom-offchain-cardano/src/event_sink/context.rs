@@ -16,14 +16,14 @@ use spectrum_offchain_cardano::deployment::ProtocolValidator::{
 use spectrum_offchain_cardano::deployment::{DeployedScriptInfo, ProtocolScriptHashes};
 use spectrum_offchain_cardano::utxo::ConsumedInputs;
 
-use crate::bounds::Bounds;
+use crate::bounds::{Bounds, SharedBounds};
 use crate::orders::limit::LimitOrderBounds;
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Clone, Debug)]
 pub struct HandlerContextProto {
     pub executor_cred: OperatorCred,
     pub scripts: ProtocolScriptHashes,
-    pub bounds: Bounds,
+    pub bounds: SharedBounds,
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -229,14 +229,14 @@ impl HandlerContext {
     pub fn new(
         output_ref: OutputRef,
         consumed_utxos: ConsumedInputs,
-        prototype: HandlerContextProto,
+        prototype: &HandlerContextProto,
     ) -> Self {
         Self {
             output_ref,
             consumed_utxos,
             executor_cred: prototype.executor_cred,
             scripts: prototype.scripts,
-            bounds: prototype.bounds,
+            bounds: prototype.bounds.get(),
         }
     }
 }
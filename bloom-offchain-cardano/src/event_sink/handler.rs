@@ -2,20 +2,25 @@ use std::collections::hash_map::Entry;
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt::{Debug, Display};
 use std::hash::Hash;
+use std::iter;
 use std::marker::PhantomData;
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use cml_crypto::TransactionHash;
-use cml_multi_era::babbage::{BabbageTransaction, BabbageTransactionOutput};
+use cml_chain::transaction::TransactionOutput;
 use either::Either;
 use futures::{stream, Sink, SinkExt};
 use log::trace;
 use tokio::sync::{Mutex, MutexGuard};
 
 use crate::event_sink::context::{HandlerContext, HandlerContextProto};
+use bloom_offchain::execution_engine::storage::DeliveredPoint;
+use cardano_chain_sync::client::Point;
 use cardano_chain_sync::data::LedgerTxEvent;
 use cardano_mempool_sync::data::MempoolUpdate;
+use spectrum_cardano_lib::chain_tip::ChainTip;
+use spectrum_cardano_lib::output::FinalizedTxOut;
+use spectrum_cardano_lib::transaction::TxViewMut;
 use spectrum_cardano_lib::OutputRef;
 use spectrum_offchain::combinators::Ior;
 use spectrum_offchain::data::event::{Channel, Confirmed, StateUpdate, Unconfirmed};
@@ -28,26 +33,29 @@ use spectrum_offchain::partitioning::Partitioned;
 use spectrum_offchain_cardano::utxo::ConsumedInputs;
 
 use crate::event_sink::entity_index::TradableEntityIndex;
+use crate::event_sink::order_bearers::OrderBearers;
 use crate::event_sink::order_index::OrderIndex;
 
 /// A Tx being processed.
-/// Outputs in [BabbageTransaction] may be partially consumed in the process.
-pub type ProcessingTransaction = (TransactionHash, BabbageTransaction);
+/// Outputs in [TxViewMut] may be partially consumed in the process.
+pub type ProcessingTransaction = TxViewMut;
 
 /// A handler for updates that routes resulted [Entity] updates
 /// into different topics [Topic] according to partitioning key [PairId].
 #[derive(Clone)]
-pub struct PairUpdateHandler<const N: usize, PairId, Topic, Entity, Index> {
-    pub topic: Partitioned<N, PairId, Topic>,
+pub struct PairUpdateHandler<PairId, Topic, Entity, Index> {
+    pub topic: Partitioned<PairId, Topic>,
     /// Index of all non-consumed states of [Entity].
     pub index: Arc<Mutex<Index>>,
     pub context: HandlerContextProto,
+    /// Ledger updates of the block being processed, committed once the block is completed.
+    pub pending: HashMap<PairId, Vec<Channel<StateUpdate<Entity>>>>,
     pub pd: PhantomData<Entity>,
 }
 
-impl<const N: usize, PairId, Topic, Entity, Index> PairUpdateHandler<N, PairId, Topic, Entity, Index> {
+impl<PairId, Topic, Entity, Index> PairUpdateHandler<PairId, Topic, Entity, Index> {
     pub fn new(
-        topic: Partitioned<N, PairId, Topic>,
+        topic: Partitioned<PairId, Topic>,
         index: Arc<Mutex<Index>>,
         context: HandlerContextProto,
     ) -> Self {
@@ -55,43 +63,252 @@ impl<const N: usize, PairId, Topic, Entity, Index> PairUpdateHandler<N, PairId,
             topic,
             index,
             context,
+            pending: HashMap::new(),
             pd: Default::default(),
         }
     }
 }
 
+impl<PairId, Topic, Entity, Index> PairUpdateHandler<PairId, Topic, Entity, Index>
+where
+    PairId: Copy + Hash + Eq,
+    Topic: Sink<(PairId, Channel<StateUpdate<Entity>>)> + Unpin,
+    Topic::Error: Debug,
+{
+    async fn commit_pending(&mut self) {
+        for (pair, updates_by_pair) in self.pending.drain() {
+            let num_updates = updates_by_pair.len();
+            let topic = self.topic.get_mut(pair);
+            for upd in updates_by_pair {
+                topic.feed((pair, upd)).await.expect("Channel is closed");
+            }
+            topic.flush().await.expect("Failed to commit updates");
+            trace!("{} updates commited", num_updates);
+        }
+    }
+
+    /// Index states confirmed before restart and hand them over to executors.
+    pub async fn restore(&mut self, states: Vec<Entity>)
+    where
+        Entity: EntitySnapshot + Tradable<PairId = PairId> + Clone,
+        Index: TradableEntityIndex<Entity>,
+    {
+        trace!("Restoring {} states", states.len());
+        let index = Arc::clone(&self.index);
+        let mut index = index.lock().await;
+        for state in states {
+            index.put_state(state.clone());
+            let upd = Channel::ledger(StateUpdate::Transition(Ior::Right(state.clone())));
+            self.pending.entry(state.pair_id()).or_default().push(upd);
+        }
+        drop(index);
+        self.commit_pending().await;
+    }
+}
+
+impl<PairId, Topic, Entity, Index> PairUpdateHandler<PairId, Topic, Entity, Index>
+where
+    PairId: Copy + Hash + Eq,
+    Entity: EntitySnapshot
+        + Tradable<PairId = PairId>
+        + TryFromLedger<TransactionOutput, HandlerContext>
+        + Clone
+        + Debug,
+    Entity::Version: From<OutputRef>,
+    Index: TradableEntityIndex<Entity>,
+{
+    /// Revert unconfirmed state transitions caused by a TX that left the mempool.
+    /// States consumed by a rejected TX are not restored as they were spent by a competing TX.
+    async fn rollback_unconfirmed(
+        &mut self,
+        tx: ProcessingTransaction,
+        is_rejected: bool,
+        updates: &mut HashMap<PairId, Vec<Channel<StateUpdate<Entity>>>>,
+    ) -> ProcessingTransaction {
+        match extract_persistent_transitions(Arc::clone(&self.index), self.context.clone(), tx).await {
+            Ok((transitions, tx)) => {
+                trace!("{} entities found in evicted TX", transitions.len());
+                let mut index = self.index.lock().await;
+                index.run_eviction();
+                for tr in transitions {
+                    let inverse_tr = if is_rejected {
+                        match tr.right() {
+                            Some(produced) => Ior::Left(produced),
+                            None => continue,
+                        }
+                    } else {
+                        tr.swap()
+                    };
+                    index_transition(&mut index, &inverse_tr);
+                    let pair = pair_id_of(&inverse_tr);
+                    let upd = Channel::mempool(StateUpdate::TransitionRollback(inverse_tr));
+                    match updates.entry(pair) {
+                        Entry::Occupied(mut entry) => {
+                            entry.get_mut().push(upd);
+                        }
+                        Entry::Vacant(entry) => {
+                            entry.insert(vec![upd]);
+                        }
+                    }
+                }
+                tx
+            }
+            Err(tx) => tx,
+        }
+    }
+}
+
 #[derive(Clone)]
-pub struct SpecializedHandler<H, OrderIndex, Pool> {
+pub struct SpecializedHandler<H, OrderIndex, Bearers, Pool> {
     general_handler: H,
     order_index: Arc<Mutex<OrderIndex>>,
+    /// Bearers of confirmed orders, kept to restore orders on restart.
+    bearers: Arc<Mutex<Bearers>>,
     pd: PhantomData<Pool>,
 }
 
-impl<H, OrderIndex, Pool> SpecializedHandler<H, OrderIndex, Pool> {
-    pub fn new(general_handler: H, order_index: Arc<Mutex<OrderIndex>>) -> Self {
+impl<H, OrderIndex, Bearers, Pool> SpecializedHandler<H, OrderIndex, Bearers, Pool> {
+    pub fn new(
+        general_handler: H,
+        order_index: Arc<Mutex<OrderIndex>>,
+        bearers: Arc<Mutex<Bearers>>,
+    ) -> Self {
         Self {
             general_handler,
             order_index,
+            bearers,
             pd: PhantomData,
         }
     }
 }
 
+impl<PairId, Topic, Pool, Order, PoolIndex, OrderIndex, Bearers>
+    SpecializedHandler<PairUpdateHandler<PairId, Topic, Order, PoolIndex>, OrderIndex, Bearers, Pool>
+where
+    PairId: Copy + Hash + Eq,
+    Pool: EntitySnapshot + Tradable<PairId = PairId>,
+    Order: SpecializedOrder<TPoolId = Pool::StableId>
+        + TryFromLedger<TransactionOutput, HandlerContext>
+        + Clone
+        + Debug,
+    Order::TOrderId: From<OutputRef> + Display,
+    OrderIndex: crate::event_sink::order_index::OrderIndex<Order>,
+    PoolIndex: TradableEntityIndex<Pool>,
+{
+    /// Revert unconfirmed order updates caused by a TX that left the mempool.
+    /// Orders consumed by a rejected TX are not restored as they were spent by a competing TX.
+    async fn rollback_unconfirmed(
+        &mut self,
+        tx: ProcessingTransaction,
+        is_rejected: bool,
+        updates: &mut HashMap<PairId, Vec<Channel<OrderUpdate<Order, Order>>>>,
+    ) -> ProcessingTransaction {
+        match extract_atomic_transitions(
+            Arc::clone(&self.order_index),
+            self.general_handler.context.clone(),
+            tx,
+        )
+        .await
+        {
+            Ok((transitions, tx)) => {
+                trace!("{} entities found in evicted TX", transitions.len());
+                let pool_index = self.general_handler.index.lock().await;
+                let mut index = self.order_index.lock().await;
+                index.run_eviction();
+                for tr in transitions {
+                    if let Some(pair) = pool_index.pair_of(&pool_ref_of(&tr)) {
+                        let inverse_tr = match tr {
+                            Either::Left(_) if is_rejected => continue,
+                            tr => tr.flip(),
+                        };
+                        index_atomic_transition(&mut index, &inverse_tr);
+                        let upd = Channel::mempool(inverse_tr.into());
+                        match updates.entry(pair) {
+                            Entry::Occupied(mut entry) => {
+                                entry.get_mut().push(upd);
+                            }
+                            Entry::Vacant(entry) => {
+                                entry.insert(vec![upd]);
+                            }
+                        }
+                    }
+                }
+                tx
+            }
+            Err(tx) => tx,
+        }
+    }
+}
+
+impl<PairId, Topic, Pool, Order, PoolIndex, OrderIndex, Bearers>
+    SpecializedHandler<PairUpdateHandler<PairId, Topic, Order, PoolIndex>, OrderIndex, Bearers, Pool>
+where
+    PairId: Copy + Hash + Eq,
+    Topic: Sink<(PairId, Channel<OrderUpdate<Order, Order>>)> + Unpin,
+    Topic::Error: Debug,
+    Pool: EntitySnapshot + Tradable<PairId = PairId>,
+    Order: SpecializedOrder<TPoolId = Pool::StableId>
+        + TryFromLedger<TransactionOutput, HandlerContext>
+        + Clone
+        + Debug,
+    OrderIndex: crate::event_sink::order_index::OrderIndex<Order>,
+    Bearers: OrderBearers,
+    PoolIndex: TradableEntityIndex<Pool>,
+{
+    /// Restore orders confirmed before restart from their bearers and hand them over to executors.
+    /// Pools must be restored beforehand, so that orders can be routed to their pairs.
+    pub async fn restore(&mut self) {
+        let mut updates: HashMap<PairId, Vec<Channel<OrderUpdate<Order, Order>>>> = HashMap::new();
+        {
+            let pool_index = self.general_handler.index.lock().await;
+            let mut index = self.order_index.lock().await;
+            let mut bearers = self.bearers.lock().await;
+            let no_inputs = ConsumedInputs::new(iter::empty());
+            for FinalizedTxOut(out, out_ref) in bearers.get_all() {
+                let ctx = HandlerContext::new(out_ref, no_inputs, &self.general_handler.context);
+                match Order::try_from_ledger(&out, &ctx).and_then(|order| {
+                    pool_index
+                        .pair_of(&order.get_pool_ref())
+                        .map(|pair| (pair, order))
+                }) {
+                    Some((pair, order)) => {
+                        index.put(order.clone());
+                        let upd = Channel::ledger(OrderUpdate::Created(order));
+                        updates.entry(pair).or_default().push(upd);
+                    }
+                    None => bearers.remove(out_ref),
+                }
+            }
+        }
+        for (pair, updates_by_pair) in updates {
+            let num_updates = updates_by_pair.len();
+            let topic = self.general_handler.topic.get_mut(pair);
+            for upd in updates_by_pair {
+                topic.feed((pair, upd)).await.expect("Channel is closed");
+            }
+            topic.flush().await.expect("Failed to commit updates");
+            trace!("{} orders restored", num_updates);
+        }
+    }
+}
+
 #[async_trait(?Send)]
-impl<const N: usize, PairId, Topic, Pool, Order, PoolIndex, OrderIndex>
+impl<PairId, Topic, Pool, Order, PoolIndex, OrderIndex, Bearers>
     EventHandler<LedgerTxEvent<ProcessingTransaction>>
-    for SpecializedHandler<PairUpdateHandler<N, PairId, Topic, Order, PoolIndex>, OrderIndex, Pool>
+    for SpecializedHandler<PairUpdateHandler<PairId, Topic, Order, PoolIndex>, OrderIndex, Bearers, Pool>
 where
     PairId: Copy + Hash + Eq,
     Topic: Sink<(PairId, Channel<OrderUpdate<Order, Order>>)> + Unpin,
     Topic::Error: Debug,
     Pool: EntitySnapshot + Tradable<PairId = PairId>,
     Order: SpecializedOrder<TPoolId = Pool::StableId>
-        + TryFromLedger<BabbageTransactionOutput, HandlerContext>
+        + TryFromLedger<TransactionOutput, HandlerContext>
+        + Into<FinalizedTxOut>
         + Clone
         + Debug,
     Order::TOrderId: From<OutputRef> + Display,
     OrderIndex: crate::event_sink::order_index::OrderIndex<Order>,
+    Bearers: OrderBearers,
     PoolIndex: TradableEntityIndex<Pool>,
 {
     async fn try_handle(
@@ -100,10 +317,10 @@ where
     ) -> Option<LedgerTxEvent<ProcessingTransaction>> {
         let mut updates: HashMap<PairId, Vec<Channel<OrderUpdate<Order, Order>>>> = HashMap::new();
         let remainder = match ev {
-            LedgerTxEvent::TxApplied { tx, slot } => {
+            LedgerTxEvent::TxApplied { tx, slot, position } => {
                 match extract_atomic_transitions(
                     Arc::clone(&self.order_index),
-                    self.general_handler.context,
+                    self.general_handler.context.clone(),
                     tx,
                 )
                 .await
@@ -112,10 +329,12 @@ where
                         trace!("{} entities found in applied TX", transitions.len());
                         let pool_index = self.general_handler.index.lock().await;
                         let mut index = self.order_index.lock().await;
+                        let mut bearers = self.bearers.lock().await;
                         index.run_eviction();
                         for tr in transitions {
                             if let Some(pair) = pool_index.pair_of(&pool_ref_of(&tr)) {
                                 index_atomic_transition(&mut index, &tr);
+                                persist_atomic_transition(&mut bearers, &tr);
                                 let upd = Channel::ledger(tr.into());
                                 match updates.entry(pair) {
                                     Entry::Occupied(mut entry) => {
@@ -127,15 +346,15 @@ where
                                 }
                             }
                         }
-                        Some(LedgerTxEvent::TxApplied { tx, slot })
+                        Some(LedgerTxEvent::TxApplied { tx, slot, position })
                     }
-                    Err(tx) => Some(LedgerTxEvent::TxApplied { tx, slot }),
+                    Err(tx) => Some(LedgerTxEvent::TxApplied { tx, slot, position }),
                 }
             }
             LedgerTxEvent::TxUnapplied(tx) => {
                 match extract_atomic_transitions(
                     Arc::clone(&self.order_index),
-                    self.general_handler.context,
+                    self.general_handler.context.clone(),
                     tx,
                 )
                 .await
@@ -144,11 +363,13 @@ where
                         trace!("{} entities found in unapplied TX", transitions.len());
                         let mut index = self.order_index.lock().await;
                         let pool_index = self.general_handler.index.lock().await;
+                        let mut bearers = self.bearers.lock().await;
                         index.run_eviction();
                         for tr in transitions {
                             if let Some(pair) = pool_index.pair_of(&pool_ref_of(&tr)) {
                                 let inverse_tr = tr.flip();
                                 index_atomic_transition(&mut index, &inverse_tr);
+                                persist_atomic_transition(&mut bearers, &inverse_tr);
                                 let upd = Channel::ledger(inverse_tr.into());
                                 match updates.entry(pair) {
                                     Entry::Occupied(mut entry) => {
@@ -165,6 +386,9 @@ where
                     Err(tx) => Some(LedgerTxEvent::TxUnapplied(tx)),
                 }
             }
+            ev @ (LedgerTxEvent::BlockCompleted(_)
+            | LedgerTxEvent::BlockRolledBack(_)
+            | LedgerTxEvent::RollbackIncomplete(_)) => Some(ev),
         };
         for (pair, updates_by_pair) in updates {
             let num_updates = updates_by_pair.len();
@@ -180,16 +404,16 @@ where
 }
 
 #[async_trait(?Send)]
-impl<const N: usize, PairId, Topic, Pool, Order, PoolIndex, OrderIndex>
+impl<PairId, Topic, Pool, Order, PoolIndex, OrderIndex, Bearers>
     EventHandler<MempoolUpdate<ProcessingTransaction>>
-    for SpecializedHandler<PairUpdateHandler<N, PairId, Topic, Order, PoolIndex>, OrderIndex, Pool>
+    for SpecializedHandler<PairUpdateHandler<PairId, Topic, Order, PoolIndex>, OrderIndex, Bearers, Pool>
 where
     PairId: Copy + Hash + Eq,
     Topic: Sink<(PairId, Channel<OrderUpdate<Order, Order>>)> + Unpin,
     Topic::Error: Debug,
     Pool: EntitySnapshot + Tradable<PairId = PairId>,
     Order: SpecializedOrder<TPoolId = Pool::StableId>
-        + TryFromLedger<BabbageTransactionOutput, HandlerContext>
+        + TryFromLedger<TransactionOutput, HandlerContext>
         + Clone
         + Debug,
     Order::TOrderId: From<OutputRef> + Display,
@@ -205,7 +429,7 @@ where
             MempoolUpdate::TxAccepted(tx) => {
                 match extract_atomic_transitions(
                     Arc::clone(&self.order_index),
-                    self.general_handler.context,
+                    self.general_handler.context.clone(),
                     tx,
                 )
                 .await
@@ -234,6 +458,12 @@ where
                     Err(tx) => Some(MempoolUpdate::TxAccepted(tx)),
                 }
             }
+            MempoolUpdate::TxDropped(tx) => Some(MempoolUpdate::TxDropped(
+                self.rollback_unconfirmed(tx, false, &mut updates).await,
+            )),
+            MempoolUpdate::TxRejected(tx) => Some(MempoolUpdate::TxRejected(
+                self.rollback_unconfirmed(tx, true, &mut updates).await,
+            )),
         };
         for (pair, updates_by_pair) in updates {
             let num_updates = updates_by_pair.len();
@@ -258,20 +488,21 @@ fn pool_ref_of<T: SpecializedOrder>(tr: &Either<T, T>) -> T::TPoolId {
 async fn extract_atomic_transitions<Order, Index>(
     index: Arc<Mutex<Index>>,
     context: HandlerContextProto,
-    (tx_hash, mut tx): ProcessingTransaction,
+    mut tx: ProcessingTransaction,
 ) -> Result<(Vec<Either<Order, Order>>, ProcessingTransaction), ProcessingTransaction>
 where
-    Order: SpecializedOrder + TryFromLedger<BabbageTransactionOutput, HandlerContext> + Clone,
+    Order: SpecializedOrder + TryFromLedger<TransactionOutput, HandlerContext> + Clone,
     Order::TOrderId: From<OutputRef> + Display,
     Index: OrderIndex<Order>,
 {
-    let num_outputs = tx.body.outputs.len();
+    let tx_hash = tx.hash;
+    let num_outputs = tx.outputs.len();
     if num_outputs == 0 {
-        return Err((tx_hash, tx));
+        return Err(tx);
     }
     let mut consumed_orders = HashMap::<Order::TOrderId, Order>::new();
     let mut consumed_utxos = Vec::new();
-    for i in &tx.body.inputs {
+    for i in &tx.inputs {
         let oref = OutputRef::from((i.transaction_id, i.index));
         consumed_utxos.push(oref);
         let state_id = Order::TOrderId::from(oref);
@@ -286,9 +517,9 @@ where
     let consumed_utxos = ConsumedInputs::new(consumed_utxos.into_iter());
     let mut ix = num_outputs - 1;
     let mut non_processed_outputs = VecDeque::new();
-    while let Some(o) = tx.body.outputs.pop() {
+    while let Some(o) = tx.outputs.pop() {
         let o_ref = OutputRef::new(tx_hash, ix as u64);
-        match Order::try_from_ledger(&o, &HandlerContext::new(o_ref, consumed_utxos, context)) {
+        match Order::try_from_ledger(&o, &HandlerContext::new(o_ref, consumed_utxos, &context)) {
             Some(order) => {
                 let order_id = order.get_self_ref();
                 trace!("Order {} created by {}", order_id, tx_hash);
@@ -303,7 +534,7 @@ where
         }
     }
     // Preserve non-processed outputs in original ordering.
-    tx.body.outputs = non_processed_outputs.into();
+    tx.outputs = non_processed_outputs.into();
 
     // Gather IDs of all recognized entities.
     let mut keys = HashSet::new();
@@ -322,28 +553,29 @@ where
     }
 
     if transitions.is_empty() {
-        return Err((tx_hash, tx));
+        return Err(tx);
     }
-    Ok((transitions, (tx_hash, tx)))
+    Ok((transitions, tx))
 }
 
 async fn extract_persistent_transitions<Entity, Index>(
     index: Arc<Mutex<Index>>,
     context: HandlerContextProto,
-    (tx_hash, mut tx): ProcessingTransaction,
+    mut tx: ProcessingTransaction,
 ) -> Result<(Vec<Ior<Entity, Entity>>, ProcessingTransaction), ProcessingTransaction>
 where
-    Entity: EntitySnapshot + Tradable + TryFromLedger<BabbageTransactionOutput, HandlerContext> + Clone,
+    Entity: EntitySnapshot + Tradable + TryFromLedger<TransactionOutput, HandlerContext> + Clone,
     Entity::Version: From<OutputRef>,
     Index: TradableEntityIndex<Entity>,
 {
-    let num_outputs = tx.body.outputs.len();
+    let tx_hash = tx.hash;
+    let num_outputs = tx.outputs.len();
     if num_outputs == 0 {
-        return Err((tx_hash, tx));
+        return Err(tx);
     }
     let mut consumed_entities = HashMap::<Entity::StableId, Entity>::new();
     let mut consumed_utxos = Vec::new();
-    for i in &tx.body.inputs {
+    for i in &tx.inputs {
         let oref = OutputRef::from((i.transaction_id, i.index));
         consumed_utxos.push(oref);
         let state_id = Entity::Version::from(oref);
@@ -360,9 +592,9 @@ where
     let mut ix = num_outputs - 1;
     let mut non_processed_outputs = VecDeque::new();
     let consumed_utxos = ConsumedInputs::new(consumed_utxos.into_iter());
-    while let Some(o) = tx.body.outputs.pop() {
+    while let Some(o) = tx.outputs.pop() {
         let o_ref = OutputRef::new(tx_hash, ix as u64);
-        match Entity::try_from_ledger(&o, &HandlerContext::new(o_ref, consumed_utxos, context)) {
+        match Entity::try_from_ledger(&o, &HandlerContext::new(o_ref, consumed_utxos, &context)) {
             Some(entity) => {
                 let entity_id = entity.stable_id();
                 trace!("Entity {} created by {}", entity_id, tx_hash);
@@ -377,7 +609,7 @@ where
         }
     }
     // Preserve non-processed outputs in original ordering.
-    tx.body.outputs = non_processed_outputs.into();
+    tx.outputs = non_processed_outputs.into();
 
     // Gather IDs of all recognized entities.
     let mut keys = HashSet::new();
@@ -394,9 +626,9 @@ where
     }
 
     if transitions.is_empty() {
-        return Err((tx_hash, tx));
+        return Err(tx);
     }
-    Ok((transitions, (tx_hash, tx)))
+    Ok((transitions, tx))
 }
 
 fn pair_id_of<T: Tradable>(xa: &Ior<T, T>) -> T::PairId {
@@ -408,15 +640,15 @@ fn pair_id_of<T: Tradable>(xa: &Ior<T, T>) -> T::PairId {
 }
 
 #[async_trait(?Send)]
-impl<const N: usize, PairId, Topic, Entity, Index> EventHandler<LedgerTxEvent<ProcessingTransaction>>
-    for PairUpdateHandler<N, PairId, Topic, Entity, Index>
+impl<PairId, Topic, Entity, Index> EventHandler<LedgerTxEvent<ProcessingTransaction>>
+    for PairUpdateHandler<PairId, Topic, Entity, Index>
 where
     PairId: Copy + Hash + Eq,
     Topic: Sink<(PairId, Channel<StateUpdate<Entity>>)> + Unpin,
     Topic::Error: Debug,
     Entity: EntitySnapshot
         + Tradable<PairId = PairId>
-        + TryFromLedger<BabbageTransactionOutput, HandlerContext>
+        + TryFromLedger<TransactionOutput, HandlerContext>
         + Clone
         + Debug,
     Entity::Version: From<OutputRef>,
@@ -427,9 +659,14 @@ where
         ev: LedgerTxEvent<ProcessingTransaction>,
     ) -> Option<LedgerTxEvent<ProcessingTransaction>> {
         let mut updates: HashMap<PairId, Vec<Channel<StateUpdate<Entity>>>> = HashMap::new();
+        // Updates of transactions observed in a block are committed along with the whole block,
+        // the same goes for transactions unapplied while the block is rolled back.
+        let mut commit = true;
         let remainder = match ev {
-            LedgerTxEvent::TxApplied { tx, slot } => {
-                match extract_persistent_transitions(Arc::clone(&self.index), self.context, tx).await {
+            LedgerTxEvent::TxApplied { tx, slot, position } => {
+                commit = position.is_none();
+                match extract_persistent_transitions(Arc::clone(&self.index), self.context.clone(), tx).await
+                {
                     Ok((transitions, tx)) => {
                         trace!("{} transitions found in applied TX", transitions.len());
                         let mut index = self.index.lock().await;
@@ -447,13 +684,15 @@ where
                                 }
                             }
                         }
-                        Some(LedgerTxEvent::TxApplied { tx, slot })
+                        Some(LedgerTxEvent::TxApplied { tx, slot, position })
                     }
-                    Err(tx) => Some(LedgerTxEvent::TxApplied { tx, slot }),
+                    Err(tx) => Some(LedgerTxEvent::TxApplied { tx, slot, position }),
                 }
             }
             LedgerTxEvent::TxUnapplied(tx) => {
-                match extract_persistent_transitions(Arc::clone(&self.index), self.context, tx).await {
+                commit = false;
+                match extract_persistent_transitions(Arc::clone(&self.index), self.context.clone(), tx).await
+                {
                     Ok((transitions, tx)) => {
                         trace!("{} entities found in unapplied TX", transitions.len());
                         let mut index = self.index.lock().await;
@@ -477,30 +716,31 @@ where
                     Err(tx) => Some(LedgerTxEvent::TxUnapplied(tx)),
                 }
             }
+            ev @ (LedgerTxEvent::BlockCompleted(_)
+            | LedgerTxEvent::BlockRolledBack(_)
+            | LedgerTxEvent::RollbackIncomplete(_)) => Some(ev),
         };
+        // Updates are queued behind pending ones to preserve ordering.
         for (pair, updates_by_pair) in updates {
-            let num_updates = updates_by_pair.len();
-            let topic = self.topic.get_mut(pair);
-            for upd in updates_by_pair {
-                topic.feed((pair, upd)).await.expect("Channel is closed");
-            }
-            topic.flush().await.expect("Failed to commit updates");
-            trace!("{} updates commited", num_updates);
+            self.pending.entry(pair).or_default().extend(updates_by_pair);
+        }
+        if commit {
+            self.commit_pending().await;
         }
         remainder
     }
 }
 
 #[async_trait(?Send)]
-impl<const N: usize, PairId, Topic, Entity, Index> EventHandler<MempoolUpdate<ProcessingTransaction>>
-    for PairUpdateHandler<N, PairId, Topic, Entity, Index>
+impl<PairId, Topic, Entity, Index> EventHandler<MempoolUpdate<ProcessingTransaction>>
+    for PairUpdateHandler<PairId, Topic, Entity, Index>
 where
     PairId: Copy + Hash + Eq,
     Topic: Sink<(PairId, Channel<StateUpdate<Entity>>)> + Unpin,
     Topic::Error: Debug,
     Entity: EntitySnapshot
         + Tradable<PairId = PairId>
-        + TryFromLedger<BabbageTransactionOutput, HandlerContext>
+        + TryFromLedger<TransactionOutput, HandlerContext>
         + Clone
         + Debug,
     Entity::Version: From<OutputRef>,
@@ -513,7 +753,8 @@ where
         let mut updates: HashMap<PairId, Vec<Channel<StateUpdate<Entity>>>> = HashMap::new();
         let remainder = match ev {
             MempoolUpdate::TxAccepted(tx) => {
-                match extract_persistent_transitions(Arc::clone(&self.index), self.context, tx).await {
+                match extract_persistent_transitions(Arc::clone(&self.index), self.context.clone(), tx).await
+                {
                     Ok((transitions, tx)) => {
                         trace!("{} entities found in accepted TX", transitions.len());
                         let mut index = self.index.lock().await;
@@ -536,6 +777,12 @@ where
                     Err(tx) => Some(MempoolUpdate::TxAccepted(tx)),
                 }
             }
+            MempoolUpdate::TxDropped(tx) => Some(MempoolUpdate::TxDropped(
+                self.rollback_unconfirmed(tx, false, &mut updates).await,
+            )),
+            MempoolUpdate::TxRejected(tx) => Some(MempoolUpdate::TxRejected(
+                self.rollback_unconfirmed(tx, true, &mut updates).await,
+            )),
         };
         for (pair, updates_by_pair) in updates {
             let num_updates = updates_by_pair.len();
@@ -550,6 +797,62 @@ where
     }
 }
 
+/// Advances the chain tip transactions are validated against as blocks are completed.
+#[derive(Clone)]
+pub struct ChainTipHandler {
+    pub tip: ChainTip,
+}
+
+impl ChainTipHandler {
+    pub fn new(tip: ChainTip) -> Self {
+        Self { tip }
+    }
+}
+
+#[async_trait(?Send)]
+impl EventHandler<LedgerTxEvent<ProcessingTransaction>> for ChainTipHandler {
+    async fn try_handle(
+        &mut self,
+        ev: LedgerTxEvent<ProcessingTransaction>,
+    ) -> Option<LedgerTxEvent<ProcessingTransaction>> {
+        match &ev {
+            LedgerTxEvent::BlockCompleted(info) => self.tip.set(info.slot),
+            LedgerTxEvent::RollbackIncomplete(point) => self.tip.set(point.get_slot()),
+            _ => {}
+        }
+        Some(ev)
+    }
+}
+
+/// Publishes the point of each completed block once all of its updates were sent
+/// to executors, so that they can checkpoint their state at that point.
+/// Must be the last handler in the chain.
+#[derive(Clone)]
+pub struct DeliveredPointHandler {
+    pub delivered: DeliveredPoint,
+}
+
+impl DeliveredPointHandler {
+    pub fn new(delivered: DeliveredPoint) -> Self {
+        Self { delivered }
+    }
+}
+
+#[async_trait(?Send)]
+impl EventHandler<LedgerTxEvent<ProcessingTransaction>> for DeliveredPointHandler {
+    async fn try_handle(
+        &mut self,
+        ev: LedgerTxEvent<ProcessingTransaction>,
+    ) -> Option<LedgerTxEvent<ProcessingTransaction>> {
+        if let LedgerTxEvent::BlockCompleted(info) = &ev {
+            let point = Point::Specific(info.slot, info.hash);
+            self.delivered
+                .set(bincode::serialize(&point).expect("Point is always serializable"));
+        }
+        Some(ev)
+    }
+}
+
 fn index_atomic_transition<Index, T>(index: &mut MutexGuard<Index>, tr: &Either<T, T>)
 where
     T: SpecializedOrder + Clone,
@@ -565,6 +868,22 @@ where
     }
 }
 
+fn persist_atomic_transition<Bearers, T>(bearers: &mut MutexGuard<Bearers>, tr: &Either<T, T>)
+where
+    T: Into<FinalizedTxOut> + Clone,
+    Bearers: OrderBearers,
+{
+    match tr {
+        Either::Left(consumed) => {
+            let FinalizedTxOut(_, out_ref) = consumed.clone().into();
+            bearers.remove(out_ref);
+        }
+        Either::Right(produced) => {
+            bearers.put(produced.clone().into());
+        }
+    }
+}
+
 fn index_transition<Index, T>(index: &mut MutexGuard<Index>, tr: &Ior<T, T>)
 where
     T: EntitySnapshot + Tradable + Clone,
@@ -592,8 +911,8 @@ mod tests {
 
     use cml_chain::address::{Address, RewardAddress};
     use cml_chain::certs::Credential;
-    use cml_chain::transaction::TransactionInput;
-    use cml_crypto::{Ed25519KeyHash, ScriptHash};
+    use cml_chain::transaction::{TransactionInput, TransactionOutput};
+    use cml_crypto::{BlockHeaderHash, Ed25519KeyHash, ScriptHash};
     use cml_multi_era::babbage::{
         BabbageFormatTxOut, BabbageTransaction, BabbageTransactionBody, BabbageTransactionOutput,
         BabbageTransactionWitnessSet,
@@ -602,13 +921,13 @@ mod tests {
     use futures::StreamExt;
     use tokio::sync::Mutex;
 
-    use crate::bounds::Bounds;
+    use crate::bounds::{Bounds, SharedBounds};
     use crate::event_sink::context::HandlerContextProto;
     use algebra_core::monoid::Monoid;
-    use cardano_chain_sync::data::LedgerTxEvent;
+    use cardano_chain_sync::data::{BlockInfo, LedgerTxEvent, TxPosition};
     use spectrum_cardano_lib::ex_units::ExUnits;
     use spectrum_cardano_lib::hash::hash_transaction_canonical;
-    use spectrum_cardano_lib::transaction::TransactionOutputExtension;
+    use spectrum_cardano_lib::transaction::{TransactionOutputExtension, TxViewMut};
     use spectrum_cardano_lib::OutputRef;
     use spectrum_offchain::combinators::Ior;
     use spectrum_offchain::data::event::{Channel, Confirmed, StateUpdate};
@@ -659,17 +978,17 @@ mod tests {
         }
     }
 
-    impl<C> TryFromLedger<BabbageTransactionOutput, C> for TrivialEntity
+    impl<C> TryFromLedger<TransactionOutput, C> for TrivialEntity
     where
         C: Has<OutputRef>,
     {
-        fn try_from_ledger(repr: &BabbageTransactionOutput, ctx: &C) -> Option<Self> {
+        fn try_from_ledger(repr: &TransactionOutput, ctx: &C) -> Option<Self> {
             Some(TrivialEntity(ctx.select::<OutputRef>(), repr.value().coin))
         }
     }
 
-    #[tokio::test]
-    async fn apply_unapply_transaction() {
+    /// Two transactions, the second one spends the output of the first one.
+    fn chained_transactions() -> (BabbageTransaction, BabbageTransaction) {
         let (amt_1, amt_2) = (1000u64, 98000u64);
         let fee = 1000;
         let utxo_1 = BabbageTransactionOutput::BabbageFormatTxOut(BabbageFormatTxOut::new(
@@ -709,15 +1028,13 @@ mod tests {
             true,
             None,
         );
-        let tx_2_hash = hash_transaction_canonical(&tx_2.body);
-        let entity_eviction_delay = Duration::from_secs(60 * 5);
-        let index = Arc::new(Mutex::new(
-            InMemoryEntityIndex::new(entity_eviction_delay).with_tracing(),
-        ));
-        let (snd, mut recv) = mpsc::channel::<(u8, Channel<StateUpdate<TrivialEntity>>)>(100);
+        (tx_1, tx_2)
+    }
+
+    fn handler_context() -> HandlerContextProto {
         let ex_cred = OperatorCred(Ed25519KeyHash::from([0u8; 28]));
-        let context = HandlerContextProto {
-            bounds: Bounds {
+        HandlerContextProto {
+            bounds: SharedBounds::new(Bounds {
                 limit_order: LimitOrderBounds {
                     min_cost_per_ex_step: 1000,
                 },
@@ -731,7 +1048,7 @@ mod tests {
                     min_n2t_lovelace: 1000,
                     min_t2t_lovelace: 1000,
                 },
-            },
+            }),
             executor_cred: ex_cred,
             scripts: ProtocolScriptHashes {
                 limit_order_witness: DeployedScriptInfo {
@@ -819,14 +1136,26 @@ mod tests {
                     marginal_cost: ExUnits::empty(),
                 },
             },
-        };
+        }
+    }
+
+    #[tokio::test]
+    async fn apply_unapply_transaction() {
+        let (tx_1, tx_2) = chained_transactions();
+        let entity_eviction_delay = Duration::from_secs(60 * 5);
+        let index = Arc::new(Mutex::new(
+            InMemoryEntityIndex::new(entity_eviction_delay).with_tracing(),
+        ));
+        let (snd, mut recv) = mpsc::channel::<(u8, Channel<StateUpdate<TrivialEntity>>)>(100);
+        let context = handler_context();
         let mut handler = PairUpdateHandler::new(Partitioned::new([snd]), index, context);
         // Handle tx application
         EventHandler::<LedgerTxEvent<ProcessingTransaction>>::try_handle(
             &mut handler,
             LedgerTxEvent::TxApplied {
-                tx: (tx_1_hash, tx_1),
+                tx: TxViewMut::from(tx_1),
                 slot: 0,
+                position: None,
             },
         )
         .await;
@@ -838,8 +1167,9 @@ mod tests {
         EventHandler::<LedgerTxEvent<ProcessingTransaction>>::try_handle(
             &mut handler,
             LedgerTxEvent::TxApplied {
-                tx: (tx_2_hash, tx_2.clone()),
+                tx: TxViewMut::from(tx_2.clone()),
                 slot: 1,
+                position: None,
             },
         )
         .await;
@@ -851,7 +1181,17 @@ mod tests {
         assert_eq!(e1_reversed, e1);
         EventHandler::<LedgerTxEvent<ProcessingTransaction>>::try_handle(
             &mut handler,
-            LedgerTxEvent::TxUnapplied((tx_2_hash, tx_2)),
+            LedgerTxEvent::TxUnapplied(TxViewMut::from(tx_2)),
+        )
+        .await;
+        EventHandler::<LedgerTxEvent<ProcessingTransaction>>::try_handle(
+            &mut handler,
+            LedgerTxEvent::BlockRolledBack(BlockInfo {
+                hash: BlockHeaderHash::from([0u8; 32]),
+                slot: 1,
+                time: 1596059091000,
+                height: 1,
+            }),
         )
         .await;
         let (
@@ -864,4 +1204,145 @@ mod tests {
         assert_eq!(e2_reversed, e2);
         assert_eq!(e1_revived, e1);
     }
+
+    #[tokio::test]
+    async fn commit_ledger_updates_per_block() {
+        let (tx_1, tx_2) = chained_transactions();
+        let index = Arc::new(Mutex::new(InMemoryEntityIndex::new(Duration::from_secs(60 * 5))));
+        let (snd, mut recv) = mpsc::channel::<(u8, Channel<StateUpdate<TrivialEntity>>)>(100);
+        let mut handler = PairUpdateHandler::new(Partitioned::new([snd]), index, handler_context());
+        let block = BlockInfo {
+            hash: BlockHeaderHash::from([0u8; 32]),
+            slot: 1,
+            time: 1596059091000,
+            height: 1,
+        };
+        for (index, tx) in [tx_1, tx_2].into_iter().enumerate() {
+            EventHandler::<LedgerTxEvent<ProcessingTransaction>>::try_handle(
+                &mut handler,
+                LedgerTxEvent::TxApplied {
+                    tx: TxViewMut::from(tx),
+                    slot: block.slot,
+                    position: Some(TxPosition { block, index }),
+                },
+            )
+            .await;
+        }
+        assert!(
+            recv.try_next().is_err(),
+            "Updates must be pending until the block is completed"
+        );
+        EventHandler::<LedgerTxEvent<ProcessingTransaction>>::try_handle(
+            &mut handler,
+            LedgerTxEvent::BlockCompleted(block),
+        )
+        .await;
+        let (_, Channel::Ledger(Confirmed(StateUpdate::Transition(Ior::Right(e1))))) =
+            recv.next().await.expect("Must result in new event")
+        else {
+            panic!("Must be a transition")
+        };
+        let (_, Channel::Ledger(Confirmed(StateUpdate::Transition(Ior::Both(e1_reversed, _))))) =
+            recv.next().await.expect("Must result in new event")
+        else {
+            panic!("Must be a transition")
+        };
+        assert_eq!(e1_reversed, e1);
+    }
+
+    #[tokio::test]
+    async fn commit_rollback_updates_per_block() {
+        let (tx_1, tx_2) = chained_transactions();
+        let index = Arc::new(Mutex::new(InMemoryEntityIndex::new(Duration::from_secs(60 * 5))));
+        let (snd, mut recv) = mpsc::channel::<(u8, Channel<StateUpdate<TrivialEntity>>)>(100);
+        let mut handler = PairUpdateHandler::new(Partitioned::new([snd]), index, handler_context());
+        let block = BlockInfo {
+            hash: BlockHeaderHash::from([0u8; 32]),
+            slot: 1,
+            time: 1596059091000,
+            height: 1,
+        };
+        for (index, tx) in [tx_1.clone(), tx_2.clone()].into_iter().enumerate() {
+            EventHandler::<LedgerTxEvent<ProcessingTransaction>>::try_handle(
+                &mut handler,
+                LedgerTxEvent::TxApplied {
+                    tx: TxViewMut::from(tx),
+                    slot: block.slot,
+                    position: Some(TxPosition { block, index }),
+                },
+            )
+            .await;
+        }
+        EventHandler::<LedgerTxEvent<ProcessingTransaction>>::try_handle(
+            &mut handler,
+            LedgerTxEvent::BlockCompleted(block),
+        )
+        .await;
+        let _ = recv.next().await.expect("Must result in new event");
+        let (_, Channel::Ledger(Confirmed(StateUpdate::Transition(Ior::Both(_, e2))))) =
+            recv.next().await.expect("Must result in new event")
+        else {
+            panic!("Must be a transition")
+        };
+        // Transactions are unapplied in reverse order.
+        for tx in [tx_2, tx_1] {
+            EventHandler::<LedgerTxEvent<ProcessingTransaction>>::try_handle(
+                &mut handler,
+                LedgerTxEvent::TxUnapplied(TxViewMut::from(tx)),
+            )
+            .await;
+        }
+        assert!(
+            recv.try_next().is_err(),
+            "Updates must be pending until the block is rolled back"
+        );
+        EventHandler::<LedgerTxEvent<ProcessingTransaction>>::try_handle(
+            &mut handler,
+            LedgerTxEvent::BlockRolledBack(block),
+        )
+        .await;
+        let (_, Channel::Ledger(Confirmed(StateUpdate::TransitionRollback(Ior::Both(e2_reversed, _))))) =
+            recv.next().await.expect("Must result in new event")
+        else {
+            panic!("Must be a transition rollback")
+        };
+        assert_eq!(e2_reversed, e2);
+        let (_, Channel::Ledger(Confirmed(StateUpdate::TransitionRollback(Ior::Left(_))))) =
+            recv.next().await.expect("Must result in new event")
+        else {
+            panic!("Must be a transition rollback")
+        };
+    }
+
+    #[tokio::test]
+    async fn restored_states_are_tracked_further() {
+        let (tx_1, tx_2) = chained_transactions();
+        let index = Arc::new(Mutex::new(InMemoryEntityIndex::new(Duration::from_secs(60))));
+        let (snd, mut recv) = mpsc::channel::<(u8, Channel<StateUpdate<TrivialEntity>>)>(100);
+        let mut handler = PairUpdateHandler::new(Partitioned::new([snd]), index, handler_context());
+        // State produced by TX 1 was confirmed before restart.
+        let restored = TrivialEntity(OutputRef::new(hash_transaction_canonical(&tx_1.body), 0), 1000);
+        handler.restore(vec![restored.clone()]).await;
+        let (_, Channel::Ledger(Confirmed(StateUpdate::Transition(Ior::Right(e1))))) =
+            recv.next().await.expect("Must result in new event")
+        else {
+            panic!("Must be a transition")
+        };
+        assert_eq!(e1, restored);
+        EventHandler::<LedgerTxEvent<ProcessingTransaction>>::try_handle(
+            &mut handler,
+            LedgerTxEvent::TxApplied {
+                tx: TxViewMut::from(tx_2),
+                slot: 1,
+                position: None,
+            },
+        )
+        .await;
+        let (_, Channel::Ledger(Confirmed(StateUpdate::Transition(Ior::Both(consumed, _))))) =
+            recv.next().await.expect("Must result in new event")
+        else {
+            panic!("Must be a transition")
+        };
+        assert_eq!(consumed, restored);
+    }
 }
@@ -1,6 +1,6 @@
+use cml_chain::transaction::TransactionOutput;
 use cml_chain::PolicyId;
 use cml_crypto::ScriptHash;
-use cml_multi_era::babbage::BabbageTransactionOutput;
 use either::Either;
 
 use bloom_offchain::execution_engine::bundled::Bundled;
@@ -31,6 +31,7 @@ use bloom_offchain::partitioning::Partitioning;
 pub mod context;
 pub mod entity_index;
 pub mod handler;
+pub mod order_bearers;
 pub mod order_index;
 
 #[repr(transparent)]
@@ -50,7 +51,13 @@ impl SpecializedOrder for AtomicCardanoEntity {
     }
 }
 
-impl<C> TryFromLedger<BabbageTransactionOutput, C> for AtomicCardanoEntity
+impl From<AtomicCardanoEntity> for FinalizedTxOut {
+    fn from(AtomicCardanoEntity(Bundled(_, bearer)): AtomicCardanoEntity) -> Self {
+        bearer
+    }
+}
+
+impl<C> TryFromLedger<TransactionOutput, C> for AtomicCardanoEntity
 where
     C: Copy
         + Has<OperatorCred>
@@ -68,7 +75,7 @@ where
         + Has<DepositOrderBounds>
         + Has<RedeemOrderBounds>,
 {
-    fn try_from_ledger(repr: &BabbageTransactionOutput, ctx: &C) -> Option<Self> {
+    fn try_from_ledger(repr: &TransactionOutput, ctx: &C) -> Option<Self> {
         ClassicalAMMOrder::try_from_ledger(repr, ctx).map(|inner| {
             Self(Bundled(
                 inner,
@@ -108,7 +115,7 @@ impl Tradable for EvolvingCardanoEntity {
     }
 }
 
-impl<C> TryFromLedger<BabbageTransactionOutput, C> for EvolvingCardanoEntity
+impl<C> TryFromLedger<TransactionOutput, C> for EvolvingCardanoEntity
 where
     C: Copy
         + Has<OperatorCred>
@@ -127,7 +134,7 @@ where
         + Has<DepositOrderBounds>
         + Has<PoolBounds>,
 {
-    fn try_from_ledger(repr: &BabbageTransactionOutput, ctx: &C) -> Option<Self> {
+    fn try_from_ledger(repr: &TransactionOutput, ctx: &C) -> Option<Self> {
         <Either<Baked<AnyOrder, OutputRef>, Baked<AnyPool, OutputRef>>>::try_from_ledger(repr, ctx).map(
             |inner| {
                 Self(Bundled(
@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use log::error;
+use rocksdb::{Direction, IteratorMode};
+
+use spectrum_cardano_lib::output::FinalizedTxOut;
+use spectrum_cardano_lib::OutputRef;
+use spectrum_offchain::binary::prefixed_key;
+
+/// Ledger outputs of confirmed orders.
+/// Orders are not tracked by the persistent state index, so their bearers are kept
+/// to restore orders awaiting execution on restart.
+pub trait OrderBearers {
+    fn put(&mut self, bearer: FinalizedTxOut);
+    fn remove(&mut self, out_ref: OutputRef);
+    fn get_all(&self) -> Vec<FinalizedTxOut>;
+}
+
+#[derive(Clone, Default)]
+pub struct InMemoryOrderBearers(HashMap<OutputRef, FinalizedTxOut>);
+
+impl OrderBearers for InMemoryOrderBearers {
+    fn put(&mut self, bearer: FinalizedTxOut) {
+        self.0.insert(bearer.1, bearer);
+    }
+
+    fn remove(&mut self, out_ref: OutputRef) {
+        self.0.remove(&out_ref);
+    }
+
+    fn get_all(&self) -> Vec<FinalizedTxOut> {
+        self.0.values().cloned().collect()
+    }
+}
+
+/// Persistent [OrderBearers].
+/// Written as ledger updates are handled, i.e. possibly ahead of the execution checkpoint,
+/// which is fine as blocks replayed from the checkpoint create and eliminate orders anew.
+#[derive(Clone)]
+pub struct OrderBearersRocksDB {
+    db: Arc<rocksdb::OptimisticTransactionDB>,
+}
+
+impl OrderBearersRocksDB {
+    pub fn with_db(db: Arc<rocksdb::OptimisticTransactionDB>) -> Self {
+        Self { db }
+    }
+}
+
+const ORDER_BEARER_PREFIX: &str = "order_bearers";
+
+impl OrderBearers for OrderBearersRocksDB {
+    fn put(&mut self, bearer: FinalizedTxOut) {
+        self.db
+            .put(
+                prefixed_key(ORDER_BEARER_PREFIX, &bearer.1),
+                bincode::serialize(&bearer).unwrap(),
+            )
+            .unwrap();
+    }
+
+    fn remove(&mut self, out_ref: OutputRef) {
+        self.db
+            .delete(prefixed_key(ORDER_BEARER_PREFIX, &out_ref))
+            .unwrap();
+    }
+
+    fn get_all(&self) -> Vec<FinalizedTxOut> {
+        let raw_prefix = bincode::serialize(ORDER_BEARER_PREFIX).unwrap();
+        self.db
+            .iterator(IteratorMode::From(&raw_prefix, Direction::Forward))
+            .map(|item| item.unwrap())
+            .take_while(|(key, _)| key.starts_with(&raw_prefix))
+            .filter_map(|(_, value)| {
+                bincode::deserialize(&value)
+                    .map_err(|err| error!("Failed to decode order bearer: {}", err))
+                    .ok()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use cml_chain::address::Address;
+    use cml_chain::transaction::{ConwayFormatTxOut, TransactionOutput};
+    use cml_crypto::TransactionHash;
+
+    use spectrum_cardano_lib::output::FinalizedTxOut;
+    use spectrum_cardano_lib::OutputRef;
+
+    use crate::event_sink::order_bearers::{OrderBearers, OrderBearersRocksDB};
+
+    fn bearer(ix: u64) -> FinalizedTxOut {
+        let addr = Address::from_bech32("addr1z8d70g7c58vznyye9guwagdza74x36f3uff0eyk2zwpcpxmha8dg8af2w4umay478pg92nzy3643k89rwd8dyqd5sjgspt95mw").unwrap();
+        FinalizedTxOut(
+            TransactionOutput::new_conway_format_tx_out(ConwayFormatTxOut::new(addr, 2_000_000u64.into())),
+            OutputRef::new(TransactionHash::from([1u8; 32]), ix),
+        )
+    }
+
+    #[test]
+    fn bearers_survive_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let mut bearers = OrderBearersRocksDB::with_db(Arc::new(
+                rocksdb::OptimisticTransactionDB::open_default(dir.path()).unwrap(),
+            ));
+            bearers.put(bearer(0));
+            bearers.put(bearer(1));
+            bearers.remove(bearer(0).1);
+        }
+        let bearers = OrderBearersRocksDB::with_db(Arc::new(
+            rocksdb::OptimisticTransactionDB::open_default(dir.path()).unwrap(),
+        ));
+        assert_eq!(bearers.get_all(), vec![bearer(1)]);
+    }
+}
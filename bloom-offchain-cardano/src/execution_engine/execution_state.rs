@@ -99,6 +99,23 @@ impl TxBlueprint {
         }
     }
 
+    /// All UTxOs the resulting transaction consumes or references.
+    pub fn resolved_utxos(&self) -> Vec<TransactionUnspentOutput> {
+        let script_inputs = self
+            .script_io
+            .iter()
+            .map(|(i, _)| TransactionUnspentOutput::new(i.reference.into(), i.utxo.clone()));
+        let reference_inputs = self
+            .reference_inputs
+            .iter()
+            .map(|(ref_in, ref_utxo)| TransactionUnspentOutput::new(ref_in.clone(), ref_utxo.clone()));
+        let witness_refs = self.witness_scripts.keys().map(|wit| wit.reference_utxo.clone());
+        script_inputs
+            .chain(reference_inputs)
+            .chain(witness_refs)
+            .collect()
+    }
+
     pub fn project_onto_builder(
         self,
         mut txb: TransactionBuilder,
@@ -1,8 +1,13 @@
+use std::cmp::Reverse;
 use std::fmt::Debug;
 
-use cml_chain::builders::tx_builder::{ChangeSelectionAlgo, SignedTxBuilder, TransactionBuilder};
+use cml_chain::builders::redeemer_builder::RedeemerWitnessKey;
+use cml_chain::builders::tx_builder::{
+    ChangeSelectionAlgo, SignedTxBuilder, TransactionBuilder, TransactionBuilderConfig,
+};
+use cml_chain::plutus::RedeemerTag;
 use either::Either;
-use log::trace;
+use log::{trace, warn};
 use num_rational::Ratio;
 use tailcall::tailcall;
 
@@ -10,16 +15,18 @@ use bloom_offchain::execution_engine::batch_exec::BatchExec;
 use bloom_offchain::execution_engine::bundled::Bundled;
 use bloom_offchain::execution_engine::liquidity_book::core::{Execution, ExecutionRecipe, Make, Take};
 use bloom_offchain::execution_engine::liquidity_book::fragment::{MarketTaker, TakerBehaviour};
-use bloom_offchain::execution_engine::liquidity_book::interpreter::RecipeInterpreter;
+use bloom_offchain::execution_engine::liquidity_book::interpreter::{RecipeInterpreter, RecipeRejection};
+use bloom_offchain::execution_engine::liquidity_book::ExecutionCap;
 use spectrum_cardano_lib::collateral::Collateral;
+use spectrum_cardano_lib::ex_units::ExUnits;
 use spectrum_cardano_lib::hash::hash_transaction_canonical;
 use spectrum_cardano_lib::output::FinalizedTxOut;
-use spectrum_cardano_lib::protocol_params::constant_tx_builder;
 use spectrum_cardano_lib::{NetworkId, OutputRef};
 use spectrum_offchain::data::{Baked, Has};
 use spectrum_offchain_cardano::creds::{OperatorCred, OperatorRewardAddress};
 use spectrum_offchain_cardano::deployment::DeployedValidator;
 use spectrum_offchain_cardano::deployment::ProtocolValidator::{GridOrderNative, LimitOrderWitnessV1};
+use spectrum_offchain_cardano::script_eval::{evaluate_ex_units, set_evaluated_ex_units, SlotConfig};
 
 use crate::execution_engine::execution_state::ExecutionState;
 use crate::execution_engine::instances::{EffectPreview, FinalizedEffect, Magnet};
@@ -39,23 +46,36 @@ where
         + Sized
         + Has<Collateral>
         + Has<NetworkId>
+        + Has<SlotConfig>
         + Has<OperatorRewardAddress>
+        + Has<TransactionBuilderConfig>
+        + Has<ExecutionCap<ExUnits>>
         + Has<DeployedValidator<{ LimitOrderWitnessV1 as u8 }>>,
 {
     fn run(
         &mut self,
         ExecutionRecipe(instructions): ExecutionRecipe<Fr, Pl, FinalizedTxOut>,
         ctx: Ctx,
-    ) -> (
-        SignedTxBuilder,
-        Vec<FinalizedEffect<Either<Baked<Fr, OutputRef>, Baked<Pl, OutputRef>>>>,
-    ) {
-        let (mut tx_builder, effects, ctx) = execute_recipe(ctx, instructions);
+    ) -> Result<
+        (
+            SignedTxBuilder,
+            Vec<FinalizedEffect<Either<Baked<Fr, OutputRef>, Baked<Pl, OutputRef>>>>,
+        ),
+        RecipeRejection<Fr, Pl, OutputRef>,
+    > {
+        let funded_by_operator = is_funded_by_operator(&instructions);
+        let (mut tx_builder, effects, ctx) = execute_recipe(ctx, instructions)?;
         let execution_fee_address = ctx.select::<OperatorRewardAddress>().into();
         // Build tx, change is execution fee.
-        let tx = tx_builder
-            .build(ChangeSelectionAlgo::Default, &execution_fee_address)
-            .unwrap();
+        let tx = match tx_builder.build(ChangeSelectionAlgo::Default, &execution_fee_address) {
+            Ok(tx) => tx,
+            // Arbitrage yielding too little to pay for itself is dropped.
+            Err(err) if funded_by_operator => {
+                trace!("Arbitrage doesn't cover TX fee: {:?}", err);
+                return Err(RecipeRejection::Invalid(vec![]));
+            }
+            Err(err) => panic!("Failed to build TX: {:?}", err),
+        };
         let tx_body_cloned = tx.body();
         let tx_hash = hash_transaction_canonical(&tx_body_cloned);
 
@@ -87,15 +107,18 @@ where
             ))
         }
         trace!("Finished Tx: {}", tx_hash);
-        (tx, finalized_effects)
+        Ok((tx, finalized_effects))
     }
 }
 
+/// Build a transaction executing the recipe with fees balanced and ExUnits evaluated.
+/// Fails with a set of takers to exclude if the recipe doesn't fit into the hard [ExecutionCap],
+/// or with the participant whose script fails.
 #[tailcall]
 fn execute_recipe<Fr, Pl, Ctx>(
     ctx: Ctx,
     instructions: Vec<Execution<Fr, Pl, FinalizedTxOut>>,
-) -> (TransactionBuilder, Vec<EffectPreview<Either<Fr, Pl>>>, Ctx)
+) -> Result<(TransactionBuilder, Vec<EffectPreview<Either<Fr, Pl>>>, Ctx), RecipeRejection<Fr, Pl, OutputRef>>
 where
     Fr: MarketTaker + TakerBehaviour + Copy,
     Pl: Copy,
@@ -105,7 +128,10 @@ where
         + Sized
         + Has<Collateral>
         + Has<NetworkId>
+        + Has<SlotConfig>
         + Has<OperatorRewardAddress>
+        + Has<TransactionBuilderConfig>
+        + Has<ExecutionCap<ExUnits>>
         + Has<DeployedValidator<{ LimitOrderWitnessV1 as u8 }>>,
 {
     let state = ExecutionState::new();
@@ -118,11 +144,53 @@ where
         ctx,
     ) = execute(ctx, state, Vec::new(), instructions.clone());
     trace!("Going to interpret blueprint: {}", tx_blueprint);
-    let mut tx_builder = tx_blueprint.project_onto_builder(constant_tx_builder(), ctx.select::<NetworkId>());
-    tx_builder
-        .add_collateral(ctx.select::<Collateral>().into())
-        .unwrap();
-
+    let tx_builder_config = ctx.select::<TransactionBuilderConfig>();
+    let collateral = ctx.select::<Collateral>();
+    let mut resolved_utxos = tx_blueprint.resolved_utxos();
+    resolved_utxos.push(collateral.clone().into());
+    let mut tx_builder = tx_blueprint.project_onto_builder(
+        TransactionBuilder::new(tx_builder_config.clone()),
+        ctx.select::<NetworkId>(),
+    );
+    tx_builder.add_collateral(collateral.into()).unwrap();
+
+    // Static budgets are kept as a fallback in case local evaluation isn't possible.
+    let rejection = match evaluate_ex_units(
+        &tx_builder,
+        &ctx.select::<OperatorRewardAddress>().into(),
+        &resolved_utxos,
+        &tx_builder_config.cost_models,
+        ctx.select::<SlotConfig>(),
+    ) {
+        Ok(evaluated) => {
+            let total = set_evaluated_ex_units(&mut tx_builder, &evaluated);
+            let cap = ctx.select::<ExecutionCap<ExUnits>>().hard;
+            trace!("Evaluated ExUnits: {:?}, cap: {:?}", total, cap);
+            if exceeds(total, cap) {
+                Some(RecipeRejection::Oversized(select_takers_to_exclude(
+                    total,
+                    cap,
+                    &evaluated,
+                    &instructions,
+                )))
+            } else {
+                None
+            }
+        }
+        // Participant whose script failed is blamed, the whole recipe is dropped if it's unknown.
+        Err(err) if err.is_script_failure() => {
+            warn!("Recipe is rejected: {}", err);
+            let culprit = err
+                .failed_redeemer()
+                .filter(|key| key.tag == RedeemerTag::Spend)
+                .and_then(|key| spent_participants(&instructions).get(key.index as usize).copied());
+            Some(RecipeRejection::Invalid(culprit.into_iter().collect()))
+        }
+        Err(err) => {
+            warn!("Falling back to static ExUnits: {}", err);
+            None
+        }
+    };
     let estimated_fee = tx_builder.min_fee(true).unwrap();
     let fee_mismatch = reserved_fee as i64 - estimated_fee as i64;
     trace!(
@@ -131,13 +199,78 @@ where
         reserved_fee,
         fee_mismatch
     );
-    if fee_mismatch != 0 {
+    if let Some(rejection) = rejection {
+        Err(rejection)
+    } else if fee_mismatch != 0 && !is_funded_by_operator(&instructions) {
         let fee_rescale_factor = Ratio::new(estimated_fee, reserved_fee);
         let corrected_recipe = balance_fee(fee_mismatch, fee_rescale_factor, instructions);
         execute_recipe(ctx, corrected_recipe)
     } else {
-        (tx_builder, effects, ctx)
+        Ok((tx_builder, effects, ctx))
+    }
+}
+
+/// Recipes without takers (arbitrage between makers) pay TX fee out of the profit collected as change.
+fn is_funded_by_operator<Fr, Pl, Bearer>(instructions: &[Execution<Fr, Pl, Bearer>]) -> bool {
+    instructions.iter().all(|i| i.is_right())
+}
+
+fn exceeds(ex_units: ExUnits, cap: ExUnits) -> bool {
+    ex_units.mem > cap.mem || ex_units.steps > cap.steps
+}
+
+/// Participants of the recipe in the order their inputs are spent.
+/// Spending redeemers are indexed in the order of sorted inputs, see [TxBlueprint::project_onto_builder].
+fn spent_participants<Fr, Pl>(
+    instructions: &[Execution<Fr, Pl, FinalizedTxOut>],
+) -> Vec<Either<Baked<Fr, OutputRef>, Baked<Pl, OutputRef>>>
+where
+    Fr: Copy,
+    Pl: Copy,
+{
+    let mut participants = instructions
+        .iter()
+        .map(|i| match i {
+            Either::Left(take) => Either::Left(Baked::new(take.target.0, take.target.1 .1)),
+            Either::Right(make) => Either::Right(Baked::new(make.target.0, make.target.1 .1)),
+        })
+        .collect::<Vec<_>>();
+    participants.sort_by_key(|p| p.as_ref().either(|tk| tk.version, |mk| mk.version));
+    participants
+}
+
+/// Pick the most expensive takers until the rest of the recipe fits into the [cap].
+fn select_takers_to_exclude<Fr, Pl>(
+    total: ExUnits,
+    cap: ExUnits,
+    evaluated: &[(RedeemerWitnessKey, ExUnits)],
+    instructions: &[Execution<Fr, Pl, FinalizedTxOut>],
+) -> Vec<Fr>
+where
+    Fr: Copy,
+    Pl: Copy,
+{
+    let inputs = spent_participants(instructions);
+    let mut taker_costs = evaluated
+        .iter()
+        .filter(|(key, _)| key.tag == RedeemerTag::Spend)
+        .filter_map(|(key, ex_units)| {
+            inputs
+                .get(key.index as usize)
+                .and_then(|participant| participant.left().map(|tk| (tk.entity, *ex_units)))
+        })
+        .collect::<Vec<_>>();
+    taker_costs.sort_by_key(|(_, ex_units)| Reverse(*ex_units));
+    let mut remaining = total;
+    let mut excluded = vec![];
+    for (taker, ex_units) in taker_costs {
+        if !exceeds(remaining, cap) {
+            break;
+        }
+        remaining -= ex_units;
+        excluded.push(taker);
     }
+    excluded
 }
 
 fn balance_fee<Fr, Pl, Bearer>(
@@ -206,17 +339,51 @@ mod tests {
 
     use either::Either;
     use num_rational::Ratio;
+    use type_equalities::IsEqual;
 
+    use bloom_offchain::execution_engine::batch_exec::BatchExec;
     use bloom_offchain::execution_engine::bundled::Bundled;
-    use bloom_offchain::execution_engine::liquidity_book::core::{Next, TerminalTake, Trans, Unit};
+    use bloom_offchain::execution_engine::execution_effect::ExecutionEff;
+    use bloom_offchain::execution_engine::liquidity_book::core::{
+        Execution, ExecutionRecipe, Make, Next, Take, TerminalTake, Trans, Unit,
+    };
     use bloom_offchain::execution_engine::liquidity_book::fragment::{MarketTaker, TakerBehaviour};
+    use bloom_offchain::execution_engine::liquidity_book::interpreter::RecipeInterpreter;
     use bloom_offchain::execution_engine::liquidity_book::side::Side;
     use bloom_offchain::execution_engine::liquidity_book::time::TimeBounds;
     use bloom_offchain::execution_engine::liquidity_book::types::{
         AbsolutePrice, ExCostUnits, FeeAsset, InputAsset, OutputAsset,
     };
-
-    use crate::execution_engine::interpreter::balance_fee;
+    use bloom_offchain::execution_engine::liquidity_book::ExecutionCap;
+
+    use cml_chain::address::{Address, EnterpriseAddress};
+    use cml_chain::builders::redeemer_builder::RedeemerWitnessKey;
+    use cml_chain::builders::tx_builder::{TransactionBuilderConfig, TransactionUnspentOutput};
+    use cml_chain::certs::StakeCredential;
+    use cml_chain::plutus::{PlutusData, PlutusV2Script, RedeemerTag};
+    use cml_chain::transaction::{ConwayFormatTxOut, DatumOption, TransactionInput, TransactionOutput};
+    use cml_chain::utils::BigInteger;
+    use cml_chain::{Script, Value};
+    use cml_crypto::{Ed25519KeyHash, TransactionHash};
+    use spectrum_cardano_lib::collateral::Collateral;
+    use spectrum_cardano_lib::ex_units::ExUnits;
+    use spectrum_cardano_lib::hash::hash_transaction_canonical;
+    use spectrum_cardano_lib::output::FinalizedTxOut;
+    use spectrum_cardano_lib::protocol_params::constant_tx_builder_config;
+    use spectrum_cardano_lib::transaction::TransactionOutputExtension;
+    use spectrum_cardano_lib::{AssetClass, NetworkId, OutputRef};
+    use spectrum_offchain::data::{Baked, Has};
+    use spectrum_offchain_cardano::creds::OperatorRewardAddress;
+    use spectrum_offchain_cardano::deployment::DeployedValidator;
+    use spectrum_offchain_cardano::deployment::ProtocolValidator::LimitOrderWitnessV1;
+    use spectrum_offchain_cardano::script::{ready_cost, ready_redeemer, ScriptWitness};
+    use spectrum_offchain_cardano::script_eval::SlotConfig;
+
+    use crate::execution_engine::execution_state::{ExecutionState, ScriptInputBlueprint};
+    use crate::execution_engine::instances::{EffectPreview, Magnet};
+    use crate::execution_engine::interpreter::{
+        balance_fee, select_takers_to_exclude, spent_participants, CardanoRecipeInterpreter,
+    };
 
     #[test]
     fn fee_overuse_balancing() {
@@ -322,6 +489,283 @@ mod tests {
         )
     }
 
+    fn bearer(ix: u64) -> FinalizedTxOut {
+        let out = TransactionOutput::new_conway_format_tx_out(ConwayFormatTxOut {
+            address: Address::Enterprise(EnterpriseAddress::new(
+                0,
+                StakeCredential::new_pub_key(Ed25519KeyHash::from([1u8; 28])),
+            )),
+            amount: Value::from(5_000_000),
+            datum_option: None,
+            script_reference: None,
+            encodings: None,
+        });
+        FinalizedTxOut(out, OutputRef::new(TransactionHash::from([0u8; 32]), ix))
+    }
+
+    #[test]
+    fn exclude_most_expensive_takers_on_overflow() {
+        let cheap = SimpleOrderPF::new(1, 0);
+        let expensive = SimpleOrderPF::new(2, 0);
+        let instructions: Vec<Execution<SimpleOrderPF, (), FinalizedTxOut>> = vec![
+            Either::Left(Trans::new(Bundled(expensive, bearer(2)), Next::Succ(expensive))),
+            Either::Right(Trans::new(Bundled((), bearer(0)), Next::Succ(()))),
+            Either::Left(Trans::new(Bundled(cheap, bearer(1)), Next::Succ(cheap))),
+        ];
+        let units = |mem, steps| ExUnits { mem, steps };
+        let evaluated = vec![
+            (RedeemerWitnessKey::new(RedeemerTag::Spend, 0), units(500, 500)),
+            (RedeemerWitnessKey::new(RedeemerTag::Spend, 1), units(100, 100)),
+            (RedeemerWitnessKey::new(RedeemerTag::Spend, 2), units(300, 300)),
+        ];
+        let excluded = select_takers_to_exclude(units(900, 900), units(700, 700), &evaluated, &instructions);
+        assert_eq!(excluded, vec![expensive]);
+    }
+
+    #[test]
+    fn spending_redeemers_are_mapped_to_participants() {
+        let taker = SimpleOrderPF::new(1, 0);
+        let instructions: Vec<Execution<SimpleOrderPF, (), FinalizedTxOut>> = vec![
+            Either::Left(Trans::new(Bundled(taker, bearer(1)), Next::Succ(taker))),
+            Either::Right(Trans::new(Bundled((), bearer(0)), Next::Succ(()))),
+        ];
+        let participants = spent_participants(&instructions);
+        assert_eq!(participants[0], Either::Right(Baked::new((), bearer(0).1)));
+        assert_eq!(participants[1], Either::Left(Baked::new(taker, bearer(1).1)));
+    }
+
+    #[test]
+    fn routed_recipe_is_executed_in_one_tx() {
+        let ctx = TestCtx::new();
+        let taker = SimpleOrderPF::new(2_000_000, 2_000_000);
+        let executed_taker = SimpleOrderPF::new(0, 0);
+        let first_pool = SimplePool(0);
+        let second_pool = SimplePool(1);
+        let instructions: Vec<Execution<SimpleOrderPF, SimplePool, FinalizedTxOut>> = vec![
+            Either::Left(Trans::new(
+                Bundled(taker, ctx.locked(0)),
+                Next::Succ(executed_taker),
+            )),
+            Either::Right(Trans::new(
+                Bundled(first_pool, ctx.locked(1)),
+                Next::Succ(first_pool),
+            )),
+            Either::Right(Trans::new(
+                Bundled(second_pool, ctx.locked(2)),
+                Next::Succ(second_pool),
+            )),
+        ];
+        let (tx, effects) = CardanoRecipeInterpreter
+            .run(ExecutionRecipe(instructions), ctx)
+            .unwrap();
+        let tx_body = tx.body();
+        let tx_hash = hash_transaction_canonical(&tx_body);
+        assert!(tx_body.outputs.len() >= 3);
+        let mut updated = effects
+            .into_iter()
+            .map(|eff| match eff {
+                ExecutionEff::Updated(Bundled(_, consumed), Bundled(_, FinalizedTxOut(_, produced))) => {
+                    assert_eq!(produced.tx_hash(), tx_hash);
+                    consumed.1.index()
+                }
+                ExecutionEff::Eliminated(_) => panic!("Participants of the route are not eliminated"),
+            })
+            .collect::<Vec<_>>();
+        updated.sort();
+        assert_eq!(updated, vec![0, 1, 2]);
+    }
+
+    /// Maker which passes its bearer through unchanged.
+    #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+    struct SimplePool(u8);
+
+    /// `(program 1.0.0 (lam _ (lam _ (lam _ (con unit ())))))`
+    const ALWAYS_SUCCEEDS: &str = "46010000222499";
+
+    const MAINNET_SLOT_CONFIG: SlotConfig = SlotConfig {
+        zero_time: 1596059091000,
+        zero_slot: 4492800,
+        slot_length: 1000,
+    };
+
+    /// Context where every participant is locked by a script that always succeeds.
+    #[derive(Clone)]
+    struct TestCtx {
+        script: DeployedValidator<{ LimitOrderWitnessV1 as u8 }>,
+    }
+
+    impl TestCtx {
+        fn new() -> Self {
+            let script = PlutusV2Script::new(hex::decode(ALWAYS_SUCCEEDS).unwrap());
+            let hash = script.hash();
+            let reference_utxo = TransactionUnspentOutput::new(
+                TransactionInput::new(TransactionHash::from([1u8; 32]), 0),
+                TransactionOutput::new(
+                    pub_key_address(),
+                    Value::from(10_000_000),
+                    None,
+                    Some(Script::new_plutus_v2(script)),
+                ),
+            );
+            Self {
+                script: DeployedValidator {
+                    reference_utxo,
+                    hash,
+                    cost: ExUnits {
+                        mem: 500_000,
+                        steps: 200_000_000,
+                    },
+                    marginal_cost: ExUnits { mem: 0, steps: 0 },
+                },
+            }
+        }
+
+        fn locked(&self, ix: u64) -> FinalizedTxOut {
+            let address =
+                EnterpriseAddress::new(1, StakeCredential::new_script(self.script.hash)).to_address();
+            let datum = DatumOption::new_datum(PlutusData::new_integer(BigInteger::from(0)));
+            let out = TransactionOutput::new(address, Value::from(10_000_000), Some(datum), None);
+            FinalizedTxOut(out, OutputRef::new(TransactionHash::from([0u8; 32]), ix))
+        }
+
+        /// Spend the bearer of a participant and lock the output under the same script.
+        fn spend(
+            &self,
+            mut state: ExecutionState,
+            in_ref: OutputRef,
+            consumed: TransactionOutput,
+            produced: TransactionOutput,
+        ) -> ExecutionState {
+            let input = ScriptInputBlueprint {
+                reference: in_ref,
+                utxo: consumed,
+                script: ScriptWitness {
+                    hash: self.script.hash,
+                    cost: ready_cost(self.script.cost),
+                },
+                redeemer: ready_redeemer(PlutusData::new_list(vec![])),
+                required_signers: vec![],
+            };
+            state.tx_blueprint.add_io(input, produced);
+            state
+                .tx_blueprint
+                .add_ref_input(self.script.reference_utxo.clone());
+            state
+        }
+    }
+
+    fn pub_key_address() -> Address {
+        EnterpriseAddress::new(1, StakeCredential::new_pub_key(Ed25519KeyHash::from([0u8; 28]))).to_address()
+    }
+
+    impl Has<Collateral> for TestCtx {
+        fn select<U: IsEqual<Collateral>>(&self) -> Collateral {
+            Collateral::from(TransactionUnspentOutput::new(
+                TransactionInput::new(TransactionHash::from([2u8; 32]), 0),
+                TransactionOutput::new(pub_key_address(), Value::from(5_000_000), None, None),
+            ))
+        }
+    }
+
+    impl Has<NetworkId> for TestCtx {
+        fn select<U: IsEqual<NetworkId>>(&self) -> NetworkId {
+            NetworkId::from(1)
+        }
+    }
+
+    impl Has<SlotConfig> for TestCtx {
+        fn select<U: IsEqual<SlotConfig>>(&self) -> SlotConfig {
+            MAINNET_SLOT_CONFIG
+        }
+    }
+
+    impl Has<OperatorRewardAddress> for TestCtx {
+        fn select<U: IsEqual<OperatorRewardAddress>>(&self) -> OperatorRewardAddress {
+            OperatorRewardAddress(pub_key_address())
+        }
+    }
+
+    impl Has<TransactionBuilderConfig> for TestCtx {
+        fn select<U: IsEqual<TransactionBuilderConfig>>(&self) -> TransactionBuilderConfig {
+            constant_tx_builder_config()
+        }
+    }
+
+    impl Has<ExecutionCap<ExUnits>> for TestCtx {
+        fn select<U: IsEqual<ExecutionCap<ExUnits>>>(&self) -> ExecutionCap<ExUnits> {
+            let units = ExUnits {
+                mem: 14_000_000,
+                steps: 10_000_000_000,
+            };
+            ExecutionCap {
+                soft: units,
+                hard: units,
+            }
+        }
+    }
+
+    impl Has<DeployedValidator<{ LimitOrderWitnessV1 as u8 }>> for TestCtx {
+        fn select<U: IsEqual<DeployedValidator<{ LimitOrderWitnessV1 as u8 }>>>(
+            &self,
+        ) -> DeployedValidator<{ LimitOrderWitnessV1 as u8 }> {
+            self.script.clone()
+        }
+    }
+
+    impl BatchExec<ExecutionState, EffectPreview<SimpleOrderPF>, TestCtx>
+        for Magnet<Take<SimpleOrderPF, FinalizedTxOut>>
+    {
+        fn exec(
+            self,
+            mut state: ExecutionState,
+            context: TestCtx,
+        ) -> (ExecutionState, EffectPreview<SimpleOrderPF>, TestCtx) {
+            let Magnet(trans) = self;
+            let consumed_budget = trans.consumed_budget();
+            let consumed_fee = trans.consumed_fee();
+            let Trans {
+                target: Bundled(ord, FinalizedTxOut(consumed_out, in_ref)),
+                result,
+            } = trans;
+            let Next::Succ(next) = result else {
+                panic!("Taker is expected to be partially filled")
+            };
+            let mut produced_out = consumed_out.clone();
+            produced_out.sub_asset(AssetClass::Native, consumed_budget + consumed_fee);
+            state.add_fee(consumed_budget);
+            let state = context.spend(state, in_ref, consumed_out.clone(), produced_out.clone());
+            let effect = ExecutionEff::Updated(
+                Bundled(ord, FinalizedTxOut(consumed_out, in_ref)),
+                Bundled(next, produced_out),
+            );
+            (state, effect, context)
+        }
+    }
+
+    impl BatchExec<ExecutionState, EffectPreview<SimplePool>, TestCtx>
+        for Magnet<Make<SimplePool, FinalizedTxOut>>
+    {
+        fn exec(
+            self,
+            state: ExecutionState,
+            context: TestCtx,
+        ) -> (ExecutionState, EffectPreview<SimplePool>, TestCtx) {
+            let Magnet(Trans {
+                target: Bundled(pool, FinalizedTxOut(consumed_out, in_ref)),
+                result,
+            }) = self;
+            let Next::Succ(next) = result else {
+                panic!("Pool isn't supposed to terminate")
+            };
+            let state = context.spend(state, in_ref, consumed_out.clone(), consumed_out.clone());
+            let effect = ExecutionEff::Updated(
+                Bundled(pool, FinalizedTxOut(consumed_out.clone(), in_ref)),
+                Bundled(next, consumed_out),
+            );
+            (state, effect, context)
+        }
+    }
+
     /// Order that supports partial filling.
     #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
     pub struct SimpleOrderPF {
@@ -2,9 +2,9 @@ use std::cmp::{max, Ordering};
 use std::fmt::{Display, Formatter};
 
 use cml_chain::plutus::PlutusData;
+use cml_chain::transaction::TransactionOutput;
 use cml_chain::PolicyId;
 use cml_crypto::{Ed25519KeyHash, RawBytesEncoding};
-use cml_multi_era::babbage::BabbageTransactionOutput;
 use derive_more::{From, Into};
 use num_rational::Ratio;
 
@@ -34,7 +34,7 @@ use spectrum_offchain_cardano::deployment::{test_address, DeployedScriptInfo};
 use crate::relative_side::RelativeSide;
 
 /// Quote/Base price relative to order.
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Into, From)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Into, From, serde::Serialize, serde::Deserialize)]
 pub struct GridPrice(Ratio<u128>);
 impl GridPrice {
     #[inline]
@@ -53,7 +53,7 @@ impl GridPrice {
 }
 
 /// Open Grid Order.
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct GridOrder {
     pub beacon: PolicyId,
     pub base_asset: AssetClass,
@@ -414,11 +414,11 @@ pub fn unsafe_update_datum(
     cpd.set_field(DATUM_NATIVE_MAPPING.side, relative_side.into_pd());
 }
 
-impl<C> TryFromLedger<BabbageTransactionOutput, C> for GridOrder
+impl<C> TryFromLedger<TransactionOutput, C> for GridOrder
 where
     C: Has<DeployedScriptInfo<{ GridOrderNative as u8 }>>,
 {
-    fn try_from_ledger(repr: &BabbageTransactionOutput, ctx: &C) -> Option<Self> {
+    fn try_from_ledger(repr: &TransactionOutput, ctx: &C) -> Option<Self> {
         if test_address(repr.address(), ctx) {
             let value = repr.value().clone();
             let conf = DatumNative::try_from_pd(repr.datum()?.into_pd()?)?;
@@ -479,6 +479,7 @@ mod tests {
     use bloom_offchain::execution_engine::liquidity_book::linear_output_unsafe;
     use bloom_offchain::execution_engine::liquidity_book::side::Side;
     use spectrum_cardano_lib::ex_units::ExUnits;
+    use spectrum_cardano_lib::transaction::BabbageTransactionOutputExtension;
     use spectrum_cardano_lib::types::TryFromPData;
     use spectrum_cardano_lib::AssetClass;
     use spectrum_offchain::data::Has;
@@ -563,7 +564,9 @@ mod tests {
         let ctx = Context {
             grid_order: scripts.grid_order_native,
         };
-        let bearer = BabbageTransactionOutput::from_cbor_bytes(&*hex::decode(UTXO).unwrap()).unwrap();
+        let bearer = BabbageTransactionOutput::from_cbor_bytes(&*hex::decode(UTXO).unwrap())
+            .unwrap()
+            .upcast();
         let ord = GridOrder::try_from_ledger(&bearer, &ctx).unwrap();
         println!("Order: {:?}", ord);
         println!("P_abs: {}", ord.price());
@@ -2,9 +2,9 @@ use std::cmp::{max, min, Ordering};
 use std::fmt::{Display, Formatter};
 
 use cml_chain::plutus::{ConstrPlutusData, PlutusData};
+use cml_chain::transaction::TransactionOutput;
 use cml_chain::PolicyId;
 use cml_crypto::{blake2b224, Ed25519KeyHash, RawBytesEncoding};
-use cml_multi_era::babbage::BabbageTransactionOutput;
 
 use bloom_offchain::execution_engine::liquidity_book::core::{Next, TerminalTake, Unit};
 use bloom_offchain::execution_engine::liquidity_book::fragment::{MarketTaker, TakerBehaviour};
@@ -40,7 +40,7 @@ pub const EXEC_REDEEMER: PlutusData = PlutusData::ConstrPlutusData(ConstrPlutusD
 
 /// Composable limit order. Can be executed at a configured
 /// or better price as long as there is enough budget.
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct LimitOrder {
     /// Identifier of the order.
     pub beacon: PolicyId,
@@ -315,14 +315,14 @@ fn beacon_from_oref(oref: OutputRef) -> PolicyId {
 
 const MIN_LOVELACE: u64 = 1_500_000;
 
-impl<C> TryFromLedger<BabbageTransactionOutput, C> for LimitOrder
+impl<C> TryFromLedger<TransactionOutput, C> for LimitOrder
 where
     C: Has<OperatorCred>
         + Has<ConsumedInputs>
         + Has<DeployedScriptInfo<{ LimitOrderV1 as u8 }>>
         + Has<LimitOrderBounds>,
 {
-    fn try_from_ledger(repr: &BabbageTransactionOutput, ctx: &C) -> Option<Self> {
+    fn try_from_ledger(repr: &TransactionOutput, ctx: &C) -> Option<Self> {
         if test_address(repr.address(), ctx) {
             let value = repr.value().clone();
             let conf = Datum::try_from_pd(repr.datum()?.into_pd()?)?;
@@ -418,6 +418,7 @@ mod tests {
         ExecutionCap, ExternalTLBEvents, TemporalLiquidityBook, TLB,
     };
     use spectrum_cardano_lib::ex_units::ExUnits;
+    use spectrum_cardano_lib::transaction::BabbageTransactionOutputExtension;
     use spectrum_cardano_lib::types::TryFromPData;
     use spectrum_cardano_lib::{AssetName, OutputRef};
     use spectrum_offchain::data::Has;
@@ -514,7 +515,9 @@ mod tests {
             cred: OperatorCred(Ed25519KeyHash::from([0u8; 28])),
             consumed_inputs: ConsumedInputs::new(vec![].into_iter()),
         };
-        let bearer = BabbageTransactionOutput::from_cbor_bytes(&*hex::decode(ORDER_UTXO).unwrap()).unwrap();
+        let bearer = BabbageTransactionOutput::from_cbor_bytes(&*hex::decode(ORDER_UTXO).unwrap())
+            .unwrap()
+            .upcast();
         let ord = LimitOrder::try_from_ledger(&bearer, &ctx).expect("LimitOrder expected");
         println!("Order: {:?}", ord);
         println!("P_abs: {}", ord.price());
@@ -560,7 +563,8 @@ mod tests {
             }),
             script_reference: None,
             encodings: None,
-        });
+        })
+        .upcast();
         let d1 = PlutusData::from_cbor_bytes(&*hex::decode(D1).unwrap()).unwrap();
         let mut asset1 = AssetBundle::new();
         asset1.set(
@@ -580,7 +584,8 @@ mod tests {
             }),
             script_reference: None,
             encodings: None,
-        });
+        })
+        .upcast();
         dbg!(LimitOrder::try_from_ledger(&o0, &ctx));
         dbg!(LimitOrder::try_from_ledger(&o1, &ctx));
         let mut book = TLB::<LimitOrder, AnyPool, ExUnits>::new(
@@ -1,6 +1,6 @@
 use std::fmt::{Debug, Display, Formatter};
 
-use cml_multi_era::babbage::BabbageTransactionOutput;
+use cml_chain::transaction::TransactionOutput;
 
 use crate::orders::grid::GridOrder;
 use crate::orders::limit::{LimitOrder, LimitOrderBounds};
@@ -18,7 +18,20 @@ use spectrum_offchain_cardano::utxo::ConsumedInputs;
 pub mod grid;
 pub mod limit;
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, MarketTaker, Stable, Tradable)]
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    Eq,
+    PartialEq,
+    Ord,
+    PartialOrd,
+    MarketTaker,
+    Stable,
+    Tradable,
+    serde::Serialize,
+    serde::Deserialize,
+)]
 pub enum AnyOrder {
     Limit(LimitOrder),
     Grid(GridOrder),
@@ -69,14 +82,14 @@ impl TakerBehaviour for AnyOrder {
     }
 }
 
-impl<C> TryFromLedger<BabbageTransactionOutput, C> for AnyOrder
+impl<C> TryFromLedger<TransactionOutput, C> for AnyOrder
 where
     C: Has<OperatorCred>
         + Has<ConsumedInputs>
         + Has<DeployedScriptInfo<{ LimitOrderV1 as u8 }>>
         + Has<LimitOrderBounds>,
 {
-    fn try_from_ledger(repr: &BabbageTransactionOutput, ctx: &C) -> Option<Self> {
+    fn try_from_ledger(repr: &TransactionOutput, ctx: &C) -> Option<Self> {
         LimitOrder::try_from_ledger(repr, ctx).map(AnyOrder::Limit)
     }
 }
@@ -1,5 +1,5 @@
 use bloom_offchain::execution_engine::bundled::Bundled;
-use cml_chain::builders::tx_builder::SignedTxBuilder;
+use cml_chain::builders::tx_builder::{SignedTxBuilder, TransactionBuilderConfig};
 
 use spectrum_cardano_lib::collateral::Collateral;
 use spectrum_cardano_lib::output::FinalizedTxOut;
@@ -22,6 +22,7 @@ use spectrum_offchain_cardano::deployment::ProtocolValidator::{
     ConstFnPoolFeeSwitchBiDirFee, ConstFnPoolFeeSwitchV2, ConstFnPoolRedeem, ConstFnPoolSwap, ConstFnPoolV1,
     ConstFnPoolV2, StableFnPoolT2T, StableFnPoolT2TDeposit, StableFnPoolT2TRedeem,
 };
+use spectrum_offchain_cardano::script_eval::SlotConfig;
 
 /// Magnet for local instances.
 #[repr(transparent)]
@@ -34,6 +35,8 @@ where
         + Has<NetworkId>
         + Has<Collateral>
         + Has<OperatorRewardAddress>
+        + Has<SlotConfig>
+        + Has<TransactionBuilderConfig>
         + Has<DeployedValidator<{ ConstFnPoolV1 as u8 }>>
         + Has<DeployedValidator<{ ConstFnPoolV2 as u8 }>>
         + Has<DeployedValidator<{ ConstFnPoolFeeSwitch as u8 }>>
@@ -9,7 +9,7 @@ use spectrum_offchain::ledger::TryFromLedger;
 use crate::execution_engine::liquidity_book;
 
 /// Entity bundled with its source.
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct Bundled<T, Bearer>(pub T, pub Bearer);
 
 impl<T: Display, B> Display for Bundled<T, B> {
@@ -0,0 +1,120 @@
+use std::cmp::{max, min};
+
+use crate::execution_engine::liquidity_book::core::{MakeInProgress, Next, Trans, Unit};
+use crate::execution_engine::liquidity_book::market_maker::{MakerBehavior, MarketMaker};
+use crate::execution_engine::liquidity_book::side::OnSide;
+
+/// Number of slices offered input is cut into when split between makers.
+pub const SPLIT_GRANULARITY: u64 = 16;
+
+/// Share of offered input allocated to a single maker.
+#[derive(Debug, Copy, Clone)]
+pub struct Allocation<Maker> {
+    pub make: MakeInProgress<Maker>,
+    pub input: u64,
+    pub output: u64,
+}
+
+/// Split offered input between makers so that their marginal prices equalize.
+/// Input is allocated slice by slice, each slice goes to the maker offering the best price for it
+/// given the slices allocated before. Makers which got nothing allocated are left out.
+pub fn split_between_makers<M>(makers: Vec<M>, offered: OnSide<u64>, granularity: u64) -> Vec<Allocation<M>>
+where
+    M: MarketMaker + MakerBehavior + Copy,
+{
+    let total_input = offered.unwrap();
+    let slice = max(total_input / max(granularity, 1), 1);
+    // Initial state of each maker, its state after the slices allocated so far, and their sum.
+    let mut states: Vec<(M, Next<M, Unit>, u64)> = makers
+        .into_iter()
+        .map(|maker| (maker, Next::Succ(maker), 0))
+        .collect();
+    let mut remaining_input = total_input;
+    while remaining_input > 0 {
+        let chunk = min(slice, remaining_input);
+        let offered_chunk = offered.map(|_| chunk);
+        let prices = states
+            .iter()
+            .enumerate()
+            .filter_map(|(ix, (_, state, _))| match state {
+                Next::Succ(maker) => maker.real_price(offered_chunk).map(|rp| (ix, rp)),
+                Next::Term(_) => None,
+            });
+        let best_maker = match offered {
+            OnSide::Bid(_) => prices.min_by_key(|(_, rp)| *rp),
+            OnSide::Ask(_) => prices.max_by_key(|(_, rp)| *rp),
+        };
+        let Some((ix, _)) = best_maker else {
+            break;
+        };
+        let (_, state, allocated_input) = &mut states[ix];
+        if let Next::Succ(maker) = *state {
+            *state = maker.swap(offered_chunk);
+        }
+        *allocated_input += chunk;
+        remaining_input -= chunk;
+    }
+    states
+        .into_iter()
+        .filter(|(_, _, allocated_input)| *allocated_input > 0)
+        .filter_map(|(maker, state, allocated_input)| {
+            let make = Trans::new(maker, state);
+            make.loss().map(|output| Allocation {
+                make,
+                input: allocated_input,
+                output: output.unwrap(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::execution_engine::liquidity_book::allocation::split_between_makers;
+    use crate::execution_engine::liquidity_book::core::Trans;
+    use crate::execution_engine::liquidity_book::market_maker::MakerBehavior;
+    use crate::execution_engine::liquidity_book::side::OnSide;
+    use crate::execution_engine::liquidity_book::state::tests::SimpleCFMMPool;
+    use crate::execution_engine::types::StableId;
+
+    fn pool(reserves_base: u64, reserves_quote: u64) -> SimpleCFMMPool {
+        SimpleCFMMPool {
+            pool_id: StableId::random(),
+            reserves_base,
+            reserves_quote,
+            fee_num: 997,
+        }
+    }
+
+    #[test]
+    fn split_evenly_between_identical_makers() {
+        let input = OnSide::Ask(100000);
+        let allocations =
+            split_between_makers(vec![pool(1000000, 1000000), pool(1000000, 1000000)], input, 16);
+        assert_eq!(allocations.len(), 2);
+        assert_eq!(allocations.iter().map(|a| a.input).sum::<u64>(), 100000);
+        assert!(allocations[0].input.abs_diff(allocations[1].input) <= 100000 / 16);
+    }
+
+    #[test]
+    fn split_outperforms_single_maker() {
+        let input = OnSide::Bid(200000);
+        let deep_pool = pool(2000000, 2000000);
+        let allocations = split_between_makers(vec![deep_pool, pool(1000000, 1000000)], input, 16);
+        let split_output = allocations.iter().map(|a| a.output).sum::<u64>();
+        let single_output = Trans::new(deep_pool, deep_pool.swap(input))
+            .loss()
+            .unwrap()
+            .unwrap();
+        assert_eq!(allocations.iter().map(|a| a.input).sum::<u64>(), 200000);
+        assert!(split_output > single_output);
+    }
+
+    #[test]
+    fn allocate_to_single_maker_when_it_is_the_only_one() {
+        let input = OnSide::Ask(1000);
+        let allocations = split_between_makers(vec![pool(1000000, 1000000)], input, 16);
+        assert_eq!(allocations.len(), 1);
+        assert_eq!(allocations[0].input, 1000);
+    }
+}
@@ -0,0 +1,145 @@
+use crate::execution_engine::liquidity_book::core::{MakeInProgress, Trans};
+use crate::execution_engine::liquidity_book::market_maker::{MakerBehavior, MarketMaker};
+use crate::execution_engine::liquidity_book::side::OnSide;
+
+/// Settings of arbitrage between makers of the same pair.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ArbitrageConf {
+    pub enabled: bool,
+    /// Minimal profit (in base asset) an arbitrage has to yield to be executed.
+    pub min_profit: u64,
+}
+
+/// Operator-funded taker closing an arbitrage cycle.
+/// It sells `input` of base asset to one maker and buys `output` of base asset back from another one,
+/// the difference is the profit of the operator.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct SyntheticTake {
+    pub input: u64,
+    pub output: u64,
+}
+
+impl SyntheticTake {
+    pub fn profit(&self) -> u64 {
+        self.output.saturating_sub(self.input)
+    }
+
+    /// Profit which is negative when the cycle loses base asset.
+    pub fn net_profit(&self) -> i128 {
+        self.output as i128 - self.input as i128
+    }
+}
+
+/// Pair of swaps exploiting price divergence between two makers.
+#[derive(Debug, Copy, Clone)]
+pub struct Arbitrage<Maker> {
+    pub taker: SyntheticTake,
+    /// Maker base asset is sold to at the higher price.
+    pub sell: MakeInProgress<Maker>,
+    /// Maker base asset is bought back from at the lower price.
+    pub buy: MakeInProgress<Maker>,
+}
+
+/// Find the most profitable arbitrage between the makers with the highest and the lowest static price.
+pub fn find_arbitrage<M>(makers: Vec<M>) -> Option<Arbitrage<M>>
+where
+    M: MarketMaker + MakerBehavior + Copy,
+{
+    let rich = makers.iter().copied().max_by_key(|mm| mm.static_price())?;
+    let cheap = makers.iter().copied().min_by_key(|mm| mm.static_price())?;
+    if rich.static_price() <= cheap.static_price() {
+        return None;
+    }
+    // Base asset bought back can't exceed reserves of the cheap maker.
+    let mut lo = 1;
+    let mut hi = cheap.liquidity().base;
+    if hi < lo {
+        return None;
+    }
+    // Profit is concave in the amount traded, so the optimum is found by ternary search.
+    // Losses are compared as is, otherwise unprofitable points look equal and the search goes astray.
+    let profit = |input| profit_of(rich, cheap, input).unwrap_or(i128::MIN);
+    while hi - lo > 2 {
+        let m1 = lo + (hi - lo) / 3;
+        let m2 = hi - (hi - lo) / 3;
+        if profit(m1) < profit(m2) {
+            lo = m1 + 1;
+        } else {
+            hi = m2;
+        }
+    }
+    (lo..=hi)
+        .filter_map(|input| cycle(rich, cheap, input))
+        .filter(|arb| arb.taker.profit() > 0)
+        .max_by_key(|arb| arb.taker.profit())
+}
+
+fn profit_of<M>(rich: M, cheap: M, input: u64) -> Option<i128>
+where
+    M: MarketMaker + MakerBehavior + Copy,
+{
+    cycle(rich, cheap, input).map(|arb| arb.taker.net_profit())
+}
+
+fn cycle<M>(rich: M, cheap: M, input: u64) -> Option<Arbitrage<M>>
+where
+    M: MarketMaker + MakerBehavior + Copy,
+{
+    let sell = Trans::new(rich, rich.swap(OnSide::Ask(input)));
+    let quote = sell.loss()?.unwrap();
+    let buy = Trans::new(cheap, cheap.swap(OnSide::Bid(quote)));
+    let output = buy.loss()?.unwrap();
+    Some(Arbitrage {
+        taker: SyntheticTake { input, output },
+        sell,
+        buy,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::execution_engine::liquidity_book::arbitrage::{find_arbitrage, profit_of};
+    use crate::execution_engine::liquidity_book::state::tests::SimpleCFMMPool;
+    use crate::execution_engine::types::StableId;
+
+    fn pool(reserves_base: u64, reserves_quote: u64) -> SimpleCFMMPool {
+        SimpleCFMMPool {
+            pool_id: StableId::random(),
+            reserves_base,
+            reserves_quote,
+            fee_num: 997,
+        }
+    }
+
+    #[test]
+    fn no_arbitrage_between_pools_at_the_same_price() {
+        assert!(find_arbitrage(vec![pool(1000000, 2000000), pool(2000000, 4000000)]).is_none());
+    }
+
+    #[test]
+    fn arbitrage_between_diverging_pools() {
+        let rich = pool(1000000, 2000000);
+        let cheap = pool(1000000, 1000000);
+        let arb = find_arbitrage(vec![cheap, rich]).unwrap();
+        assert_eq!(arb.sell.target.pool_id, rich.pool_id);
+        assert_eq!(arb.buy.target.pool_id, cheap.pool_id);
+        assert!(arb.taker.profit() > 0);
+        // Trading more or less than the optimum yields less.
+        let profit = |input| profit_of(rich, cheap, input).unwrap_or(i128::MIN);
+        assert!(profit(arb.taker.input / 2) < arb.taker.net_profit());
+        assert!(profit(arb.taker.input * 2) < arb.taker.net_profit());
+    }
+
+    #[test]
+    fn optimum_far_from_lower_bound_is_found() {
+        let rich = pool(1_000_000_000_000, 2_000_000_000_000);
+        let cheap = pool(1_000_000_000_000, 1_000_000_000_000);
+        let arb = find_arbitrage(vec![cheap, rich]).unwrap();
+        assert!(arb.taker.input > 100_000_000_000);
+        // No coarse guess across the whole range beats the search.
+        let profit = |input| profit_of(rich, cheap, input).unwrap_or(i128::MIN);
+        let best_guess = (1..1000).map(|i| profit(i * 1_000_000_000)).max().unwrap();
+        assert!(arb.taker.net_profit() >= best_guess);
+    }
+}
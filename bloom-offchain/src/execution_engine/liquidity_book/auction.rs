@@ -0,0 +1,180 @@
+use std::cmp::{min, Reverse};
+
+use num_rational::Ratio;
+
+use crate::execution_engine::liquidity_book::core::{TakeInProgress, Trans};
+use crate::execution_engine::liquidity_book::fragment::{MarketTaker, TakerBehaviour};
+use crate::execution_engine::liquidity_book::linear_output_unsafe;
+use crate::execution_engine::liquidity_book::side::OnSide::{Ask, Bid};
+use crate::execution_engine::liquidity_book::side::Side;
+use crate::execution_engine::liquidity_book::types::AbsolutePrice;
+
+/// Single price crossing takers can be filled at.
+/// The price maximizing executed volume is chosen, ties are broken in favour of the price closest
+/// to the `anchor` (if given and within the crossing range) or to the middle of the crossing range.
+pub fn clearing_price<T: MarketTaker>(takers: &[T], anchor: Option<AbsolutePrice>) -> Option<AbsolutePrice> {
+    let best_ask = takers
+        .iter()
+        .filter(|tk| tk.side() == Side::Ask)
+        .map(|tk| tk.price())
+        .min()?;
+    let best_bid = takers
+        .iter()
+        .filter(|tk| tk.side() == Side::Bid)
+        .map(|tk| tk.price())
+        .max()?;
+    if best_ask > best_bid {
+        return None;
+    }
+    let pivot = match anchor {
+        Some(anchor) => anchor.clamp(best_ask, best_bid),
+        None => AbsolutePrice::from((best_ask.unwrap() + best_bid.unwrap()) / 2),
+    };
+    takers
+        .iter()
+        .map(|tk| tk.price())
+        .chain([pivot])
+        .filter(|price| *price >= best_ask && *price <= best_bid)
+        .map(|price| (price, executed_volume(takers, price)))
+        .filter(|(_, volume)| *volume > 0)
+        .max_by_key(|(price, volume)| (*volume, Reverse(distance(*price, pivot))))
+        .map(|(price, _)| price)
+}
+
+/// Fill all takers willing to trade at the given price.
+/// The side demanding less is filled completely, the other side is filled by price priority.
+pub fn fill_at<T>(takers: Vec<T>, price: AbsolutePrice) -> Vec<TakeInProgress<T>>
+where
+    T: MarketTaker + TakerBehaviour + Copy,
+{
+    let mut asks = takers
+        .iter()
+        .filter(|tk| tk.side() == Side::Ask && tk.price() <= price)
+        .copied()
+        .collect::<Vec<_>>();
+    let mut bids = takers
+        .iter()
+        .filter(|tk| tk.side() == Side::Bid && tk.price() >= price)
+        .copied()
+        .collect::<Vec<_>>();
+    asks.sort_by_key(|tk| tk.price());
+    bids.sort_by_key(|tk| Reverse(tk.price()));
+    let supply_base = asks.iter().map(|tk| tk.input()).sum::<u64>();
+    let demand_base = bids
+        .iter()
+        .map(|tk| linear_output_unsafe(tk.input(), Bid(price)))
+        .sum::<u64>();
+    let (complete, partial, mut remaining_input) = if demand_base <= supply_base {
+        // Base asset demanded by bids is distributed among asks.
+        (bids, asks, demand_base)
+    } else {
+        // Quote asset offered by asks is distributed among bids.
+        let supply_quote = asks
+            .iter()
+            .map(|tk| linear_output_unsafe(tk.input(), Ask(price)))
+            .sum::<u64>();
+        (asks, bids, supply_quote)
+    };
+    let mut takes = vec![];
+    for taker in complete {
+        takes.push(fill(taker, taker.input(), price));
+    }
+    for taker in partial {
+        if remaining_input == 0 {
+            break;
+        }
+        let removed_input = min(remaining_input, taker.input());
+        remaining_input -= removed_input;
+        takes.push(fill(taker, removed_input, price));
+    }
+    takes
+}
+
+fn fill<T>(taker: T, removed_input: u64, price: AbsolutePrice) -> TakeInProgress<T>
+where
+    T: MarketTaker + TakerBehaviour + Copy,
+{
+    let added_output = linear_output_unsafe(removed_input, taker.side().wrap(price));
+    Trans::new(taker, taker.with_applied_trade(removed_input, added_output))
+}
+
+/// Amount of base asset exchanged at the given price.
+fn executed_volume<T: MarketTaker>(takers: &[T], price: AbsolutePrice) -> u64 {
+    let mut supply_base = 0u64;
+    let mut demand_base = 0u64;
+    for tk in takers {
+        match tk.side() {
+            Side::Ask if tk.price() <= price => supply_base += tk.input(),
+            Side::Bid if tk.price() >= price => demand_base += linear_output_unsafe(tk.input(), Bid(price)),
+            _ => {}
+        }
+    }
+    min(supply_base, demand_base)
+}
+
+fn distance(x: AbsolutePrice, y: AbsolutePrice) -> Ratio<u128> {
+    if x > y {
+        x.unwrap() - y.unwrap()
+    } else {
+        y.unwrap() - x.unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::execution_engine::liquidity_book::auction::{clearing_price, fill_at};
+    use crate::execution_engine::liquidity_book::side::Side::{Ask, Bid};
+    use crate::execution_engine::liquidity_book::state::tests::SimpleOrderPF;
+    use crate::execution_engine::liquidity_book::types::AbsolutePrice;
+
+    #[test]
+    fn no_clearing_price_without_crossing_takers() {
+        let takers = vec![
+            SimpleOrderPF::new(Ask, 1000, AbsolutePrice::new_unsafe(2, 1), 0),
+            SimpleOrderPF::new(Bid, 1000, AbsolutePrice::new_unsafe(1, 1), 0),
+        ];
+        assert_eq!(clearing_price(&takers, None), None);
+    }
+
+    #[test]
+    fn clearing_price_is_anchored() {
+        let takers = vec![
+            SimpleOrderPF::new(Ask, 1000, AbsolutePrice::new_unsafe(1, 1), 0),
+            SimpleOrderPF::new(Bid, 3000, AbsolutePrice::new_unsafe(3, 1), 0),
+        ];
+        let anchor = AbsolutePrice::new_unsafe(2, 1);
+        assert_eq!(clearing_price(&takers, Some(anchor)), Some(anchor));
+    }
+
+    #[test]
+    fn everyone_is_filled_at_the_same_price() {
+        let takers = vec![
+            SimpleOrderPF::new(Ask, 1000, AbsolutePrice::new_unsafe(1, 1), 0),
+            SimpleOrderPF::new(Ask, 1000, AbsolutePrice::new_unsafe(3, 2), 0),
+            SimpleOrderPF::new(Bid, 1500, AbsolutePrice::new_unsafe(2, 1), 0),
+            SimpleOrderPF::new(Bid, 1500, AbsolutePrice::new_unsafe(5, 2), 0),
+        ];
+        let price = clearing_price(&takers, None).unwrap();
+        assert_eq!(price, AbsolutePrice::new_unsafe(3, 2));
+        let takes = fill_at(takers, price);
+        assert_eq!(takes.len(), 4);
+        for take in &takes {
+            let realized = match take.target.side {
+                Ask => AbsolutePrice::new(take.added_output(), take.removed_input()),
+                Bid => AbsolutePrice::new(take.removed_input(), take.added_output()),
+            };
+            assert_eq!(realized, Some(price));
+        }
+        let base_sold = takes
+            .iter()
+            .filter(|tk| tk.target.side == Ask)
+            .map(|tk| tk.removed_input())
+            .sum::<u64>();
+        let base_bought = takes
+            .iter()
+            .filter(|tk| tk.target.side == Bid)
+            .map(|tk| tk.added_output())
+            .sum::<u64>();
+        assert_eq!(base_sold, base_bought);
+    }
+}
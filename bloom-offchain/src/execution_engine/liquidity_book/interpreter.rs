@@ -6,6 +6,15 @@ use crate::execution_engine::bundled::Bundled;
 use crate::execution_engine::execution_effect::ExecutionEff;
 use crate::execution_engine::liquidity_book::core::ExecutionRecipe;
 
+/// Reasons a recipe can't be turned into a transaction.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum RecipeRejection<Fr, Pl, V> {
+    /// Recipe doesn't fit into one transaction, listed takers have to be excluded from it.
+    Oversized(Vec<Fr>),
+    /// Recipe can't be executed. Carries participants at fault, if any of them can be blamed.
+    Invalid(Vec<Either<Baked<Fr, V>, Baked<Pl, V>>>),
+}
+
 pub trait RecipeInterpreter<Fr, Pl, Ctx, V, Bearer, Txc> {
     /// Interpret recipe [ExecutionRecipe] into a transaction candidate [Txc] and
     /// a set of new sources resulted from execution.
@@ -13,13 +22,16 @@ pub trait RecipeInterpreter<Fr, Pl, Ctx, V, Bearer, Txc> {
         &mut self,
         recipe: ExecutionRecipe<Fr, Pl, Bearer>,
         ctx: Ctx,
-    ) -> (
-        Txc,
-        Vec<
-            ExecutionEff<
-                Bundled<Either<Baked<Fr, V>, Baked<Pl, V>>, Bearer>,
-                Bundled<Either<Baked<Fr, V>, Baked<Pl, V>>, Bearer>,
+    ) -> Result<
+        (
+            Txc,
+            Vec<
+                ExecutionEff<
+                    Bundled<Either<Baked<Fr, V>, Baked<Pl, V>>, Bearer>,
+                    Bundled<Either<Baked<Fr, V>, Baked<Pl, V>>, Bearer>,
+                >,
             >,
-        >,
-    );
+        ),
+        RecipeRejection<Fr, Pl, V>,
+    >;
 }
@@ -7,7 +7,9 @@ use std::cmp::Ordering;
 
 /// Price of a theoretical 0-swap in pool.
 #[repr(transparent)]
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Div, Mul, From, Into, Display)]
+#[derive(
+    Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Div, Mul, From, Into, Display, serde::Serialize,
+)]
 pub struct SpotPrice(AbsolutePrice);
 
 impl SpotPrice {
@@ -16,7 +18,7 @@ impl SpotPrice {
     }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, serde::Serialize)]
 pub struct AbsoluteReserves {
     pub base: u64,
     pub quote: u64,
@@ -45,7 +47,7 @@ pub trait MakerBehavior: Sized {
     fn swap(self, input: OnSide<u64>) -> Next<Self, Unit>;
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Into, From, Display)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Into, From, Display, serde::Serialize)]
 pub struct PoolQuality(u128);
 
 impl PartialOrd for PoolQuality {
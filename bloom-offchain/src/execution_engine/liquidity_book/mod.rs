@@ -1,19 +1,28 @@
+use std::cmp::Reverse;
 use std::fmt::{Debug, Display};
 use std::ops::AddAssign;
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
 
 use algebra_core::monoid::Monoid;
+use either::Either;
 use log::{trace, warn};
 use num_rational::Ratio;
 use primitive_types::U256;
 
 use crate::display::{display_option, display_tuple};
+use crate::execution_engine::liquidity_book::allocation::{
+    split_between_makers, Allocation, SPLIT_GRANULARITY,
+};
+use crate::execution_engine::liquidity_book::arbitrage::{find_arbitrage, Arbitrage, ArbitrageConf};
+use crate::execution_engine::liquidity_book::auction::{clearing_price, fill_at};
 use crate::execution_engine::liquidity_book::core::{
     MakeInProgress, MatchmakingAttempt, MatchmakingRecipe, Next, TakeInProgress, Trans,
 };
 use crate::execution_engine::liquidity_book::fragment::{MarketTaker, TakerBehaviour};
 use crate::execution_engine::liquidity_book::market_maker::{MakerBehavior, MarketMaker, SpotPrice};
 use spectrum_offchain::data::{Has, Stable};
-use spectrum_offchain::maker::Maker;
+use spectrum_offchain::maker::{Maker, Reconfigure};
 
 use crate::execution_engine::liquidity_book::side::OnSide::{Ask, Bid};
 use crate::execution_engine::liquidity_book::side::{OnSide, Side};
@@ -22,11 +31,16 @@ use crate::execution_engine::liquidity_book::state::queries::{max_by_distance_to
 use crate::execution_engine::liquidity_book::state::{IdleState, TLBState};
 use crate::execution_engine::liquidity_book::types::{AbsolutePrice, RelativePrice};
 use crate::execution_engine::types::Time;
+use crate::metrics;
 
+pub mod allocation;
+pub mod arbitrage;
+pub mod auction;
 pub mod core;
 pub mod fragment;
 pub mod interpreter;
 pub mod market_maker;
+pub mod routing;
 pub mod side;
 pub mod stashing_option;
 mod state;
@@ -34,6 +48,8 @@ pub mod time;
 pub mod types;
 pub mod weight;
 
+pub use state::price_range::AllowedPriceRange;
+
 /// TLB is a Universal Liquidity Aggregator (ULA), it is able to aggregate every piece of composable
 /// liquidity available in the market.
 ///
@@ -53,6 +69,52 @@ pub trait ExternalTLBEvents<T, M> {
     fn remove_pool(&mut self, pool: M);
 }
 
+/// Number of active takers on each side of the book and the number of makers.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub struct BookDepth {
+    pub asks: usize,
+    pub bids: usize,
+    pub makers: usize,
+}
+
+/// TLB API for inspecting the size of the book.
+pub trait TLBDepth {
+    fn depth(&self) -> BookDepth;
+}
+
+/// Point-in-time copy of the book.
+#[derive(Debug, Clone)]
+pub struct BookSnapshot<T, M> {
+    pub active_takers: Vec<T>,
+    pub inactive_takers: Vec<T>,
+    pub makers: Vec<M>,
+    pub allowed_price_range: AllowedPriceRange,
+}
+
+/// TLB API for taking snapshots of the book.
+pub trait TLBSnapshot<T, M> {
+    fn snapshot(&self) -> BookSnapshot<T, M>;
+}
+
+/// TLB API for matching takers of one book with makers of other books.
+/// Takes and makes applied to the book are kept in preview until [TLBFeedback] settles them.
+pub trait TLBRouting<T, M> {
+    /// Active takers of the book.
+    fn active_takers(&self) -> Vec<T>;
+    /// Maker offering the best price for the given input.
+    fn best_maker(&self, input: OnSide<u64>) -> Option<M>;
+    /// Apply take matched outside of the book.
+    fn apply_take(&mut self, take: TakeInProgress<T>);
+    /// Apply make matched outside of the book.
+    fn apply_make(&mut self, make: MakeInProgress<M>);
+}
+
+/// TLB API for exploiting price divergence between makers of the book.
+/// Makes applied to the book are kept in preview until [TLBFeedback] settles them.
+pub trait TLBArbitrage<T, M> {
+    fn attempt_arbitrage(&mut self) -> Option<MatchmakingRecipe<T, M>>;
+}
+
 /// TLB API for feedback events affecting its state.
 pub trait TLBFeedback<Fr, Pl> {
     fn on_recipe_succeeded(&mut self);
@@ -65,10 +127,45 @@ pub struct ExecutionCap<U> {
     pub hard: U,
 }
 
+/// [ExecutionCap] which can be replaced at runtime.
+#[derive(Debug, Clone)]
+pub struct SharedExecutionCap<U>(Arc<RwLock<ExecutionCap<U>>>);
+
+impl<U: Copy> SharedExecutionCap<U> {
+    pub fn new(cap: ExecutionCap<U>) -> Self {
+        Self(Arc::new(RwLock::new(cap)))
+    }
+
+    /// Get the latest cap.
+    pub fn get(&self) -> ExecutionCap<U> {
+        *self.0.read().unwrap()
+    }
+
+    /// Replace current cap.
+    pub fn set(&self, cap: ExecutionCap<U>) {
+        *self.0.write().unwrap() = cap;
+    }
+}
+
+/// How takers of a book are matched with each other.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MatchmakingMode {
+    /// Takers are matched one by one, each match is settled at its own price.
+    #[default]
+    Continuous,
+    /// Crossing takers are filled at a single clearing price,
+    /// which is optionally anchored to spot price of the best maker.
+    #[serde(rename_all = "camelCase")]
+    UniformPrice { anchor_to_spot: bool },
+}
+
 #[derive(Debug, Clone)]
 pub struct TLB<Taker, Maker: Stable, U> {
     state: TLBState<Taker, Maker>,
     execution_cap: ExecutionCap<U>,
+    mode: MatchmakingMode,
+    arbitrage: ArbitrageConf,
 }
 
 impl<Taker, Maker, U> TLBFeedback<Taker, Maker> for TLB<Taker, Maker, U>
@@ -81,10 +178,34 @@ where
     }
 
     fn on_recipe_failed(&mut self, stashing_opt: StashingOption<Taker>) {
+        if let StashingOption::Stash(_) = &stashing_opt {
+            metrics::on_takers_stashed();
+        }
         self.state.rollback(stashing_opt);
     }
 }
 
+impl<Taker, Maker, U> TLBDepth for TLB<Taker, Maker, U>
+where
+    Taker: MarketTaker + Ord + Copy,
+    Maker: MarketMaker + Stable + Copy,
+{
+    fn depth(&self) -> BookDepth {
+        self.state.depth()
+    }
+}
+
+impl<Taker, Maker, U> TLBSnapshot<Taker, Maker> for TLB<Taker, Maker, U>
+where
+    Taker: MarketTaker<U = U> + Ord + Copy + Display,
+    Maker: MarketMaker + Stable + Copy,
+    U: PartialOrd,
+{
+    fn snapshot(&self) -> BookSnapshot<Taker, Maker> {
+        self.state.snapshot()
+    }
+}
+
 impl<Taker, Maker, U> TLB<Taker, Maker, U>
 where
     Maker: Stable,
@@ -93,9 +214,19 @@ where
         Self {
             state: TLBState::new(time),
             execution_cap: conf,
+            mode: MatchmakingMode::Continuous,
+            arbitrage: ArbitrageConf::default(),
         }
     }
 
+    pub fn with_mode(self, mode: MatchmakingMode) -> Self {
+        Self { mode, ..self }
+    }
+
+    pub fn with_arbitrage(self, arbitrage: ArbitrageConf) -> Self {
+        Self { arbitrage, ..self }
+    }
+
     fn spot_price(&self) -> Option<SpotPrice>
     where
         Taker: MarketTaker,
@@ -124,6 +255,65 @@ where
     }
 }
 
+impl<Taker, Maker, U> TLBRouting<Taker, Maker> for TLB<Taker, Maker, U>
+where
+    Taker: MarketTaker<U = U> + Ord + Copy + Display,
+    Maker: MarketMaker + Stable + Copy,
+    U: PartialOrd,
+{
+    fn active_takers(&self) -> Vec<Taker> {
+        self.state.active_takers()
+    }
+
+    fn best_maker(&self, input: OnSide<u64>) -> Option<Maker> {
+        self.state.best_maker_for(input)
+    }
+
+    fn apply_take(&mut self, take: TakeInProgress<Taker>) {
+        if self.state.pick_exact_taker(&take.target).is_some() {
+            self.on_take(take.result);
+        }
+    }
+
+    fn apply_make(&mut self, make: MakeInProgress<Maker>) {
+        if self.state.pick_maker_by_id(&make.target.stable_id()).is_some() {
+            self.on_make(make.result);
+        }
+    }
+}
+
+impl<Taker, Maker, U> TLBArbitrage<Taker, Maker> for TLB<Taker, Maker, U>
+where
+    Taker: MarketTaker<U = U> + Ord + Copy + Display,
+    Maker: MarketMaker + MakerBehavior + Stable + Copy,
+    U: PartialOrd,
+{
+    fn attempt_arbitrage(&mut self) -> Option<MatchmakingRecipe<Taker, Maker>> {
+        if !self.arbitrage.enabled {
+            return None;
+        }
+        let Arbitrage { taker, sell, buy } = find_arbitrage(self.state.active_makers())?;
+        if taker.profit() < self.arbitrage.min_profit {
+            return None;
+        }
+        trace!(
+            "Arbitrage between {} and {} yields {}",
+            sell.target.stable_id(),
+            buy.target.stable_id(),
+            taker.profit()
+        );
+        for make in [sell, buy] {
+            if self.state.pick_maker_by_id(&make.target.stable_id()).is_some() {
+                self.on_make(make.result);
+            }
+        }
+        metrics::on_arbitrage_formed();
+        Some(MatchmakingRecipe {
+            instructions: vec![Either::Right(sell), Either::Right(buy)],
+        })
+    }
+}
+
 impl<Taker, Maker, U> TemporalLiquidityBook<Taker, Maker> for TLB<Taker, Maker, U>
 where
     Taker: Stable + MarketTaker<U = U> + TakerBehaviour + Ord + Copy + Display,
@@ -131,79 +321,27 @@ where
     U: Monoid + AddAssign + PartialOrd + Copy,
 {
     fn attempt(&mut self) -> Option<MatchmakingRecipe<Taker, Maker>> {
+        let started_at = Instant::now();
         loop {
             trace!("Attempting to matchmake");
-            let mut batch: MatchmakingAttempt<Taker, Maker, U> = MatchmakingAttempt::empty();
-            while batch.execution_units_consumed() < self.execution_cap.soft {
-                let spot_price = self.spot_price();
-                let price_range = self.state.allowed_price_range();
-                trace!("Spot price is: {}", display_option(spot_price));
-                trace!("Price range is: {}", price_range);
-                if let Some(target_taker) = self.state.pick_active_taker(|fs| {
-                    spot_price
-                        .map(|sp| max_by_distance_to_spot(fs, sp, price_range))
-                        .unwrap_or_else(|| max_by_volume(fs, price_range))
-                }) {
-                    trace!("Selected taker is: {}", target_taker);
-                    let target_side = target_taker.side();
-                    let target_price = target_side.wrap(target_taker.price());
-                    let maybe_price_counter_taker = self.state.best_taker_price(!target_side);
-                    let chunk_offered = batch.next_offered_chunk(&target_taker);
-                    let maybe_price_maker = self.state.preselect_market_maker(chunk_offered);
-                    trace!(
-                        "P_target: {}, P_counter: {}, P_amm: {}",
-                        target_price.unwrap(),
-                        display_option(maybe_price_counter_taker),
-                        display_option(maybe_price_maker.map(display_tuple))
-                    );
-                    match (maybe_price_counter_taker, maybe_price_maker) {
-                        (Some(price_counter_taker), maybe_price_maker)
-                            if target_price.overlaps(price_counter_taker.unwrap())
-                                && maybe_price_maker
-                                    .map(|(_, p)| price_counter_taker.better_than(p))
-                                    .unwrap_or(true) =>
-                        {
-                            if let Some(counter_taker) = self.state.try_pick_taker(!target_side, ok) {
-                                trace!("Taker {} matched with {}", target_taker, counter_taker);
-                                let make_match =
-                                    |ask: &Taker, bid: &Taker| settle_price(ask, bid, spot_price);
-                                let (take_a, take_b) =
-                                    execute_with_taker(target_taker, counter_taker, make_match);
-                                for take in vec![take_a, take_b] {
-                                    batch.add_take(take);
-                                    self.on_take(take.result);
-                                }
-                                continue;
-                            }
-                        }
-                        (_, Some((maker_sid, price_maker))) if target_price.overlaps(price_maker) => {
-                            if let Some(maker) = self.state.pick_maker_by_id(&maker_sid) {
-                                trace!("Taker {} matched with {}", target_taker, maker);
-                                let (take, make) = execute_with_maker(target_taker, maker, chunk_offered);
-                                if let Ok(_) = batch.add_make(make) {
-                                    batch.add_take(take);
-                                    self.on_take(take.result);
-                                    self.on_make(make.result);
-                                    continue;
-                                } else {
-                                    warn!("Maker {} caused an opposite swap", maker.stable_id());
-                                    self.state.pre_add_maker(maker);
-                                    self.state.pre_add_taker(target_taker);
-                                }
-                            }
-                        }
-                        _ => {}
-                    }
-                }
-                break;
-            }
+            let batch = match self.mode {
+                MatchmakingMode::Continuous => self.match_continuously(),
+                // Books without crossing takers are still matched with makers.
+                MatchmakingMode::UniformPrice { anchor_to_spot } => self
+                    .match_at_uniform_price(anchor_to_spot)
+                    .unwrap_or_else(|| self.match_continuously()),
+            };
             match MatchmakingRecipe::try_from(batch) {
                 Ok(ex_recipe) => {
                     trace!("Successfully formed a batch {}", ex_recipe);
+                    metrics::on_recipe_formed();
+                    metrics::on_matchmaking_finished(started_at.elapsed());
                     return Some(ex_recipe);
                 }
                 Err(None) => {
                     trace!("Matchmaking attempt failed");
+                    metrics::on_recipe_failed();
+                    metrics::on_matchmaking_finished(started_at.elapsed());
                     self.on_recipe_failed(StashingOption::Unstash);
                 }
                 Err(Some(unsatisfied_takers)) => {
@@ -217,6 +355,173 @@ where
     }
 }
 
+impl<Taker, Maker, U> TLB<Taker, Maker, U>
+where
+    Taker: Stable + MarketTaker<U = U> + TakerBehaviour + Ord + Copy + Display,
+    Maker: Stable + MarketMaker<U = U> + MakerBehavior + Copy + Display,
+    U: Monoid + AddAssign + PartialOrd + Copy,
+{
+    /// Match takers one by one with the best counter taker or maker, each match is settled at its own price.
+    fn match_continuously(&mut self) -> MatchmakingAttempt<Taker, Maker, U> {
+        let mut batch: MatchmakingAttempt<Taker, Maker, U> = MatchmakingAttempt::empty();
+        while batch.execution_units_consumed() < self.execution_cap.soft {
+            let spot_price = self.spot_price();
+            let price_range = self.state.allowed_price_range();
+            trace!("Spot price is: {}", display_option(spot_price));
+            trace!("Price range is: {}", price_range);
+            if let Some(target_taker) = self.state.pick_active_taker(|fs| {
+                spot_price
+                    .map(|sp| max_by_distance_to_spot(fs, sp, price_range))
+                    .unwrap_or_else(|| max_by_volume(fs, price_range))
+            }) {
+                trace!("Selected taker is: {}", target_taker);
+                let target_side = target_taker.side();
+                let target_price = target_side.wrap(target_taker.price());
+                let maybe_price_counter_taker = self.state.best_taker_price(!target_side);
+                let chunk_offered = batch.next_offered_chunk(&target_taker);
+                let maybe_price_maker = self.state.preselect_market_maker(chunk_offered);
+                trace!(
+                    "P_target: {}, P_counter: {}, P_amm: {}",
+                    target_price.unwrap(),
+                    display_option(maybe_price_counter_taker),
+                    display_option(maybe_price_maker.map(display_tuple))
+                );
+                match (maybe_price_counter_taker, maybe_price_maker) {
+                    (Some(price_counter_taker), maybe_price_maker)
+                        if target_price.overlaps(price_counter_taker.unwrap())
+                            && maybe_price_maker
+                                .map(|(_, p)| price_counter_taker.better_than(p))
+                                .unwrap_or(true) =>
+                    {
+                        if let Some(counter_taker) = self.state.try_pick_taker(!target_side, ok) {
+                            trace!("Taker {} matched with {}", target_taker, counter_taker);
+                            let make_match = |ask: &Taker, bid: &Taker| settle_price(ask, bid, spot_price);
+                            let (take_a, take_b) =
+                                execute_with_taker(target_taker, counter_taker, make_match);
+                            for take in vec![take_a, take_b] {
+                                batch.add_take(take);
+                                self.on_take(take.result);
+                            }
+                            continue;
+                        }
+                    }
+                    (_, Some((maker_sid, price_maker))) if target_price.overlaps(price_maker) => {
+                        let allocations = split_between_makers(
+                            self.state.active_makers(),
+                            chunk_offered,
+                            SPLIT_GRANULARITY,
+                        );
+                        if allocations.len() > 1 {
+                            trace!("Splitting {} between {} makers", chunk_offered, allocations.len());
+                            let mut removed_input = 0;
+                            let mut added_output = 0;
+                            for Allocation { make, input, output } in allocations {
+                                if let Some(maker) = self.state.pick_maker_by_id(&make.target.stable_id()) {
+                                    if let Ok(_) = batch.add_make(make) {
+                                        self.on_make(make.result);
+                                        removed_input += input;
+                                        added_output += output;
+                                    } else {
+                                        warn!("Maker {} caused an opposite swap", maker.stable_id());
+                                        self.state.pre_add_maker(maker);
+                                    }
+                                }
+                            }
+                            // Shares of makers which couldn't be used remain with the taker.
+                            if removed_input > 0 {
+                                let next_taker = target_taker.with_applied_trade(removed_input, added_output);
+                                let take = Trans::new(target_taker, next_taker);
+                                batch.add_take(take);
+                                self.on_take(take.result);
+                                continue;
+                            }
+                            self.state.pre_add_taker(target_taker);
+                        } else if let Some(maker) = self.state.pick_maker_by_id(&maker_sid) {
+                            trace!("Taker {} matched with {}", target_taker, maker);
+                            let (take, make) = execute_with_maker(target_taker, maker, chunk_offered);
+                            if let Ok(_) = batch.add_make(make) {
+                                batch.add_take(take);
+                                self.on_take(take.result);
+                                self.on_make(make.result);
+                                continue;
+                            } else {
+                                warn!("Maker {} caused an opposite swap", maker.stable_id());
+                                self.state.pre_add_maker(maker);
+                                self.state.pre_add_taker(target_taker);
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            break;
+        }
+        batch
+    }
+
+    /// Fill all crossing takers at a single clearing price.
+    fn match_at_uniform_price(
+        &mut self,
+        anchor_to_spot: bool,
+    ) -> Option<MatchmakingAttempt<Taker, Maker, U>> {
+        let anchor = if anchor_to_spot {
+            self.spot_price().map(AbsolutePrice::from)
+        } else {
+            None
+        };
+        let takers = self.crossing_takers_within_cap();
+        let price = clearing_price(&takers, anchor)?;
+        trace!("Clearing price is: {}", price);
+        let mut batch = MatchmakingAttempt::empty();
+        for take in fill_at(takers, price) {
+            if self.state.pick_exact_taker(&take.target).is_some() {
+                batch.add_take(take);
+                self.on_take(take.result);
+            }
+        }
+        Some(batch)
+    }
+
+    /// Takers which may cross the opposite side, best priced first, as many as the execution cap allows.
+    fn crossing_takers_within_cap(&self) -> Vec<Taker> {
+        let (asks, bids): (Vec<_>, Vec<_>) = self
+            .state
+            .active_takers()
+            .into_iter()
+            .partition(|tk| tk.side() == Side::Ask);
+        let best_ask = asks.iter().map(|tk| tk.price()).min();
+        let best_bid = bids.iter().map(|tk| tk.price()).max();
+        let mut asks = asks
+            .into_iter()
+            .filter(|tk| best_bid.map_or(false, |bid| tk.price() <= bid))
+            .collect::<Vec<_>>();
+        let mut bids = bids
+            .into_iter()
+            .filter(|tk| best_ask.map_or(false, |ask| tk.price() >= ask))
+            .collect::<Vec<_>>();
+        // Best priced takers go last to be popped first.
+        asks.sort_by_key(|tk| Reverse(tk.price()));
+        bids.sort_by_key(|tk| tk.price());
+        let mut selected = vec![];
+        let mut units_consumed = U::empty();
+        let mut ask_turn = true;
+        while units_consumed < self.execution_cap.soft {
+            let next_taker = if ask_turn {
+                asks.pop().or_else(|| bids.pop())
+            } else {
+                bids.pop().or_else(|| asks.pop())
+            };
+            let Some(taker) = next_taker else {
+                break;
+            };
+            units_consumed += taker.marginal_cost_hint();
+            selected.push(taker);
+            ask_turn = !ask_turn;
+        }
+        selected
+    }
+}
+
 fn execute_with_maker<Taker, Maker>(
     target_taker: Taker,
     maker: Maker,
@@ -271,10 +576,22 @@ fn ok<T>(_: &T) -> bool {
 impl<Fr, Pl, Ctx, U> Maker<Ctx> for TLB<Fr, Pl, U>
 where
     Pl: Stable,
-    Ctx: Has<Time> + Has<ExecutionCap<U>>,
+    Ctx: Has<Time> + Has<ExecutionCap<U>> + Has<MatchmakingMode> + Has<ArbitrageConf>,
 {
     fn make(ctx: &Ctx) -> Self {
         Self::new(ctx.select::<Time>().into(), ctx.select::<ExecutionCap<U>>())
+            .with_mode(ctx.select::<MatchmakingMode>())
+            .with_arbitrage(ctx.select::<ArbitrageConf>())
+    }
+}
+
+impl<Fr, Pl, Ctx, U> Reconfigure<Ctx> for TLB<Fr, Pl, U>
+where
+    Pl: Stable,
+    Ctx: Has<ExecutionCap<U>>,
+{
+    fn reconfigure(&mut self, ctx: &Ctx) {
+        self.execution_cap = ctx.select::<ExecutionCap<U>>();
     }
 }
 
@@ -383,6 +700,7 @@ pub fn linear_output_unsafe(input: u64, price: OnSide<AbsolutePrice>) -> u64 {
 
 #[cfg(test)]
 mod tests {
+    use crate::execution_engine::liquidity_book::arbitrage::ArbitrageConf;
     use crate::execution_engine::liquidity_book::fragment::MarketTaker;
     use crate::execution_engine::liquidity_book::market_maker::MarketMaker;
     use crate::execution_engine::liquidity_book::side::Side::{Ask, Bid};
@@ -392,9 +710,12 @@ mod tests {
     use crate::execution_engine::liquidity_book::types::AbsolutePrice;
     use crate::execution_engine::liquidity_book::{
         execute_with_maker, execute_with_taker, settle_price, ExecutionCap, ExternalTLBEvents,
-        TemporalLiquidityBook, TLB,
+        MatchmakingMode, SharedExecutionCap, TLBArbitrage, TemporalLiquidityBook, TLB,
     };
     use crate::execution_engine::types::StableId;
+    use spectrum_offchain::data::Has;
+    use spectrum_offchain::maker::Reconfigure;
+    use type_equalities::IsEqual;
 
     #[test]
     fn recipe_fill_fragment_from_fragment_batch() {
@@ -459,6 +780,89 @@ mod tests {
         dbg!(recipe);
     }
 
+    #[test]
+    fn split_taker_chunk_between_makers() {
+        let ask = SimpleOrderPF::new(Ask, 200000, AbsolutePrice::new_unsafe(1, 2), 0);
+        let pool = || SimpleCFMMPool {
+            pool_id: StableId::random(),
+            reserves_base: 1000000,
+            reserves_quote: 1000000,
+            fee_num: 997,
+        };
+        let mut book = TLB::new(
+            0,
+            ExecutionCap {
+                soft: 1000000,
+                hard: 1600000,
+            },
+        );
+        book.add_fragment(ask);
+        book.update_pool(pool());
+        book.update_pool(pool());
+        let recipe = book.attempt().expect("Recipe is formed");
+        let makes = recipe.instructions.iter().filter(|i| i.is_right()).count();
+        assert_eq!(makes, 2);
+    }
+
+    #[test]
+    fn fill_crossing_takers_at_uniform_price() {
+        let mut book = TLB::new(
+            0,
+            ExecutionCap {
+                soft: 1000000,
+                hard: 1600000,
+            },
+        )
+        .with_mode(MatchmakingMode::UniformPrice {
+            anchor_to_spot: false,
+        });
+        book.add_fragment(SimpleOrderPF::new(Ask, 1000, AbsolutePrice::new_unsafe(1, 1), 0));
+        book.add_fragment(SimpleOrderPF::new(Ask, 1000, AbsolutePrice::new_unsafe(3, 2), 0));
+        book.add_fragment(SimpleOrderPF::new(Bid, 1500, AbsolutePrice::new_unsafe(2, 1), 0));
+        book.add_fragment(SimpleOrderPF::new(Bid, 1500, AbsolutePrice::new_unsafe(5, 2), 0));
+        let recipe = book.attempt().expect("Recipe is formed");
+        let takes = recipe
+            .instructions
+            .iter()
+            .filter_map(|i| i.as_ref().left())
+            .collect::<Vec<_>>();
+        assert_eq!(takes.len(), 4);
+        for take in takes {
+            assert_eq!(take.removed_input(), take.target.input);
+        }
+    }
+
+    #[test]
+    fn arbitrage_diverging_makers() {
+        let mut book = TLB::<SimpleOrderPF, _, _>::new(
+            0,
+            ExecutionCap {
+                soft: 1000000,
+                hard: 1600000,
+            },
+        )
+        .with_arbitrage(ArbitrageConf {
+            enabled: true,
+            min_profit: 1000,
+        });
+        book.update_pool(SimpleCFMMPool {
+            pool_id: StableId::random(),
+            reserves_base: 1000000,
+            reserves_quote: 2000000,
+            fee_num: 997,
+        });
+        book.update_pool(SimpleCFMMPool {
+            pool_id: StableId::random(),
+            reserves_base: 1000000,
+            reserves_quote: 1000000,
+            fee_num: 997,
+        });
+        assert!(book.attempt().is_none());
+        let recipe = book.attempt_arbitrage().expect("Arbitrage is formed");
+        assert_eq!(recipe.instructions.len(), 2);
+        assert!(recipe.instructions.iter().all(|i| i.is_right()));
+    }
+
     #[test]
     fn match_taker_with_taker() {
         // Assuming pair ADA/USDT @ 0.37
@@ -693,4 +1097,30 @@ mod tests {
         let other_fr_price = AbsolutePrice::new_unsafe(1, 1);
         assert!(rem_side.wrap(rem_price).overlaps(other_fr_price))
     }
+
+    struct SharedCapCtx(SharedExecutionCap<u64>);
+
+    impl Has<ExecutionCap<u64>> for SharedCapCtx {
+        fn select<U: IsEqual<ExecutionCap<u64>>>(&self) -> ExecutionCap<u64> {
+            self.0.get()
+        }
+    }
+
+    #[test]
+    fn reconfigure_picks_up_reloaded_execution_cap() {
+        let cap = SharedExecutionCap::new(ExecutionCap {
+            soft: 1000000,
+            hard: 1600000,
+        });
+        let ctx = SharedCapCtx(cap.clone());
+        let mut book = TLB::<SimpleOrderPF, SimpleCFMMPool, u64>::new(0, ctx.select::<ExecutionCap<u64>>());
+        cap.set(ExecutionCap {
+            soft: 2000000,
+            hard: 3200000,
+        });
+        assert_eq!(book.execution_cap.soft, 1000000);
+        book.reconfigure(&ctx);
+        assert_eq!(book.execution_cap.soft, 2000000);
+        assert_eq!(book.execution_cap.hard, 3200000);
+    }
 }
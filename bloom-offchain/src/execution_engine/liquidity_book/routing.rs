@@ -0,0 +1,287 @@
+use std::fmt::Display;
+use std::hash::Hash;
+
+use either::Either;
+use log::trace;
+
+use spectrum_offchain::maker::{Maker, Scoped};
+
+use crate::execution_engine::liquidity_book::core::{
+    MakeInProgress, MatchmakingRecipe, TakeInProgress, Trans,
+};
+use crate::execution_engine::liquidity_book::fragment::{MarketTaker, TakerBehaviour};
+use crate::execution_engine::liquidity_book::market_maker::{MakerBehavior, MarketMaker};
+use crate::execution_engine::liquidity_book::side::{OnSide, Side};
+use crate::execution_engine::liquidity_book::types::AbsolutePrice;
+use crate::execution_engine::liquidity_book::TLBRouting;
+use crate::execution_engine::multi_pair::MultiPair;
+
+/// Pair of assets which can be chained with other pairs sharing one of its assets.
+pub trait AssetPair {
+    type Asset: Copy + Eq + Hash;
+    /// Base and quote assets of the pair.
+    fn assets(&self) -> (Self::Asset, Self::Asset);
+    /// Pair the given assets are traded in.
+    fn of(x: Self::Asset, y: Self::Asset) -> Self;
+}
+
+/// Recipe matched across several books.
+#[derive(Debug, Clone)]
+pub struct RoutedRecipe<Pair, Taker, Maker> {
+    pub recipe: MatchmakingRecipe<Taker, Maker>,
+    /// Books touched by the recipe, the book of the taker goes first.
+    pub pairs: Vec<Pair>,
+}
+
+/// Taker executed through makers of two pairs chained by an intermediate asset.
+struct Route<Pair, Taker, Maker> {
+    take: TakeInProgress<Taker>,
+    hops: [(Pair, MakeInProgress<Maker>); 2],
+}
+
+impl<Pair, Book, Ctx> MultiPair<Pair, Book, Ctx>
+where
+    Pair: AssetPair + Copy + Eq + Hash + Display,
+    Ctx: Scoped<Pair> + Clone,
+{
+    /// Match a taker of the given pair with makers of other pairs, e.g. sell A for B through A/X and X/B.
+    /// Books touched by the resulting recipe are left in preview and have to be settled as a whole.
+    pub fn attempt_routed<T, M>(&mut self, pair: &Pair) -> Option<RoutedRecipe<Pair, T, M>>
+    where
+        Book: TLBRouting<T, M> + Maker<Ctx>,
+        T: MarketTaker + TakerBehaviour + Copy + Display,
+        M: MarketMaker + MakerBehavior + Copy,
+    {
+        let Route {
+            take,
+            hops: [(first_pair, first_make), (second_pair, second_make)],
+        } = self
+            .get(pair)?
+            .active_takers()
+            .into_iter()
+            .find_map(|taker| self.best_route(pair, taker))?;
+        trace!(
+            "Routing {} through {} and {}",
+            take.target,
+            first_pair,
+            second_pair
+        );
+        self.get_mut(pair).apply_take(take);
+        self.get_mut(&first_pair).apply_make(first_make);
+        self.get_mut(&second_pair).apply_make(second_make);
+        Some(RoutedRecipe {
+            recipe: MatchmakingRecipe {
+                instructions: vec![
+                    Either::Left(take),
+                    Either::Right(first_make),
+                    Either::Right(second_make),
+                ],
+            },
+            pairs: vec![*pair, first_pair, second_pair],
+        })
+    }
+
+    /// Route yielding the most output for the whole remaining input of the taker.
+    /// Realized price has to be within the limit price of the taker.
+    fn best_route<T, M>(&self, pair: &Pair, taker: T) -> Option<Route<Pair, T, M>>
+    where
+        Book: TLBRouting<T, M> + Maker<Ctx>,
+        T: MarketTaker + TakerBehaviour + Copy,
+        M: MarketMaker + MakerBehavior + Copy,
+    {
+        let (base, quote) = pair.assets();
+        let (sold, bought) = match taker.side() {
+            Side::Ask => (base, quote),
+            Side::Bid => (quote, base),
+        };
+        let input = taker.input();
+        let mut best_hops: Option<(u64, [(Pair, MakeInProgress<M>); 2])> = None;
+        for first_pair in self.pairs_with(sold) {
+            let via = match first_pair.assets() {
+                (x, y) if x == sold && y != bought => y,
+                (x, y) if y == sold && x != bought => x,
+                _ => continue,
+            };
+            let second_pair = Pair::of(via, bought);
+            let (Some(first_book), Some(second_book)) = (self.get(first_pair), self.get(&second_pair)) else {
+                continue;
+            };
+            let first_input = side_selling(first_pair, sold).wrap(input);
+            let Some((first_make, intermediate)) = first_book
+                .best_maker(first_input)
+                .and_then(|maker| swap(maker, first_input))
+            else {
+                continue;
+            };
+            let second_input = side_selling(&second_pair, via).wrap(intermediate);
+            let Some((second_make, output)) = second_book
+                .best_maker(second_input)
+                .and_then(|maker| swap(maker, second_input))
+            else {
+                continue;
+            };
+            if best_hops
+                .as_ref()
+                .map_or(true, |(best_output, _)| output > *best_output)
+            {
+                best_hops = Some((output, [(*first_pair, first_make), (second_pair, second_make)]));
+            }
+        }
+        let (output, hops) = best_hops?;
+        let realized_price = match taker.side() {
+            Side::Ask => AbsolutePrice::new(output, input),
+            Side::Bid => AbsolutePrice::new(input, output),
+        }?;
+        if output > 0
+            && output >= taker.min_marginal_output()
+            && taker.side().wrap(taker.price()).overlaps(realized_price)
+        {
+            Some(Route {
+                take: Trans::new(taker, taker.with_applied_trade(input, output)),
+                hops,
+            })
+        } else {
+            None
+        }
+    }
+}
+
+/// Side of a trade selling the given asset in the pair.
+fn side_selling<Pair: AssetPair>(pair: &Pair, asset: Pair::Asset) -> Side {
+    if pair.assets().0 == asset {
+        Side::Ask
+    } else {
+        Side::Bid
+    }
+}
+
+fn swap<M>(maker: M, input: OnSide<u64>) -> Option<(MakeInProgress<M>, u64)>
+where
+    M: MarketMaker + MakerBehavior + Copy,
+{
+    let make = Trans::new(maker, maker.swap(input));
+    let output = make.loss()?.unwrap();
+    Some((make, output))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::{Display, Formatter};
+
+    use type_equalities::IsEqual;
+
+    use spectrum_offchain::data::Has;
+    use spectrum_offchain::maker::Scoped;
+
+    use crate::execution_engine::liquidity_book::arbitrage::ArbitrageConf;
+    use crate::execution_engine::liquidity_book::routing::AssetPair;
+    use crate::execution_engine::liquidity_book::side::Side;
+    use crate::execution_engine::liquidity_book::state::tests::{SimpleCFMMPool, SimpleOrderPF};
+    use crate::execution_engine::liquidity_book::types::AbsolutePrice;
+    use crate::execution_engine::liquidity_book::{ExecutionCap, ExternalTLBEvents, MatchmakingMode, TLB};
+    use crate::execution_engine::multi_pair::MultiPair;
+    use crate::execution_engine::types::{StableId, Time};
+
+    #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+    struct Pair(u8, u8);
+
+    impl Display for Pair {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            f.write_str(&*format!("{}/{}", self.0, self.1))
+        }
+    }
+
+    impl AssetPair for Pair {
+        type Asset = u8;
+        fn assets(&self) -> (u8, u8) {
+            (self.0, self.1)
+        }
+        fn of(x: u8, y: u8) -> Self {
+            Pair(x.min(y), x.max(y))
+        }
+    }
+
+    #[derive(Copy, Clone)]
+    struct Ctx;
+
+    impl Has<Time> for Ctx {
+        fn select<U: IsEqual<Time>>(&self) -> Time {
+            0.into()
+        }
+    }
+
+    impl Has<ExecutionCap<u64>> for Ctx {
+        fn select<U: IsEqual<ExecutionCap<u64>>>(&self) -> ExecutionCap<u64> {
+            ExecutionCap {
+                soft: 1000000,
+                hard: 1600000,
+            }
+        }
+    }
+
+    impl Has<MatchmakingMode> for Ctx {
+        fn select<U: IsEqual<MatchmakingMode>>(&self) -> MatchmakingMode {
+            MatchmakingMode::Continuous
+        }
+    }
+
+    impl Has<ArbitrageConf> for Ctx {
+        fn select<U: IsEqual<ArbitrageConf>>(&self) -> ArbitrageConf {
+            ArbitrageConf::default()
+        }
+    }
+
+    impl Scoped<Pair> for Ctx {
+        fn scoped(&self, _: &Pair) -> Self {
+            *self
+        }
+    }
+
+    fn pool() -> SimpleCFMMPool {
+        SimpleCFMMPool {
+            pool_id: StableId::random(),
+            reserves_base: 1000000,
+            reserves_quote: 1000000,
+            fee_num: 997,
+        }
+    }
+
+    fn books(taker: SimpleOrderPF) -> MultiPair<Pair, TLB<SimpleOrderPF, SimpleCFMMPool, u64>, Ctx> {
+        let mut books = MultiPair::new::<TLB<SimpleOrderPF, SimpleCFMMPool, u64>>(Ctx, "Book");
+        books.get_mut(&Pair(1, 2)).add_fragment(taker);
+        books.get_mut(&Pair(0, 1)).update_pool(pool());
+        books.get_mut(&Pair(0, 2)).update_pool(pool());
+        books
+    }
+
+    #[test]
+    fn route_taker_through_intermediate_asset() {
+        let taker = SimpleOrderPF::new(Side::Ask, 1000, AbsolutePrice::new_unsafe(1, 2), 0);
+        let mut books = books(taker);
+        let routed = books.attempt_routed(&Pair(1, 2)).expect("Route exists");
+        assert_eq!(routed.pairs, vec![Pair(1, 2), Pair(0, 1), Pair(0, 2)]);
+        let takes = routed.recipe.instructions.iter().filter(|i| i.is_left()).count();
+        let makes = routed.recipe.instructions.iter().filter(|i| i.is_right()).count();
+        assert_eq!((takes, makes), (1, 2));
+    }
+
+    #[test]
+    fn respect_limit_price_of_taker() {
+        let taker = SimpleOrderPF::new(Side::Ask, 1000, AbsolutePrice::new_unsafe(2, 1), 0);
+        let mut books = books(taker);
+        assert!(books.attempt_routed(&Pair(1, 2)).is_none());
+    }
+
+    #[test]
+    fn pairs_are_indexed_by_asset() {
+        let taker = SimpleOrderPF::new(Side::Ask, 1000, AbsolutePrice::new_unsafe(1, 2), 0);
+        let mut books = books(taker);
+        let mut with_zero = books.pairs_with(0).copied().collect::<Vec<_>>();
+        with_zero.sort_by_key(|pair| pair.assets());
+        assert_eq!(with_zero, vec![Pair(0, 1), Pair(0, 2)]);
+        books.remove(&Pair(0, 1));
+        assert_eq!(books.pairs_with(0).collect::<Vec<_>>(), vec![&Pair(0, 2)]);
+        assert_eq!(books.pairs_with(1).collect::<Vec<_>>(), vec![&Pair(1, 2)]);
+        assert!(books.attempt_routed(&Pair(1, 2)).is_none());
+    }
+}
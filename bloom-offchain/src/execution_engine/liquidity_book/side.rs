@@ -4,7 +4,7 @@ use std::ops::Not;
 use derive_more::{Display, From, Into};
 
 /// Side marker.
-#[derive(Debug, Display, Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Display, Copy, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub enum Side {
     Bid,
     Ask,
@@ -17,8 +17,9 @@ use crate::execution_engine::liquidity_book::stashing_option::StashingOption;
 use crate::execution_engine::liquidity_book::state::price_range::AllowedPriceRange;
 use crate::execution_engine::liquidity_book::types::{AbsolutePrice, InputAsset};
 use crate::execution_engine::liquidity_book::weight::Weighted;
+use crate::execution_engine::liquidity_book::{BookDepth, BookSnapshot};
 
-mod price_range;
+pub mod price_range;
 pub mod queries;
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -332,6 +333,21 @@ where
     T: MarketTaker + Ord + Copy,
     M: Stable + Copy,
 {
+    pub fn depth(&self) -> BookDepth {
+        let takers = self.active_fragments();
+        BookDepth {
+            asks: takers.asks.len(),
+            bids: takers.bids.len(),
+            makers: self.pools().values.len(),
+        }
+    }
+
+    /// Copies of active takers, asks go first.
+    pub fn active_takers(&self) -> Vec<T> {
+        let takers = self.active_fragments();
+        takers.asks.iter().chain(&takers.bids).copied().collect()
+    }
+
     pub fn commit(&mut self) {
         match self {
             TLBState::PartialPreview(st) => {
@@ -433,6 +449,41 @@ where
         format!("Fragments(active): {}, Pools: {}", fragments, pools)
     }
 
+    /// Copy of the committed state of the book, i.e. without uncommitted previews.
+    pub fn snapshot(&self) -> BookSnapshot<T, M> {
+        let (takers, takers_in_preview, makers) = match self {
+            TLBState::Idle(st) => (&st.takers, vec![], &st.makers),
+            TLBState::PartialPreview(st) => (
+                &st.takers_preview,
+                st.consumed_active_takers
+                    .iter()
+                    .chain(&st.stashed_active_takers)
+                    .copied()
+                    .collect(),
+                &st.makers_intact,
+            ),
+            TLBState::Preview(st) => (&st.takers_intact, vec![], &st.makers_intact),
+        };
+        BookSnapshot {
+            active_takers: takers
+                .active
+                .asks
+                .iter()
+                .chain(&takers.active.bids)
+                .copied()
+                .chain(takers_in_preview)
+                .collect(),
+            inactive_takers: takers
+                .inactive
+                .values()
+                .flat_map(|tks| tks.asks.iter().chain(&tks.bids))
+                .copied()
+                .collect(),
+            makers: makers.values.values().copied().collect(),
+            allowed_price_range: self.allowed_price_range(),
+        }
+    }
+
     pub fn allowed_price_range(&self) -> AllowedPriceRange {
         match self {
             TLBState::Idle(_) => AllowedPriceRange::default(),
@@ -492,6 +543,15 @@ where
         self.pick_active_taker(|af| try_pick_fr(af, side, test))
     }
 
+    /// Pick exactly the given active fragment.
+    pub fn pick_exact_taker(&mut self, fr: &T) -> Option<T> {
+        trace!(target: "state", "pick_exact_taker");
+        self.pick_active_taker(|af| match fr.side() {
+            Side::Bid => af.bids.take(fr),
+            Side::Ask => af.asks.take(fr),
+        })
+    }
+
     /// Add preview fragment [T].
     pub fn pre_add_taker(&mut self, fr: T) {
         trace!(target: "state", "pre_add_fragment");
@@ -619,6 +679,28 @@ where
         }
     }
 
+    /// Pools available for matchmaking at the moment.
+    pub fn active_makers(&self) -> Vec<M>
+    where
+        M: MarketMaker,
+    {
+        self.pools()
+            .values
+            .values()
+            .filter(|pool| pool.is_active())
+            .copied()
+            .collect()
+    }
+
+    /// Active pool offering the best real price for the given input.
+    pub fn best_maker_for(&self, offered_amount: OnSide<InputAsset<u64>>) -> Option<M>
+    where
+        M: MarketMaker,
+    {
+        self.preselect_market_maker(offered_amount)
+            .and_then(|(pid, _)| self.pools().values.get(&pid).copied())
+    }
+
     pub fn try_select_pool(&self, trade_hint: OnSide<u64>) -> Option<(AbsolutePrice, SpotPrice, M::StableId)>
     where
         M: MarketMaker,
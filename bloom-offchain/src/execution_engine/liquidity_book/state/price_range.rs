@@ -3,7 +3,7 @@ use crate::execution_engine::liquidity_book::fragment::MarketTaker;
 use crate::execution_engine::liquidity_book::types::AbsolutePrice;
 use std::fmt::{Display, Formatter};
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, serde::Serialize)]
 pub struct AllowedPriceRange {
     pub max_ask_price: Option<AbsolutePrice>,
     pub min_bid_price: Option<AbsolutePrice>,
@@ -21,7 +21,9 @@ pub type FeeAsset<T> = T;
 
 /// Price of base asset denominated in units of quote asset (Quote/Base).
 #[repr(transparent)]
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Div, Mul, Sub, Add, From, Into)]
+#[derive(
+    Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Div, Mul, Sub, Add, From, Into, serde::Serialize,
+)]
 pub struct AbsolutePrice(Ratio<u128>);
 
 impl AbsolutePrice {
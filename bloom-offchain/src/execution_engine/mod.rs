@@ -1,9 +1,12 @@
-use std::collections::HashSet;
+use std::collections::{HashSet, VecDeque};
 use std::fmt::{Debug, Display};
 use std::hash::Hash;
 use std::marker::PhantomData;
+use std::mem;
+use std::num::NonZeroUsize;
 use std::pin::Pin;
 use std::task::{Context, Poll};
+use std::time::Instant;
 
 use either::Either;
 use futures::channel::mpsc;
@@ -18,24 +21,34 @@ use crate::execution_engine::bundled::Bundled;
 use crate::execution_engine::execution_effect::ExecutionEff;
 use crate::execution_engine::focus_set::FocusSet;
 use crate::execution_engine::liquidity_book::core::ExecutionRecipe;
-use crate::execution_engine::liquidity_book::fragment::MarketTaker;
-use crate::execution_engine::liquidity_book::{ExternalTLBEvents, TLBFeedback, TemporalLiquidityBook};
+use crate::execution_engine::liquidity_book::fragment::{MarketTaker, TakerBehaviour};
+use crate::execution_engine::liquidity_book::market_maker::{MakerBehavior, MarketMaker};
+use crate::execution_engine::liquidity_book::routing::{AssetPair, RoutedRecipe};
+use crate::execution_engine::liquidity_book::stashing_option::StashingOption;
+use crate::execution_engine::liquidity_book::{
+    ExternalTLBEvents, TLBArbitrage, TLBDepth, TLBFeedback, TLBRouting, TLBSnapshot, TemporalLiquidityBook,
+};
 use crate::execution_engine::multi_pair::MultiPair;
+use crate::execution_engine::quarantine::{Quarantine, QuarantineConfig, Verdict};
 use crate::execution_engine::resolver::resolve_source_state;
+use crate::execution_engine::snapshots::{PairSnapshot, Snapshots};
 use crate::execution_engine::storage::kv_store::KvStore;
-use crate::execution_engine::storage::StateIndex;
-use liquidity_book::interpreter::RecipeInterpreter;
-use liquidity_book::stashing_option::StashingOption;
+use crate::execution_engine::storage::{DeliveredPoint, StateIndex};
+use crate::execution_engine::types::Time;
+use crate::metrics;
+use liquidity_book::interpreter::{RecipeInterpreter, RecipeRejection};
 use spectrum_offchain::backlog::HotBacklog;
 use spectrum_offchain::circular_filter::CircularFilter;
 use spectrum_offchain::combinators::Ior;
 use spectrum_offchain::data::event::{Channel, Confirmed, Predicted, StateUpdate, Unconfirmed};
 use spectrum_offchain::data::order::{OrderUpdate, SpecializedOrder};
-use spectrum_offchain::data::{Baked, EntitySnapshot, Stable};
-use spectrum_offchain::maker::Maker;
+use spectrum_offchain::data::{Baked, EntitySnapshot, Has, Stable, Tradable};
+use spectrum_offchain::executor::{AttributeRejection, TxSubmissionError};
+use spectrum_offchain::maker::{Maker, Reconfigure, Scoped};
 use spectrum_offchain::network::Network;
 use spectrum_offchain::tx_hash::CanonicalHash;
 use spectrum_offchain::tx_prover::TxProver;
+use spectrum_offchain::tx_validator::{Remedy, TxValidator};
 
 pub mod backlog;
 pub mod batch_exec;
@@ -45,7 +58,9 @@ mod focus_set;
 pub mod liquidity_book;
 pub mod multi_pair;
 pub mod partial_fill;
+pub mod quarantine;
 pub mod resolver;
+pub mod snapshots;
 pub mod storage;
 pub mod types;
 
@@ -64,18 +79,45 @@ enum PendingEffects<CompOrd, SpecOrd, Pool, Ver, Bearer> {
             >,
         >,
     ),
-    FromBacklog(Bundled<Baked<Pool, Ver>, Bearer>, Bundled<SpecOrd, Bearer>),
+    /// Consumed pool, updated pool and consumed order.
+    FromBacklog(
+        EvolvingEntity<CompOrd, Pool, Ver, Bearer>,
+        Bundled<Baked<Pool, Ver>, Bearer>,
+        Bundled<SpecOrd, Bearer>,
+    ),
+}
+
+impl<CompOrd, SpecOrd, Pool, Ver, Bearer: Clone> PendingEffects<CompOrd, SpecOrd, Pool, Ver, Bearer> {
+    /// Outputs spent by the TX.
+    fn spent_bearers(&self) -> Vec<Bearer> {
+        match self {
+            PendingEffects::FromLiquidityBook(effects) => effects
+                .iter()
+                .map(|eff| match eff {
+                    ExecutionEff::Updated(Bundled(_, bearer), _)
+                    | ExecutionEff::Eliminated(Bundled(_, bearer)) => bearer.clone(),
+                })
+                .collect(),
+            PendingEffects::FromBacklog(Bundled(_, pool_bearer), _, Bundled(_, order_bearer)) => {
+                vec![pool_bearer.clone(), order_bearer.clone()]
+            }
+        }
+    }
 }
 
 struct PendingEffectsByPair<Pair, TxHash, CompOrd, SpecOrd, Pool, Ver, Bearer> {
     pair: Pair,
     tx_hash: TxHash,
     consumed_versions: HashSet<Ver>,
+    /// Versions of predicted states produced by the TX.
+    /// Subsequent TXs may consume them before this one is settled.
+    produced_versions: HashSet<Ver>,
     pending_effects: PendingEffects<CompOrd, SpecOrd, Pool, Ver, Bearer>,
 }
 
 /// Instantiate execution stream partition.
 /// Each partition serves total_pairs/num_partitions pairs.
+/// Up to `max_in_flight_txs` TXs are submitted without waiting for the results of preceding ones.
 pub fn execution_part_stream<
     'a,
     Upstream,
@@ -98,6 +140,7 @@ pub fn execution_part_stream<
     RecInterpreter,
     SpecInterpreter,
     Prover,
+    Validator,
     Net,
     Err,
 >(
@@ -105,41 +148,69 @@ pub fn execution_part_stream<
     cache: Cache,
     book: MultiPair<Pair, Book, Ctx>,
     backlog: MultiPair<Pair, Backlog, Ctx>,
+    snapshots: Snapshots<Pair, CompOrd, Pool, Bundled<SpecOrd, Bearer>>,
     context: Ctx,
     rec_interpreter: RecInterpreter,
     spec_interpreter: SpecInterpreter,
     prover: Prover,
+    validator: Validator,
     upstream: Upstream,
     network: Net,
+    max_in_flight_txs: NonZeroUsize,
+    quarantine_conf: QuarantineConfig,
+    delivered: DeliveredPoint,
     mut tip_reached_signal: broadcast::Receiver<bool>,
 ) -> impl Stream<Item = ()> + 'a
 where
     Upstream: Stream<Item = (Pair, Event<CompOrd, SpecOrd, Pool, Bearer, Ver>)> + Unpin + 'a,
-    Pair: Copy + Eq + Ord + Hash + Display + Unpin + 'a,
+    Pair: AssetPair + Copy + Eq + Ord + Hash + Display + Unpin + 'a,
     StableId: Copy + Eq + Hash + Debug + Display + Unpin + 'a,
     Ver: Copy + Eq + Hash + Display + Unpin + 'a,
-    Pool: Stable<StableId = StableId> + Copy + Debug + Unpin + Display + 'a,
-    CompOrd: Stable<StableId = StableId> + MarketTaker<U = ExUnits> + Copy + Debug + Unpin + Display + 'a,
+    Pool: Stable<StableId = StableId>
+        + Tradable<PairId = Pair>
+        + MarketMaker
+        + MakerBehavior
+        + Copy
+        + Debug
+        + Unpin
+        + Display
+        + 'a,
+    CompOrd: Stable<StableId = StableId>
+        + Tradable<PairId = Pair>
+        + MarketTaker<U = ExUnits>
+        + TakerBehaviour
+        + Copy
+        + Debug
+        + Unpin
+        + Display
+        + 'a,
     SpecOrd: SpecializedOrder<TPoolId = StableId, TOrderId = Ver> + Debug + Unpin + 'a,
     Bearer: Clone + Unpin + Debug + 'a,
     TxCandidate: Unpin + 'a,
     Tx: CanonicalHash<Hash = TxHash> + Unpin + 'a,
-    TxHash: Display + Unpin + 'a,
-    Ctx: Clone + Unpin + 'a,
+    TxHash: Copy + Eq + Display + Unpin + 'a,
+    Ctx: Scoped<Pair> + Has<Time> + Clone + Unpin + 'a,
     Index: StateIndex<EvolvingEntity<CompOrd, Pool, Ver, Bearer>> + Unpin + 'a,
     Cache: KvStore<StableId, EvolvingEntity<CompOrd, Pool, Ver, Bearer>> + Unpin + 'a,
     Book: TemporalLiquidityBook<CompOrd, Pool>
         + ExternalTLBEvents<CompOrd, Pool>
+        + TLBDepth
+        + TLBSnapshot<CompOrd, Pool>
         + TLBFeedback<CompOrd, Pool>
+        + TLBRouting<CompOrd, Pool>
+        + TLBArbitrage<CompOrd, Pool>
         + Maker<Ctx>
+        + Reconfigure<Ctx>
         + Unpin
         + 'a,
     Backlog: HotBacklog<Bundled<SpecOrd, Bearer>> + Maker<Ctx> + Unpin + 'a,
     RecInterpreter: RecipeInterpreter<CompOrd, Pool, Ctx, Ver, Bearer, TxCandidate> + Unpin + 'a,
     SpecInterpreter: SpecializedInterpreter<Pool, SpecOrd, Ver, TxCandidate, Bearer, Ctx> + Unpin + 'a,
     Prover: TxProver<TxCandidate, Tx> + Unpin + 'a,
+    Validator: TxValidator<Tx, Bearer, Ctx> + Unpin + 'a,
+    Validator::Error: Into<Remedy<Ver>> + Display,
     Net: Network<Tx, Err> + Clone + 'a,
-    Err: TryInto<HashSet<Ver>> + Unpin + Debug + Display + 'a,
+    Err: AttributeRejection<Bearer, Ver> + Unpin + Debug + Display + 'a,
 {
     let (feedback_out, feedback_in) = mpsc::channel(100);
     let executor = Executor::new(
@@ -147,26 +218,43 @@ where
         cache,
         book,
         backlog,
+        snapshots,
         context,
         rec_interpreter,
         spec_interpreter,
         prover,
+        validator,
         upstream,
         feedback_in,
+        max_in_flight_txs,
+        quarantine_conf,
+        delivered,
     );
     let wait_signal = async move {
         let _ = tip_reached_signal.recv().await;
     };
     wait_signal
         .map(move |_| {
-            executor.then(move |tx| {
-                let mut network = network.clone();
-                let mut feedback = feedback_out.clone();
-                async move {
-                    let result = network.submit_tx(tx).await;
-                    feedback.send(result).await.expect("Filed to propagate feedback.");
-                }
-            })
+            executor
+                .map(move |tx| {
+                    let mut network = network.clone();
+                    async move {
+                        let tx_hash = tx.canonical_hash();
+                        let result = network.submit_tx(tx).await;
+                        (tx_hash, result)
+                    }
+                })
+                // Submissions are started in the order TXs are produced by the executor.
+                .buffered(max_in_flight_txs.get())
+                .then(move |feedback_item| {
+                    let mut feedback = feedback_out.clone();
+                    async move {
+                        feedback
+                            .send(feedback_item)
+                            .await
+                            .expect("Filed to propagate feedback.");
+                    }
+                })
         })
         .flatten_stream()
 }
@@ -191,8 +279,11 @@ pub struct Executor<
     TradeInterpreter,
     SpecInterpreter,
     Prover,
+    Validator,
     Err,
-> {
+> where
+    Pair: AssetPair,
+{
     /// Storage for all on-chain states.
     index: Index,
     /// Hot storage for resolved states.
@@ -201,51 +292,84 @@ pub struct Executor<
     multi_book: MultiPair<Pair, Book, Ctx>,
     /// Separate Backlogs for each pair (for specialized operations such as Deposit/Redeem)
     multi_backlog: MultiPair<Pair, Backlog, Ctx>,
+    /// Snapshots of books and backlogs published for external readers.
+    snapshots: Snapshots<Pair, CompOrd, Pool, Bundled<SpecOrd, Bearer>>,
+    /// Pairs whose published snapshots are outdated.
+    stale_snapshots: HashSet<Pair>,
     context: Ctx,
     trade_interpreter: TradeInterpreter,
     spec_interpreter: SpecInterpreter,
     prover: Prover,
+    /// Local checks TXs have to pass before submission.
+    validator: Validator,
     upstream: Upstream,
     /// Feedback channel is used to signal the status of transaction submitted earlier by the executor.
-    feedback: mpsc::Receiver<Result<(), Err>>,
-    /// Pending effects resulted from execution of a batch trade in a certain [Pair].
-    pending_effects: Option<PendingEffectsByPair<Pair, TxHash, CompOrd, SpecOrd, Pool, Ver, Bearer>>,
+    feedback: mpsc::Receiver<(TxHash, Result<(), Err>)>,
+    /// Pending effects of submitted TXs in the order of submission.
+    in_flight: VecDeque<PendingEffectsByPair<Pair, TxHash, CompOrd, SpecOrd, Pool, Ver, Bearer>>,
+    /// Max number of TXs awaiting feedback.
+    max_in_flight: NonZeroUsize,
     /// Which pair should we process in the first place.
     focus_set: FocusSet<Pair>,
     /// Temporarily memoize entities that came from unconfirmed updates.
     skip_filter: CircularFilter<256, Ver>,
+    /// Entities excluded from execution after causing TXs to fail.
+    quarantine: Quarantine<Pair, StableId, EvolvingEntity<CompOrd, Pool, Ver, Bearer>>,
+    /// Point up to which ledger updates have been delivered to the upstream.
+    delivered: DeliveredPoint,
     pd: PhantomData<(StableId, Ver, TxCandidate, Tx, Err)>,
 }
 
-impl<S, PR, SID, V, CO, SO, P, B, TC, TX, TH, C, IX, CH, TLB, L, RIR, SIR, PRV, E>
-    Executor<S, PR, SID, V, CO, SO, P, B, TC, TX, TH, C, IX, CH, TLB, L, RIR, SIR, PRV, E>
+impl<S, PR, SID, V, CO, SO, P, B, TC, TX, TH, C, IX, CH, TLB, L, RIR, SIR, PRV, VAL, E>
+    Executor<S, PR, SID, V, CO, SO, P, B, TC, TX, TH, C, IX, CH, TLB, L, RIR, SIR, PRV, VAL, E>
+where
+    PR: AssetPair,
 {
     fn new(
         index: IX,
         cache: CH,
         multi_book: MultiPair<PR, TLB, C>,
         multi_backlog: MultiPair<PR, L, C>,
+        snapshots: Snapshots<PR, CO, P, Bundled<SO, B>>,
         context: C,
         trade_interpreter: RIR,
         spec_interpreter: SIR,
         prover: PRV,
+        validator: VAL,
         upstream: S,
-        feedback: mpsc::Receiver<Result<(), E>>,
-    ) -> Self {
+        feedback: mpsc::Receiver<(TH, Result<(), E>)>,
+        max_in_flight: NonZeroUsize,
+        quarantine_conf: QuarantineConfig,
+        delivered: DeliveredPoint,
+    ) -> Self
+    where
+        SID: Copy + Eq + Hash + Debug + Display,
+        V: Copy + Eq + Hash + Display,
+        CO: Stable<StableId = SID>,
+        P: Stable<StableId = SID>,
+        IX: StateIndex<EvolvingEntity<CO, P, V, B>>,
+    {
+        let quarantine = Quarantine::with_blacklist(quarantine_conf, index.get_blacklisted());
         Self {
             index,
             cache,
             multi_book,
             multi_backlog,
+            snapshots,
+            stale_snapshots: HashSet::new(),
             context,
             trade_interpreter,
             spec_interpreter,
             prover,
+            validator,
             upstream,
             feedback,
-            pending_effects: None,
+            in_flight: VecDeque::new(),
+            max_in_flight,
             focus_set: FocusSet::new(),
             skip_filter: CircularFilter::new(),
+            quarantine,
+            delivered,
             pd: Default::default(),
         }
     }
@@ -256,7 +380,7 @@ impl<S, PR, SID, V, CO, SO, P, B, TC, TX, TH, C, IX, CH, TLB, L, RIR, SIR, PRV,
         V: Copy + Eq + Hash + Display,
         SO: SpecializedOrder<TOrderId = V>,
         L: HotBacklog<Bundled<SO, B>> + Maker<C>,
-        C: Clone,
+        C: Scoped<PR> + Clone,
     {
         let is_confirmed = matches!(update, Channel::Ledger(_));
         let (Channel::Ledger(Confirmed(upd))
@@ -289,12 +413,12 @@ impl<S, PR, SID, V, CO, SO, P, B, TC, TX, TH, C, IX, CH, TLB, L, RIR, SIR, PRV,
         SID: Copy + Eq + Hash + Debug + Display,
         V: Copy + Eq + Hash + Display,
         B: Clone + Debug,
-        C: Clone,
+        C: Scoped<PR> + Clone,
         CO: Stable<StableId = SID> + Clone + Debug,
         P: Stable<StableId = SID> + Clone + Debug,
         IX: StateIndex<EvolvingEntity<CO, P, V, B>>,
         CH: KvStore<SID, EvolvingEntity<CO, P, V, B>>,
-        TLB: ExternalTLBEvents<CO, P> + Maker<C>,
+        TLB: ExternalTLBEvents<CO, P> + TLBDepth + Maker<C>,
     {
         trace!(target: "executor", "syncing book pair: {}", pair);
         match transition {
@@ -305,18 +429,32 @@ impl<S, PR, SID, V, CO, SO, P, B, TC, TX, TH, C, IX, CH, TLB, L, RIR, SIR, PRV,
             Ior::Both(old, new) => match (old, new) {
                 (Either::Left(old), Either::Left(new)) => {
                     self.multi_book.get_mut(pair).remove_fragment(old.entity);
-                    self.multi_book.get_mut(pair).add_fragment(new.entity);
+                    if !self.quarantine.is_excluded(&new.entity.stable_id()) {
+                        self.multi_book.get_mut(pair).add_fragment(new.entity);
+                    }
                 }
                 (_, Either::Right(new)) => {
-                    self.multi_book.get_mut(pair).update_pool(new.entity);
+                    if self.quarantine.is_excluded(&new.entity.stable_id()) {
+                        self.multi_book.get_mut(pair).remove_pool(new.entity);
+                    } else {
+                        self.multi_book.get_mut(pair).update_pool(new.entity);
+                    }
                 }
                 _ => unreachable!(),
             },
-            Ior::Right(new) => match new {
-                Either::Left(new) => self.multi_book.get_mut(pair).add_fragment(new.entity),
-                Either::Right(new) => self.multi_book.get_mut(pair).update_pool(new.entity),
-            },
+            Ior::Right(new) => {
+                if !self.quarantine.is_excluded(
+                    &new.as_ref()
+                        .either(|o| o.entity.stable_id(), |p| p.entity.stable_id()),
+                ) {
+                    match new {
+                        Either::Left(new) => self.multi_book.get_mut(pair).add_fragment(new.entity),
+                        Either::Right(new) => self.multi_book.get_mut(pair).update_pool(new.entity),
+                    }
+                }
+            }
         }
+        metrics::on_book_synced(&pair.to_string(), self.multi_book.get_mut(pair).depth());
     }
 
     fn cache<T>(&mut self, new_entity_state: Bundled<T, B>) -> Option<Ior<T, T>>
@@ -337,34 +475,395 @@ impl<S, PR, SID, V, CO, SO, P, B, TC, TX, TH, C, IX, CH, TLB, L, RIR, SIR, PRV,
         }
     }
 
-    fn invalidate_versions(&mut self, pair: &PR, versions: HashSet<V>)
+    fn invalidate_versions(&mut self, versions: HashSet<V>)
     where
         PR: Copy + Eq + Hash + Display,
         SID: Copy + Eq + Hash + Debug + Display,
         V: Copy + Eq + Hash + Display,
         B: Clone + Debug,
-        C: Clone,
-        CO: Stable<StableId = SID> + Clone + Debug + Display,
-        P: Stable<StableId = SID> + Clone + Debug,
+        C: Scoped<PR> + Clone,
+        CO: Stable<StableId = SID> + Tradable<PairId = PR> + Clone + Debug + Display,
+        P: Stable<StableId = SID> + Tradable<PairId = PR> + Clone + Debug,
         IX: StateIndex<EvolvingEntity<CO, P, V, B>>,
         CH: KvStore<SID, EvolvingEntity<CO, P, V, B>>,
-        TLB: ExternalTLBEvents<CO, P> + Maker<C>,
+        TLB: ExternalTLBEvents<CO, P> + TLBDepth + Maker<C>,
     {
         for ver in versions {
             if let Some(stable_id) = self.index.invalidate_version(ver) {
                 trace!("Invalidating snapshot {} of {}", ver, stable_id);
-                let maybe_transition = match resolve_source_state(stable_id, &self.index) {
-                    None => self
-                        .cache
-                        .remove(stable_id)
-                        .map(|Bundled(elim_state, _)| Ior::Left(elim_state)),
-                    Some(latest_state) => self.cache(latest_state),
-                };
-                if let Some(tr) = maybe_transition {
-                    trace!("Resulting transition is {}", tr);
-                    self.sync_book(pair, tr);
+                self.resync(stable_id);
+            }
+        }
+    }
+
+    /// Bring cached state of the entity and its book in line with the index.
+    fn resync(&mut self, stable_id: SID)
+    where
+        PR: Copy + Eq + Hash + Display,
+        SID: Copy + Eq + Hash + Debug + Display,
+        V: Copy + Eq + Hash + Display,
+        B: Clone + Debug,
+        C: Scoped<PR> + Clone,
+        CO: Stable<StableId = SID> + Tradable<PairId = PR> + Clone + Debug + Display,
+        P: Stable<StableId = SID> + Tradable<PairId = PR> + Clone + Debug,
+        IX: StateIndex<EvolvingEntity<CO, P, V, B>>,
+        CH: KvStore<SID, EvolvingEntity<CO, P, V, B>>,
+        TLB: ExternalTLBEvents<CO, P> + TLBDepth + Maker<C>,
+    {
+        let maybe_transition = match resolve_source_state(stable_id, &self.index) {
+            None => self
+                .cache
+                .remove(stable_id)
+                .map(|Bundled(elim_state, _)| Ior::Left(elim_state)),
+            Some(latest_state) => self.cache(latest_state),
+        };
+        if let Some(tr) = maybe_transition {
+            trace!("Resulting transition is {}", tr);
+            let (Ior::Left(st) | Ior::Right(st) | Ior::Both(st, _)) = &tr;
+            let pair = st.pair_id();
+            self.sync_book(&pair, tr);
+        }
+    }
+
+    /// Discard predicted state `produced` and fall back to the `prior` one.
+    fn revert_prediction(&mut self, prior: EvolvingEntity<CO, P, V, B>, produced: V)
+    where
+        PR: Copy + Eq + Hash + Display,
+        SID: Copy + Eq + Hash + Debug + Display,
+        V: Copy + Eq + Hash + Display,
+        B: Clone + Debug,
+        C: Scoped<PR> + Clone,
+        CO: Stable<StableId = SID> + Tradable<PairId = PR> + Clone + Debug + Display,
+        P: Stable<StableId = SID> + Tradable<PairId = PR> + Clone + Debug,
+        IX: StateIndex<EvolvingEntity<CO, P, V, B>>,
+        CH: KvStore<SID, EvolvingEntity<CO, P, V, B>>,
+        TLB: ExternalTLBEvents<CO, P> + TLBDepth + Maker<C>,
+    {
+        let stable_id = prior.stable_id();
+        self.index.invalidate_version(produced);
+        // Prior state is live again, updates carrying it must not be skipped anymore.
+        self.skip_filter.remove(&prior.version());
+        // Prior state produced by a TX which is still in flight has to be predicted again,
+        // as the index keeps track of the latest prediction only.
+        let prior_ver = prior.version();
+        if self
+            .in_flight
+            .iter()
+            .any(|tx| tx.produced_versions.contains(&prior_ver))
+        {
+            self.index.put_predicted(Predicted(prior));
+        }
+        self.resync(stable_id);
+    }
+
+    /// Exclude the entity which caused a TX to fail from execution for a while.
+    /// The book must be settled.
+    fn put_in_quarantine(&mut self, entity: Either<Baked<CO, V>, Baked<P, V>>)
+    where
+        PR: Copy + Eq + Hash + Display,
+        SID: Copy + Eq + Hash + Debug + Display,
+        V: Copy + Eq + Hash + Display,
+        CO: Stable<StableId = SID> + Tradable<PairId = PR>,
+        P: Stable<StableId = SID> + Tradable<PairId = PR>,
+        IX: StateIndex<EvolvingEntity<CO, P, V, B>>,
+        TLB: ExternalTLBEvents<CO, P>,
+    {
+        let pair = entity.pair_id();
+        let stable_id = entity
+            .as_ref()
+            .either(|tk| tk.entity.stable_id(), |mk| mk.entity.stable_id());
+        match self.quarantine.strike(pair, stable_id, Instant::now()) {
+            Verdict::Quarantined { until } => warn!(
+                "Quarantining {} for {}s",
+                stable_id,
+                until.saturating_duration_since(Instant::now()).as_secs()
+            ),
+            Verdict::Blacklisted => {
+                warn!("Blacklisting {}", stable_id);
+                self.index.put_blacklisted(stable_id);
+            }
+        }
+        match entity {
+            Either::Left(taker) => self.multi_book.get_mut(&pair).remove_fragment(taker.entity),
+            Either::Right(maker) => self.multi_book.get_mut(&pair).remove_pool(maker.entity),
+        }
+    }
+
+    /// Commit the recipe in all books it was matched in.
+    fn commit_books(&mut self, pairs: &[PR])
+    where
+        PR: Copy + Eq + Hash + Display,
+        C: Scoped<PR> + Clone,
+        TLB: TLBFeedback<CO, P> + Maker<C>,
+    {
+        for pair in pairs {
+            self.multi_book.get_mut(pair).on_recipe_succeeded();
+        }
+    }
+
+    /// Roll back the recipe in all books it was matched in.
+    /// Takers to stash belong to the first book.
+    fn rollback_books(&mut self, pairs: &[PR], takers_to_stash: Vec<CO>)
+    where
+        PR: Copy + Eq + Hash + Display,
+        C: Scoped<PR> + Clone,
+        TLB: TLBFeedback<CO, P> + Maker<C>,
+    {
+        if let Some((taker_pair, other_pairs)) = pairs.split_first() {
+            self.multi_book
+                .get_mut(taker_pair)
+                .on_recipe_failed(StashingOption::Stash(takers_to_stash));
+            for pair in other_pairs {
+                self.multi_book
+                    .get_mut(pair)
+                    .on_recipe_failed(StashingOption::Stash(vec![]));
+            }
+        }
+    }
+
+    /// Roll back the recipe whose TX didn't pass validation and put the blame on its participants.
+    fn on_recipe_invalid(
+        &mut self,
+        pairs: Vec<PR>,
+        effects: Vec<ExecutionEff<EvolvingEntity<CO, P, V, B>, EvolvingEntity<CO, P, V, B>>>,
+        remedy: Remedy<V>,
+    ) where
+        PR: Copy + Eq + Hash + Display,
+        SID: Copy + Eq + Hash + Debug + Display,
+        V: Copy + Eq + Hash + Display,
+        B: Clone + Debug,
+        C: Scoped<PR> + Clone,
+        CO: Stable<StableId = SID> + Tradable<PairId = PR> + Clone + Debug + Display,
+        P: Stable<StableId = SID> + Tradable<PairId = PR> + Clone + Debug,
+        IX: StateIndex<EvolvingEntity<CO, P, V, B>>,
+        CH: KvStore<SID, EvolvingEntity<CO, P, V, B>>,
+        TLB: ExternalTLBEvents<CO, P> + TLBFeedback<CO, P> + TLBDepth + Maker<C>,
+    {
+        let mut participants = effects.into_iter().map(|eff| match eff {
+            ExecutionEff::Updated(Bundled(consumed, _), produced) => (consumed, Some(produced.version())),
+            ExecutionEff::Eliminated(Bundled(consumed, _)) => (consumed, None),
+        });
+        let blamed: Vec<Either<Baked<CO, V>, Baked<P, V>>> = match &remedy {
+            Remedy::Exclude(culprits) => participants
+                .filter(|(consumed, produced)| {
+                    culprits.contains(&consumed.as_ref().either(|tk| tk.version, |mk| mk.version))
+                        || produced.map_or(false, |ver| culprits.contains(&ver))
+                })
+                .map(|(consumed, _)| consumed)
+                .collect(),
+            // Takers are excluded one by one until the TX fits.
+            Remedy::Shrink => participants
+                .rfind(|(consumed, _)| consumed.is_left())
+                .map(|(consumed, _)| consumed)
+                .into_iter()
+                .collect(),
+            Remedy::RetryLater => vec![],
+        };
+        let retry_now = !blamed.is_empty();
+        if let Remedy::Exclude(_) = remedy {
+            // Excluded participants are faulty, they are kept out of the book for a while.
+            self.rollback_books(&pairs, vec![]);
+            for participant in blamed {
+                self.put_in_quarantine(participant);
+            }
+        } else {
+            let takers_to_stash = blamed
+                .into_iter()
+                .filter_map(|participant| participant.left().map(|taker| taker.entity))
+                .collect();
+            self.rollback_books(&pairs, takers_to_stash);
+        }
+        if let (true, Some(taker_pair)) = (retry_now, pairs.first()) {
+            self.focus_set.push_back(*taker_pair);
+        }
+    }
+
+    /// Deal with backlog order whose TX didn't pass validation.
+    fn on_backlog_tx_invalid(
+        &mut self,
+        pair: PR,
+        pool_version: V,
+        updated_pool_version: V,
+        order: Bundled<SO, B>,
+        remedy: Remedy<V>,
+    ) where
+        PR: Copy + Eq + Hash + Display,
+        SID: Copy + Eq + Hash + Debug + Display,
+        V: Copy + Eq + Hash + Display,
+        B: Clone + Debug,
+        C: Scoped<PR> + Clone,
+        CO: Stable<StableId = SID> + Tradable<PairId = PR> + Clone + Debug + Display,
+        P: Stable<StableId = SID> + Tradable<PairId = PR> + Clone + Debug,
+        IX: StateIndex<EvolvingEntity<CO, P, V, B>>,
+        CH: KvStore<SID, EvolvingEntity<CO, P, V, B>>,
+        TLB: ExternalTLBEvents<CO, P> + TLBDepth + Maker<C>,
+        SO: SpecializedOrder<TOrderId = V>,
+        L: HotBacklog<Bundled<SO, B>> + Maker<C>,
+    {
+        match remedy {
+            Remedy::Exclude(culprits)
+                if culprits.contains(&pool_version) || culprits.contains(&updated_pool_version) =>
+            {
+                self.invalidate_versions(HashSet::from([pool_version]));
+                self.multi_backlog.get_mut(&pair).put(order);
+            }
+            // Any other output of the TX belongs to the order.
+            Remedy::Exclude(_) | Remedy::Shrink => {
+                warn!("Dropping order {}", order.get_self_ref());
+            }
+            Remedy::RetryLater => self.multi_backlog.get_mut(&pair).put(order),
+        }
+    }
+
+    /// Detach the given TX along with all in-flight TXs depending on its outputs.
+    /// TXs are returned in reverse order of submission.
+    fn take_dependent_chain(
+        &mut self,
+        failed_tx: PendingEffectsByPair<PR, TH, CO, SO, P, V, B>,
+    ) -> Vec<PendingEffectsByPair<PR, TH, CO, SO, P, V, B>>
+    where
+        V: Copy + Eq + Hash,
+    {
+        let mut tainted_versions = failed_tx.produced_versions.clone();
+        let mut chain = vec![failed_tx];
+        let mut independent = VecDeque::new();
+        while let Some(tx) = self.in_flight.pop_front() {
+            if tx
+                .consumed_versions
+                .iter()
+                .any(|ver| tainted_versions.contains(ver))
+            {
+                tainted_versions.extend(tx.produced_versions.iter().copied());
+                chain.push(tx);
+            } else {
+                independent.push_back(tx);
+            }
+        }
+        self.in_flight = independent;
+        chain.reverse();
+        chain
+    }
+
+    fn on_tx_succeeded(&mut self, settled_tx: PendingEffectsByPair<PR, TH, CO, SO, P, V, B>)
+    where
+        SID: Copy + Eq + Hash + Display,
+        V: Copy + Eq + Hash + Display,
+        TH: Display,
+        B: Clone,
+        CO: Stable<StableId = SID> + Clone,
+        P: Stable<StableId = SID> + Clone,
+        IX: StateIndex<EvolvingEntity<CO, P, V, B>>,
+        CH: KvStore<SID, EvolvingEntity<CO, P, V, B>>,
+    {
+        trace!("TX {} succeeded", settled_tx.tx_hash);
+        match settled_tx.pending_effects {
+            PendingEffects::FromLiquidityBook(effects) => {
+                for effect in effects {
+                    match effect {
+                        ExecutionEff::Updated(elim, _) => self.quarantine.pardon(&elim.stable_id()),
+                        // Updated states were predicted upon submission, only eliminations are left.
+                        ExecutionEff::Eliminated(elim) => {
+                            self.quarantine.pardon(&elim.stable_id());
+                            self.update_state(Channel::tx_submit(StateUpdate::Transition(Ior::Left(elim))));
+                        }
+                    }
                 }
             }
+            PendingEffects::FromBacklog(consumed_pool, _, _) => {
+                self.quarantine.pardon(&consumed_pool.stable_id())
+            }
+        }
+    }
+
+    fn on_tx_failed(&mut self, failed_tx: PendingEffectsByPair<PR, TH, CO, SO, P, V, B>, err: E)
+    where
+        PR: Copy + Eq + Hash + Display,
+        SID: Copy + Eq + Hash + Debug + Display,
+        V: Copy + Eq + Hash + Display,
+        B: Clone + Debug,
+        C: Scoped<PR> + Clone,
+        CO: Stable<StableId = SID> + Tradable<PairId = PR> + Clone + Debug + Display,
+        P: Stable<StableId = SID> + Tradable<PairId = PR> + Clone + Debug,
+        IX: StateIndex<EvolvingEntity<CO, P, V, B>>,
+        CH: KvStore<SID, EvolvingEntity<CO, P, V, B>>,
+        TLB: ExternalTLBEvents<CO, P> + TLBDepth + Maker<C>,
+        TH: Display,
+        SO: SpecializedOrder<TOrderId = V>,
+        L: HotBacklog<Bundled<SO, B>> + Maker<C>,
+        E: AttributeRejection<B, V> + Debug,
+    {
+        warn!("TX {} failed {:?}", failed_tx.tx_hash, err);
+        let consumed_versions = failed_tx.consumed_versions.clone();
+        let spent_bearers = failed_tx.pending_effects.spent_bearers();
+        let mut missing_bearers = HashSet::new();
+        let mut failed_bearers = HashSet::new();
+        for error in err.attribute(&spent_bearers) {
+            match error {
+                TxSubmissionError::MissingInputs(inputs) => missing_bearers.extend(inputs),
+                TxSubmissionError::ScriptFailure {
+                    input: Some(input), ..
+                } => {
+                    failed_bearers.insert(input);
+                }
+                _ => {}
+            }
+        }
+        // Participants whose scripts failed.
+        let mut culprits = vec![];
+        for PendingEffectsByPair {
+            pair,
+            tx_hash,
+            pending_effects,
+            ..
+        } in self.take_dependent_chain(failed_tx)
+        {
+            trace!("Unwinding effects of TX {}", tx_hash);
+            self.stale_snapshots.insert(pair);
+            match pending_effects {
+                PendingEffects::FromLiquidityBook(effects) => {
+                    for effect in effects {
+                        match effect {
+                            ExecutionEff::Updated(elim, upd) => {
+                                if failed_bearers.contains(&elim.version()) {
+                                    culprits.push(elim.0.clone());
+                                }
+                                self.stale_snapshots.insert(elim.0.pair_id());
+                                self.revert_prediction(elim, upd.version())
+                            }
+                            ExecutionEff::Eliminated(elim) => {
+                                if failed_bearers.contains(&elim.version()) {
+                                    culprits.push(elim.0.clone());
+                                }
+                                self.stale_snapshots.insert(elim.0.pair_id());
+                                self.skip_filter.remove(&elim.version());
+                                self.resync(elim.stable_id())
+                            }
+                        }
+                    }
+                }
+                PendingEffects::FromBacklog(consumed_pool, updated_pool, order) => {
+                    if failed_bearers.contains(&consumed_pool.version()) {
+                        culprits.push(consumed_pool.0.clone());
+                    }
+                    self.revert_prediction(consumed_pool, updated_pool.0.version);
+                    let order_ref = order.get_self_ref();
+                    if missing_bearers.contains(&order_ref) || failed_bearers.contains(&order_ref) {
+                        self.multi_backlog.get_mut(&pair).soft_evict(order_ref);
+                    } else {
+                        self.skip_filter.remove(&order_ref);
+                        self.multi_backlog.get_mut(&pair).put(order);
+                    }
+                }
+            }
+        }
+        for culprit in culprits {
+            self.put_in_quarantine(culprit);
+        }
+        // Defensive programming against node sending error for an irrelevant TX.
+        let has_relevant_bearers = missing_bearers.intersection(&consumed_versions).next().is_some();
+        if has_relevant_bearers {
+            trace!("Going to process missing bearers");
+            self.invalidate_versions(missing_bearers);
         }
     }
 
@@ -382,6 +881,14 @@ impl<S, PR, SID, V, CO, SO, P, B, TC, TX, TH, C, IX, CH, TLB, L, RIR, SIR, PRV,
         let (Channel::Ledger(Confirmed(upd))
         | Channel::Mempool(Unconfirmed(upd))
         | Channel::TxSubmit(Predicted(upd))) = update;
+        if from_mempool {
+            if let StateUpdate::TransitionRollback(
+                Ior::Left(rolled_back_state) | Ior::Both(rolled_back_state, _),
+            ) = upd
+            {
+                return self.rollback_unconfirmed(rolled_back_state);
+            }
+        }
         if let StateUpdate::TransitionRollback(Ior::Both(rolled_back_state, _)) = &upd {
             trace!(
                 "State {} was eliminated in result of rollback.",
@@ -415,12 +922,76 @@ impl<S, PR, SID, V, CO, SO, P, B, TC, TX, TH, C, IX, CH, TLB, L, RIR, SIR, PRV,
                 }
             }
             StateUpdate::Transition(Ior::Left(st)) | StateUpdate::TransitionRollback(Ior::Left(st)) => {
-                self.index.eliminate(st.stable_id());
+                let id = st.stable_id();
+                self.index.eliminate(id);
+                if self.quarantine.evict(&id) {
+                    trace!("Eliminated {} is no longer blacklisted", id);
+                    self.index.remove_blacklisted(id);
+                }
                 Some(Ior::Left(st.0))
             }
         }
     }
 
+    /// Discard unconfirmed state which was produced by a TX evicted from the mempool.
+    fn rollback_unconfirmed<T>(&mut self, rolled_back_state: Bundled<T, B>) -> Option<Ior<T, T>>
+    where
+        SID: Copy + Eq + Hash + Display,
+        V: Copy + Eq + Hash + Display,
+        T: EntitySnapshot<StableId = SID, Version = V> + Clone,
+        B: Clone,
+        IX: StateIndex<Bundled<T, B>>,
+        CH: KvStore<SID, Bundled<T, B>>,
+    {
+        let id = rolled_back_state.stable_id();
+        let ver = rolled_back_state.version();
+        let is_confirmed = self
+            .index
+            .get_last_confirmed(id)
+            .map(|Confirmed(st)| st.version() == ver)
+            .unwrap_or(false);
+        if is_confirmed {
+            trace!("Unconfirmed state {} of {} is already confirmed", ver, id);
+            return None;
+        }
+        self.index.invalidate_version(ver)?;
+        trace!("Unconfirmed state {} of {} was evicted from mempool", ver, id);
+        match resolve_source_state(id, &self.index) {
+            None => self
+                .cache
+                .remove(id)
+                .map(|Bundled(elim_state, _)| Ior::Left(elim_state)),
+            Some(latest_state) => self.cache(latest_state),
+        }
+    }
+
+    /// Publish snapshots of pairs touched since the last publication.
+    fn publish_snapshots(&mut self)
+    where
+        PR: Copy + Eq + Hash + Display,
+        C: Scoped<PR> + Clone,
+        TLB: TLBSnapshot<CO, P> + Maker<C>,
+        L: HotBacklog<Bundled<SO, B>> + Maker<C>,
+        SO: SpecializedOrder,
+    {
+        if self.stale_snapshots.is_empty() {
+            return;
+        }
+        let stale_pairs = mem::take(&mut self.stale_snapshots);
+        let multi_book = &mut self.multi_book;
+        let multi_backlog = &mut self.multi_backlog;
+        let published = self
+            .snapshots
+            .try_publish(stale_pairs.iter().copied(), |pair| PairSnapshot {
+                book: multi_book.get_mut(pair).snapshot(),
+                backlog: multi_backlog.get_mut(pair).orders(),
+            });
+        if !published {
+            trace!(target: "executor", "Snapshots are busy, postponing publication");
+            self.stale_snapshots = stale_pairs;
+        }
+    }
+
     fn processed(&mut self, ver: V)
     where
         V: Copy + Eq + Hash + Display,
@@ -430,125 +1001,76 @@ impl<S, PR, SID, V, CO, SO, P, B, TC, TX, TH, C, IX, CH, TLB, L, RIR, SIR, PRV,
     }
 }
 
-impl<S, PR, SID, V, CO, SO, P, B, TC, TX, TH, U, C, IX, CH, TLB, L, RIR, SIR, PRV, E> Stream
-    for Executor<S, PR, SID, V, CO, SO, P, B, TC, TX, TH, C, IX, CH, TLB, L, RIR, SIR, PRV, E>
+impl<S, PR, SID, V, CO, SO, P, B, TC, TX, TH, U, C, IX, CH, TLB, L, RIR, SIR, PRV, VAL, E> Stream
+    for Executor<S, PR, SID, V, CO, SO, P, B, TC, TX, TH, C, IX, CH, TLB, L, RIR, SIR, PRV, VAL, E>
 where
     S: Stream<Item = (PR, Event<CO, SO, P, B, V>)> + Unpin,
-    PR: Copy + Eq + Ord + Hash + Display + Unpin,
+    PR: AssetPair + Copy + Eq + Ord + Hash + Display + Unpin,
     SID: Copy + Eq + Hash + Debug + Display + Unpin,
     V: Copy + Eq + Hash + Display + Unpin,
-    P: Stable<StableId = SID> + Copy + Debug + Unpin + Display,
-    CO: Stable<StableId = SID> + MarketTaker<U = U> + Copy + Debug + Unpin + Display,
+    P: Stable<StableId = SID>
+        + Tradable<PairId = PR>
+        + MarketMaker
+        + MakerBehavior
+        + Copy
+        + Debug
+        + Unpin
+        + Display,
+    CO: Stable<StableId = SID>
+        + Tradable<PairId = PR>
+        + MarketTaker<U = U>
+        + TakerBehaviour
+        + Copy
+        + Debug
+        + Unpin
+        + Display,
     SO: SpecializedOrder<TPoolId = SID, TOrderId = V> + Unpin,
     B: Clone + Debug + Unpin,
     TC: Unpin,
     TX: CanonicalHash<Hash = TH> + Unpin,
-    TH: Display + Unpin,
-    C: Clone + Unpin,
+    TH: Copy + Eq + Display + Unpin,
+    C: Scoped<PR> + Has<Time> + Clone + Unpin,
     IX: StateIndex<EvolvingEntity<CO, P, V, B>> + Unpin,
     CH: KvStore<SID, EvolvingEntity<CO, P, V, B>> + Unpin,
-    TLB: TemporalLiquidityBook<CO, P> + ExternalTLBEvents<CO, P> + TLBFeedback<CO, P> + Maker<C> + Unpin,
+    TLB: TemporalLiquidityBook<CO, P>
+        + ExternalTLBEvents<CO, P>
+        + TLBDepth
+        + TLBSnapshot<CO, P>
+        + TLBFeedback<CO, P>
+        + TLBRouting<CO, P>
+        + TLBArbitrage<CO, P>
+        + Maker<C>
+        + Reconfigure<C>
+        + Unpin,
     L: HotBacklog<Bundled<SO, B>> + Maker<C> + Unpin,
     RIR: RecipeInterpreter<CO, P, C, V, B, TC> + Unpin,
     SIR: SpecializedInterpreter<P, SO, V, TC, B, C> + Unpin,
     PRV: TxProver<TC, TX> + Unpin,
-    E: TryInto<HashSet<V>> + Unpin + Debug + Display,
+    VAL: TxValidator<TX, B, C> + Unpin,
+    VAL::Error: Into<Remedy<V>> + Display,
+    E: AttributeRejection<B, V> + Unpin + Debug + Display,
 {
     type Item = TX;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
         loop {
-            // Wait for the feedback from the last pending job.
-            if let Some(PendingEffectsByPair {
-                pair,
-                tx_hash,
-                consumed_versions,
-                pending_effects,
-            }) = self.pending_effects.take()
+            // Settle TXs the network responded to.
+            while let Poll::Ready(Some((tx_hash, result))) =
+                Stream::poll_next(Pin::new(&mut self.feedback), cx)
             {
-                match Stream::poll_next(Pin::new(&mut self.feedback), cx) {
-                    Poll::Ready(Some(result)) => match result {
-                        Ok(_) => {
-                            trace!("TX {} succeeded", tx_hash);
-                            match pending_effects {
-                                PendingEffects::FromLiquidityBook(mut pending_effects) => {
-                                    while let Some(effect) = pending_effects.pop() {
-                                        match effect {
-                                            ExecutionEff::Updated(elim, upd) => {
-                                                self.processed(elim.version());
-                                                self.update_state(Channel::tx_submit(
-                                                    StateUpdate::Transition(Ior::Both(elim, upd)),
-                                                ));
-                                            }
-                                            ExecutionEff::Eliminated(elim) => {
-                                                self.processed(elim.version());
-                                                self.update_state(Channel::tx_submit(
-                                                    StateUpdate::Transition(Ior::Left(elim)),
-                                                ));
-                                            }
-                                        }
-                                    }
-                                    self.multi_book.get_mut(&pair).on_recipe_succeeded();
-                                }
-                                PendingEffects::FromBacklog(new_pool, consumed_ord) => {
-                                    self.processed(consumed_ord.get_self_ref());
-                                    self.update_state(Channel::tx_submit(StateUpdate::Transition(
-                                        Ior::Right(new_pool.map(Either::Right)),
-                                    )));
-                                }
-                            }
-                        }
-                        Err(err) => {
-                            warn!("TX {} failed {:?}", tx_hash, err);
-                            if let Ok(missing_bearers) = err.try_into() {
-                                match pending_effects {
-                                    PendingEffects::FromLiquidityBook(_) => {
-                                        self.multi_book
-                                            .get_mut(&pair)
-                                            .on_recipe_failed(StashingOption::Unstash);
-                                    }
-                                    PendingEffects::FromBacklog(_, order) => {
-                                        let order_ref = order.get_self_ref();
-                                        if missing_bearers.contains(&order_ref) {
-                                            self.multi_backlog.get_mut(&pair).soft_evict(order_ref);
-                                        } else {
-                                            self.multi_backlog.get_mut(&pair).put(order);
-                                        }
-                                    }
-                                }
-                                // Defensive programming against node sending error for an irrelevant TX.
-                                let has_relevant_bearers =
-                                    missing_bearers.intersection(&consumed_versions).next().is_some();
-                                if has_relevant_bearers {
-                                    trace!("Going to process missing bearers");
-                                    self.invalidate_versions(&pair, missing_bearers.clone());
-                                }
-                            } else {
-                                warn!("Unknown Tx submission error!");
-                                match pending_effects {
-                                    PendingEffects::FromLiquidityBook(_) => {
-                                        self.multi_book
-                                            .get_mut(&pair)
-                                            .on_recipe_failed(StashingOption::Unstash);
-                                    }
-                                    PendingEffects::FromBacklog(_, order) => {
-                                        self.multi_backlog.get_mut(&pair).put(order);
-                                    }
-                                }
-                            }
-                        }
-                    },
-                    _ => {
-                        let _ = self.pending_effects.insert(PendingEffectsByPair {
-                            pair,
-                            tx_hash,
-                            consumed_versions,
-                            pending_effects,
-                        });
-                        return Poll::Pending;
+                // Feedback for TXs unwound earlier as dependents of a failed one is ignored.
+                if let Some(pos) = self.in_flight.iter().position(|tx| tx.tx_hash == tx_hash) {
+                    let settled = self.in_flight.remove(pos).unwrap();
+                    self.stale_snapshots.insert(settled.pair);
+                    match result {
+                        Ok(_) => self.on_tx_succeeded(settled),
+                        Err(err) => self.on_tx_failed(settled, err),
                     }
                 }
             }
+            // Read before draining the upstream, so that all updates preceding the point are applied
+            // by the time the upstream is found empty.
+            let delivered = self.delivered.get();
             // Prioritize external updates over local work.
             if let Poll::Ready(Some((pair, update))) = Stream::poll_next(Pin::new(&mut self.upstream), cx) {
                 match update {
@@ -559,13 +1081,46 @@ where
                     }
                     Either::Right(atomic_entity) => self.sync_backlog(&pair, atomic_entity),
                 }
+                self.stale_snapshots.insert(pair);
                 self.focus_set.push_back(pair);
                 continue;
             }
+            if let Some(point) = delivered {
+                self.index.checkpoint(point);
+            }
+            // Entities whose quarantine is over are returned to the books.
+            for (pair, stable_id) in self.quarantine.release_expired(Instant::now()) {
+                trace!("Releasing {} from quarantine", stable_id);
+                self.resync(stable_id);
+                self.focus_set.push_back(pair);
+            }
             // Finally attempt to execute something.
-            while let Some(focus_pair) = self.focus_set.pop_front() {
+            while self.in_flight.len() < self.max_in_flight.get() {
+                let Some(focus_pair) = self.focus_set.pop_front() else {
+                    break;
+                };
+                self.stale_snapshots.insert(focus_pair);
                 // Try TLB:
-                if let Some(recipe) = self.multi_book.get_mut(&focus_pair).attempt() {
+                self.multi_book.reconfigure(&focus_pair);
+                let now = self.context.select::<Time>();
+                self.multi_book.get_mut(&focus_pair).advance_clocks(now.into());
+                // Takers which can't be matched within their own pair are routed through adjacent ones.
+                // Once no taker can be served, divergence between makers of the pair is arbitraged.
+                let matched = match self.multi_book.get_mut(&focus_pair).attempt() {
+                    Some(recipe) => Some((recipe, vec![focus_pair])),
+                    None => self
+                        .multi_book
+                        .attempt_routed(&focus_pair)
+                        .map(|RoutedRecipe { recipe, pairs }| (recipe, pairs))
+                        .or_else(|| {
+                            self.multi_book
+                                .get_mut(&focus_pair)
+                                .attempt_arbitrage()
+                                .map(|recipe| (recipe, vec![focus_pair]))
+                        }),
+                };
+                if let Some((recipe, pairs)) = matched {
+                    self.stale_snapshots.extend(pairs.iter().copied());
                     let (linked_recipe, consumed_versions) = ExecutionRecipe::link(recipe, |id| {
                         self.cache
                             .get(id)
@@ -573,13 +1128,66 @@ where
                     })
                     .expect("State is inconsistent");
                     let ctx = self.context.clone();
-                    let (txc, effects) = self.trade_interpreter.run(linked_recipe, ctx);
+                    let (txc, effects) = match self.trade_interpreter.run(linked_recipe, ctx) {
+                        Ok(result) => result,
+                        Err(RecipeRejection::Oversized(takers_to_exclude)) => {
+                            trace!("Recipe exceeds execution cap, splitting");
+                            let retry = !takers_to_exclude.is_empty();
+                            self.rollback_books(&pairs, takers_to_exclude);
+                            // Stashed takers are returned to the book once a smaller recipe is committed.
+                            if retry {
+                                self.focus_set.push_back(focus_pair);
+                            }
+                            continue;
+                        }
+                        Err(RecipeRejection::Invalid(culprits)) => {
+                            trace!("Recipe is rejected, dropping");
+                            self.rollback_books(&pairs, vec![]);
+                            // Pair is retried without participants which made the recipe fail.
+                            if !culprits.is_empty() {
+                                for culprit in culprits {
+                                    self.put_in_quarantine(culprit);
+                                }
+                                self.focus_set.push_back(focus_pair);
+                            }
+                            continue;
+                        }
+                    };
                     let tx = self.prover.prove(txc);
                     let tx_hash = tx.canonical_hash();
-                    let _ = self.pending_effects.insert(PendingEffectsByPair {
+                    let consumed_bearers = effects
+                        .iter()
+                        .map(|eff| match eff {
+                            ExecutionEff::Updated(Bundled(_, bearer), _)
+                            | ExecutionEff::Eliminated(Bundled(_, bearer)) => bearer.clone(),
+                        })
+                        .collect::<Vec<_>>();
+                    if let Err(err) = self.validator.validate(&tx, &consumed_bearers, &self.context) {
+                        warn!("TX {} didn't pass validation: {}", tx_hash, err);
+                        self.on_recipe_invalid(pairs, effects, err.into());
+                        continue;
+                    }
+                    // Books are committed right away so that next recipes are built on top of this one.
+                    self.commit_books(&pairs);
+                    let mut produced_versions = HashSet::new();
+                    for effect in &effects {
+                        match effect {
+                            ExecutionEff::Updated(elim, upd) => {
+                                self.processed(elim.version());
+                                produced_versions.insert(upd.version());
+                                self.update_state(Channel::tx_submit(StateUpdate::Transition(Ior::Both(
+                                    elim.clone(),
+                                    upd.clone(),
+                                ))));
+                            }
+                            ExecutionEff::Eliminated(elim) => self.processed(elim.version()),
+                        }
+                    }
+                    self.in_flight.push_back(PendingEffectsByPair {
                         pair: focus_pair,
                         tx_hash,
                         consumed_versions,
+                        produced_versions,
                         pending_effects: PendingEffects::FromLiquidityBook(effects),
                     });
                     // Return pair to focus set to make sure corresponding TLB will be exhausted.
@@ -588,9 +1196,13 @@ where
                 }
                 // Try Backlog:
                 if let Some(next_order) = self.multi_backlog.get_mut(&focus_pair).try_pop() {
-                    if let Some(Bundled(Either::Right(pool), pool_bearer)) =
+                    if self.quarantine.is_excluded(&next_order.0.get_pool_ref()) {
+                        // Order waits for the pool to be released.
+                        self.multi_backlog.get_mut(&focus_pair).put(next_order);
+                    } else if let Some(Bundled(Either::Right(pool), pool_bearer)) =
                         self.cache.get(next_order.0.get_pool_ref())
                     {
+                        let consumed_pool = Bundled(Either::Right(pool), pool_bearer.clone());
                         let ctx = self.context.clone();
                         if let Some((txc, updated_pool, consumed_ord)) =
                             self.spec_interpreter
@@ -598,13 +1210,37 @@ where
                         {
                             let tx = self.prover.prove(txc);
                             let tx_hash = tx.canonical_hash();
+                            let consumed_bearers = [consumed_pool.1.clone(), consumed_ord.1.clone()];
+                            if let Err(err) = self.validator.validate(&tx, &consumed_bearers, &self.context) {
+                                warn!("TX {} didn't pass validation: {}", tx_hash, err);
+                                self.on_backlog_tx_invalid(
+                                    focus_pair,
+                                    pool.version,
+                                    updated_pool.0.version,
+                                    consumed_ord,
+                                    err.into(),
+                                );
+                                continue;
+                            }
                             let consumed_versions =
                                 HashSet::from_iter(vec![pool.version, consumed_ord.get_self_ref()]);
-                            let _ = self.pending_effects.insert(PendingEffectsByPair {
+                            let produced_versions = HashSet::from_iter(vec![updated_pool.0.version]);
+                            self.processed(consumed_ord.get_self_ref());
+                            if let Some(tr) = self.update_state(Channel::tx_submit(StateUpdate::Transition(
+                                Ior::Right(updated_pool.clone().map(Either::Right)),
+                            ))) {
+                                self.sync_book(&focus_pair, tr);
+                            }
+                            self.in_flight.push_back(PendingEffectsByPair {
                                 pair: focus_pair,
                                 tx_hash,
                                 consumed_versions,
-                                pending_effects: PendingEffects::FromBacklog(updated_pool, consumed_ord),
+                                produced_versions,
+                                pending_effects: PendingEffects::FromBacklog(
+                                    consumed_pool,
+                                    updated_pool,
+                                    consumed_ord,
+                                ),
                             });
                             // Return pair to focus set to make sure corresponding TLB will be exhausted.
                             self.focus_set.push_back(focus_pair);
@@ -613,36 +1249,565 @@ where
                     }
                 }
             }
+            self.publish_snapshots();
             return Poll::Pending;
         }
     }
 }
 
-impl<S, PR, ST, V, CO, SO, P, B, TC, TX, TH, U, C, IX, CH, TLB, L, RIR, SIR, PRV, E> FusedStream
-    for Executor<S, PR, ST, V, CO, SO, P, B, TC, TX, TH, C, IX, CH, TLB, L, RIR, SIR, PRV, E>
+impl<S, PR, ST, V, CO, SO, P, B, TC, TX, TH, U, C, IX, CH, TLB, L, RIR, SIR, PRV, VAL, E> FusedStream
+    for Executor<S, PR, ST, V, CO, SO, P, B, TC, TX, TH, C, IX, CH, TLB, L, RIR, SIR, PRV, VAL, E>
 where
     S: Stream<Item = (PR, Event<CO, SO, P, B, V>)> + Unpin,
-    PR: Copy + Eq + Ord + Hash + Display + Unpin,
+    PR: AssetPair + Copy + Eq + Ord + Hash + Display + Unpin,
     ST: Copy + Eq + Hash + Debug + Display + Unpin,
     V: Copy + Eq + Hash + Display + Unpin,
-    P: Stable<StableId = ST> + Copy + Debug + Unpin + Display,
-    CO: Stable<StableId = ST> + MarketTaker<U = U> + Copy + Debug + Unpin + Display,
+    P: Stable<StableId = ST>
+        + Tradable<PairId = PR>
+        + MarketMaker
+        + MakerBehavior
+        + Copy
+        + Debug
+        + Unpin
+        + Display,
+    CO: Stable<StableId = ST>
+        + Tradable<PairId = PR>
+        + MarketTaker<U = U>
+        + TakerBehaviour
+        + Copy
+        + Debug
+        + Unpin
+        + Display,
     SO: SpecializedOrder<TPoolId = ST, TOrderId = V> + Unpin,
     B: Clone + Debug + Unpin,
     TC: Unpin,
     TX: CanonicalHash<Hash = TH> + Unpin,
-    TH: Display + Unpin,
-    C: Clone + Unpin,
+    TH: Copy + Eq + Display + Unpin,
+    C: Scoped<PR> + Has<Time> + Clone + Unpin,
     IX: StateIndex<EvolvingEntity<CO, P, V, B>> + Unpin,
     CH: KvStore<ST, EvolvingEntity<CO, P, V, B>> + Unpin,
-    TLB: TemporalLiquidityBook<CO, P> + ExternalTLBEvents<CO, P> + TLBFeedback<CO, P> + Maker<C> + Unpin,
+    TLB: TemporalLiquidityBook<CO, P>
+        + ExternalTLBEvents<CO, P>
+        + TLBDepth
+        + TLBSnapshot<CO, P>
+        + TLBFeedback<CO, P>
+        + TLBRouting<CO, P>
+        + TLBArbitrage<CO, P>
+        + Maker<C>
+        + Reconfigure<C>
+        + Unpin,
     L: HotBacklog<Bundled<SO, B>> + Maker<C> + Unpin,
     RIR: RecipeInterpreter<CO, P, C, V, B, TC> + Unpin,
     SIR: SpecializedInterpreter<P, SO, V, TC, B, C> + Unpin,
     PRV: TxProver<TC, TX> + Unpin,
-    E: TryInto<HashSet<V>> + Unpin + Debug + Display,
+    VAL: TxValidator<TX, B, C> + Unpin,
+    VAL::Error: Into<Remedy<V>> + Display,
+    E: AttributeRejection<B, V> + Unpin + Debug + Display,
 {
     fn is_terminated(&self) -> bool {
         false
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+    use std::fmt::{Display, Formatter};
+    use std::num::NonZeroUsize;
+    use std::time::Duration;
+
+    use either::Either;
+    use futures::channel::mpsc;
+    use type_equalities::IsEqual;
+
+    use spectrum_offchain::backlog::HotBacklog;
+    use spectrum_offchain::combinators::Ior;
+    use spectrum_offchain::data::event::{Channel, StateUpdate};
+    use spectrum_offchain::data::order::SpecializedOrder;
+    use spectrum_offchain::data::{Baked, EntitySnapshot, Has, Stable, Tradable};
+    use spectrum_offchain::executor::TxSubmissionError;
+    use spectrum_offchain::maker::{Maker, Scoped};
+    use spectrum_offchain::rocks::RocksConfig;
+
+    use crate::execution_engine::bundled::Bundled;
+    use crate::execution_engine::execution_effect::ExecutionEff;
+    use crate::execution_engine::liquidity_book::routing::AssetPair;
+    use crate::execution_engine::liquidity_book::{BookDepth, ExternalTLBEvents, TLBDepth};
+    use crate::execution_engine::multi_pair::MultiPair;
+    use crate::execution_engine::quarantine::QuarantineConfig;
+    use crate::execution_engine::resolver::resolve_source_state;
+    use crate::execution_engine::snapshots::Snapshots;
+    use crate::execution_engine::storage::kv_store::{InMemoryKvStore, KvStore};
+    use crate::execution_engine::storage::{
+        DeliveredPoint, InMemoryStateIndex, StateIndex, StateIndexRocksDB,
+    };
+    use crate::execution_engine::types::Time;
+    use crate::execution_engine::{EvolvingEntity, Executor, PendingEffects, PendingEffectsByPair};
+
+    #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+    struct Pair(u8, u8);
+
+    impl Display for Pair {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{}/{}", self.0, self.1)
+        }
+    }
+
+    impl AssetPair for Pair {
+        type Asset = u8;
+        fn assets(&self) -> (u8, u8) {
+            (self.0, self.1)
+        }
+        fn of(x: u8, y: u8) -> Self {
+            Pair(x.min(y), x.max(y))
+        }
+    }
+
+    const PAIR: Pair = Pair(0, 1);
+
+    #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, serde::Serialize, serde::Deserialize)]
+    struct EntityId(u8);
+
+    impl Display for EntityId {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            write!(f, "EntityId({})", self.0)
+        }
+    }
+
+    impl From<EntityId> for [u8; 28] {
+        fn from(id: EntityId) -> Self {
+            [id.0; 28]
+        }
+    }
+
+    /// Maker whose reserves mirror the version of its state.
+    #[derive(Copy, Clone, Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Pool {
+        id: EntityId,
+        reserves: u64,
+    }
+
+    impl Display for Pool {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            write!(f, "Pool({}, {})", self.id, self.reserves)
+        }
+    }
+
+    impl Stable for Pool {
+        type StableId = EntityId;
+        fn stable_id(&self) -> Self::StableId {
+            self.id
+        }
+        fn is_quasi_permanent(&self) -> bool {
+            true
+        }
+    }
+
+    impl Tradable for Pool {
+        type PairId = Pair;
+        fn pair_id(&self) -> Self::PairId {
+            PAIR
+        }
+    }
+
+    #[derive(Copy, Clone, Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Taker(EntityId);
+
+    impl Display for Taker {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            write!(f, "Taker({})", self.0)
+        }
+    }
+
+    impl Stable for Taker {
+        type StableId = EntityId;
+        fn stable_id(&self) -> Self::StableId {
+            self.0
+        }
+        fn is_quasi_permanent(&self) -> bool {
+            false
+        }
+    }
+
+    impl Tradable for Taker {
+        type PairId = Pair;
+        fn pair_id(&self) -> Self::PairId {
+            PAIR
+        }
+    }
+
+    #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+    struct Order(u32, EntityId);
+
+    impl SpecializedOrder for Order {
+        type TOrderId = u32;
+        type TPoolId = EntityId;
+        fn get_self_ref(&self) -> Self::TOrderId {
+            self.0
+        }
+        fn get_pool_ref(&self) -> Self::TPoolId {
+            self.1
+        }
+    }
+
+    #[derive(Copy, Clone)]
+    struct Ctx;
+
+    impl Scoped<Pair> for Ctx {
+        fn scoped(&self, _: &Pair) -> Self {
+            *self
+        }
+    }
+
+    impl Has<Time> for Ctx {
+        fn select<U: IsEqual<Time>>(&self) -> Time {
+            0.into()
+        }
+    }
+
+    #[derive(Default)]
+    struct Book {
+        makers: HashMap<EntityId, Pool>,
+    }
+
+    impl ExternalTLBEvents<Taker, Pool> for Book {
+        fn advance_clocks(&mut self, _: u64) {}
+        fn add_fragment(&mut self, _: Taker) {}
+        fn remove_fragment(&mut self, _: Taker) {}
+        fn update_pool(&mut self, pool: Pool) {
+            self.makers.insert(pool.id, pool);
+        }
+        fn remove_pool(&mut self, pool: Pool) {
+            self.makers.remove(&pool.id);
+        }
+    }
+
+    impl TLBDepth for Book {
+        fn depth(&self) -> BookDepth {
+            BookDepth::default()
+        }
+    }
+
+    impl Maker<Ctx> for Book {
+        fn make(_: &Ctx) -> Self {
+            Self::default()
+        }
+    }
+
+    #[derive(Default)]
+    struct Backlog(Vec<Bundled<Order, ()>>);
+
+    impl HotBacklog<Bundled<Order, ()>> for Backlog {
+        fn put<'a>(&mut self, ord: Bundled<Order, ()>)
+        where
+            Bundled<Order, ()>: 'a,
+        {
+            self.0.push(ord);
+        }
+
+        fn try_pop(&mut self) -> Option<Bundled<Order, ()>> {
+            self.0.pop()
+        }
+
+        fn exists<'a>(&self, ord_id: u32) -> bool
+        where
+            u32: 'a,
+        {
+            self.0.iter().any(|ord| ord.get_self_ref() == ord_id)
+        }
+
+        fn remove<'a>(&mut self, ord_id: u32)
+        where
+            u32: 'a + Clone,
+        {
+            self.0.retain(|ord| ord.get_self_ref() != ord_id);
+        }
+
+        fn soft_evict<'a>(&mut self, ord_id: u32)
+        where
+            Bundled<Order, ()>: 'a,
+        {
+            self.0.retain(|ord| ord.get_self_ref() != ord_id);
+        }
+
+        fn orders(&self) -> Vec<Bundled<Order, ()>> {
+            self.0.clone()
+        }
+    }
+
+    impl Maker<Ctx> for Backlog {
+        fn make(_: &Ctx) -> Self {
+            Self::default()
+        }
+    }
+
+    type Entity = EvolvingEntity<Taker, Pool, u32, ()>;
+
+    type TestExecutor<IX = InMemoryStateIndex<Entity>> = Executor<
+        (),
+        Pair,
+        EntityId,
+        u32,
+        Taker,
+        Order,
+        Pool,
+        (),
+        (),
+        (),
+        u8,
+        Ctx,
+        IX,
+        InMemoryKvStore<EntityId, Entity>,
+        Book,
+        Backlog,
+        (),
+        (),
+        (),
+        (),
+        Vec<TxSubmissionError<u32>>,
+    >;
+
+    fn executor() -> TestExecutor {
+        executor_with_index(InMemoryStateIndex::new())
+    }
+
+    fn executor_with_index<IX>(index: IX) -> TestExecutor<IX> {
+        let (_, feedback) = mpsc::channel(1);
+        Executor::new(
+            index,
+            InMemoryKvStore::new(),
+            MultiPair::new::<Book>(Ctx, "Book"),
+            MultiPair::new::<Backlog>(Ctx, "Backlog"),
+            Snapshots::new(),
+            Ctx,
+            (),
+            (),
+            (),
+            (),
+            (),
+            feedback,
+            NonZeroUsize::new(4).unwrap(),
+            QuarantineConfig {
+                base_backoff: Duration::from_secs(60),
+                max_strikes: 3,
+            },
+            DeliveredPoint::default(),
+        )
+    }
+
+    fn pool(id: u8, ver: u32) -> Entity {
+        let pool = Pool {
+            id: EntityId(id),
+            reserves: ver as u64,
+        };
+        Bundled(Either::Right(Baked::new(pool, ver)), ())
+    }
+
+    fn confirm<IX: StateIndex<Entity>>(executor: &mut TestExecutor<IX>, state: Entity) {
+        if let Some(tr) = executor.update_state(Channel::ledger(StateUpdate::Transition(Ior::Right(state)))) {
+            executor.sync_book(&PAIR, tr);
+        }
+    }
+
+    fn taker(id: u8, ver: u32) -> Entity {
+        Bundled(Either::Left(Baked::new(Taker(EntityId(id)), ver)), ())
+    }
+
+    /// Apply the effects of a TX the way the executor does upon submission.
+    fn submit(executor: &mut TestExecutor, tx_hash: u8, effects: Vec<ExecutionEff<Entity, Entity>>) {
+        let mut consumed_versions = HashSet::new();
+        let mut produced_versions = HashSet::new();
+        for effect in &effects {
+            match effect {
+                ExecutionEff::Updated(consumed, produced) => {
+                    executor.processed(consumed.version());
+                    consumed_versions.insert(consumed.version());
+                    produced_versions.insert(produced.version());
+                    if let Some(tr) = executor.update_state(Channel::tx_submit(StateUpdate::Transition(
+                        Ior::Both(consumed.clone(), produced.clone()),
+                    ))) {
+                        executor.sync_book(&PAIR, tr);
+                    }
+                }
+                ExecutionEff::Eliminated(consumed) => {
+                    executor.processed(consumed.version());
+                    consumed_versions.insert(consumed.version());
+                }
+            }
+        }
+        executor.in_flight.push_back(PendingEffectsByPair {
+            pair: PAIR,
+            tx_hash,
+            consumed_versions,
+            produced_versions,
+            pending_effects: PendingEffects::FromLiquidityBook(effects),
+        });
+    }
+
+    fn swap(consumed: Entity, produced: Entity) -> Vec<ExecutionEff<Entity, Entity>> {
+        vec![ExecutionEff::Updated(consumed, produced)]
+    }
+
+    fn take_in_flight(
+        executor: &mut TestExecutor,
+        tx_hash: u8,
+    ) -> PendingEffectsByPair<Pair, u8, Taker, Order, Pool, u32, ()> {
+        let pos = executor
+            .in_flight
+            .iter()
+            .position(|tx| tx.tx_hash == tx_hash)
+            .unwrap();
+        executor.in_flight.remove(pos).unwrap()
+    }
+
+    fn in_flight_hashes(executor: &TestExecutor) -> Vec<u8> {
+        executor.in_flight.iter().map(|tx| tx.tx_hash).collect()
+    }
+
+    fn reserves_in_book<IX>(executor: &TestExecutor<IX>, id: u8) -> Option<u64> {
+        executor
+            .multi_book
+            .get(&PAIR)
+            .and_then(|book| book.makers.get(&EntityId(id)))
+            .map(|pool| pool.reserves)
+    }
+
+    /// Pool 1 goes through 1 -> 2 -> 3 -> 4 in TXs 1, 2, 3, taker 5 is fully executed in TX 2,
+    /// pool 2 goes through 10 -> 11 in TX 4.
+    fn executor_with_chain() -> TestExecutor {
+        let mut executor = executor();
+        confirm(&mut executor, pool(1, 1));
+        confirm(&mut executor, pool(2, 10));
+        confirm(&mut executor, taker(5, 20));
+        submit(&mut executor, 1, swap(pool(1, 1), pool(1, 2)));
+        let mut tx_2 = swap(pool(1, 2), pool(1, 3));
+        tx_2.push(ExecutionEff::Eliminated(taker(5, 20)));
+        submit(&mut executor, 2, tx_2);
+        submit(&mut executor, 3, swap(pool(1, 3), pool(1, 4)));
+        submit(&mut executor, 4, swap(pool(2, 10), pool(2, 11)));
+        executor
+    }
+
+    #[test]
+    fn take_dependent_chain_detaches_transitive_dependents() {
+        let mut executor = executor_with_chain();
+        let failed_tx = take_in_flight(&mut executor, 2);
+        let chain = executor.take_dependent_chain(failed_tx);
+        assert_eq!(chain.iter().map(|tx| tx.tx_hash).collect::<Vec<_>>(), vec![3, 2]);
+        assert_eq!(in_flight_hashes(&executor), vec![1, 4]);
+    }
+
+    #[test]
+    fn revert_prediction_falls_back_to_confirmed_state() {
+        let mut executor = executor();
+        confirm(&mut executor, pool(1, 1));
+        submit(&mut executor, 1, swap(pool(1, 1), pool(1, 2)));
+        take_in_flight(&mut executor, 1);
+        executor.revert_prediction(pool(1, 1), 2);
+        let state = resolve_source_state(EntityId(1), &executor.index).unwrap();
+        assert_eq!(state.version(), 1);
+        assert_eq!(executor.cache.get(EntityId(1)).unwrap().version(), 1);
+        assert_eq!(reserves_in_book(&executor, 1), Some(1));
+        assert!(!executor.index.exists(&2));
+        assert!(!executor.skip_filter.contains(&1));
+    }
+
+    #[test]
+    fn failure_mid_chain_reverts_dependents_and_restores_prior_state() {
+        let mut executor = executor_with_chain();
+        let failed_tx = take_in_flight(&mut executor, 2);
+        executor.on_tx_failed(failed_tx, vec![TxSubmissionError::FeeTooSmall]);
+        assert_eq!(in_flight_hashes(&executor), vec![1, 4]);
+        // State predicted by TX 1 which is still in flight is restored.
+        let state = resolve_source_state(EntityId(1), &executor.index).unwrap();
+        assert_eq!(state.version(), 2);
+        assert_eq!(executor.cache.get(EntityId(1)).unwrap().version(), 2);
+        assert_eq!(reserves_in_book(&executor, 1), Some(2));
+        assert!(!executor.index.exists(&3));
+        assert!(!executor.index.exists(&4));
+        // Only versions consumed by TXs still in flight are skipped.
+        assert!(executor.skip_filter.contains(&1));
+        assert!(!executor.skip_filter.contains(&2));
+        assert!(!executor.skip_filter.contains(&3));
+        // Taker executed by the failed TX is back.
+        assert_eq!(executor.cache.get(EntityId(5)).unwrap().version(), 20);
+        assert!(!executor.skip_filter.contains(&20));
+        // Independent TX is untouched.
+        assert_eq!(executor.cache.get(EntityId(2)).unwrap().version(), 11);
+        assert_eq!(reserves_in_book(&executor, 2), Some(11));
+    }
+
+    #[test]
+    fn failed_backlog_tx_returns_order_and_pool() {
+        let mut executor = executor();
+        confirm(&mut executor, pool(1, 1));
+        let order = Bundled(Order(100, EntityId(1)), ());
+        let Bundled(Either::Right(updated_pool), _) = pool(1, 2) else {
+            unreachable!()
+        };
+        executor.processed(100);
+        if let Some(tr) = executor.update_state(Channel::tx_submit(StateUpdate::Transition(Ior::Right(
+            pool(1, 2),
+        )))) {
+            executor.sync_book(&PAIR, tr);
+        }
+        let failed_tx = PendingEffectsByPair {
+            pair: PAIR,
+            tx_hash: 1,
+            consumed_versions: HashSet::from([1, 100]),
+            produced_versions: HashSet::from([2]),
+            pending_effects: PendingEffects::FromBacklog(pool(1, 1), Bundled(updated_pool, ()), order),
+        };
+        executor.on_tx_failed(failed_tx, vec![TxSubmissionError::FeeTooSmall]);
+        assert_eq!(executor.cache.get(EntityId(1)).unwrap().version(), 1);
+        assert_eq!(reserves_in_book(&executor, 1), Some(1));
+        assert!(executor.multi_backlog.get_mut(&PAIR).exists(100));
+        assert!(!executor.skip_filter.contains(&100));
+    }
+
+    fn apply_block<IX: StateIndex<Entity>>(
+        executor: &mut TestExecutor<IX>,
+        updates: Vec<Ior<Entity, Entity>>,
+    ) {
+        for upd in updates {
+            if let Some(tr) = executor.update_state(Channel::ledger(StateUpdate::Transition(upd))) {
+                executor.sync_book(&PAIR, tr);
+            }
+        }
+    }
+
+    /// On restart states confirmed as of the checkpoint are handed over to a fresh executor
+    /// as ledger updates, then blocks from the checkpoint on are replayed on top of them.
+    #[test]
+    fn books_are_restored_after_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().to_str().unwrap().to_string();
+        {
+            let mut executor = executor_with_index(StateIndexRocksDB::new(RocksConfig {
+                db_path: db_path.clone(),
+            }));
+            apply_block(
+                &mut executor,
+                vec![Ior::Right(pool(1, 1)), Ior::Right(pool(2, 10))],
+            );
+            StateIndex::<Entity>::checkpoint(&mut executor.index, bincode::serialize(&1u64).unwrap());
+            apply_block(&mut executor, vec![Ior::Both(pool(1, 1), pool(1, 2))]);
+            StateIndex::<Entity>::checkpoint(&mut executor.index, bincode::serialize(&2u64).unwrap());
+            // Block 3 is not checkpointed before shutdown.
+            apply_block(&mut executor, vec![Ior::Left(pool(2, 10))]);
+        }
+        let index = StateIndexRocksDB::new(RocksConfig { db_path });
+        assert_eq!(index.recover::<u64>(1), Some(2));
+        let restored = index.get_all_confirmed::<Entity>();
+        let mut executor = executor_with_index(index);
+        apply_block(&mut executor, restored.into_iter().map(Ior::Right).collect());
+        assert_eq!(reserves_in_book(&executor, 1), Some(2));
+        assert_eq!(reserves_in_book(&executor, 2), Some(10));
+        // Replay starts with the checkpointed block which was already applied.
+        apply_block(&mut executor, vec![Ior::Both(pool(1, 1), pool(1, 2))]);
+        apply_block(&mut executor, vec![Ior::Left(pool(2, 10))]);
+        assert_eq!(reserves_in_book(&executor, 1), Some(2));
+        assert_eq!(reserves_in_book(&executor, 2), None);
+        assert_eq!(executor.cache.get(EntityId(1)).unwrap().version(), 2);
+    }
+}
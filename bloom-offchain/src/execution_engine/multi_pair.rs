@@ -1,25 +1,33 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::hash::Hash;
 
 use log::trace;
 use type_equalities::IsEqual;
 
-use spectrum_offchain::maker::Maker;
+use spectrum_offchain::maker::{Maker, Reconfigure, Scoped};
 
+use crate::execution_engine::liquidity_book::routing::AssetPair;
+
+/// Resources kept per pair along with an index of pairs by the assets they trade.
 #[derive(Debug, Clone)]
-pub struct MultiPair<PairId, R, Ctx>(HashMap<PairId, R>, Ctx, &'static str);
+pub struct MultiPair<PairId: AssetPair, R, Ctx>(
+    HashMap<PairId, R>,
+    Ctx,
+    &'static str,
+    HashMap<PairId::Asset, HashSet<PairId>>,
+);
 
-impl<PairId, R, Ctx> MultiPair<PairId, R, Ctx> {
+impl<PairId: AssetPair, R, Ctx> MultiPair<PairId, R, Ctx> {
     pub fn new<Hint: IsEqual<R>>(context: Ctx, tag: &'static str) -> Self {
-        Self(HashMap::new(), context, tag)
+        Self(HashMap::new(), context, tag, HashMap::new())
     }
 }
 
 impl<PairId, R, Ctx> MultiPair<PairId, R, Ctx>
 where
-    PairId: Copy + Eq + Hash + std::fmt::Display,
+    PairId: AssetPair + Copy + Eq + Hash + std::fmt::Display,
     R: Maker<Ctx>,
-    Ctx: Clone,
+    Ctx: Scoped<PairId> + Clone,
 {
     pub fn with_resource_mut<F, T>(&mut self, pair: &PairId, f: F) -> T
     where
@@ -33,12 +41,44 @@ where
             self.0.get_mut(pair).unwrap()
         } else {
             trace!(target: "offchain", "MultiPair[{}]: new pair: {}", self.2, pair);
-            self.0.insert(*pair, Maker::make(&self.1));
+            self.0.insert(*pair, Maker::make(&self.1.scoped(pair)));
+            let (x, y) = pair.assets();
+            self.3.entry(x).or_default().insert(*pair);
+            self.3.entry(y).or_default().insert(*pair);
             self.get_mut(pair)
         }
     }
 
+    pub fn get(&self, pair: &PairId) -> Option<&R> {
+        self.0.get(pair)
+    }
+
+    /// Pairs seen so far which trade the given asset.
+    pub fn pairs_with(&self, asset: PairId::Asset) -> impl Iterator<Item = &PairId> {
+        self.3.get(&asset).into_iter().flatten()
+    }
+
     pub fn remove(&mut self, pair: &PairId) {
-        self.0.remove(pair);
+        if self.0.remove(pair).is_some() {
+            let (x, y) = pair.assets();
+            for asset in [x, y] {
+                if let Some(pairs) = self.3.get_mut(&asset) {
+                    pairs.remove(pair);
+                    if pairs.is_empty() {
+                        self.3.remove(&asset);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Let the resource of the given pair pick up changes in the context.
+    pub fn reconfigure(&mut self, pair: &PairId)
+    where
+        R: Reconfigure<Ctx>,
+    {
+        if let Some(resource) = self.0.get_mut(pair) {
+            resource.reconfigure(&self.1);
+        }
     }
 }
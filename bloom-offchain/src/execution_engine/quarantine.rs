@@ -0,0 +1,210 @@
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+
+use spectrum_offchain::box_resolver::blacklist::EntityBlacklist;
+use spectrum_offchain::data::EntitySnapshot;
+
+#[derive(Debug, Copy, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuarantineConfig {
+    /// Period an entity is excluded for after the first failure.
+    /// Doubles with each subsequent failure.
+    pub base_backoff: Duration,
+    /// Number of failures after which an entity is excluded for good.
+    pub max_strikes: u32,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Verdict {
+    Quarantined { until: Instant },
+    Blacklisted,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Record<Pair> {
+    pair: Pair,
+    strikes: u32,
+    /// Set while the entity is excluded.
+    until: Option<Instant>,
+}
+
+/// Keeps entities which caused TXs to fail out of execution.
+/// Entities are released after a backoff period growing exponentially with the number of failures,
+/// repeat offenders are blacklisted permanently.
+pub struct Quarantine<Pair, StableId, T> {
+    conf: QuarantineConfig,
+    records: HashMap<StableId, Record<Pair>>,
+    blacklist: HashSet<StableId>,
+    pd: PhantomData<T>,
+}
+
+impl<Pair, StableId, T> Quarantine<Pair, StableId, T> {
+    pub fn new(conf: QuarantineConfig) -> Self {
+        Self {
+            conf,
+            records: HashMap::new(),
+            blacklist: HashSet::new(),
+            pd: PhantomData,
+        }
+    }
+}
+
+impl<Pair, StableId, T> Quarantine<Pair, StableId, T>
+where
+    Pair: Copy,
+    StableId: Copy + Eq + Hash,
+{
+    /// Restore quarantine with entities blacklisted before.
+    pub fn with_blacklist<I: IntoIterator<Item = StableId>>(conf: QuarantineConfig, blacklist: I) -> Self {
+        Self {
+            conf,
+            records: HashMap::new(),
+            blacklist: blacklist.into_iter().collect(),
+            pd: PhantomData,
+        }
+    }
+
+    /// Register failure caused by the entity.
+    pub fn strike(&mut self, pair: Pair, id: StableId, now: Instant) -> Verdict {
+        let record = self.records.entry(id).or_insert(Record {
+            pair,
+            strikes: 0,
+            until: None,
+        });
+        record.strikes += 1;
+        if record.strikes >= self.conf.max_strikes {
+            self.records.remove(&id);
+            self.blacklist.insert(id);
+            Verdict::Blacklisted
+        } else {
+            let backoff = self.conf.base_backoff * 2u32.saturating_pow(record.strikes - 1);
+            let until = now + backoff;
+            record.until = Some(until);
+            Verdict::Quarantined { until }
+        }
+    }
+
+    /// Whether the entity is excluded from execution at the moment.
+    pub fn is_excluded(&self, id: &StableId) -> bool {
+        self.blacklist.contains(id) || self.records.get(id).map_or(false, |rec| rec.until.is_some())
+    }
+
+    /// Release entities whose backoff period is over.
+    /// Strikes are kept so that the next failure is punished harder.
+    pub fn release_expired(&mut self, now: Instant) -> Vec<(Pair, StableId)> {
+        let mut released = vec![];
+        for (id, record) in self.records.iter_mut() {
+            if record.until.map_or(false, |until| until <= now) {
+                record.until = None;
+                released.push((record.pair, *id));
+            }
+        }
+        released
+    }
+
+    /// Entity was eliminated, there is nothing to keep out of execution anymore.
+    /// Returns `true` if the entity was blacklisted.
+    pub fn evict(&mut self, id: &StableId) -> bool {
+        self.records.remove(id);
+        self.blacklist.remove(id)
+    }
+
+    /// Entity took part in a successful TX, its past failures are forgiven.
+    pub fn pardon(&mut self, id: &StableId) {
+        if self.records.get(id).map_or(false, |rec| rec.until.is_none()) {
+            self.records.remove(id);
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl<Pair, T> EntityBlacklist<T> for Quarantine<Pair, T::StableId, T>
+where
+    T: EntitySnapshot,
+{
+    async fn is_blacklisted(&self, id: &T::StableId) -> bool {
+        self.blacklist.contains(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::execution_engine::quarantine::{Quarantine, QuarantineConfig, Verdict};
+
+    const CONF: QuarantineConfig = QuarantineConfig {
+        base_backoff: Duration::from_secs(10),
+        max_strikes: 3,
+    };
+
+    #[test]
+    fn backoff_grows_exponentially_until_blacklisted() {
+        let mut quarantine = Quarantine::<u8, u8, ()>::new(CONF);
+        let now = Instant::now();
+        assert_eq!(
+            quarantine.strike(0, 1, now),
+            Verdict::Quarantined {
+                until: now + Duration::from_secs(10)
+            }
+        );
+        assert!(quarantine.is_excluded(&1));
+        assert_eq!(quarantine.release_expired(now + Duration::from_secs(9)), vec![]);
+        assert_eq!(
+            quarantine.release_expired(now + Duration::from_secs(10)),
+            vec![(0, 1)]
+        );
+        assert!(!quarantine.is_excluded(&1));
+        assert_eq!(
+            quarantine.strike(0, 1, now),
+            Verdict::Quarantined {
+                until: now + Duration::from_secs(20)
+            }
+        );
+        assert_eq!(quarantine.strike(0, 1, now), Verdict::Blacklisted);
+        assert!(quarantine.is_excluded(&1));
+        assert_eq!(
+            quarantine.release_expired(now + Duration::from_secs(3600)),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn pardoned_entity_starts_over() {
+        let mut quarantine = Quarantine::<u8, u8, ()>::new(CONF);
+        let now = Instant::now();
+        quarantine.strike(0, 1, now);
+        quarantine.pardon(&1);
+        // Entity is still serving its term.
+        assert!(quarantine.is_excluded(&1));
+        quarantine.release_expired(now + Duration::from_secs(10));
+        quarantine.pardon(&1);
+        assert_eq!(
+            quarantine.strike(0, 1, now),
+            Verdict::Quarantined {
+                until: now + Duration::from_secs(10)
+            }
+        );
+    }
+
+    #[test]
+    fn eliminated_entities_are_evicted() {
+        let mut quarantine = Quarantine::<u8, u8, ()>::with_blacklist(CONF, [2]);
+        let now = Instant::now();
+        quarantine.strike(0, 1, now);
+        assert!(quarantine.is_excluded(&1));
+        assert!(quarantine.is_excluded(&2));
+        assert!(!quarantine.evict(&1));
+        assert!(quarantine.evict(&2));
+        assert!(!quarantine.is_excluded(&1));
+        assert!(!quarantine.is_excluded(&2));
+        assert_eq!(
+            quarantine.release_expired(now + Duration::from_secs(3600)),
+            vec![]
+        );
+    }
+}
@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+
+use crate::execution_engine::liquidity_book::BookSnapshot;
+
+/// Published state of a single pair.
+#[derive(Debug, Clone)]
+pub struct PairSnapshot<Taker, Maker, Order> {
+    pub book: BookSnapshot<Taker, Maker>,
+    pub backlog: Vec<Order>,
+}
+
+/// Latest snapshots of all pairs served by the executors.
+/// Executors never wait for the lock: if it's held by a reader
+/// publication is postponed until the executor is idle next time.
+#[derive(Debug)]
+pub struct Snapshots<Pair, Taker, Maker, Order>(Arc<Mutex<HashMap<Pair, PairSnapshot<Taker, Maker, Order>>>>);
+
+impl<Pair, Taker, Maker, Order> Clone for Snapshots<Pair, Taker, Maker, Order> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+impl<Pair, Taker, Maker, Order> Snapshots<Pair, Taker, Maker, Order> {
+    pub fn new() -> Self {
+        Self(Arc::new(Mutex::new(HashMap::new())))
+    }
+}
+
+impl<Pair, Taker, Maker, Order> Snapshots<Pair, Taker, Maker, Order>
+where
+    Pair: Copy + Eq + Hash,
+    Taker: Clone,
+    Maker: Clone,
+    Order: Clone,
+{
+    /// Snapshots of all known pairs.
+    pub fn all(&self) -> Vec<(Pair, PairSnapshot<Taker, Maker, Order>)> {
+        self.0
+            .lock()
+            .iter()
+            .map(|(pair, snapshot)| (*pair, snapshot.clone()))
+            .collect()
+    }
+
+    pub fn get(&self, pair: &Pair) -> Option<PairSnapshot<Taker, Maker, Order>> {
+        self.0.lock().get(pair).cloned()
+    }
+}
+
+impl<Pair, Taker, Maker, Order> Snapshots<Pair, Taker, Maker, Order>
+where
+    Pair: Eq + Hash,
+{
+    /// Publish snapshots produced by [make_snapshot] for the given pairs.
+    /// Returns `false` if the store is busy, nothing is published in this case.
+    pub(crate) fn try_publish<I, F>(&self, pairs: I, mut make_snapshot: F) -> bool
+    where
+        I: IntoIterator<Item = Pair>,
+        F: FnMut(&Pair) -> PairSnapshot<Taker, Maker, Order>,
+    {
+        if let Some(mut store) = self.0.try_lock() {
+            for pair in pairs {
+                let snapshot = make_snapshot(&pair);
+                store.insert(pair, snapshot);
+            }
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl<Pair, Taker, Maker, Order> Default for Snapshots<Pair, Taker, Maker, Order> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,5 +1,14 @@
 use std::collections::HashMap;
 use std::hash::Hash;
+use std::sync::Arc;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use spectrum_offchain::binary::prefixed_key;
+use spectrum_offchain::rocks::RocksConfig;
+
+use crate::execution_engine::storage::decode;
 
 pub trait KvStore<K, V> {
     fn insert(&mut self, key: K, value: V) -> Option<V>;
@@ -33,3 +42,103 @@ where
         self.0.remove(&key)
     }
 }
+
+/// Persistent [KvStore].
+#[derive(Clone)]
+pub struct KvStoreRocksDB {
+    db: Arc<rocksdb::OptimisticTransactionDB>,
+}
+
+impl KvStoreRocksDB {
+    pub fn new(conf: RocksConfig) -> Self {
+        Self {
+            db: Arc::new(rocksdb::OptimisticTransactionDB::open_default(conf.db_path).unwrap()),
+        }
+    }
+
+    pub fn with_db(db: Arc<rocksdb::OptimisticTransactionDB>) -> Self {
+        Self { db }
+    }
+}
+
+const KV_PREFIX: &str = "kv_store";
+
+impl<K, V> KvStore<K, V> for KvStoreRocksDB
+where
+    K: Serialize,
+    V: Serialize + DeserializeOwned,
+{
+    fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let key = prefixed_key(KV_PREFIX, &key);
+        let tx = self.db.transaction();
+        let old_value = tx.get(&key).unwrap().and_then(|bytes| decode(&bytes));
+        tx.put(key, bincode::serialize(&value).unwrap()).unwrap();
+        tx.commit().unwrap();
+        old_value
+    }
+
+    fn get(&self, key: K) -> Option<V> {
+        self.db
+            .get(prefixed_key(KV_PREFIX, &key))
+            .unwrap()
+            .and_then(|bytes| decode(&bytes))
+    }
+
+    fn remove(&mut self, key: K) -> Option<V> {
+        let key = prefixed_key(KV_PREFIX, &key);
+        let tx = self.db.transaction();
+        let old_value = tx.get(&key).unwrap().and_then(|bytes| decode(&bytes));
+        tx.delete(key).unwrap();
+        tx.commit().unwrap();
+        old_value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use spectrum_offchain::rocks::RocksConfig;
+
+    use crate::execution_engine::storage::kv_store::{KvStore, KvStoreRocksDB};
+
+    fn rocks_db_store(dir: &TempDir) -> KvStoreRocksDB {
+        KvStoreRocksDB::new(RocksConfig {
+            db_path: dir.path().to_str().unwrap().to_string(),
+        })
+    }
+
+    #[test]
+    fn rocksdb_store_insert_get_remove() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = rocks_db_store(&dir);
+        assert_eq!(store.insert(1u64, "a".to_string()), None);
+        assert_eq!(store.insert(1u64, "b".to_string()), Some("a".to_string()));
+        assert_eq!(KvStore::<u64, String>::get(&store, 1), Some("b".to_string()));
+        assert_eq!(
+            KvStore::<u64, String>::remove(&mut store, 1),
+            Some("b".to_string())
+        );
+        assert_eq!(KvStore::<u64, String>::get(&store, 1), None);
+    }
+
+    #[test]
+    fn rocksdb_store_survives_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let mut store = rocks_db_store(&dir);
+            store.insert(7u64, 42u32);
+        }
+        let store = rocks_db_store(&dir);
+        assert_eq!(KvStore::<u64, u32>::get(&store, 7), Some(42));
+    }
+
+    #[test]
+    fn corrupted_value_is_not_returned() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = rocks_db_store(&dir);
+        store.insert(1u64, 7u8);
+        // A single byte can't be decoded as u64.
+        assert_eq!(KvStore::<u64, u64>::get(&store, 1), None);
+    }
+}
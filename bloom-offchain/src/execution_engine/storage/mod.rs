@@ -1,11 +1,17 @@
 use std::collections::hash_map::Entry;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::{Debug, Display, Formatter, Write};
+use std::sync::Arc;
 
-use log::trace;
+use log::{error, trace};
+use rocksdb::{Direction, IteratorMode};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 
+use spectrum_offchain::binary::{prefixed_key, raw_prefixed_key};
 use spectrum_offchain::data::event::{Confirmed, Predicted, Unconfirmed};
 use spectrum_offchain::data::{EntitySnapshot, Stable};
+use spectrum_offchain::rocks::RocksConfig;
 
 pub mod kv_store;
 
@@ -26,6 +32,32 @@ pub trait StateIndex<T: EntitySnapshot> {
     fn eliminate<'a>(&mut self, sid: T::StableId);
     fn exists<'a>(&self, sid: &T::Version) -> bool;
     fn get_state<'a>(&self, sid: T::Version) -> Option<T>;
+    /// Mark the entity as excluded from execution for good.
+    fn put_blacklisted(&mut self, _sid: T::StableId) {}
+    /// Forget the entity was blacklisted.
+    fn remove_blacklisted(&mut self, _sid: T::StableId) {}
+    /// Get all blacklisted entities.
+    fn get_blacklisted(&self) -> Vec<T::StableId> {
+        vec![]
+    }
+    /// Persist changes made so far along with the ledger point (serialized)
+    /// all updates preceding which have been applied.
+    fn checkpoint(&mut self, _point: Vec<u8>) {}
+}
+
+/// Ledger point (serialized) up to which all updates have been delivered to execution partitions.
+/// Partitions checkpoint their states at it once they have drained their upstreams.
+#[derive(Clone, Default)]
+pub struct DeliveredPoint(Arc<std::sync::Mutex<Option<Vec<u8>>>>);
+
+impl DeliveredPoint {
+    pub fn set(&self, point: Vec<u8>) {
+        *self.0.lock().unwrap() = Some(point);
+    }
+
+    pub fn get(&self) -> Option<Vec<u8>> {
+        self.0.lock().unwrap().clone()
+    }
 }
 
 #[derive(Clone)]
@@ -120,6 +152,27 @@ where
         trace!("state_index::get_state({}) -> {}", sid, Displayed(&res));
         res
     }
+
+    fn put_blacklisted(&mut self, sid: T::StableId) {
+        trace!("state_index::put_blacklisted({})", sid);
+        self.0.put_blacklisted(sid);
+    }
+
+    fn remove_blacklisted(&mut self, sid: T::StableId) {
+        trace!("state_index::remove_blacklisted({})", sid);
+        self.0.remove_blacklisted(sid);
+    }
+
+    fn get_blacklisted(&self) -> Vec<T::StableId> {
+        let res = self.0.get_blacklisted();
+        trace!("state_index::get_blacklisted() -> {} entities", res.len());
+        res
+    }
+
+    fn checkpoint(&mut self, point: Vec<u8>) {
+        trace!("state_index::checkpoint({})", hex::encode(&point));
+        self.0.checkpoint(point);
+    }
 }
 
 const MAX_ROLLBACK_DEPTH: usize = 32;
@@ -246,6 +299,278 @@ where
     }
 }
 
+/// Persistent [StateIndex].
+/// Changes are staged in memory and written along with the ledger point they are consistent with
+/// in a single transaction on [StateIndex::checkpoint], so that stored states never lag behind
+/// the checkpoint execution resumes from.
+/// Each execution partition owns its own instance, checkpointed independently.
+#[derive(Clone)]
+pub struct StateIndexRocksDB {
+    db: Arc<rocksdb::OptimisticTransactionDB>,
+    partition: usize,
+    /// Changes made since the last checkpoint (key -> new value, `None` if deleted).
+    staged: HashMap<Vec<u8>, Option<Vec<u8>>>,
+    last_checkpoint: Option<Vec<u8>>,
+}
+
+impl StateIndexRocksDB {
+    pub fn new(conf: RocksConfig) -> Self {
+        Self::with_db(Arc::new(
+            rocksdb::OptimisticTransactionDB::open_default(conf.db_path).unwrap(),
+        ))
+    }
+
+    pub fn with_db(db: Arc<rocksdb::OptimisticTransactionDB>) -> Self {
+        Self {
+            db,
+            partition: 0,
+            staged: HashMap::new(),
+            last_checkpoint: None,
+        }
+    }
+
+    /// Instance checkpointed on behalf of the given execution partition.
+    pub fn partition(self, partition: usize) -> Self {
+        Self { partition, ..self }
+    }
+
+    /// Bring the index into a consistent state on startup.
+    /// Predicted and unconfirmed states are dropped as TXs they came from can't be tracked
+    /// across restarts. Checkpoints of all partitions are reset to the earliest one,
+    /// which is returned as the point to resume from. Partitions that got further re-apply
+    /// blocks they have already seen, which converges to the same states as the replay
+    /// proceeds: each confirmed transition overwrites the last state of the entity.
+    pub fn recover<P>(&self, num_partitions: usize) -> Option<P>
+    where
+        P: Serialize + DeserializeOwned + Ord,
+    {
+        let tx = self.db.transaction();
+        let mut earliest_checkpoint = None;
+        for (key, point) in scan_prefix(&self.db, CHECKPOINT_KEY_PREFIX) {
+            if let Some(point) = decode::<P>(&point) {
+                earliest_checkpoint = Some(match earliest_checkpoint {
+                    Some(earliest) if earliest < point => earliest,
+                    _ => point,
+                });
+            }
+            tx.delete(key).unwrap();
+        }
+        if let Some(point) = &earliest_checkpoint {
+            let raw_point = bincode::serialize(point).unwrap();
+            for partition in 0..num_partitions {
+                tx.put(prefixed_key(CHECKPOINT_KEY_PREFIX, &partition), &raw_point)
+                    .unwrap();
+            }
+        }
+        for prefix in [LAST_PREDICTED_KEY_PREFIX, LAST_UNCONFIRMED_KEY_PREFIX] {
+            for (index_key, ver) in scan_prefix(&self.db, prefix) {
+                let raw_sid = &index_key[bincode::serialize(prefix).unwrap().len()..];
+                let confirmed_key = raw_prefixed_key(LAST_CONFIRMED_KEY_PREFIX, raw_sid);
+                if tx.get(confirmed_key).unwrap().as_deref() != Some(ver.as_slice()) {
+                    tx.delete(raw_state_key(&ver)).unwrap();
+                }
+                tx.delete(index_key).unwrap();
+            }
+        }
+        tx.commit().unwrap();
+        earliest_checkpoint
+    }
+
+    /// All confirmed states as of the last checkpoints.
+    /// Used to restore execution state on startup.
+    pub fn get_all_confirmed<T: DeserializeOwned>(&self) -> Vec<T> {
+        scan_prefix(&self.db, LAST_CONFIRMED_KEY_PREFIX)
+            .into_iter()
+            .filter_map(|(_, ver)| self.get(&raw_state_key(&ver)))
+            .filter_map(|bytes| decode(&bytes))
+            .collect()
+    }
+
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        match self.staged.get(key) {
+            Some(staged) => staged.clone(),
+            None => self.db.get(key).unwrap(),
+        }
+    }
+
+    fn stage(&mut self, key: Vec<u8>, value: Option<Vec<u8>>) {
+        self.staged.insert(key, value);
+    }
+
+    fn get_indexed<T: DeserializeOwned>(&self, index_key: Vec<u8>) -> Option<T> {
+        self.get(&index_key)
+            .and_then(|ver| self.get(&raw_state_key(&ver)))
+            .and_then(|bytes| decode(&bytes))
+    }
+
+    fn put<T>(&mut self, index_key: Vec<u8>, value: T)
+    where
+        T: EntitySnapshot + Serialize,
+        T::Version: Serialize,
+    {
+        if let Some(old_ver) = self.get(&index_key) {
+            self.stage(raw_state_key(&old_ver), None);
+        }
+        let new_ver = bincode::serialize(&value.version()).unwrap();
+        self.stage(raw_state_key(&new_ver), Some(bincode::serialize(&value).unwrap()));
+        self.stage(index_key, Some(new_ver));
+    }
+}
+
+const STATE_PREFIX: &str = "state_index:state";
+const LAST_CONFIRMED_KEY_PREFIX: &str = "state_index:confirmed:last";
+const LAST_UNCONFIRMED_KEY_PREFIX: &str = "state_index:unconfirmed:last";
+const LAST_PREDICTED_KEY_PREFIX: &str = "state_index:predicted:last";
+const CHECKPOINT_KEY_PREFIX: &str = "state_index:checkpoint";
+const BLACKLIST_KEY_PREFIX: &str = "state_index:blacklist";
+
+fn raw_state_key(ver: &[u8]) -> Vec<u8> {
+    raw_prefixed_key(STATE_PREFIX, ver)
+}
+
+fn scan_prefix(db: &rocksdb::OptimisticTransactionDB, prefix: &str) -> Vec<(Vec<u8>, Vec<u8>)> {
+    let raw_prefix = bincode::serialize(prefix).unwrap();
+    db.iterator(IteratorMode::From(&raw_prefix, Direction::Forward))
+        .map(|item| item.unwrap())
+        .take_while(|(key, _)| key.starts_with(&raw_prefix))
+        .map(|(key, value)| (key.into_vec(), value.into_vec()))
+        .collect()
+}
+
+pub(crate) fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Option<T> {
+    bincode::deserialize(bytes)
+        .map_err(|err| error!("Failed to decode stored value: {}", err))
+        .ok()
+}
+
+impl<T> StateIndex<T> for StateIndexRocksDB
+where
+    T: EntitySnapshot + Serialize + DeserializeOwned,
+    <T as EntitySnapshot>::Version: Copy + Eq + Serialize,
+    <T as Stable>::StableId: Copy + Serialize + DeserializeOwned,
+{
+    fn get_last_confirmed(&self, id: T::StableId) -> Option<Confirmed<T>> {
+        self.get_indexed(prefixed_key(LAST_CONFIRMED_KEY_PREFIX, &id))
+            .map(Confirmed)
+    }
+
+    fn get_last_unconfirmed(&self, id: T::StableId) -> Option<Unconfirmed<T>> {
+        self.get_indexed(prefixed_key(LAST_UNCONFIRMED_KEY_PREFIX, &id))
+            .map(Unconfirmed)
+    }
+
+    fn get_last_predicted(&self, id: T::StableId) -> Option<Predicted<T>> {
+        self.get_indexed(prefixed_key(LAST_PREDICTED_KEY_PREFIX, &id))
+            .map(Predicted)
+    }
+
+    fn put_confirmed(&mut self, Confirmed(entity): Confirmed<T>) {
+        let index_key = prefixed_key(LAST_CONFIRMED_KEY_PREFIX, &entity.stable_id());
+        self.put(index_key, entity);
+    }
+
+    fn put_unconfirmed(&mut self, Unconfirmed(entity): Unconfirmed<T>) {
+        let index_key = prefixed_key(LAST_UNCONFIRMED_KEY_PREFIX, &entity.stable_id());
+        self.put(index_key, entity);
+    }
+
+    fn put_predicted(&mut self, Predicted(entity): Predicted<T>) {
+        let index_key = prefixed_key(LAST_PREDICTED_KEY_PREFIX, &entity.stable_id());
+        self.put(index_key, entity);
+    }
+
+    fn invalidate_version(&mut self, ver: T::Version) -> Option<T::StableId> {
+        let raw_ver = bincode::serialize(&ver).unwrap();
+        let state_key = raw_state_key(&raw_ver);
+        let sid = self
+            .get(&state_key)
+            .and_then(|bytes| decode::<T>(&bytes))
+            .map(|entity| entity.stable_id());
+        if let Some(sid) = sid {
+            self.stage(state_key, None);
+            for prefix in [
+                LAST_PREDICTED_KEY_PREFIX,
+                LAST_UNCONFIRMED_KEY_PREFIX,
+                LAST_CONFIRMED_KEY_PREFIX,
+            ] {
+                let index_key = prefixed_key(prefix, &sid);
+                if self.get(&index_key).as_deref() == Some(raw_ver.as_slice()) {
+                    self.stage(index_key, None);
+                }
+            }
+        }
+        sid
+    }
+
+    fn eliminate(&mut self, sid: T::StableId) {
+        for prefix in [
+            LAST_PREDICTED_KEY_PREFIX,
+            LAST_UNCONFIRMED_KEY_PREFIX,
+            LAST_CONFIRMED_KEY_PREFIX,
+        ] {
+            let index_key = prefixed_key(prefix, &sid);
+            if let Some(ver) = self.get(&index_key) {
+                self.stage(raw_state_key(&ver), None);
+                self.stage(index_key, None);
+            }
+        }
+    }
+
+    fn exists(&self, sid: &T::Version) -> bool {
+        self.get(&raw_state_key(&bincode::serialize(sid).unwrap()))
+            .is_some()
+    }
+
+    fn get_state(&self, sid: T::Version) -> Option<T> {
+        self.get(&raw_state_key(&bincode::serialize(&sid).unwrap()))
+            .and_then(|bytes| decode(&bytes))
+    }
+
+    fn put_blacklisted(&mut self, sid: T::StableId) {
+        self.stage(prefixed_key(BLACKLIST_KEY_PREFIX, &sid), Some(vec![]));
+    }
+
+    fn remove_blacklisted(&mut self, sid: T::StableId) {
+        self.stage(prefixed_key(BLACKLIST_KEY_PREFIX, &sid), None);
+    }
+
+    fn get_blacklisted(&self) -> Vec<T::StableId> {
+        let raw_prefix = bincode::serialize(BLACKLIST_KEY_PREFIX).unwrap();
+        let stored_keys = scan_prefix(&self.db, BLACKLIST_KEY_PREFIX)
+            .into_iter()
+            .map(|(key, _)| key);
+        let staged_keys = self
+            .staged
+            .keys()
+            .filter(|key| key.starts_with(&raw_prefix))
+            .cloned();
+        stored_keys
+            .chain(staged_keys)
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .filter(|key| self.get(key).is_some())
+            .filter_map(|key| decode(&key[raw_prefix.len()..]))
+            .collect()
+    }
+
+    fn checkpoint(&mut self, point: Vec<u8>) {
+        if self.staged.is_empty() && self.last_checkpoint.as_ref() == Some(&point) {
+            return;
+        }
+        let tx = self.db.transaction();
+        for (key, value) in self.staged.drain() {
+            match value {
+                Some(value) => tx.put(key, value).unwrap(),
+                None => tx.delete(key).unwrap(),
+            }
+        }
+        tx.put(prefixed_key(CHECKPOINT_KEY_PREFIX, &self.partition), &point)
+            .unwrap();
+        tx.commit().unwrap();
+        self.last_checkpoint = Some(point);
+    }
+}
+
 pub fn index_key<T: Into<[u8; 28]>>(prefix: u8, id: T) -> InMemoryIndexKey {
     let mut arr = [prefix; 29];
     let raw_id: [u8; 28] = id.into();
@@ -254,3 +579,188 @@ pub fn index_key<T: Into<[u8; 28]>>(prefix: u8, id: T) -> InMemoryIndexKey {
     }
     arr
 }
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use spectrum_offchain::data::event::{Confirmed, Predicted, Unconfirmed};
+    use spectrum_offchain::data::{EntitySnapshot, Stable};
+    use spectrum_offchain::rocks::RocksConfig;
+
+    use crate::execution_engine::storage::{StateIndex, StateIndexRocksDB};
+
+    #[derive(Copy, Clone, Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct TestEntity {
+        id: u64,
+        ver: u64,
+    }
+
+    impl Stable for TestEntity {
+        type StableId = u64;
+        fn stable_id(&self) -> Self::StableId {
+            self.id
+        }
+        fn is_quasi_permanent(&self) -> bool {
+            false
+        }
+    }
+
+    impl EntitySnapshot for TestEntity {
+        type Version = u64;
+        fn version(&self) -> Self::Version {
+            self.ver
+        }
+    }
+
+    fn rocks_db_index(dir: &TempDir) -> StateIndexRocksDB {
+        StateIndexRocksDB::new(RocksConfig {
+            db_path: dir.path().to_str().unwrap().to_string(),
+        })
+    }
+
+    #[test]
+    fn rocksdb_index_tracks_last_states() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut index = rocks_db_index(&dir);
+        let s0 = TestEntity { id: 1, ver: 0 };
+        let s1 = TestEntity { id: 1, ver: 1 };
+        let s2 = TestEntity { id: 1, ver: 2 };
+        index.put_confirmed(Confirmed(s0));
+        index.put_unconfirmed(Unconfirmed(s1));
+        index.put_predicted(Predicted(s2));
+        let confirmed: Option<Confirmed<TestEntity>> = index.get_last_confirmed(1);
+        let unconfirmed: Option<Unconfirmed<TestEntity>> = index.get_last_unconfirmed(1);
+        let predicted: Option<Predicted<TestEntity>> = index.get_last_predicted(1);
+        assert_eq!(confirmed.map(|Confirmed(e)| e), Some(s0));
+        assert_eq!(unconfirmed.map(|Unconfirmed(e)| e), Some(s1));
+        assert_eq!(predicted, Some(Predicted(s2)));
+        assert_eq!(
+            StateIndex::<TestEntity>::invalidate_version(&mut index, 2),
+            Some(1)
+        );
+        let predicted: Option<Predicted<TestEntity>> = index.get_last_predicted(1);
+        assert_eq!(predicted, None);
+        assert!(!StateIndex::<TestEntity>::exists(&index, &2));
+        StateIndex::<TestEntity>::eliminate(&mut index, 1);
+        let confirmed: Option<Confirmed<TestEntity>> = index.get_last_confirmed(1);
+        assert!(confirmed.is_none());
+        assert!(!StateIndex::<TestEntity>::exists(&index, &0));
+    }
+
+    #[test]
+    fn rocksdb_index_survives_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = TestEntity { id: 7, ver: 3 };
+        {
+            let mut index = rocks_db_index(&dir);
+            index.put_confirmed(Confirmed(state));
+            StateIndex::<TestEntity>::checkpoint(&mut index, vec![1]);
+        }
+        let index = rocks_db_index(&dir);
+        let confirmed: Option<Confirmed<TestEntity>> = index.get_last_confirmed(7);
+        assert_eq!(confirmed.map(|Confirmed(e)| e), Some(state));
+    }
+
+    #[test]
+    fn changes_after_last_checkpoint_are_not_persisted() {
+        let dir = tempfile::tempdir().unwrap();
+        let s0 = TestEntity { id: 7, ver: 0 };
+        let s1 = TestEntity { id: 7, ver: 1 };
+        {
+            let mut index = rocks_db_index(&dir);
+            index.put_confirmed(Confirmed(s0));
+            StateIndex::<TestEntity>::checkpoint(&mut index, vec![1]);
+            index.put_confirmed(Confirmed(s1));
+            let confirmed: Option<Confirmed<TestEntity>> = index.get_last_confirmed(7);
+            assert_eq!(confirmed.map(|Confirmed(e)| e), Some(s1));
+        }
+        let index = rocks_db_index(&dir);
+        let confirmed: Option<Confirmed<TestEntity>> = index.get_last_confirmed(7);
+        assert_eq!(confirmed.map(|Confirmed(e)| e), Some(s0));
+    }
+
+    #[test]
+    fn blacklist_is_persisted_with_checkpoint() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let mut index = rocks_db_index(&dir);
+            StateIndex::<TestEntity>::put_blacklisted(&mut index, 1);
+            StateIndex::<TestEntity>::put_blacklisted(&mut index, 2);
+            let mut blacklisted = StateIndex::<TestEntity>::get_blacklisted(&index);
+            blacklisted.sort();
+            assert_eq!(blacklisted, vec![1, 2]);
+            StateIndex::<TestEntity>::checkpoint(&mut index, bincode::serialize(&5u64).unwrap());
+            StateIndex::<TestEntity>::remove_blacklisted(&mut index, 1);
+            StateIndex::<TestEntity>::put_blacklisted(&mut index, 3);
+            let mut blacklisted = StateIndex::<TestEntity>::get_blacklisted(&index);
+            blacklisted.sort();
+            assert_eq!(blacklisted, vec![2, 3]);
+        }
+        let index = rocks_db_index(&dir);
+        assert_eq!(index.recover::<u64>(1), Some(5));
+        let mut blacklisted = StateIndex::<TestEntity>::get_blacklisted(&index);
+        blacklisted.sort();
+        assert_eq!(blacklisted, vec![1, 2]);
+    }
+
+    #[test]
+    fn recover_drops_volatile_states_and_resumes_from_earliest_checkpoint() {
+        let dir = tempfile::tempdir().unwrap();
+        let confirmed = TestEntity { id: 1, ver: 0 };
+        let unconfirmed = TestEntity { id: 1, ver: 1 };
+        let predicted = TestEntity { id: 1, ver: 2 };
+        {
+            let mut partition_0 = rocks_db_index(&dir);
+            let mut partition_1 = partition_0.clone().partition(1);
+            partition_0.put_confirmed(Confirmed(confirmed));
+            partition_0.put_unconfirmed(Unconfirmed(unconfirmed));
+            partition_0.put_predicted(Predicted(predicted));
+            StateIndex::<TestEntity>::checkpoint(&mut partition_0, bincode::serialize(&12u64).unwrap());
+            StateIndex::<TestEntity>::checkpoint(&mut partition_1, bincode::serialize(&10u64).unwrap());
+        }
+        let index = rocks_db_index(&dir);
+        assert_eq!(index.recover::<u64>(2), Some(10));
+        let last_confirmed: Option<Confirmed<TestEntity>> = index.get_last_confirmed(1);
+        let last_unconfirmed: Option<Unconfirmed<TestEntity>> = index.get_last_unconfirmed(1);
+        let last_predicted: Option<Predicted<TestEntity>> = index.get_last_predicted(1);
+        assert_eq!(last_confirmed.map(|Confirmed(e)| e), Some(confirmed));
+        assert!(last_unconfirmed.is_none());
+        assert!(last_predicted.is_none());
+        assert!(!StateIndex::<TestEntity>::exists(&index, &2));
+        // Checkpoints are reset, so recovering again yields the same point.
+        assert_eq!(index.recover::<u64>(2), Some(10));
+    }
+
+    #[test]
+    fn reapplying_transitions_past_checkpoint_is_harmless() {
+        let dir = tempfile::tempdir().unwrap();
+        let s0 = TestEntity { id: 1, ver: 0 };
+        let s1 = TestEntity { id: 1, ver: 1 };
+        let s2 = TestEntity { id: 2, ver: 5 };
+        {
+            let mut partition_0 = rocks_db_index(&dir);
+            let mut partition_1 = partition_0.clone().partition(1);
+            // Partition 0 got through blocks 10 and 11: entity 1 evolved, entity 2 was eliminated.
+            partition_0.put_confirmed(Confirmed(s0));
+            partition_0.put_confirmed(Confirmed(s2));
+            StateIndex::<TestEntity>::checkpoint(&mut partition_0, bincode::serialize(&10u64).unwrap());
+            partition_0.put_confirmed(Confirmed(s1));
+            StateIndex::<TestEntity>::eliminate(&mut partition_0, 2);
+            StateIndex::<TestEntity>::checkpoint(&mut partition_0, bincode::serialize(&11u64).unwrap());
+            StateIndex::<TestEntity>::checkpoint(&mut partition_1, bincode::serialize(&10u64).unwrap());
+        }
+        let mut index = rocks_db_index(&dir);
+        assert_eq!(index.recover::<u64>(2), Some(10));
+        // Block 11 is replayed on top of the states partition 0 already has.
+        index.put_confirmed(Confirmed(s1));
+        StateIndex::<TestEntity>::eliminate(&mut index, 2);
+        StateIndex::<TestEntity>::checkpoint(&mut index, bincode::serialize(&11u64).unwrap());
+        let last_confirmed: Option<Confirmed<TestEntity>> = index.get_last_confirmed(1);
+        assert_eq!(last_confirmed.map(|Confirmed(e)| e), Some(s1));
+        let eliminated: Option<Confirmed<TestEntity>> = index.get_last_confirmed(2);
+        assert!(eliminated.is_none());
+        assert!(!StateIndex::<TestEntity>::exists(&index, &0));
+        assert_eq!(index.get_all_confirmed::<TestEntity>(), vec![s1]);
+    }
+}
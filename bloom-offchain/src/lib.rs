@@ -1,3 +1,4 @@
 mod display;
 pub mod execution_engine;
+mod metrics;
 pub mod partitioning;
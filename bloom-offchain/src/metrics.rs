@@ -0,0 +1,66 @@
+use std::time::Duration;
+
+use lazy_static::lazy_static;
+use prometheus::{
+    register_histogram, register_int_counter, register_int_counter_vec, register_int_gauge_vec, Histogram,
+    IntCounter, IntCounterVec, IntGaugeVec,
+};
+
+use crate::execution_engine::liquidity_book::BookDepth;
+
+lazy_static! {
+    static ref MATCHMAKING_ATTEMPTS: IntCounterVec = register_int_counter_vec!(
+        "matchmaking_attempts_total",
+        "Number of matchmaking attempts made by liquidity books, by outcome",
+        &["outcome"]
+    )
+    .unwrap();
+    static ref MATCHMAKING_LATENCY: Histogram = register_histogram!(
+        "matchmaking_latency_seconds",
+        "Time it takes a liquidity book to form a recipe or give up"
+    )
+    .unwrap();
+    static ref TAKERS_STASHED: IntCounter = register_int_counter!(
+        "takers_stashed_total",
+        "Number of times takers were stashed after a failed recipe"
+    )
+    .unwrap();
+    static ref BOOK_DEPTH: IntGaugeVec = register_int_gauge_vec!(
+        "book_depth",
+        "Number of active takers and makers in the liquidity book, by pair and kind",
+        &["pair", "kind"]
+    )
+    .unwrap();
+}
+
+pub(crate) fn on_recipe_formed() {
+    MATCHMAKING_ATTEMPTS.with_label_values(&["formed"]).inc();
+}
+
+pub(crate) fn on_recipe_failed() {
+    MATCHMAKING_ATTEMPTS.with_label_values(&["failed"]).inc();
+}
+
+pub(crate) fn on_arbitrage_formed() {
+    MATCHMAKING_ATTEMPTS.with_label_values(&["arbitrage"]).inc();
+}
+
+pub(crate) fn on_matchmaking_finished(elapsed: Duration) {
+    MATCHMAKING_LATENCY.observe(elapsed.as_secs_f64());
+}
+
+pub(crate) fn on_takers_stashed() {
+    TAKERS_STASHED.inc();
+}
+
+pub(crate) fn on_book_synced(pair: &str, depth: BookDepth) {
+    BOOK_DEPTH
+        .with_label_values(&[pair, "asks"])
+        .set(depth.asks as i64);
+    BOOK_DEPTH
+        .with_label_values(&[pair, "bids"])
+        .set(depth.bids as i64);
+    BOOK_DEPTH
+        .with_label_values(&[pair, "makers"])
+        .set(depth.makers as i64);
+}
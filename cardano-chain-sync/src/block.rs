@@ -0,0 +1,93 @@
+use std::collections::HashSet;
+
+use cml_core::serialization::Deserialize;
+use cml_core::{DeserializeError, Slot};
+use cml_crypto::BlockHeaderHash;
+use cml_multi_era::babbage::BabbageBlock;
+use cml_multi_era::MultiEraBlock;
+
+use spectrum_cardano_lib::hash::hash_block_header_canonical;
+use spectrum_cardano_lib::time::SlotConfig;
+use spectrum_cardano_lib::transaction::TxViewMut;
+
+use crate::data::{BlockInfo, TxPosition};
+
+/// Decode block from its network representation, i.e. tagged with the era it belongs to.
+pub fn decode_block(bytes: &[u8]) -> Result<MultiEraBlock, DeserializeError> {
+    MultiEraBlock::from_explicit_network_cbor_bytes(bytes)
+}
+
+/// Decode block persisted in the ledger cache.
+/// Blocks cached before multi-era support was introduced are stored as untagged Babbage blocks.
+pub fn decode_cached_block(bytes: &[u8]) -> Option<MultiEraBlock> {
+    decode_block(bytes).ok().or_else(|| {
+        BabbageBlock::from_cbor_bytes(bytes)
+            .ok()
+            .map(MultiEraBlock::Babbage)
+    })
+}
+
+/// Slot and header hash of the given block.
+/// Returns `None` for eras preceding Babbage as they are not followed.
+pub fn block_id(block: &MultiEraBlock) -> Option<(Slot, BlockHeaderHash)> {
+    match block {
+        MultiEraBlock::Babbage(blk) => Some((
+            blk.header.header_body.slot,
+            hash_block_header_canonical(&blk.header),
+        )),
+        MultiEraBlock::Conway(blk) => Some((
+            blk.header.header_body.slot,
+            hash_block_header_canonical(&blk.header),
+        )),
+        _ => None,
+    }
+}
+
+/// Header info of the given block, its time is derived from the slot according to [SlotConfig].
+/// Returns `None` for eras preceding Babbage as they are not followed.
+pub fn block_info(block: &MultiEraBlock, slot_config: SlotConfig) -> Option<BlockInfo> {
+    let height = match block {
+        MultiEraBlock::Babbage(blk) => blk.header.header_body.block_number,
+        MultiEraBlock::Conway(blk) => blk.header.header_body.block_number,
+        _ => return None,
+    };
+    let (slot, hash) = block_id(block)?;
+    Some(BlockInfo {
+        hash,
+        slot,
+        time: slot_config.slot_to_posix_time(slot),
+        height,
+    })
+}
+
+/// Extract all valid transactions from the given block along with their position in the chain.
+pub fn unpack_positioned_transactions(
+    block: MultiEraBlock,
+    slot_config: SlotConfig,
+) -> Vec<(TxViewMut, TxPosition)> {
+    let Some(info) = block_info(&block, slot_config) else {
+        return vec![];
+    };
+    let position = |index| TxPosition { block: info, index };
+    match block {
+        MultiEraBlock::Babbage(blk) => valid_tx_bodies(blk.transaction_bodies, blk.invalid_transactions)
+            .map(|(ix, tb)| (TxViewMut::from(tb), position(ix)))
+            .collect(),
+        MultiEraBlock::Conway(blk) => valid_tx_bodies(blk.transaction_bodies, blk.invalid_transactions)
+            .map(|(ix, tb)| (TxViewMut::from(tb), position(ix)))
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/// Valid transaction bodies along with their index in the block.
+fn valid_tx_bodies<TxBody>(
+    transaction_bodies: Vec<TxBody>,
+    invalid_transactions: Vec<u16>,
+) -> impl Iterator<Item = (usize, TxBody)> {
+    let invalid_indices: HashSet<u16> = HashSet::from_iter(invalid_transactions);
+    transaction_bodies
+        .into_iter()
+        .enumerate()
+        .filter(move |(ix, _)| !invalid_indices.contains(&(*ix as u16)))
+}
@@ -1,9 +1,10 @@
 use async_std::stream::Stream;
+use cml_core::Slot;
 use cml_crypto::RawBytesEncoding;
 use futures::channel::mpsc;
 use futures::executor::block_on;
 use futures::SinkExt;
-use log::trace;
+use log::{error, trace};
 use rocksdb::{Direction, IteratorMode};
 use std::path::Path;
 use std::sync::Arc;
@@ -12,9 +13,13 @@ use serde::Serialize;
 use tokio::task::spawn_blocking;
 
 use crate::client::Point;
+use crate::retention::BlockArchive;
 
 #[derive(serde::Serialize, serde::Deserialize)]
-pub struct LinkedBlock(/*block bytes*/ pub Vec<u8>, /*prev point*/ pub Point);
+pub struct LinkedBlock(
+    /*era-tagged block bytes*/ pub Vec<u8>,
+    /*prev point*/ pub Point,
+);
 
 pub type Inclusive<T> = T;
 
@@ -29,18 +34,71 @@ pub trait LedgerCache {
 
 pub struct LedgerCacheRocksDB {
     pub db: Arc<rocksdb::OptimisticTransactionDB>,
+    /// Blocks pruned from the cache are moved here if set.
+    archive: Option<BlockArchive>,
 }
 
 impl LedgerCacheRocksDB {
     pub fn new<P: AsRef<Path>>(path: P) -> Self {
         Self {
             db: Arc::new(rocksdb::OptimisticTransactionDB::open_default(path).unwrap()),
+            archive: None,
         }
     }
+
+    /// Archive pruned blocks and replay them from the archive.
+    pub fn with_archive(self, archive: Option<BlockArchive>) -> Self {
+        Self { archive, ..self }
+    }
+
+    /// Remove at most `batch_size` of the oldest blocks which are more than `retained_slots` behind the tip,
+    /// archiving them first if the archive is set. Returns the number of pruned blocks.
+    pub async fn prune(&self, retained_slots: Slot, batch_size: usize) -> usize {
+        let Some(cutoff_slot) = self
+            .get_tip()
+            .await
+            .and_then(|tip| tip.get_slot().checked_sub(retained_slots))
+        else {
+            return 0;
+        };
+        let db = self.db.clone();
+        let archive = self.archive.clone();
+        spawn_blocking(move || {
+            // Blocks are keyed by slot, so all blocks to prune precede the cutoff key.
+            let cutoff_key = slot_key(POINT_PREFIX, cutoff_slot);
+            let records = db
+                .iterator(IteratorMode::From(POINT_PREFIX.as_bytes(), Direction::Forward))
+                .map(|item| item.unwrap())
+                .take_while(|(key, _)| key.as_ref() < cutoff_key.as_slice())
+                .take(batch_size)
+                .map(|(key, raw_blk)| (key.into_vec(), raw_blk.into_vec()))
+                .collect::<Vec<_>>();
+            let Some((first_key, _)) = records.first() else {
+                return 0;
+            };
+            if let Some(archive) = archive {
+                // Blocks are retained until they are archived, pruning is retried next round.
+                if let Err(err) = archive.write_segment(key_slot(POINT_PREFIX, first_key), &records) {
+                    error!(target: "chain_sync", "Failed to archive blocks, pruning is skipped: {}", err);
+                    return 0;
+                }
+            }
+            let tx = db.transaction();
+            for (key, _) in &records {
+                tx.delete(key).unwrap();
+            }
+            tx.commit().unwrap();
+            db.compact_range(Some(POINT_PREFIX.as_bytes()), Some(cutoff_key.as_slice()));
+            trace!("{} blocks pruned before slot {}", records.len(), cutoff_slot);
+            records.len()
+        })
+        .await
+        .unwrap()
+    }
 }
 
 const LATEST_POINT: &str = "a:";
-const POINT_PREFIX: &str = "b:";
+pub(crate) const POINT_PREFIX: &str = "b:";
 
 impl LedgerCache for LedgerCacheRocksDB {
     async fn set_tip(&self, point: Point) {
@@ -92,17 +150,25 @@ impl LedgerCache for LedgerCacheRocksDB {
 
     fn replay<'a>(&self, from_point: Inclusive<Point>) -> impl Stream<Item = LinkedBlock> + Send + 'a {
         let db = self.db.clone();
+        let archive = self.archive.clone();
         let (mut snd, recv) = mpsc::unbounded();
         spawn_blocking(move || {
             trace!("Replaying blocks from point {:?}", from_point);
-            let key = point_key(POINT_PREFIX, &from_point);
-            let iter = db.iterator(IteratorMode::From(&key, Direction::Forward));
             let mut counter = 0;
-            for item in iter {
-                if let Some(blk) = item
-                    .ok()
-                    .and_then(|(_, raw_blk)| bincode::deserialize::<LinkedBlock>(raw_blk.as_ref()).ok())
-                {
+            // Archived blocks precede all blocks retained in the cache.
+            if let Some(archive) = archive {
+                for blk in archive.read_from(from_point) {
+                    counter += 1;
+                    block_on(snd.send(blk)).unwrap();
+                }
+            }
+            let key = point_key(POINT_PREFIX, &from_point);
+            let iter = db
+                .iterator(IteratorMode::From(&key, Direction::Forward))
+                .filter_map(|item| item.ok())
+                .take_while(|(key, _)| key.starts_with(POINT_PREFIX.as_bytes()));
+            for (_, raw_blk) in iter {
+                if let Ok(blk) = bincode::deserialize::<LinkedBlock>(raw_blk.as_ref()) {
                     counter += 1;
                     block_on(snd.send(blk)).unwrap();
                 }
@@ -113,7 +179,7 @@ impl LedgerCache for LedgerCacheRocksDB {
     }
 }
 
-fn point_key(prefix: &str, point: &Point) -> Vec<u8> {
+pub(crate) fn point_key(prefix: &str, point: &Point) -> Vec<u8> {
     let mut key_bytes = Vec::from(prefix.as_bytes());
     let (slot, hash) = match point {
         Point::Origin => (0, vec![]),
@@ -123,3 +189,47 @@ fn point_key(prefix: &str, point: &Point) -> Vec<u8> {
     key_bytes.extend_from_slice(&hash);
     key_bytes
 }
+
+fn slot_key(prefix: &str, slot: Slot) -> Vec<u8> {
+    let mut key_bytes = Vec::from(prefix.as_bytes());
+    key_bytes.extend_from_slice(&slot.to_be_bytes());
+    key_bytes
+}
+
+fn key_slot(prefix: &str, key: &[u8]) -> Slot {
+    let slot_bytes = &key[prefix.len()..prefix.len() + 8];
+    Slot::from_be_bytes(slot_bytes.try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use cml_crypto::BlockHeaderHash;
+    use futures::StreamExt;
+
+    use crate::cache::{LedgerCache, LedgerCacheRocksDB, LinkedBlock};
+    use crate::client::Point;
+
+    #[tokio::test]
+    async fn replay_stops_at_the_end_of_cached_blocks() {
+        let dir = std::env::temp_dir().join(format!("ledger_cache_{}", rand::random::<u64>()));
+        let cache = LedgerCacheRocksDB::new(dir);
+        let point = Point::Specific(1, BlockHeaderHash::from([1u8; 32]));
+        cache.put_block(point, LinkedBlock(vec![1], Point::Origin)).await;
+        // Keys of other stores sharing the DB may follow the blocks.
+        cache
+            .db
+            .put(
+                "c:foreign",
+                bincode::serialize(&LinkedBlock(vec![2], Point::Origin)).unwrap(),
+            )
+            .unwrap();
+        let replayed = cache.replay(Point::Origin).collect::<Vec<_>>().await;
+        assert_eq!(
+            replayed
+                .into_iter()
+                .map(|LinkedBlock(blk, _)| blk)
+                .collect::<Vec<_>>(),
+            vec![vec![1]]
+        );
+    }
+}
@@ -1,28 +1,51 @@
-use std::marker::PhantomData;
+use std::cmp::max;
+use std::future::Future;
 use std::path::Path;
 use std::sync::Arc;
 
-use cml_core::serialization::Deserialize;
-use cml_core::Slot;
-use cml_crypto::BlockHeaderHash;
-use log::debug;
-use pallas_network::miniprotocols::chainsync::{BlockContent, NextResponse, State};
+use cml_core::{DeserializeError, Slot};
+use cml_crypto::{blake2b256, BlockHeaderHash};
+use cml_multi_era::MultiEraBlock;
+use log::{debug, trace};
+use pallas_network::miniprotocols::chainsync::{BlockContent, HeaderContent, NextResponse, State, Tip};
 use pallas_network::miniprotocols::handshake::RefuseReason;
-use pallas_network::miniprotocols::{chainsync, handshake, PROTOCOL_N2C_CHAIN_SYNC, PROTOCOL_N2C_HANDSHAKE};
+use pallas_network::miniprotocols::{
+    blockfetch, chainsync, handshake, keepalive, PROTOCOL_N2C_CHAIN_SYNC, PROTOCOL_N2C_HANDSHAKE,
+    PROTOCOL_N2N_BLOCK_FETCH, PROTOCOL_N2N_CHAIN_SYNC, PROTOCOL_N2N_HANDSHAKE, PROTOCOL_N2N_KEEP_ALIVE,
+};
 use pallas_network::multiplexer;
 use pallas_network::multiplexer::{Bearer, RunningPlexer};
+use tokio::net::ToSocketAddrs;
 use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
 
-use crate::cache::LedgerCache;
+use spectrum_cardano_lib::connection::keep_alive;
+use spectrum_cardano_lib::era::SharedEra;
+
+use crate::block::decode_block;
+use crate::cache::{LedgerCache, LinkedBlock};
 use crate::data::ChainUpgrade;
 
-pub struct ChainSyncClient<Block> {
+pub struct ChainSyncClient {
     plexer: RunningPlexer,
-    chain_sync: chainsync::N2CClient,
-    block: PhantomData<Block>,
+    protocol: ChainSyncProtocol,
+}
+
+enum ChainSyncProtocol {
+    /// Local node streams whole blocks.
+    N2C(chainsync::N2CClient),
+    /// Relay streams headers only, blocks are fetched separately.
+    N2N {
+        chain_sync: chainsync::N2NClient,
+        block_fetch: blockfetch::Client,
+        keep_alive: JoinHandle<()>,
+        /// Era of the latest header.
+        era: SharedEra,
+    },
 }
 
-impl<Block> ChainSyncClient<Block> {
+impl ChainSyncClient {
+    /// Follow the chain through the local node.
     #[cfg(not(target_os = "windows"))]
     pub async fn init<'a, Cache>(
         cache: Arc<Mutex<Cache>>,
@@ -56,60 +79,220 @@ impl<Block> ChainSyncClient<Block> {
 
         let mut cs_client = chainsync::Client::new(cs_channel);
 
-        let best_point = cache.lock().await.get_tip().await.unwrap_or(starting_point);
+        find_intersection(cache, starting_point, |points| cs_client.find_intersect(points)).await?;
+
+        Ok(Self {
+            plexer,
+            protocol: ChainSyncProtocol::N2C(cs_client),
+        })
+    }
+
+    /// Follow the chain through a relay.
+    pub async fn init_remote<'a, Cache>(
+        cache: Arc<Mutex<Cache>>,
+        peer: impl ToSocketAddrs,
+        magic: u64,
+        starting_point: Point,
+        era: SharedEra,
+    ) -> Result<Self, Error>
+    where
+        Cache: LedgerCache,
+    {
+        let bearer = Bearer::connect_tcp(peer).await.map_err(Error::ConnectFailure)?;
+
+        let mut mplex = multiplexer::Plexer::new(bearer);
+
+        let hs_channel = mplex.subscribe_client(PROTOCOL_N2N_HANDSHAKE);
+        let cs_channel = mplex.subscribe_client(PROTOCOL_N2N_CHAIN_SYNC);
+        let bf_channel = mplex.subscribe_client(PROTOCOL_N2N_BLOCK_FETCH);
+        let ka_channel = mplex.subscribe_client(PROTOCOL_N2N_KEEP_ALIVE);
+
+        let plexer = mplex.spawn();
 
-        debug!("Using {:?} as a starting point", best_point);
+        let versions = handshake::n2n::VersionTable::v7_and_above(magic);
+        let mut client = handshake::Client::new(hs_channel);
 
-        if let (None, _) = cs_client
-            .find_intersect(vec![best_point.into()])
+        let handshake = client
+            .handshake(versions)
             .await
-            .map_err(Error::ChainSyncProtocol)?
+            .map_err(Error::HandshakeProtocol)?;
+
+        if let handshake::Confirmation::Rejected(reason) = handshake {
+            return Err(Error::HandshakeRefused(reason));
+        }
+
+        let keep_alive = keep_alive(keepalive::Client::new(ka_channel));
+
+        let mut cs_client = chainsync::Client::new(cs_channel);
+
+        if let Err(err) =
+            find_intersection(cache, starting_point, |points| cs_client.find_intersect(points)).await
         {
-            return Err(Error::IntersectionNotFound);
+            keep_alive.abort();
+            return Err(err);
         }
 
         Ok(Self {
             plexer,
-            chain_sync: cs_client,
-            block: PhantomData::default(),
+            protocol: ChainSyncProtocol::N2N {
+                chain_sync: cs_client,
+                block_fetch: blockfetch::Client::new(bf_channel),
+                keep_alive,
+                era,
+            },
         })
     }
 
-    pub async fn try_pull_next(&mut self) -> Option<ChainUpgrade<Block>>
-    where
-        Block: Deserialize,
-    {
-        let response = match self.chain_sync.state() {
-            State::MustReply => self.chain_sync.recv_while_can_await().await,
-            _ => self.chain_sync.request_next().await,
-        };
-        match response {
-            Ok(NextResponse::RollForward(BlockContent(raw), _)) => {
-                let original_bytes = raw[BLK_START..].to_vec();
-                match Block::from_cbor_bytes(&original_bytes) {
-                    Ok(blk) => Some(ChainUpgrade::RollForward {
-                        blk,
-                        blk_bytes: original_bytes,
-                        replayed: false,
-                    }),
-                    Err(err) => panic!(
-                        "Block deserialization failed: {}, bytes: {}",
-                        err,
-                        hex::encode(original_bytes)
-                    ),
+    /// Pull next chain update, `None` means the tip is reached.
+    /// Fails if the connection to the node is broken.
+    pub async fn try_pull_next(&mut self) -> Result<Option<ChainUpgrade<MultiEraBlock>>, Error> {
+        match &mut self.protocol {
+            ChainSyncProtocol::N2C(chain_sync) => {
+                let response = match chain_sync.state() {
+                    State::MustReply => chain_sync.recv_while_can_await().await,
+                    _ => chain_sync.request_next().await,
+                };
+                match response.map_err(Error::ChainSyncProtocol)? {
+                    NextResponse::RollForward(BlockContent(raw), _) => roll_forward(raw).map(Some),
+                    NextResponse::RollBackward(pt, _) => Ok(Some(ChainUpgrade::RollBackward(pt.into()))),
+                    NextResponse::Await => Ok(None),
+                }
+            }
+            ChainSyncProtocol::N2N {
+                chain_sync,
+                block_fetch,
+                era,
+                ..
+            } => {
+                let response = match chain_sync.state() {
+                    State::MustReply => chain_sync.recv_while_can_await().await,
+                    _ => chain_sync.request_next().await,
+                };
+                match response.map_err(Error::ChainSyncProtocol)? {
+                    NextResponse::RollForward(header, _) => {
+                        let point = header_point(&header)?;
+                        // Header variant is the index of the era in the hard fork combinator.
+                        era.set(header.variant as u16);
+                        trace!("Fetching block at {:?}", point);
+                        let raw = block_fetch
+                            .fetch_single(point.into())
+                            .await
+                            .map_err(Error::BlockFetchProtocol)?;
+                        roll_forward(raw).map(Some)
+                    }
+                    NextResponse::RollBackward(pt, _) => Ok(Some(ChainUpgrade::RollBackward(pt.into()))),
+                    NextResponse::Await => Ok(None),
                 }
             }
-            Ok(NextResponse::RollBackward(pt, _)) => Some(ChainUpgrade::RollBackward(pt.into())),
-            _ => None,
         }
     }
 
     pub async fn close(self) {
+        if let ChainSyncProtocol::N2N { keep_alive, .. } = self.protocol {
+            keep_alive.abort();
+        }
         self.plexer.abort().await
     }
 }
 
-const BLK_START: usize = 2;
+/// Intersect with the node at the most recent point of the local chain it knows.
+/// Node rolls back to the intersection in response to the first request,
+/// so blocks cached past it are unapplied downstream.
+async fn find_intersection<Cache, F, Fut>(
+    cache: Arc<Mutex<Cache>>,
+    starting_point: Point,
+    find_intersect: F,
+) -> Result<(), Error>
+where
+    Cache: LedgerCache,
+    F: FnOnce(Vec<pallas_network::miniprotocols::Point>) -> Fut,
+    Fut: Future<Output = Result<(Option<pallas_network::miniprotocols::Point>, Tip), chainsync::ClientError>>,
+{
+    let candidates = intersection_candidates(cache, starting_point).await;
+    match find_intersect(candidates.into_iter().map(Into::into).collect())
+        .await
+        .map_err(Error::ChainSyncProtocol)?
+    {
+        (Some(point), _) => {
+            debug!("Intersected at {:?}", point);
+            Ok(())
+        }
+        (None, _) => Err(Error::IntersectionNotFound),
+    }
+}
+
+/// Points to intersect at, most recent first: the tip of the [LedgerCache] and blocks
+/// 1, 2, 4, .. back from it, so that the intersection is found even if the tip was rolled back
+/// while the client was offline. The configured starting point is offered as a last resort.
+async fn intersection_candidates<Cache: LedgerCache>(
+    cache: Arc<Mutex<Cache>>,
+    starting_point: Point,
+) -> Vec<Point> {
+    let cache = cache.lock().await;
+    let mut candidates = vec![];
+    if let Some(tip) = cache.get_tip().await {
+        let mut point = tip;
+        let mut depth = 0;
+        let mut next_candidate_depth = 0;
+        loop {
+            if depth == next_candidate_depth {
+                candidates.push(point);
+                next_candidate_depth = max(1, depth * 2);
+            }
+            match cache.get_block(point).await {
+                Some(LinkedBlock(_, prev_point))
+                    if prev_point != Point::Origin && depth < MAX_ROLLBACK_DEPTH =>
+                {
+                    point = prev_point;
+                    depth += 1;
+                }
+                // The deepest known point is always offered.
+                _ => {
+                    if candidates.last() != Some(&point) {
+                        candidates.push(point);
+                    }
+                    break;
+                }
+            }
+        }
+    }
+    if !candidates.contains(&starting_point) {
+        candidates.push(starting_point);
+    }
+    debug!("Offering {:?} as intersection candidates", candidates);
+    candidates
+}
+
+/// Blocks deeper than the security parameter of the chain are never rolled back.
+const MAX_ROLLBACK_DEPTH: usize = 2160;
+
+fn roll_forward(raw: Vec<u8>) -> Result<ChainUpgrade<MultiEraBlock>, Error> {
+    let blk = decode_block(&raw).map_err(|err| {
+        debug!("Undecodable block: {}", hex::encode(&raw));
+        Error::BlockDecoding(err)
+    })?;
+    Ok(ChainUpgrade::RollForward {
+        blk,
+        blk_bytes: raw,
+        replayed: false,
+    })
+}
+
+/// Point of the block the given header belongs to.
+/// Only headers of Shelley-based eras are supported, i.e. `[[block_number, slot, ..], signature]`.
+fn header_point(header: &HeaderContent) -> Result<Point, Error> {
+    if header.byron_prefix.is_some() {
+        return Err(Error::UnsupportedHeader(header.variant));
+    }
+    let mut decoder = minicbor::Decoder::new(&header.cbor);
+    let slot = decoder
+        .array()
+        .and_then(|_| decoder.array())
+        .and_then(|_| decoder.u64())
+        .and_then(|_| decoder.u64())
+        .map_err(|_| Error::UnsupportedHeader(header.variant))?;
+    Ok(Point::Specific(slot, blake2b256(&header.cbor).into()))
+}
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -122,14 +305,26 @@ pub enum Error {
     #[error("chain-sync protocol error")]
     ChainSyncProtocol(chainsync::ClientError),
 
+    #[error("block-fetch protocol error")]
+    BlockFetchProtocol(blockfetch::ClientError),
+
+    #[error("header of era {0} is not supported")]
+    UnsupportedHeader(u8),
+
+    #[error("block deserialization failed: {0}")]
+    BlockDecoding(DeserializeError),
+
     #[error("handshake version not accepted")]
     HandshakeRefused(RefuseReason),
 
     #[error("intersection not found")]
     IntersectionNotFound,
+
+    #[error("no peers to connect to")]
+    NoPeers,
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, serde::Serialize, serde::Deserialize)]
 pub enum Point {
     Origin,
     Specific(u64, BlockHeaderHash),
@@ -165,3 +360,139 @@ impl Into<Point> for pallas_network::miniprotocols::Point {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use cml_crypto::{blake2b256, BlockHeaderHash};
+    use pallas_network::miniprotocols::chainsync::{HeaderContent, Tip};
+    use tokio::sync::Mutex;
+
+    use crate::cache::{LedgerCache, LedgerCacheRocksDB, LinkedBlock};
+    use crate::client::{
+        find_intersection, header_point, intersection_candidates, roll_forward, Error, Point,
+        MAX_ROLLBACK_DEPTH,
+    };
+
+    fn point(slot: u64) -> Point {
+        Point::Specific(slot, BlockHeaderHash::from([slot as u8; 32]))
+    }
+
+    /// Cache holding a chain of blocks at slots `1..=height`.
+    async fn cached_chain(height: u64) -> Arc<Mutex<LedgerCacheRocksDB>> {
+        let dir = std::env::temp_dir().join(format!("ledger_cache_{}", rand::random::<u64>()));
+        let cache = LedgerCacheRocksDB::new(dir);
+        for slot in 1..=height {
+            let prev = if slot == 1 { Point::Origin } else { point(slot - 1) };
+            cache.put_block(point(slot), LinkedBlock(vec![], prev)).await;
+            cache.set_tip(point(slot)).await;
+        }
+        Arc::new(Mutex::new(cache))
+    }
+
+    #[tokio::test]
+    async fn only_starting_point_is_offered_without_cached_blocks() {
+        let candidates = intersection_candidates(cached_chain(0).await, point(100)).await;
+        assert_eq!(candidates, vec![point(100)]);
+    }
+
+    #[tokio::test]
+    async fn candidates_are_exponentially_spaced_down_to_the_deepest_block() {
+        let candidates = intersection_candidates(cached_chain(10).await, Point::Origin).await;
+        assert_eq!(
+            candidates,
+            vec![
+                point(10),
+                point(9),
+                point(8),
+                point(6),
+                point(2),
+                point(1),
+                Point::Origin
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn candidates_do_not_go_deeper_than_max_rollback_depth() {
+        let height = MAX_ROLLBACK_DEPTH as u64 + 100;
+        let candidates = intersection_candidates(cached_chain(height).await, point(1)).await;
+        let deepest = height - MAX_ROLLBACK_DEPTH as u64;
+        assert_eq!(candidates[candidates.len() - 2..], [point(deepest), point(1)]);
+        assert_eq!(candidates[candidates.len() - 3], point(height - 2048));
+    }
+
+    #[tokio::test]
+    async fn intersection_is_looked_up_among_candidates() {
+        let tip = Tip(pallas_network::miniprotocols::Point::Origin, 0);
+        let found = find_intersection(cached_chain(2).await, Point::Origin, |points| {
+            let intersection = points.last().cloned();
+            assert_eq!(points.len(), 3);
+            async move { Ok((intersection, tip)) }
+        })
+        .await;
+        assert!(found.is_ok());
+        let tip = Tip(pallas_network::miniprotocols::Point::Origin, 0);
+        let not_found = find_intersection(cached_chain(2).await, Point::Origin, |_| async move {
+            Ok((None, tip))
+        })
+        .await;
+        assert!(matches!(not_found, Err(Error::IntersectionNotFound)));
+    }
+
+    fn header_cbor(block_number: u64, slot: u64) -> Vec<u8> {
+        let mut encoder = minicbor::Encoder::new(vec![]);
+        encoder
+            .array(2)
+            .and_then(|e| e.array(3))
+            .and_then(|e| e.u64(block_number))
+            .and_then(|e| e.u64(slot))
+            .and_then(|e| e.bytes(&[0u8; 32]))
+            .and_then(|e| e.bytes(&[1u8; 64]))
+            .unwrap();
+        encoder.into_writer()
+    }
+
+    #[test]
+    fn header_point_is_slot_and_header_hash() {
+        let cbor = header_cbor(10_000, 123_456);
+        let header = HeaderContent {
+            variant: 6,
+            byron_prefix: None,
+            cbor: cbor.clone(),
+        };
+        assert_eq!(
+            header_point(&header).unwrap(),
+            Point::Specific(123_456, blake2b256(&cbor).into())
+        );
+    }
+
+    #[test]
+    fn byron_headers_are_not_supported() {
+        let header = HeaderContent {
+            variant: 0,
+            byron_prefix: Some((1, 0)),
+            cbor: header_cbor(1, 1),
+        };
+        assert!(matches!(header_point(&header), Err(Error::UnsupportedHeader(0))));
+    }
+
+    #[test]
+    fn malformed_headers_are_rejected() {
+        let header = HeaderContent {
+            variant: 6,
+            byron_prefix: None,
+            cbor: vec![0x01],
+        };
+        assert!(matches!(header_point(&header), Err(Error::UnsupportedHeader(6))));
+    }
+
+    #[test]
+    fn undecodable_blocks_are_reported() {
+        assert!(matches!(
+            roll_forward(vec![0x82, 0x01]),
+            Err(Error::BlockDecoding(_))
+        ));
+    }
+}
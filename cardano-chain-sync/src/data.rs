@@ -1,3 +1,6 @@
+use cml_core::Slot;
+use cml_crypto::BlockHeaderHash;
+
 use crate::client::Point;
 
 #[derive(Clone)]
@@ -8,8 +11,38 @@ pub enum LedgerBlockEvent<Block> {
 
 #[derive(Clone, Debug)]
 pub enum LedgerTxEvent<Tx> {
-    TxApplied { tx: Tx, slot: u64 },
+    /// Position is unknown for transactions which are not observed in a block, e.g. in simulations.
+    TxApplied {
+        tx: Tx,
+        slot: u64,
+        position: Option<TxPosition>,
+    },
     TxUnapplied(Tx),
+    /// All transactions of the block are applied.
+    BlockCompleted(BlockInfo),
+    /// All transactions of the block are unapplied.
+    BlockRolledBack(BlockInfo),
+    /// Chain was rolled back to the point past blocks which are no longer cached,
+    /// their transactions could not be unapplied. Handlers are expected to resync their state.
+    RollbackIncomplete(Point),
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct BlockInfo {
+    pub hash: BlockHeaderHash,
+    pub slot: Slot,
+    /// POSIX time of the block in milliseconds, derived from its slot.
+    pub time: u64,
+    /// Number of the block in the chain.
+    pub height: u64,
+}
+
+/// Location of a transaction in the chain.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct TxPosition {
+    pub block: BlockInfo,
+    /// Index of the transaction within the block, invalid transactions included.
+    pub index: usize,
 }
 
 #[derive(Clone)]
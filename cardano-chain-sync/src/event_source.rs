@@ -1,22 +1,23 @@
-use std::collections::HashSet;
 use std::pin::Pin;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 use async_stream::stream;
-use cml_core::serialization::Deserialize;
 use cml_core::Slot;
-use cml_multi_era::babbage::{BabbageBlock, BabbageTransaction};
+use cml_multi_era::MultiEraBlock;
 use futures::stream::StreamExt;
-use futures::{stream, Stream};
+use futures::{future, stream, Stream};
 use log::{info, trace, warn};
 use tokio::sync::Mutex;
 
-use spectrum_cardano_lib::hash::hash_block_header_canonical;
+use spectrum_cardano_lib::time::SlotConfig;
+use spectrum_cardano_lib::transaction::TxViewMut;
 
+use crate::block::{block_id, block_info, decode_cached_block, unpack_positioned_transactions};
 use crate::cache::{LedgerCache, LinkedBlock};
 use crate::client::Point;
 use crate::data::{ChainUpgrade, LedgerBlockEvent, LedgerTxEvent};
+use crate::metrics;
 
 /// Stream ledger updates as individual transactions.
 pub async fn ledger_transactions<'a, S, Cache>(
@@ -27,9 +28,11 @@ pub async fn ledger_transactions<'a, S, Cache>(
     // Reapply known blocks before pulling new ones.
     replay_from: Option<Point>,
     rollback_in_progress: Arc<AtomicBool>,
-) -> impl Stream<Item = LedgerTxEvent<BabbageTransaction>> + 'a
+    // Used to derive time of blocks.
+    slot_config: SlotConfig,
+) -> impl Stream<Item = LedgerTxEvent<TxViewMut>> + 'a
 where
-    S: Stream<Item = ChainUpgrade<BabbageBlock>> + 'a,
+    S: Stream<Item = ChainUpgrade<MultiEraBlock>> + 'a,
     Cache: LedgerCache + 'a,
 {
     let raw_replayed_blocks = match replay_from {
@@ -41,13 +44,11 @@ where
     };
     let replayed_blocks = raw_replayed_blocks
         .map(|LinkedBlock(raw_blk, _)| {
-            BabbageBlock::from_cbor_bytes(&raw_blk)
-                .ok()
-                .map(|blk| ChainUpgrade::RollForward {
-                    blk,
-                    blk_bytes: raw_blk,
-                    replayed: true,
-                })
+            decode_cached_block(&raw_blk).map(|blk| ChainUpgrade::RollForward {
+                blk,
+                blk_bytes: raw_blk,
+                replayed: true,
+            })
         })
         .filter_map(|result| async { result });
     replayed_blocks
@@ -58,6 +59,7 @@ where
                 u,
                 handle_rollbacks_after,
                 rollback_in_progress.clone(),
+                slot_config,
             )
         })
         .flatten()
@@ -70,9 +72,9 @@ pub fn ledger_blocks<'a, S, Cache>(
     // Rollbacks will not be handled until the specified slot is reached.
     handle_rollbacks_after: Slot,
     rollback_in_progress: Arc<AtomicBool>,
-) -> impl Stream<Item = LedgerBlockEvent<BabbageBlock>> + 'a
+) -> impl Stream<Item = LedgerBlockEvent<MultiEraBlock>> + 'a
 where
-    S: Stream<Item = ChainUpgrade<BabbageBlock>> + 'a,
+    S: Stream<Item = ChainUpgrade<MultiEraBlock>> + 'a,
     Cache: LedgerCache + 'a,
 {
     upstream.flat_map(move |u| {
@@ -87,10 +89,11 @@ where
 
 async fn process_upstream_by_txs<'a, Cache>(
     cache: Arc<Mutex<Cache>>,
-    upgr: ChainUpgrade<BabbageBlock>,
+    upgr: ChainUpgrade<MultiEraBlock>,
     handle_rollbacks_after: Slot,
     rollback_in_progress: Arc<AtomicBool>,
-) -> Pin<Box<dyn Stream<Item = LedgerTxEvent<BabbageTransaction>> + 'a>>
+    slot_config: SlotConfig,
+) -> Pin<Box<dyn Stream<Item = LedgerTxEvent<TxViewMut>> + 'a>>
 where
     Cache: LedgerCache + 'a,
 {
@@ -100,31 +103,51 @@ where
             blk_bytes,
             replayed,
         } => {
+            let Some(block) = block_info(&blk, slot_config) else {
+                warn!("Skipping block of unsupported era");
+                return Box::pin(stream::empty());
+            };
+            let (slot, hash) = (block.slot, block.hash);
             if !replayed {
-                if blk.header.header_body.slot > handle_rollbacks_after {
-                    cache_block(cache, &blk, blk_bytes).await;
+                let point = Point::Specific(slot, hash);
+                if slot > handle_rollbacks_after {
+                    cache_block(cache, point, blk_bytes).await;
                 } else {
-                    cache_point(cache, &blk).await;
+                    cache_point(cache, point).await;
                 }
             }
-            info!(
-                "Scanning Block {}",
-                hash_block_header_canonical(&blk.header).to_hex()
-            );
-            let applied_txs: Vec<_> = unpack_valid_transactions(blk)
-                .map(|(tx, slot)| LedgerTxEvent::TxApplied { tx, slot })
+            info!("Scanning Block {}", hash.to_hex());
+            metrics::on_block_applied(replayed);
+            let mut events: Vec<_> = unpack_positioned_transactions(blk, slot_config)
+                .into_iter()
+                .map(|(tx, position)| LedgerTxEvent::TxApplied {
+                    tx,
+                    slot,
+                    position: Some(position),
+                })
                 .collect();
-            Box::pin(stream::iter(applied_txs))
+            events.push(LedgerTxEvent::BlockCompleted(block));
+            Box::pin(stream::iter(events))
         }
         ChainUpgrade::RollBackward(point) if point.get_slot() > handle_rollbacks_after => {
             info!("Node requested rollback to point {:?}", point);
             Box::pin(
-                rollback(cache, point.into(), rollback_in_progress).flat_map(|blk| {
-                    let unapplied_txs: Vec<_> = unpack_valid_transactions(blk)
-                        .map(|(tx, _)| LedgerTxEvent::TxUnapplied(tx))
-                        .rev()
-                        .collect();
-                    stream::iter(unapplied_txs)
+                rollback(cache, point.into(), rollback_in_progress).flat_map(move |rolled_back| {
+                    match rolled_back {
+                        RolledBack::Block(blk) => {
+                            let block = block_info(&blk, slot_config);
+                            let mut events: Vec<_> = unpack_positioned_transactions(blk, slot_config)
+                                .into_iter()
+                                .map(|(tx, _)| LedgerTxEvent::TxUnapplied(tx))
+                                .rev()
+                                .collect();
+                            events.extend(block.map(LedgerTxEvent::BlockRolledBack));
+                            stream::iter(events)
+                        }
+                        RolledBack::Uncached(point) => {
+                            stream::iter(vec![LedgerTxEvent::RollbackIncomplete(point)])
+                        }
+                    }
                 }),
             )
         }
@@ -135,62 +158,24 @@ where
     }
 }
 
-async fn cache_block<Cache: LedgerCache>(cache: Arc<Mutex<Cache>>, blk: &BabbageBlock, blk_bytes: Vec<u8>) {
+async fn cache_block<Cache: LedgerCache>(cache: Arc<Mutex<Cache>>, point: Point, blk_bytes: Vec<u8>) {
     let cache = cache.lock().await;
-    let point = Point::Specific(
-        blk.header.header_body.slot,
-        hash_block_header_canonical(&blk.header),
-    );
     let prev_point = cache.get_tip().await.unwrap_or(Point::Origin);
     cache.set_tip(point).await;
     cache.put_block(point, LinkedBlock(blk_bytes, prev_point)).await;
 }
 
-async fn cache_point<Cache: LedgerCache>(cache: Arc<Mutex<Cache>>, blk: &BabbageBlock) {
+async fn cache_point<Cache: LedgerCache>(cache: Arc<Mutex<Cache>>, point: Point) {
     let cache = cache.lock().await;
-    let point = Point::Specific(
-        blk.header.header_body.slot,
-        hash_block_header_canonical(&blk.header),
-    );
     cache.set_tip(point).await;
 }
 
-fn unpack_valid_transactions(
-    block: BabbageBlock,
-) -> impl DoubleEndedIterator<Item = (BabbageTransaction, u64)> {
-    let BabbageBlock {
-        header,
-        transaction_bodies,
-        transaction_witness_sets,
-        mut auxiliary_data_set,
-        invalid_transactions,
-        ..
-    } = block;
-    let invalid_indices: HashSet<u16> = HashSet::from_iter(invalid_transactions);
-    transaction_bodies
-        .into_iter()
-        .zip(transaction_witness_sets)
-        .enumerate()
-        .filter(move |(ix, _)| !invalid_indices.contains(&(*ix as u16)))
-        .map(move |(ix, (tb, tw))| {
-            let tx_ix = &(ix as u16);
-            let tx = BabbageTransaction {
-                body: tb,
-                witness_set: tw,
-                is_valid: true,
-                auxiliary_data: auxiliary_data_set.remove(tx_ix),
-                encodings: None,
-            };
-            (tx, header.header_body.slot)
-        })
-}
-
 fn process_upstream_by_blocks<'a, Cache>(
     cache: Arc<Mutex<Cache>>,
-    upgr: ChainUpgrade<BabbageBlock>,
+    upgr: ChainUpgrade<MultiEraBlock>,
     handle_rollbacks_after: Slot,
     rollback_in_progress: Arc<AtomicBool>,
-) -> Pin<Box<dyn Stream<Item = LedgerBlockEvent<BabbageBlock>> + 'a>>
+) -> Pin<Box<dyn Stream<Item = LedgerBlockEvent<MultiEraBlock>> + 'a>>
 where
     Cache: LedgerCache + 'a,
 {
@@ -200,17 +185,26 @@ where
             blk_bytes,
             replayed,
         } => Box::pin(stream::once(async move {
+            metrics::on_block_applied(replayed);
             if !replayed {
-                if blk.header.header_body.slot > handle_rollbacks_after {
-                    cache_block(cache, &blk, blk_bytes).await;
-                } else {
-                    cache_point(cache, &blk).await;
+                if let Some((slot, hash)) = block_id(&blk) {
+                    let point = Point::Specific(slot, hash);
+                    if slot > handle_rollbacks_after {
+                        cache_block(cache, point, blk_bytes).await;
+                    } else {
+                        cache_point(cache, point).await;
+                    }
                 }
             }
             LedgerBlockEvent::RollForward(blk)
         })),
         ChainUpgrade::RollBackward(point) if point.get_slot() > handle_rollbacks_after => Box::pin(
-            rollback(cache, point.into(), rollback_in_progress.clone()).map(LedgerBlockEvent::RollBackward),
+            rollback(cache, point.into(), rollback_in_progress.clone()).filter_map(|rolled_back| {
+                future::ready(match rolled_back {
+                    RolledBack::Block(blk) => Some(LedgerBlockEvent::RollBackward(blk)),
+                    RolledBack::Uncached(_) => None,
+                })
+            }),
         ),
         ChainUpgrade::RollBackward(_) => {
             warn!("Node requested rollback while rollbacks are disabled.");
@@ -219,12 +213,18 @@ where
     }
 }
 
+enum RolledBack {
+    Block(MultiEraBlock),
+    /// Blocks between the last rolled back one and the given point are not cached.
+    Uncached(Point),
+}
+
 /// Handle rollback to a specific point in the past.
 fn rollback<Cache>(
     cache: Arc<Mutex<Cache>>,
     to_point: Point,
     rollback_in_progress: Arc<AtomicBool>,
-) -> impl Stream<Item = BabbageBlock>
+) -> impl Stream<Item = RolledBack>
 where
     Cache: LedgerCache,
 {
@@ -238,10 +238,16 @@ where
                     if let Some(LinkedBlock(block_bytes, prev_point)) = cache.get_block(tip.clone()).await {
                         cache.delete(tip).await;
                         cache.set_tip(prev_point).await;
-                        let block = BabbageBlock::from_cbor_bytes(&block_bytes).expect("Block deserialization failed");
-                        yield block;
+                        let block = decode_cached_block(&block_bytes).expect("Block deserialization failed");
+                        metrics::on_block_rolled_back();
+                        yield RolledBack::Block(block);
                         continue;
                     }
+                    // Blocks between the intersection and the remaining known point are unavailable,
+                    // the tip is moved anyway so that blocks are linked to the actual chain from now on.
+                    warn!("Rollback to point {:?} stopped at {:?}, blocks past it are not cached", to_point, tip);
+                    cache.set_tip(to_point).await;
+                    yield RolledBack::Uncached(to_point);
                 }
             }
             info!("Rolled back to point {:?}", to_point);
@@ -250,3 +256,54 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    use cml_crypto::BlockHeaderHash;
+    use futures::StreamExt;
+    use tokio::sync::Mutex;
+
+    use spectrum_cardano_lib::time::SlotConfig;
+
+    use crate::cache::{LedgerCache, LedgerCacheRocksDB};
+    use crate::client::Point;
+    use crate::data::{ChainUpgrade, LedgerTxEvent};
+    use crate::event_source::process_upstream_by_txs;
+
+    fn point(slot: u64) -> Point {
+        Point::Specific(slot, BlockHeaderHash::from([slot as u8; 32]))
+    }
+
+    #[tokio::test]
+    async fn rollback_past_uncached_blocks_is_signalled() {
+        let dir = std::env::temp_dir().join(format!("ledger_cache_{}", rand::random::<u64>()));
+        let cache = LedgerCacheRocksDB::new(dir);
+        // Tip is known, but the block itself was never cached, e.g. it's past the retention window.
+        cache.set_tip(point(10)).await;
+        let cache = Arc::new(Mutex::new(cache));
+        let rollback_in_progress = Arc::new(AtomicBool::new(false));
+        let events = process_upstream_by_txs(
+            Arc::clone(&cache),
+            ChainUpgrade::RollBackward(point(5)),
+            0,
+            rollback_in_progress.clone(),
+            SlotConfig {
+                zero_time: 1596059091000,
+                zero_slot: 4492800,
+                slot_length: 1000,
+            },
+        )
+        .await
+        .collect::<Vec<_>>()
+        .await;
+        assert!(matches!(
+            events.as_slice(),
+            [LedgerTxEvent::RollbackIncomplete(pt)] if *pt == point(5)
+        ));
+        assert_eq!(cache.lock().await.get_tip().await, Some(point(5)));
+        assert!(!rollback_in_progress.load(Ordering::Relaxed));
+    }
+}
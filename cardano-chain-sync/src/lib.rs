@@ -1,42 +1,72 @@
 use std::time::Duration;
 
 use async_stream::stream;
-use cml_core::serialization::Deserialize;
-use futures::lock::Mutex;
+use cml_multi_era::MultiEraBlock;
 use futures::Stream;
 use futures_timer::Delay;
-use log::trace;
+use log::{trace, warn};
 use tokio::sync::broadcast;
 
-use crate::client::ChainSyncClient;
+use spectrum_cardano_lib::connection::{Backoff, ConnectionState, ReconnectPolicy, SharedConnectionState};
+
+use crate::cache::LedgerCache;
 use crate::data::ChainUpgrade;
+use crate::supervisor::ChainSyncConnector;
 
+pub mod block;
 pub mod cache;
 pub mod client;
 pub mod data;
 pub mod event_source;
+mod metrics;
+pub mod retention;
+pub mod supervisor;
 
-pub fn chain_sync_stream<'a, Block>(
-    mut chain_sync: ChainSyncClient<Block>,
+/// Stream chain updates, reconnecting to the node whenever the connection breaks.
+/// Updates are pulled lazily, so by the time a connection is re-established all updates
+/// yielded before are processed and chain-sync is resumed from the [LedgerCache].
+pub fn chain_sync_stream<'a, Cache>(
+    connector: ChainSyncConnector<Cache>,
+    reconnect_policy: ReconnectPolicy,
+    connection: SharedConnectionState,
     tip_reached_signal: broadcast::Sender<bool>,
-) -> impl Stream<Item = ChainUpgrade<Block>> + 'a
+) -> impl Stream<Item = ChainUpgrade<MultiEraBlock>> + 'a
 where
-    Block: Deserialize + 'a,
+    Cache: LedgerCache + 'a,
 {
-    let delay_mux: Mutex<Option<Delay>> = Mutex::new(None);
     stream! {
+        let mut backoff = Backoff::new(reconnect_policy);
         loop {
-            let delay = {delay_mux.lock().await.take()};
-            if let Some(delay) = delay {
-                delay.await;
-            }
-            if let Some(upgr) = chain_sync.try_pull_next().await {
-                yield upgr;
-            } else {
-                trace!(target: "chain_sync", "Tip reached, waiting for new blocks ..");
-                *delay_mux.lock().await = Some(Delay::new(Duration::from_secs(THROTTLE_SECS)));
-                let _ = tip_reached_signal.send(true);
+            connection.set(ConnectionState::Connecting);
+            match connector.connect().await {
+                Ok(mut chain_sync) => {
+                    trace!(target: "chain_sync", "Connected to the node");
+                    connection.set(ConnectionState::Connected);
+                    metrics::on_connection_state(ConnectionState::Connected);
+                    backoff.reset();
+                    loop {
+                        match chain_sync.try_pull_next().await {
+                            Ok(Some(upgr)) => yield upgr,
+                            Ok(None) => {
+                                trace!(target: "chain_sync", "Tip reached, waiting for new blocks ..");
+                                let _ = tip_reached_signal.send(true);
+                                Delay::new(Duration::from_secs(THROTTLE_SECS)).await;
+                            }
+                            Err(err) => {
+                                warn!(target: "chain_sync", "Chain-sync is interrupted: {}", err);
+                                break;
+                            }
+                        }
+                    }
+                    chain_sync.close().await;
+                }
+                Err(err) => warn!(target: "chain_sync", "Failed to connect to the node: {}", err),
             }
+            connection.set(ConnectionState::Disconnected);
+            metrics::on_connection_state(ConnectionState::Disconnected);
+            let delay = backoff.next_delay();
+            trace!(target: "chain_sync", "Reconnecting in {:?}", delay);
+            Delay::new(delay).await;
         }
     }
 }
@@ -0,0 +1,38 @@
+use lazy_static::lazy_static;
+use prometheus::{
+    register_int_counter, register_int_counter_vec, register_int_gauge, IntCounter, IntCounterVec, IntGauge,
+};
+
+use spectrum_cardano_lib::connection::ConnectionState;
+
+lazy_static! {
+    static ref BLOCKS_APPLIED: IntCounterVec = register_int_counter_vec!(
+        "chain_sync_blocks_applied_total",
+        "Number of blocks applied to the local view of the ledger",
+        &["source"]
+    )
+    .unwrap();
+    static ref BLOCKS_ROLLED_BACK: IntCounter = register_int_counter!(
+        "chain_sync_blocks_rolled_back_total",
+        "Number of blocks rolled back from the local view of the ledger"
+    )
+    .unwrap();
+    static ref NODE_CONNECTED: IntGauge = register_int_gauge!(
+        "chain_sync_node_connected",
+        "Whether chain-sync is connected to the node (1) or not (0)"
+    )
+    .unwrap();
+}
+
+pub(crate) fn on_block_applied(replayed: bool) {
+    let source = if replayed { "cache" } else { "node" };
+    BLOCKS_APPLIED.with_label_values(&[source]).inc();
+}
+
+pub(crate) fn on_block_rolled_back() {
+    BLOCKS_ROLLED_BACK.inc();
+}
+
+pub(crate) fn on_connection_state(state: ConnectionState) {
+    NODE_CONNECTED.set((state == ConnectionState::Connected) as i64);
+}
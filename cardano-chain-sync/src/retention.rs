@@ -0,0 +1,257 @@
+use std::cmp::max;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_stream::stream;
+use cml_core::Slot;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use futures::Stream;
+use futures_timer::Delay;
+use log::{info, warn};
+use tokio::sync::Mutex;
+
+use crate::cache::{point_key, LedgerCacheRocksDB, LinkedBlock, POINT_PREFIX};
+use crate::client::Point;
+
+/// How long blocks are kept in the [LedgerCacheRocksDB].
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetentionPolicy {
+    /// Blocks this many slots behind the tip are pruned.
+    /// Blocks within the stability window are always kept as they may still be rolled back.
+    pub retain_slots: Slot,
+    pub prune_interval: Duration,
+    /// Pruned blocks are archived to this directory if set, otherwise they are dropped.
+    #[serde(default)]
+    pub archive_dir: Option<PathBuf>,
+    /// Parameters of the followed network, the stability window is derived from them.
+    #[serde(default)]
+    pub genesis: GenesisParams,
+}
+
+impl RetentionPolicy {
+    pub fn archive(&self) -> Option<BlockArchive> {
+        self.archive_dir.clone().map(BlockArchive::new)
+    }
+
+    fn retained_slots(&self) -> Slot {
+        max(self.retain_slots, self.genesis.stability_window())
+    }
+}
+
+/// Consensus parameters as given in the Shelley genesis of the network, mainnet by default.
+#[derive(Debug, Copy, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenesisParams {
+    /// Security parameter `k`, the maximum number of blocks which can be rolled back.
+    pub security_param: u64,
+    /// Active slot coefficient `f`, the fraction of slots expected to have a block.
+    pub active_slots_coeff: f64,
+}
+
+impl GenesisParams {
+    /// 3k/f slots, past which the chain can't be rolled back.
+    pub fn stability_window(&self) -> Slot {
+        (3.0 * self.security_param as f64 / self.active_slots_coeff).ceil() as Slot
+    }
+}
+
+impl Default for GenesisParams {
+    fn default() -> Self {
+        Self {
+            security_param: 2160,
+            active_slots_coeff: 0.05,
+        }
+    }
+}
+
+/// Prune the cache periodically according to the given policy.
+pub fn pruning_stream<'a>(
+    cache: Arc<Mutex<LedgerCacheRocksDB>>,
+    policy: RetentionPolicy,
+) -> impl Stream<Item = ()> + 'a {
+    stream! {
+        loop {
+            Delay::new(policy.prune_interval).await;
+            let mut pruned = 0;
+            loop {
+                // Blocks are pruned in batches so that chain-sync isn't blocked for too long.
+                let pruned_in_batch = cache.lock().await.prune(policy.retained_slots(), PRUNE_BATCH_SIZE).await;
+                pruned += pruned_in_batch;
+                if pruned_in_batch < PRUNE_BATCH_SIZE {
+                    break;
+                }
+            }
+            if pruned > 0 {
+                info!(target: "chain_sync", "{} blocks pruned from the ledger cache", pruned);
+            }
+            yield ();
+        }
+    }
+}
+
+const PRUNE_BATCH_SIZE: usize = 1000;
+
+/// Blocks pruned from the [LedgerCacheRocksDB], stored as compressed segment files.
+/// Each segment holds consecutive blocks and is named after the first slot in it,
+/// segments are written in the order blocks were pruned, i.e. from the oldest to the latest.
+#[derive(Debug, Clone)]
+pub struct BlockArchive {
+    dir: PathBuf,
+}
+
+/// Cache key of a block along with the block itself, as stored in the cache.
+pub(crate) type RawRecord = (Vec<u8>, Vec<u8>);
+
+impl BlockArchive {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Store the given records as a new segment.
+    /// Segment is written to a temporary file first, so that a partially written segment is never read.
+    pub(crate) fn write_segment(&self, first_slot: Slot, records: &[RawRecord]) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self.dir.join(segment_name(first_slot));
+        let tmp_path = path.with_extension("tmp");
+        let mut encoder = GzEncoder::new(BufWriter::new(File::create(&tmp_path)?), Compression::default());
+        bincode::serialize_into(&mut encoder, records)
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+        encoder.finish()?;
+        fs::rename(tmp_path, path)
+    }
+
+    /// Archived blocks starting from the given point, in order.
+    pub(crate) fn read_from(&self, from_point: Point) -> impl Iterator<Item = LinkedBlock> {
+        let from_slot = from_point.get_slot();
+        let from_key = point_key(POINT_PREFIX, &from_point);
+        let segments = self.segments();
+        // A segment can be skipped if the next one starts before the requested slot.
+        let first_relevant = segments
+            .iter()
+            .rposition(|(first_slot, _)| *first_slot < from_slot)
+            .unwrap_or(0);
+        segments
+            .into_iter()
+            .skip(first_relevant)
+            .flat_map(|(_, path)| match read_segment(&path) {
+                Ok(records) => records,
+                Err(err) => {
+                    warn!(target: "chain_sync", "Cannot read archived segment {:?}: {}", path, err);
+                    vec![]
+                }
+            })
+            .filter(move |(key, _)| *key >= from_key)
+            .filter_map(|(_, raw_blk)| bincode::deserialize(&raw_blk).ok())
+    }
+
+    /// Segments sorted by their first slot.
+    fn segments(&self) -> Vec<(Slot, PathBuf)> {
+        let mut segments = fs::read_dir(&self.dir)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                let first_slot = path
+                    .file_name()?
+                    .to_str()?
+                    .strip_suffix(SEGMENT_EXTENSION)?
+                    .parse()
+                    .ok()?;
+                Some((first_slot, path))
+            })
+            .collect::<Vec<_>>();
+        segments.sort();
+        segments
+    }
+}
+
+const SEGMENT_EXTENSION: &str = ".seg.gz";
+
+fn segment_name(first_slot: Slot) -> String {
+    format!("{:020}{}", first_slot, SEGMENT_EXTENSION)
+}
+
+fn read_segment(path: &Path) -> io::Result<Vec<RawRecord>> {
+    let decoder = GzDecoder::new(BufReader::new(File::open(path)?));
+    bincode::deserialize_from(decoder).map_err(|err| io::Error::new(io::ErrorKind::Other, err))
+}
+
+#[cfg(test)]
+mod tests {
+    use cml_crypto::BlockHeaderHash;
+
+    use crate::cache::{point_key, LedgerCache, LedgerCacheRocksDB, LinkedBlock, POINT_PREFIX};
+    use crate::client::Point;
+    use crate::retention::{BlockArchive, GenesisParams};
+
+    fn point(slot: u64) -> Point {
+        Point::Specific(slot, BlockHeaderHash::from([0u8; 32]))
+    }
+
+    fn record(slot: u64) -> (Vec<u8>, Vec<u8>) {
+        let blk = LinkedBlock(slot.to_be_bytes().to_vec(), point(slot - 1));
+        (
+            point_key(POINT_PREFIX, &point(slot)),
+            bincode::serialize(&blk).unwrap(),
+        )
+    }
+
+    #[test]
+    fn read_archived_blocks_from_any_slot() {
+        let dir = std::env::temp_dir().join(format!("block_archive_{}", rand::random::<u64>()));
+        let archive = BlockArchive::new(&dir);
+        archive
+            .write_segment(1, &[record(1), record(2), record(3)])
+            .unwrap();
+        archive.write_segment(4, &[record(4), record(5)]).unwrap();
+        let slots_from = |slot: u64| {
+            archive
+                .read_from(point(slot))
+                .map(|LinkedBlock(raw, _)| u64::from_be_bytes(raw.try_into().unwrap()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(slots_from(1), vec![1, 2, 3, 4, 5]);
+        assert_eq!(slots_from(3), vec![3, 4, 5]);
+        assert_eq!(slots_from(5), vec![5]);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn stability_window_is_derived_from_genesis() {
+        assert_eq!(GenesisParams::default().stability_window(), 129600);
+        let preview = GenesisParams {
+            security_param: 432,
+            active_slots_coeff: 0.05,
+        };
+        assert_eq!(preview.stability_window(), 25920);
+    }
+
+    #[tokio::test]
+    async fn blocks_are_retained_if_archiving_fails() {
+        let tmp = std::env::temp_dir();
+        let db_dir = tmp.join(format!("ledger_cache_{}", rand::random::<u64>()));
+        // Archive directory can't be created where a file exists.
+        let archive_dir = tmp.join(format!("block_archive_{}", rand::random::<u64>()));
+        std::fs::write(&archive_dir, []).unwrap();
+        let cache = LedgerCacheRocksDB::new(db_dir).with_archive(Some(BlockArchive::new(&archive_dir)));
+        for slot in 1..=3 {
+            cache
+                .put_block(point(slot), LinkedBlock(vec![], point(slot - 1)))
+                .await;
+        }
+        cache.set_tip(point(100)).await;
+        assert_eq!(cache.prune(10, 1000).await, 0);
+        for slot in 1..=3 {
+            assert!(cache.get_block(point(slot)).await.is_some());
+        }
+        std::fs::remove_file(archive_dir).unwrap();
+    }
+}
@@ -0,0 +1,73 @@
+use std::sync::Arc;
+
+use log::warn;
+use tokio::sync::Mutex;
+
+use spectrum_cardano_lib::connection::NodeAddress;
+use spectrum_cardano_lib::era::SharedEra;
+
+use crate::cache::LedgerCache;
+use crate::client::{ChainSyncClient, Error, Point};
+
+/// Everything needed to (re-)establish chain-sync with the node.
+pub struct ChainSyncConnector<Cache> {
+    cache: Arc<Mutex<Cache>>,
+    address: NodeAddress,
+    magic: u64,
+    starting_point: Point,
+    era: SharedEra,
+}
+
+impl<Cache> ChainSyncConnector<Cache> {
+    pub fn new(cache: Arc<Mutex<Cache>>, address: NodeAddress, magic: u64, starting_point: Point) -> Self {
+        Self {
+            cache,
+            address,
+            magic,
+            starting_point,
+            era: SharedEra::default(),
+        }
+    }
+
+    /// Track the era of blocks received from relays.
+    /// Local node is asked for the era over local state query instead.
+    pub fn with_era(self, era: SharedEra) -> Self {
+        Self { era, ..self }
+    }
+
+    /// Connect to the node and intersect at the most recent block of the [LedgerCache] still on chain,
+    /// so that blocks processed before the connection broke are not pulled again.
+    /// Relays are tried in turn, the error of the last one is returned if none is reachable.
+    #[cfg(not(target_os = "windows"))]
+    pub async fn connect(&self) -> Result<ChainSyncClient, Error>
+    where
+        Cache: LedgerCache,
+    {
+        match &self.address {
+            NodeAddress::Local(path) => {
+                ChainSyncClient::init(Arc::clone(&self.cache), path, self.magic, self.starting_point).await
+            }
+            NodeAddress::Remote(peers) => {
+                let mut last_err = None;
+                for peer in peers {
+                    match ChainSyncClient::init_remote(
+                        Arc::clone(&self.cache),
+                        peer.as_str(),
+                        self.magic,
+                        self.starting_point,
+                        self.era.clone(),
+                    )
+                    .await
+                    {
+                        Ok(client) => return Ok(client),
+                        Err(err) => {
+                            warn!(target: "chain_sync", "Failed to connect to {}: {}", peer, err);
+                            last_err = Some(err);
+                        }
+                    }
+                }
+                Err(last_err.unwrap_or(Error::NoPeers))
+            }
+        }
+    }
+}
@@ -0,0 +1,296 @@
+use std::io::Error;
+use std::ops::RangeInclusive;
+use std::path::Path;
+
+use cml_chain::address::Address;
+use cml_chain::builders::tx_builder::TransactionUnspentOutput;
+use cml_chain::plutus::{PlutusData, PlutusV1Script, PlutusV2Script, PlutusV3Script};
+use cml_chain::transaction::{ConwayFormatTxOut, DatumOption, TransactionInput, TransactionOutput};
+use cml_chain::{PolicyId, Script, Value};
+use cml_core::serialization::Deserialize;
+use cml_crypto::{DatumHash, TransactionHash};
+use log::warn;
+use serde::de::DeserializeOwned;
+use tokio::fs;
+
+use spectrum_cardano_lib::value::ValueExtension;
+use spectrum_cardano_lib::AssetClass::{Native, Token};
+use spectrum_cardano_lib::{AssetName, OutputRef, PaymentCredential};
+
+use crate::{CardanoNetwork, Network};
+
+const MAINNET_URL: &str = "https://cardano-mainnet.blockfrost.io/api/v0";
+const PREPROD_URL: &str = "https://cardano-preprod.blockfrost.io/api/v0";
+
+const LOVELACE_UNIT: &str = "lovelace";
+const POLICY_ID_HEX_LEN: usize = 56;
+
+pub struct Blockfrost {
+    client: reqwest::Client,
+    base_url: &'static str,
+    project_id: String,
+}
+
+impl Blockfrost {
+    pub async fn new<P: AsRef<Path>>(path: P, network: Network) -> Result<Self, Error> {
+        let project_id = fs::read_to_string(path).await?.replace("\n", "");
+        let base_url = match network {
+            Network::Preprod => PREPROD_URL,
+            Network::Mainnet => MAINNET_URL,
+        };
+        Ok(Self {
+            client: reqwest::Client::new(),
+            base_url,
+            project_id,
+        })
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: String) -> Option<T> {
+        let resp = self
+            .client
+            .get(format!("{}{}", self.base_url, path))
+            .header("project_id", self.project_id.as_str())
+            .send()
+            .await
+            .ok()?;
+        if !resp.status().is_success() {
+            warn!("Blockfrost request {} failed with status {}", path, resp.status());
+            return None;
+        }
+        resp.json::<T>().await.ok()
+    }
+
+    async fn pull_script(&self, hash: &str) -> Option<Script> {
+        let meta = self.get::<ScriptMeta>(format!("/scripts/{}", hash)).await?;
+        let cbor = self.get::<ScriptCbor>(format!("/scripts/{}/cbor", hash)).await?;
+        let raw = hex::decode(cbor.cbor?).ok()?;
+        Some(match meta.typ.as_str() {
+            "plutusV1" => Script::new_plutus_v1(PlutusV1Script::from_cbor_bytes(&*raw).ok()?),
+            "plutusV2" => Script::new_plutus_v2(PlutusV2Script::from_cbor_bytes(&*raw).ok()?),
+            "plutusV3" => Script::new_plutus_v3(PlutusV3Script::from_cbor_bytes(&*raw).ok()?),
+            other => {
+                warn!("Unsupported reference script {} of type {}", hash, other);
+                return None;
+            }
+        })
+    }
+
+    async fn restore_output(&self, out: BlockfrostTxOut) -> Option<TransactionUnspentOutput> {
+        let input = TransactionInput::new(
+            TransactionHash::from_hex(out.tx_hash.as_str()).ok()?,
+            out.output_index,
+        );
+        let datum_option = if let Some(datum) = out.inline_datum {
+            Some(DatumOption::new_datum(
+                PlutusData::from_cbor_bytes(&*hex::decode(datum).ok()?).ok()?,
+            ))
+        } else if let Some(hash) = out.data_hash {
+            Some(DatumOption::new_hash(DatumHash::from_hex(hash.as_str()).ok()?))
+        } else {
+            None
+        };
+        let script_reference = if let Some(hash) = out.reference_script_hash {
+            Some(self.pull_script(hash.as_str()).await?)
+        } else {
+            None
+        };
+        let output = TransactionOutput::new_conway_format_tx_out(ConwayFormatTxOut {
+            address: Address::from_bech32(out.address.as_str()).ok()?,
+            amount: restore_value(out.amount)?,
+            datum_option,
+            script_reference,
+            encodings: None,
+        });
+        Some(TransactionUnspentOutput::new(input, output))
+    }
+}
+
+impl CardanoNetwork for Blockfrost {
+    async fn utxo_by_ref(&self, oref: OutputRef) -> Option<TransactionUnspentOutput> {
+        let tx_hash = oref.tx_hash().to_hex();
+        let tx = self.get::<TxUtxos>(format!("/txs/{}/utxos", tx_hash)).await?;
+        let out = tx
+            .outputs
+            .into_iter()
+            .find(|o| o.output_index == oref.index() && o.consumed_by_tx.is_none())?;
+        self.restore_output(BlockfrostTxOut {
+            tx_hash,
+            output_index: out.output_index,
+            address: out.address,
+            amount: out.amount,
+            data_hash: out.data_hash,
+            inline_datum: out.inline_datum,
+            reference_script_hash: out.reference_script_hash,
+        })
+        .await
+    }
+
+    /// Blockfrost paginates by page number, an [offset] which isn't a multiple of [limit]
+    /// is served out of two adjacent pages.
+    async fn utxos_by_pay_cred(
+        &self,
+        payment_credential: PaymentCredential,
+        offset: u32,
+        limit: u16,
+    ) -> Vec<TransactionUnspentOutput> {
+        let (pages, skip) = covering_pages(offset, limit);
+        let mut outs = vec![];
+        for page in pages {
+            let path = format!(
+                "/addresses/{}/utxos?count={}&page={}",
+                String::from(payment_credential.clone()),
+                limit,
+                page
+            );
+            let batch = self.get::<Vec<BlockfrostTxOut>>(path).await.unwrap_or(vec![]);
+            let exhausted = batch.len() < limit as usize;
+            outs.extend(batch);
+            if exhausted {
+                break;
+            }
+        }
+        let mut utxos = vec![];
+        for out in outs.into_iter().skip(skip).take(limit as usize) {
+            if let Some(utxo) = self.restore_output(out).await {
+                utxos.push(utxo);
+            }
+        }
+        utxos
+    }
+}
+
+/// Pages of size [limit] holding [limit] items starting at [offset],
+/// along with the number of items to skip in the first page. Pages are numbered from 1.
+fn covering_pages(offset: u32, limit: u16) -> (RangeInclusive<u32>, usize) {
+    let size = limit.max(1) as u32;
+    let first = offset / size + 1;
+    let skip = offset % size;
+    let last = if skip == 0 { first } else { first + 1 };
+    (first..=last, skip as usize)
+}
+
+fn restore_value(amount: Vec<BlockfrostAsset>) -> Option<Value> {
+    let mut value = Value::zero();
+    for asset in amount {
+        let qty = asset.quantity.parse::<u64>().ok()?;
+        if asset.unit == LOVELACE_UNIT {
+            value.add_unsafe(Native, qty);
+        } else if asset.unit.len() >= POLICY_ID_HEX_LEN {
+            let (policy, name) = asset.unit.split_at(POLICY_ID_HEX_LEN);
+            let policy_id = PolicyId::from_hex(policy).ok()?;
+            let token_name = AssetName::try_from_hex(name)?;
+            value.add_unsafe(Token((policy_id, token_name)), qty);
+        } else {
+            return None;
+        }
+    }
+    Some(value)
+}
+
+#[derive(serde::Deserialize)]
+struct BlockfrostAsset {
+    unit: String,
+    quantity: String,
+}
+
+#[derive(serde::Deserialize)]
+struct BlockfrostTxOut {
+    tx_hash: String,
+    output_index: u64,
+    address: String,
+    amount: Vec<BlockfrostAsset>,
+    data_hash: Option<String>,
+    inline_datum: Option<String>,
+    reference_script_hash: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct TxUtxos {
+    outputs: Vec<TxUtxosOutput>,
+}
+
+#[derive(serde::Deserialize)]
+struct TxUtxosOutput {
+    output_index: u64,
+    address: String,
+    amount: Vec<BlockfrostAsset>,
+    data_hash: Option<String>,
+    inline_datum: Option<String>,
+    reference_script_hash: Option<String>,
+    consumed_by_tx: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct ScriptMeta {
+    #[serde(rename = "type")]
+    typ: String,
+}
+
+#[derive(serde::Deserialize)]
+struct ScriptCbor {
+    cbor: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use cml_chain::PolicyId;
+
+    use spectrum_cardano_lib::value::ValueExtension;
+    use spectrum_cardano_lib::AssetClass::{Native, Token};
+    use spectrum_cardano_lib::AssetName;
+
+    use crate::blockfrost::{covering_pages, restore_value, BlockfrostAsset};
+
+    const POLICY: &str = "a0028f350aaabe0545fdcb56b039bfb08e4bb4d8c4d7c3c7d481c235";
+
+    fn amount(json: &str) -> Vec<BlockfrostAsset> {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn units_are_split_into_policy_and_name() {
+        let value = restore_value(amount(&format!(
+            r#"[
+                {{"unit": "lovelace", "quantity": "2000000"}},
+                {{"unit": "{POLICY}484f534b59", "quantity": "42"}},
+                {{"unit": "{POLICY}", "quantity": "7"}}
+            ]"#
+        )))
+        .unwrap();
+        let policy = PolicyId::from_hex(POLICY).unwrap();
+        let hosky = AssetName::try_from_hex("484f534b59").unwrap();
+        let nameless = AssetName::try_from_hex("").unwrap();
+        assert_eq!(value.amount_of(Native), Some(2000000));
+        assert_eq!(value.amount_of(Token((policy, hosky))), Some(42));
+        assert_eq!(value.amount_of(Token((policy, nameless))), Some(7));
+    }
+
+    #[test]
+    fn malformed_quantities_are_rejected() {
+        for quantity in ["-1", "1.5", "18446744073709551616", ""] {
+            let json = format!(r#"[{{"unit": "lovelace", "quantity": "{quantity}"}}]"#);
+            assert!(restore_value(amount(&json)).is_none(), "{}", quantity);
+        }
+    }
+
+    #[test]
+    fn malformed_units_are_rejected() {
+        for unit in ["ada", &POLICY[..50], format!("{POLICY}zz").as_str()] {
+            let json = format!(r#"[{{"unit": "{unit}", "quantity": "1"}}]"#);
+            assert!(restore_value(amount(&json)).is_none(), "{}", unit);
+        }
+    }
+
+    #[test]
+    fn aligned_offset_is_served_out_of_one_page() {
+        assert_eq!(covering_pages(0, 50), (1..=1, 0));
+        assert_eq!(covering_pages(100, 50), (3..=3, 0));
+        assert_eq!(covering_pages(1, 1), (2..=2, 0));
+    }
+
+    #[test]
+    fn unaligned_offset_is_served_out_of_two_pages() {
+        assert_eq!(covering_pages(120, 50), (3..=4, 20));
+        assert_eq!(covering_pages(1, 2), (1..=2, 1));
+    }
+}
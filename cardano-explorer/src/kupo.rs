@@ -0,0 +1,275 @@
+use std::collections::HashMap;
+
+use cml_chain::address::Address;
+use cml_chain::builders::tx_builder::TransactionUnspentOutput;
+use cml_chain::plutus::{PlutusData, PlutusV1Script, PlutusV2Script, PlutusV3Script};
+use cml_chain::transaction::{
+    ConwayFormatTxOut, DatumOption, NativeScript, TransactionInput, TransactionOutput,
+};
+use cml_chain::{PolicyId, Script, Value};
+use cml_core::serialization::Deserialize;
+use cml_crypto::{DatumHash, TransactionHash};
+use log::warn;
+use serde::de::DeserializeOwned;
+
+use spectrum_cardano_lib::value::ValueExtension;
+use spectrum_cardano_lib::AssetClass::{Native, Token};
+use spectrum_cardano_lib::{AssetName, OutputRef, PaymentCredential};
+
+use crate::CardanoNetwork;
+
+/// Kupo chain indexer, usually run next to Ogmios.
+/// Kupo doesn't paginate matches, so pages are cut locally.
+pub struct Kupo {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl Kupo {
+    pub fn new(url: String) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: String) -> Option<T> {
+        let resp = self
+            .client
+            .get(format!("{}{}", self.url, path))
+            .send()
+            .await
+            .ok()?;
+        if !resp.status().is_success() {
+            warn!("Kupo request {} failed with status {}", path, resp.status());
+            return None;
+        }
+        resp.json::<T>().await.ok()
+    }
+
+    async fn pull_datum(&self, hash: &str) -> Option<PlutusData> {
+        let datum = self
+            .get::<Option<KupoDatum>>(format!("/datums/{}", hash))
+            .await??;
+        PlutusData::from_cbor_bytes(&*hex::decode(datum.datum).ok()?).ok()
+    }
+
+    async fn pull_script(&self, hash: &str) -> Option<Script> {
+        let script = self
+            .get::<Option<KupoScript>>(format!("/scripts/{}", hash))
+            .await??;
+        let raw = hex::decode(script.script).ok()?;
+        Some(match script.language.as_str() {
+            "native" => Script::new_native(NativeScript::from_cbor_bytes(&*raw).ok()?),
+            "plutus:v1" => Script::new_plutus_v1(PlutusV1Script::new(raw)),
+            "plutus:v2" => Script::new_plutus_v2(PlutusV2Script::new(raw)),
+            "plutus:v3" => Script::new_plutus_v3(PlutusV3Script::new(raw)),
+            other => {
+                warn!("Unsupported reference script {} of language {}", hash, other);
+                return None;
+            }
+        })
+    }
+
+    async fn restore_output(&self, m: KupoMatch) -> Option<TransactionUnspentOutput> {
+        let input = TransactionInput::new(
+            TransactionHash::from_hex(m.transaction_id.as_str()).ok()?,
+            m.output_index,
+        );
+        let datum_option = match (m.datum_type.as_deref(), m.datum_hash) {
+            (Some("inline"), Some(hash)) => {
+                Some(DatumOption::new_datum(self.pull_datum(hash.as_str()).await?))
+            }
+            (_, Some(hash)) => Some(DatumOption::new_hash(DatumHash::from_hex(hash.as_str()).ok()?)),
+            _ => None,
+        };
+        let script_reference = if let Some(hash) = m.script_hash {
+            Some(self.pull_script(hash.as_str()).await?)
+        } else {
+            None
+        };
+        let output = TransactionOutput::new_conway_format_tx_out(ConwayFormatTxOut {
+            address: Address::from_bech32(m.address.as_str()).ok()?,
+            amount: m.value.try_into_value()?,
+            datum_option,
+            script_reference,
+            encodings: None,
+        });
+        Some(TransactionUnspentOutput::new(input, output))
+    }
+}
+
+impl CardanoNetwork for Kupo {
+    async fn utxo_by_ref(&self, oref: OutputRef) -> Option<TransactionUnspentOutput> {
+        let path = format!("/matches/{}@{}?unspent", oref.index(), oref.tx_hash().to_hex());
+        let m = self.get::<Vec<KupoMatch>>(path).await?.into_iter().next()?;
+        self.restore_output(m).await
+    }
+
+    async fn utxos_by_pay_cred(
+        &self,
+        payment_credential: PaymentCredential,
+        offset: u32,
+        limit: u16,
+    ) -> Vec<TransactionUnspentOutput> {
+        let path = format!("/matches/{}/*?unspent", String::from(payment_credential));
+        let matches = self.get::<Vec<KupoMatch>>(path).await.unwrap_or(vec![]);
+        let mut utxos = vec![];
+        for m in matches.into_iter().skip(offset as usize).take(limit as usize) {
+            if let Some(utxo) = self.restore_output(m).await {
+                utxos.push(utxo);
+            }
+        }
+        utxos
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct KupoMatch {
+    transaction_id: String,
+    output_index: u64,
+    address: String,
+    value: KupoValue,
+    datum_hash: Option<String>,
+    datum_type: Option<String>,
+    script_hash: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct KupoValue {
+    coins: u64,
+    #[serde(default)]
+    assets: HashMap<String, u64>,
+}
+
+impl KupoValue {
+    /// Assets are keyed as `{policy_id}.{asset_name}`, the name part is omitted when empty.
+    fn try_into_value(self) -> Option<Value> {
+        let mut value = Value::zero();
+        value.add_unsafe(Native, self.coins);
+        for (asset, qty) in self.assets {
+            let (policy, name) = asset.split_once('.').unwrap_or((asset.as_str(), ""));
+            let policy_id = PolicyId::from_hex(policy).ok()?;
+            let token_name = AssetName::try_from_hex(name)?;
+            value.add_unsafe(Token((policy_id, token_name)), qty);
+        }
+        Some(value)
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct KupoDatum {
+    datum: String,
+}
+
+#[derive(serde::Deserialize)]
+struct KupoScript {
+    language: String,
+    script: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use cml_chain::transaction::DatumOption;
+    use cml_chain::PolicyId;
+
+    use spectrum_cardano_lib::value::ValueExtension;
+    use spectrum_cardano_lib::AssetClass::{Native, Token};
+    use spectrum_cardano_lib::{AssetName, OutputRef};
+
+    use crate::kupo::{Kupo, KupoMatch, KupoValue};
+
+    const POLICY: &str = "a0028f350aaabe0545fdcb56b039bfb08e4bb4d8c4d7c3c7d481c235";
+
+    /// Response of `/matches/{pattern}?unspent`.
+    const MATCHES: &str = r#"[
+      {
+        "transaction_index": 3,
+        "transaction_id": "ca48d512339cad1d2f6170cc9859c3a29125fa1de5923becf702bba14028be8b",
+        "output_index": 1,
+        "address": "addr_test1vz09v9yfxguvlp0zsnrpa3tdtm7el8xufp3m5lsm7qxzclgmzkket",
+        "value": {
+          "coins": 1500000,
+          "assets": {
+            "a0028f350aaabe0545fdcb56b039bfb08e4bb4d8c4d7c3c7d481c235.484f534b59": 42,
+            "a0028f350aaabe0545fdcb56b039bfb08e4bb4d8c4d7c3c7d481c235": 7
+          }
+        },
+        "datum_hash": "63f37ac0469da9317aeb145f20e0a64941411c03d861393ad62a06319248e0de",
+        "datum_type": "hash",
+        "script_hash": null,
+        "created_at": {
+          "slot_no": 51043123,
+          "header_hash": "8790b8d325dedc685f939a6ed7753675c65a6d86ef07f519eda2862ec4fb295d"
+        },
+        "spent_at": null
+      },
+      {
+        "transaction_index": 0,
+        "transaction_id": "ca48d512339cad1d2f6170cc9859c3a29125fa1de5923becf702bba14028be8b",
+        "output_index": 2,
+        "address": "addr_test1vz09v9yfxguvlp0zsnrpa3tdtm7el8xufp3m5lsm7qxzclgmzkket",
+        "value": {
+          "coins": 2000000
+        },
+        "datum_hash": null,
+        "script_hash": null,
+        "created_at": {
+          "slot_no": 51043123,
+          "header_hash": "8790b8d325dedc685f939a6ed7753675c65a6d86ef07f519eda2862ec4fb295d"
+        },
+        "spent_at": null
+      }
+    ]"#;
+
+    #[tokio::test]
+    async fn matches_are_restored_from_response() {
+        let matches: Vec<KupoMatch> = serde_json::from_str(MATCHES).unwrap();
+        // Neither of the matches refers to a datum or a script which would have to be pulled.
+        let kupo = Kupo::new("http://127.0.0.1:1442/".to_string());
+        let mut utxos = vec![];
+        for m in matches {
+            utxos.push(kupo.restore_output(m).await.unwrap());
+        }
+        let refs = utxos
+            .iter()
+            .map(|utxo| OutputRef::from(utxo.input.clone()).index())
+            .collect::<Vec<_>>();
+        assert_eq!(refs, vec![1, 2]);
+        let value = utxos[0].output.amount();
+        let policy = PolicyId::from_hex(POLICY).unwrap();
+        assert_eq!(value.amount_of(Native), Some(1500000));
+        assert_eq!(
+            value.amount_of(Token((policy, AssetName::try_from_hex("484f534b59").unwrap()))),
+            Some(42)
+        );
+        assert_eq!(
+            value.amount_of(Token((policy, AssetName::try_from_hex("").unwrap()))),
+            Some(7)
+        );
+        assert!(matches!(utxos[0].output.datum(), Some(DatumOption::Hash { .. })));
+        assert_eq!(utxos[1].output.amount().amount_of(Native), Some(2000000));
+        assert!(utxos[1].output.datum().is_none());
+    }
+
+    #[test]
+    fn malformed_assets_are_rejected() {
+        for asset in [
+            format!("{}.zz", POLICY),
+            format!("{}.{}", POLICY, "00".repeat(33)),
+            "ada".to_string(),
+        ] {
+            let json = format!(r#"{{"coins": 1, "assets": {{"{}": 1}}}}"#, asset);
+            let value: KupoValue = serde_json::from_str(&json).unwrap();
+            assert!(value.try_into_value().is_none(), "{}", asset);
+        }
+    }
+
+    #[test]
+    fn malformed_quantities_are_rejected() {
+        for qty in ["-1", "1.5", "18446744073709551616", "\"1\""] {
+            let json = format!(r#"{{"coins": 1, "assets": {{"{}": {}}}}}"#, POLICY, qty);
+            assert!(serde_json::from_str::<KupoValue>(&json).is_err(), "{}", qty);
+        }
+    }
+}
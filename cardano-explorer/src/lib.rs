@@ -12,9 +12,15 @@ use tokio::fs;
 use crate::constants::{MAINNET_PREFIX, PREPROD_PREFIX};
 use spectrum_cardano_lib::{NetworkId, OutputRef, PaymentCredential};
 
+use crate::blockfrost::Blockfrost;
+use crate::kupo::Kupo;
+use crate::local::LocalSnapshot;
 use crate::Network::{Mainnet, Preprod};
 
+pub mod blockfrost;
 pub mod client;
+pub mod kupo;
+pub mod local;
 
 pub mod constants;
 pub mod data;
@@ -107,3 +113,67 @@ impl CardanoNetwork for Maestro {
             .unwrap_or(vec![])
     }
 }
+
+/// Selects one of the [CardanoNetwork] implementations.
+#[derive(serde::Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum CardanoNetworkConfig {
+    #[serde(rename_all = "camelCase")]
+    Maestro { key_path: String },
+    #[serde(rename_all = "camelCase")]
+    Blockfrost { key_path: String },
+    #[serde(rename_all = "camelCase")]
+    Kupo { url: String },
+    #[serde(rename_all = "camelCase")]
+    Local { snapshot_path: String },
+}
+
+/// [CardanoNetwork] backend chosen at runtime.
+pub enum AnyCardanoNetwork {
+    Maestro(Maestro),
+    Blockfrost(Blockfrost),
+    Kupo(Kupo),
+    Local(LocalSnapshot),
+}
+
+impl AnyCardanoNetwork {
+    pub async fn new(config: CardanoNetworkConfig, network: Network) -> Result<Self, Error> {
+        Ok(match config {
+            CardanoNetworkConfig::Maestro { key_path } => {
+                Self::Maestro(Maestro::new(key_path, network).await?)
+            }
+            CardanoNetworkConfig::Blockfrost { key_path } => {
+                Self::Blockfrost(Blockfrost::new(key_path, network).await?)
+            }
+            CardanoNetworkConfig::Kupo { url } => Self::Kupo(Kupo::new(url)),
+            CardanoNetworkConfig::Local { snapshot_path } => {
+                Self::Local(LocalSnapshot::load(snapshot_path).await?)
+            }
+        })
+    }
+}
+
+impl CardanoNetwork for AnyCardanoNetwork {
+    async fn utxo_by_ref(&self, oref: OutputRef) -> Option<TransactionUnspentOutput> {
+        match self {
+            Self::Maestro(net) => net.utxo_by_ref(oref).await,
+            Self::Blockfrost(net) => net.utxo_by_ref(oref).await,
+            Self::Kupo(net) => net.utxo_by_ref(oref).await,
+            Self::Local(net) => net.utxo_by_ref(oref).await,
+        }
+    }
+
+    async fn utxos_by_pay_cred(
+        &self,
+        payment_credential: PaymentCredential,
+        offset: u32,
+        limit: u16,
+    ) -> Vec<TransactionUnspentOutput> {
+        match self {
+            Self::Maestro(net) => net.utxos_by_pay_cred(payment_credential, offset, limit).await,
+            Self::Blockfrost(net) => net.utxos_by_pay_cred(payment_credential, offset, limit).await,
+            Self::Kupo(net) => net.utxos_by_pay_cred(payment_credential, offset, limit).await,
+            Self::Local(net) => net.utxos_by_pay_cred(payment_credential, offset, limit).await,
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+
+use cml_chain::builders::tx_builder::TransactionUnspentOutput;
+use cml_chain::certs::StakeCredential;
+use cml_chain::transaction::TransactionOutput;
+use cml_core::serialization::Deserialize;
+use tokio::fs;
+
+use spectrum_cardano_lib::{OutputRef, PaymentCredential};
+
+use crate::CardanoNetwork;
+
+/// UTxO set loaded from a local snapshot file, handy when no third-party API is available.
+/// The snapshot is a JSON array of `{"outputRef": "<tx_hash>#<index>", "output": "<cbor hex>"}`.
+pub struct LocalSnapshot {
+    utxos: Vec<TransactionUnspentOutput>,
+}
+
+impl LocalSnapshot {
+    pub fn new(utxos: Vec<TransactionUnspentOutput>) -> Self {
+        Self { utxos }
+    }
+
+    pub async fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let raw = fs::read(path).await?;
+        let entries: Vec<SnapshotEntry> =
+            serde_json::from_slice(&raw).map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+        let mut utxos = vec![];
+        for SnapshotEntry { output_ref, output } in entries {
+            let output = hex::decode(output)
+                .ok()
+                .and_then(|bytes| TransactionOutput::from_cbor_bytes(&*bytes).ok())
+                .ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::InvalidData,
+                        format!("Invalid output at {}", output_ref),
+                    )
+                })?;
+            utxos.push(TransactionUnspentOutput::new(output_ref.into(), output));
+        }
+        Ok(Self::new(utxos))
+    }
+}
+
+impl CardanoNetwork for LocalSnapshot {
+    async fn utxo_by_ref(&self, oref: OutputRef) -> Option<TransactionUnspentOutput> {
+        self.utxos
+            .iter()
+            .find(|utxo| OutputRef::from(utxo.input.clone()) == oref)
+            .cloned()
+    }
+
+    async fn utxos_by_pay_cred(
+        &self,
+        payment_credential: PaymentCredential,
+        offset: u32,
+        limit: u16,
+    ) -> Vec<TransactionUnspentOutput> {
+        let payment_credential = String::from(payment_credential);
+        self.utxos
+            .iter()
+            .filter(|utxo| {
+                utxo.output
+                    .address()
+                    .payment_cred()
+                    .and_then(bech32_cred)
+                    .map_or(false, |cred| cred == payment_credential)
+            })
+            .skip(offset as usize)
+            .take(limit as usize)
+            .cloned()
+            .collect()
+    }
+}
+
+fn bech32_cred(cred: &StakeCredential) -> Option<String> {
+    match cred {
+        StakeCredential::PubKey { hash, .. } => hash.to_bech32("addr_vkh").ok(),
+        StakeCredential::Script { hash, .. } => hash.to_bech32("script").ok(),
+    }
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SnapshotEntry {
+    output_ref: OutputRef,
+    output: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use cml_chain::address::{Address, EnterpriseAddress};
+    use cml_chain::builders::tx_builder::TransactionUnspentOutput;
+    use cml_chain::certs::StakeCredential;
+    use cml_chain::transaction::{ConwayFormatTxOut, TransactionOutput};
+    use cml_chain::Value;
+    use cml_crypto::{Ed25519KeyHash, TransactionHash};
+
+    use spectrum_cardano_lib::OutputRef;
+
+    use crate::local::LocalSnapshot;
+    use crate::CardanoNetwork;
+
+    fn utxo(owner: Ed25519KeyHash, ix: u64) -> TransactionUnspentOutput {
+        let address = Address::Enterprise(EnterpriseAddress::new(0, StakeCredential::new_pub_key(owner)));
+        let output = TransactionOutput::new_conway_format_tx_out(ConwayFormatTxOut {
+            address,
+            amount: Value::from(5_000_000),
+            datum_option: None,
+            script_reference: None,
+            encodings: None,
+        });
+        TransactionUnspentOutput::new(
+            OutputRef::new(TransactionHash::from([0u8; 32]), ix).into(),
+            output,
+        )
+    }
+
+    #[tokio::test]
+    async fn pages_utxos_of_payment_credential() {
+        let alice = Ed25519KeyHash::from([1u8; 28]);
+        let bob = Ed25519KeyHash::from([2u8; 28]);
+        let snapshot = LocalSnapshot::new(vec![utxo(alice, 0), utxo(bob, 1), utxo(alice, 2), utxo(alice, 3)]);
+        let cred = alice.to_bech32("addr_vkh").unwrap().into();
+        let page = snapshot.utxos_by_pay_cred(cred, 1, 1).await;
+        assert_eq!(page.len(), 1);
+        assert_eq!(OutputRef::from(page[0].input.clone()).index(), 2);
+        let by_ref = snapshot
+            .utxo_by_ref(OutputRef::new(TransactionHash::from([0u8; 32]), 1))
+            .await;
+        assert!(by_ref.is_some());
+    }
+}
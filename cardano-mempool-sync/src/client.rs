@@ -1,28 +1,30 @@
-use std::collections::{HashSet, VecDeque};
-use std::marker::PhantomData;
+use std::collections::HashMap;
 use std::path::Path;
 use std::sync::Arc;
 
 use async_stream::stream;
-use cml_core::serialization::Deserialize;
-use cml_crypto::blake2b224;
 use futures::Stream;
+use log::warn;
 use pallas_network::miniprotocols::{handshake, txmonitor, PROTOCOL_N2C_HANDSHAKE};
 use pallas_network::multiplexer;
 use pallas_network::multiplexer::{Bearer, RunningPlexer};
 use tokio::sync::Mutex;
 
+use spectrum_cardano_lib::transaction::TxViewMut;
+
 use crate::data::MempoolUpdate;
+use crate::ledger::LedgerObserver;
+use crate::snapshot::{hash_tx_bytes, MempoolSnapshot};
 
-pub struct LocalTxMonitorClient<Tx> {
+pub struct LocalTxMonitorClient {
     plexer: RunningPlexer,
     tx_monitor: Arc<Mutex<MonitorState>>,
-    tx: PhantomData<Tx>,
+    ledger: LedgerObserver,
 }
 
-impl<Tx> LocalTxMonitorClient<Tx> {
+impl LocalTxMonitorClient {
     #[cfg(not(target_os = "windows"))]
-    pub async fn connect(path: impl AsRef<Path>, magic: u64) -> Result<Self, Error> {
+    pub async fn connect(path: impl AsRef<Path>, magic: u64, ledger: LedgerObserver) -> Result<Self, Error> {
         let bearer = Bearer::connect_unix(path).await.map_err(Error::ConnectFailure)?;
 
         let mut mplex = multiplexer::Plexer::new(bearer);
@@ -46,88 +48,80 @@ impl<Tx> LocalTxMonitorClient<Tx> {
 
         let state = MonitorState {
             client: txmonitor::Client::new(tm_channel),
-            filter: TxFilter::new(FILTER_CAP),
+            snapshot: MempoolSnapshot::default(),
         };
 
         Ok(Self {
             plexer,
             tx_monitor: Arc::new(Mutex::new(state)),
-            tx: PhantomData::default(),
+            ledger,
         })
     }
 
-    pub fn stream_updates<'a>(&'a self) -> impl Stream<Item = MempoolUpdate<Tx>> + 'a
-    where
-        Tx: Deserialize,
-    {
+    /// Stream mempool updates until the connection to the node breaks.
+    pub fn stream_updates<'a>(&'a self) -> impl Stream<Item = MempoolUpdate<TxViewMut>> + 'a {
         stream! {
             loop {
                 let mut tx_monitor = self.tx_monitor.lock().await;
-                if let Ok(_) = tx_monitor.client.acquire().await {
-                    loop {
-                        if let Ok(Some(raw_tx)) = tx_monitor.client.query_next_tx().await {
-                            let bytes = &*raw_tx.1;
-                            if !tx_monitor.filter.register(hash_tx_bytes(bytes)) {
-                                if let Ok(tx) = Tx::from_cbor_bytes(bytes) {
-                                    yield MempoolUpdate::TxAccepted(tx);
-                                }
+                if let Err(err) = tx_monitor.client.acquire().await {
+                    warn!("Failed to acquire mempool snapshot: {}", err);
+                    break;
+                }
+                let mut next_snapshot = HashMap::new();
+                let mut snapshot_complete = false;
+                loop {
+                    match tx_monitor.client.query_next_tx().await {
+                        Ok(Some(raw_tx)) => {
+                            let (era, bytes) = (raw_tx.0, &*raw_tx.1);
+                            let tx_hash = hash_tx_bytes(bytes);
+                            let new_tx = tx_monitor
+                                .snapshot
+                                .observe(&mut next_snapshot, tx_hash, || TxViewMut::from_era_bytes(era, bytes));
+                            if let Some(tx) = new_tx {
+                                yield MempoolUpdate::TxAccepted(tx);
                             }
-                        } else {
+                        }
+                        Ok(None) => {
+                            snapshot_complete = true;
                             break;
                         }
+                        Err(_) => break,
                     }
                 }
+                let updates = if snapshot_complete {
+                    tx_monitor.snapshot.complete(next_snapshot, &self.ledger)
+                } else {
+                    tx_monitor.snapshot.abandon(next_snapshot);
+                    tx_monitor.snapshot.settle(&self.ledger)
+                };
+                for upd in updates {
+                    yield upd;
+                }
             }
         }
     }
 
-    pub async fn close(self) {
-        self.plexer.abort().await
+    /// Mempool snapshot observed so far, to be carried over to a new connection.
+    pub async fn snapshot(&self) -> MempoolSnapshot {
+        self.tx_monitor.lock().await.snapshot.clone()
     }
-}
-
-const PROTOCOL_N2C_TX_MONITOR: u16 = 9;
-const FILTER_CAP: usize = 4096;
 
-#[derive(Copy, Clone, Eq, PartialEq, Hash)]
-struct RawTxHash([u8; 28]);
-
-struct TxFilter {
-    known_txs: HashSet<RawTxHash>,
-    eviction_queue: VecDeque<RawTxHash>,
-    cap: usize,
-}
-
-impl TxFilter {
-    fn new(cap: usize) -> Self {
-        Self {
-            known_txs: HashSet::with_capacity(cap),
-            eviction_queue: VecDeque::with_capacity(cap),
-            cap,
-        }
+    /// Resume from the snapshot observed through a previous connection,
+    /// so that txs gone in the meantime are reported and known txs are not reported again.
+    pub async fn restore_snapshot(&self, snapshot: MempoolSnapshot) {
+        self.tx_monitor.lock().await.snapshot = snapshot;
     }
-    fn register(&mut self, tx: RawTxHash) -> bool {
-        if self.known_txs.contains(&tx) {
-            return true;
-        }
-        if self.known_txs.len() > self.cap {
-            if let Some(candidate) = self.eviction_queue.pop_back() {
-                self.known_txs.remove(&candidate);
-            }
-        }
-        self.known_txs.insert(tx);
-        self.eviction_queue.push_front(tx);
-        false
+
+    pub async fn close(self) {
+        self.plexer.abort().await
     }
 }
 
-fn hash_tx_bytes(tx: &[u8]) -> RawTxHash {
-    RawTxHash(blake2b224(tx))
-}
+const PROTOCOL_N2C_TX_MONITOR: u16 = 9;
 
 struct MonitorState {
     client: txmonitor::Client,
-    filter: TxFilter,
+    snapshot: MempoolSnapshot,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -1,4 +1,9 @@
 #[derive(Debug, Clone)]
 pub enum MempoolUpdate<Tx> {
+    /// Tx entered the mempool.
     TxAccepted(Tx),
+    /// Tx left the mempool without being included in a block (expired, invalidated, etc.).
+    TxDropped(Tx),
+    /// Tx left the mempool while a competing tx spending some of the same inputs entered it.
+    TxRejected(Tx),
 }
@@ -0,0 +1,49 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use cml_crypto::TransactionHash;
+
+/// Txs recently included in blocks, as observed by chain-sync.
+/// Lets mempool-sync tell txs that left the mempool because they were included in a block
+/// from the ones dropped for other reasons.
+#[derive(Clone, Default)]
+pub struct LedgerObserver(Arc<Mutex<ObservedLedger>>);
+
+#[derive(Default)]
+struct ObservedLedger {
+    blocks_processed: u64,
+    /// Included txs along with the number of blocks processed by the time they were observed.
+    included_txs: HashMap<TransactionHash, u64>,
+}
+
+impl LedgerObserver {
+    pub fn on_tx_applied(&self, tx_hash: TransactionHash) {
+        let mut ledger = self.0.lock().unwrap();
+        let blocks_processed = ledger.blocks_processed;
+        ledger.included_txs.insert(tx_hash, blocks_processed);
+    }
+
+    pub fn on_tx_unapplied(&self, tx_hash: TransactionHash) {
+        self.0.lock().unwrap().included_txs.remove(&tx_hash);
+    }
+
+    pub fn on_block_completed(&self) {
+        let mut ledger = self.0.lock().unwrap();
+        ledger.blocks_processed += 1;
+        let blocks_processed = ledger.blocks_processed;
+        ledger
+            .included_txs
+            .retain(|_, observed_at| blocks_processed - *observed_at <= INCLUDED_TXS_RETENTION_BLOCKS);
+    }
+
+    pub(crate) fn blocks_processed(&self) -> u64 {
+        self.0.lock().unwrap().blocks_processed
+    }
+
+    pub fn is_included(&self, tx_hash: &TransactionHash) -> bool {
+        self.0.lock().unwrap().included_txs.contains_key(tx_hash)
+    }
+}
+
+/// Included txs are only looked up for drops observed around the time of inclusion.
+const INCLUDED_TXS_RETENTION_BLOCKS: u64 = 64;
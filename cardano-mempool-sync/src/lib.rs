@@ -1,23 +1,61 @@
-use cml_core::serialization::Deserialize;
-use futures::FutureExt;
-use futures::Stream;
+use async_stream::stream;
+use futures::{Stream, StreamExt};
+use futures_timer::Delay;
+use log::{trace, warn};
 use tokio::sync::broadcast;
 
-use crate::client::LocalTxMonitorClient;
+use spectrum_cardano_lib::connection::{Backoff, ConnectionState, ReconnectPolicy, SharedConnectionState};
+use spectrum_cardano_lib::transaction::TxViewMut;
+
 use crate::data::MempoolUpdate;
+use crate::snapshot::MempoolSnapshot;
+use crate::supervisor::LocalTxMonitorConnector;
 
 pub mod client;
 pub mod data;
+pub mod ledger;
+mod metrics;
+pub mod snapshot;
+pub mod supervisor;
 
-pub fn mempool_stream<'a, Tx>(
-    client: &'a LocalTxMonitorClient<Tx>,
+/// Stream mempool updates once the tip of the chain is reached,
+/// reconnecting to the node whenever the connection breaks.
+pub fn mempool_stream<'a>(
+    connector: LocalTxMonitorConnector,
+    reconnect_policy: ReconnectPolicy,
+    connection: SharedConnectionState,
     mut tip_reached_signal: broadcast::Receiver<bool>,
-) -> impl Stream<Item = MempoolUpdate<Tx>> + 'a
-where
-    Tx: Deserialize + 'a,
-{
-    let wait_signal = async move {
+) -> impl Stream<Item = MempoolUpdate<TxViewMut>> + 'a {
+    stream! {
         let _ = tip_reached_signal.recv().await;
-    };
-    wait_signal.map(move |_| client.stream_updates()).flatten_stream()
+        let mut backoff = Backoff::new(reconnect_policy);
+        let mut snapshot = MempoolSnapshot::default();
+        loop {
+            connection.set(ConnectionState::Connecting);
+            match connector.connect().await {
+                Ok(client) => {
+                    trace!("Connected to the node");
+                    connection.set(ConnectionState::Connected);
+                    metrics::on_connection_state(ConnectionState::Connected);
+                    backoff.reset();
+                    client.restore_snapshot(snapshot).await;
+                    let mut updates = Box::pin(client.stream_updates());
+                    while let Some(upd) = updates.next().await {
+                        yield upd;
+                    }
+                    drop(updates);
+                    warn!("Connection to the node is broken");
+                    snapshot = client.snapshot().await;
+                    client.close().await;
+                }
+                Err(err) => warn!("Failed to connect to the node: {}", err),
+            }
+            connection.set(ConnectionState::Disconnected);
+            metrics::on_connection_state(ConnectionState::Disconnected);
+            let delay = backoff.next_delay();
+            trace!("Reconnecting in {:?}", delay);
+            Delay::new(delay).await;
+        }
+    }
+    .inspect(metrics::on_mempool_update)
 }
@@ -0,0 +1,33 @@
+use lazy_static::lazy_static;
+use prometheus::{register_int_counter_vec, register_int_gauge, IntCounterVec, IntGauge};
+
+use spectrum_cardano_lib::connection::ConnectionState;
+
+use crate::data::MempoolUpdate;
+
+lazy_static! {
+    static ref MEMPOOL_TXS: IntCounterVec = register_int_counter_vec!(
+        "mempool_txs_total",
+        "Number of mempool transactions observed, by event",
+        &["event"]
+    )
+    .unwrap();
+    static ref NODE_CONNECTED: IntGauge = register_int_gauge!(
+        "mempool_sync_node_connected",
+        "Whether mempool-sync is connected to the node (1) or not (0)"
+    )
+    .unwrap();
+}
+
+pub(crate) fn on_mempool_update<Tx>(upd: &MempoolUpdate<Tx>) {
+    let event = match upd {
+        MempoolUpdate::TxAccepted(_) => "accepted",
+        MempoolUpdate::TxDropped(_) => "dropped",
+        MempoolUpdate::TxRejected(_) => "rejected",
+    };
+    MEMPOOL_TXS.with_label_values(&[event]).inc();
+}
+
+pub(crate) fn on_connection_state(state: ConnectionState) {
+    NODE_CONNECTED.set((state == ConnectionState::Connected) as i64);
+}
@@ -0,0 +1,224 @@
+use std::collections::{HashMap, HashSet};
+use std::mem;
+
+use cml_crypto::blake2b224;
+
+use spectrum_cardano_lib::transaction::TxViewMut;
+use spectrum_cardano_lib::OutputRef;
+
+use crate::data::MempoolUpdate;
+use crate::ledger::LedgerObserver;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct RawTxHash([u8; 28]);
+
+pub(crate) fn hash_tx_bytes(tx: &[u8]) -> RawTxHash {
+    RawTxHash(blake2b224(tx))
+}
+
+/// Txs observed in the mempool, compared snapshot to snapshot.
+#[derive(Clone, Default)]
+pub struct MempoolSnapshot {
+    /// Txs observed in the last complete snapshot.
+    txs: HashMap<RawTxHash, TxViewMut>,
+    /// Txs gone from the mempool without a conflict,
+    /// held until chain-sync shows whether they were included in a block.
+    gone_txs: Vec<GoneTx>,
+}
+
+#[derive(Clone)]
+struct GoneTx {
+    tx: TxViewMut,
+    /// Number of blocks processed by chain-sync by the time the tx was found gone.
+    observed_at: u64,
+}
+
+impl MempoolSnapshot {
+    /// Account a tx read from the next snapshot.
+    /// Returns the tx if it is new to the mempool.
+    pub(crate) fn observe<F>(
+        &mut self,
+        next: &mut HashMap<RawTxHash, TxViewMut>,
+        tx_hash: RawTxHash,
+        decode: F,
+    ) -> Option<TxViewMut>
+    where
+        F: FnOnce() -> Option<TxViewMut>,
+    {
+        if let Some(known_tx) = self.txs.remove(&tx_hash) {
+            next.insert(tx_hash, known_tx);
+            None
+        } else {
+            let tx = decode()?;
+            next.insert(tx_hash, tx.clone());
+            Some(tx)
+        }
+    }
+
+    /// Replace the snapshot with the next one, read completely.
+    /// Txs gone while a competing tx spending some of the same inputs entered the mempool are rejected,
+    /// others are dropped later unless chain-sync sees them included in a block.
+    pub(crate) fn complete(
+        &mut self,
+        next: HashMap<RawTxHash, TxViewMut>,
+        ledger: &LedgerObserver,
+    ) -> Vec<MempoolUpdate<TxViewMut>> {
+        let spent_inputs = next
+            .values()
+            .flat_map(|tx| {
+                tx.inputs
+                    .iter()
+                    .map(|i| OutputRef::from((i.transaction_id, i.index)))
+            })
+            .collect::<HashSet<_>>();
+        let observed_at = ledger.blocks_processed();
+        let mut updates = vec![];
+        for (_, tx) in mem::replace(&mut self.txs, next) {
+            let has_conflicts = tx
+                .inputs
+                .iter()
+                .any(|i| spent_inputs.contains(&OutputRef::from((i.transaction_id, i.index))));
+            if has_conflicts {
+                updates.push(MempoolUpdate::TxRejected(tx));
+            } else {
+                self.gone_txs.push(GoneTx { tx, observed_at });
+            }
+        }
+        updates.extend(self.settle(ledger));
+        updates
+    }
+
+    /// Snapshot was read partially, so we can't judge which txs are gone yet.
+    pub(crate) fn abandon(&mut self, next: HashMap<RawTxHash, TxViewMut>) {
+        self.txs.extend(next);
+    }
+
+    /// Drop gone txs which chain-sync did not see included in a block since they left the mempool.
+    /// Included txs are forgotten as chain-sync reports them on its own.
+    pub(crate) fn settle(&mut self, ledger: &LedgerObserver) -> Vec<MempoolUpdate<TxViewMut>> {
+        let blocks_processed = ledger.blocks_processed();
+        let mut dropped = vec![];
+        for gone in mem::take(&mut self.gone_txs) {
+            if ledger.is_included(&gone.tx.hash) {
+                continue;
+            }
+            if blocks_processed >= gone.observed_at + DROP_DELAY_BLOCKS {
+                dropped.push(MempoolUpdate::TxDropped(gone.tx));
+            } else {
+                self.gone_txs.push(gone);
+            }
+        }
+        dropped
+    }
+}
+
+/// Chain-sync may lag behind the mempool by a block.
+const DROP_DELAY_BLOCKS: u64 = 2;
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use cml_chain::transaction::TransactionInput;
+    use cml_crypto::TransactionHash;
+
+    use spectrum_cardano_lib::transaction::TxViewMut;
+
+    use crate::data::MempoolUpdate;
+    use crate::ledger::LedgerObserver;
+    use crate::snapshot::{MempoolSnapshot, RawTxHash};
+
+    fn tx(id: u8, spends: u8) -> (RawTxHash, TxViewMut) {
+        let tx = TxViewMut {
+            hash: TransactionHash::from([id; 32]),
+            inputs: vec![TransactionInput::new(TransactionHash::from([spends; 32]), 0)],
+            outputs: vec![],
+        };
+        (RawTxHash([id; 28]), tx)
+    }
+
+    fn read(
+        snapshot: &mut MempoolSnapshot,
+        txs: &[(RawTxHash, TxViewMut)],
+    ) -> (HashMap<RawTxHash, TxViewMut>, Vec<TxViewMut>) {
+        let mut next = HashMap::new();
+        let mut accepted = vec![];
+        for (tx_hash, tx) in txs {
+            accepted.extend(snapshot.observe(&mut next, *tx_hash, || Some(tx.clone())));
+        }
+        (next, accepted)
+    }
+
+    fn block(ledger: &LedgerObserver, txs: &[&TxViewMut]) {
+        for tx in txs {
+            ledger.on_tx_applied(tx.hash);
+        }
+        ledger.on_block_completed();
+    }
+
+    #[test]
+    fn tx_included_in_block_is_not_dropped() {
+        let ledger = LedgerObserver::default();
+        let mut snapshot = MempoolSnapshot::default();
+        let tx_1 = tx(1, 0);
+        let (next, accepted) = read(&mut snapshot, &[tx_1.clone()]);
+        assert_eq!(accepted.len(), 1);
+        assert!(snapshot.complete(next, &ledger).is_empty());
+        // The tx leaves the mempool before chain-sync processes the block including it.
+        let (next, _) = read(&mut snapshot, &[]);
+        assert!(snapshot.complete(next, &ledger).is_empty());
+        block(&ledger, &[&tx_1.1]);
+        block(&ledger, &[]);
+        assert!(snapshot.settle(&ledger).is_empty());
+    }
+
+    #[test]
+    fn tx_not_included_is_dropped_once_ledger_caught_up() {
+        let ledger = LedgerObserver::default();
+        let mut snapshot = MempoolSnapshot::default();
+        let tx_1 = tx(1, 0);
+        let (next, _) = read(&mut snapshot, &[tx_1.clone()]);
+        snapshot.complete(next, &ledger);
+        let (next, _) = read(&mut snapshot, &[]);
+        assert!(snapshot.complete(next, &ledger).is_empty());
+        block(&ledger, &[]);
+        assert!(snapshot.settle(&ledger).is_empty());
+        block(&ledger, &[]);
+        let updates = snapshot.settle(&ledger);
+        assert!(matches!(updates.as_slice(), [MempoolUpdate::TxDropped(tx)] if tx.hash == tx_1.1.hash));
+    }
+
+    #[test]
+    fn tx_replaced_by_conflicting_tx_is_rejected() {
+        let ledger = LedgerObserver::default();
+        let mut snapshot = MempoolSnapshot::default();
+        let tx_1 = tx(1, 0);
+        let competitor = tx(2, 0);
+        let (next, _) = read(&mut snapshot, &[tx_1.clone()]);
+        snapshot.complete(next, &ledger);
+        let (next, accepted) = read(&mut snapshot, &[competitor.clone()]);
+        assert_eq!(accepted.len(), 1);
+        let updates = snapshot.complete(next, &ledger);
+        assert!(matches!(updates.as_slice(), [MempoolUpdate::TxRejected(tx)] if tx.hash == tx_1.1.hash));
+    }
+
+    #[test]
+    fn partially_read_snapshot_reports_nothing_gone() {
+        let ledger = LedgerObserver::default();
+        let mut snapshot = MempoolSnapshot::default();
+        let (tx_1, tx_2) = (tx(1, 0), tx(2, 1));
+        let (next, _) = read(&mut snapshot, &[tx_1.clone(), tx_2.clone()]);
+        snapshot.complete(next, &ledger);
+        // Connection breaks after the first tx is read.
+        let (next, accepted) = read(&mut snapshot, &[tx_1.clone()]);
+        assert!(accepted.is_empty());
+        snapshot.abandon(next);
+        block(&ledger, &[]);
+        block(&ledger, &[]);
+        assert!(snapshot.settle(&ledger).is_empty());
+        // Both txs are still known, so they are not reported as accepted again.
+        let (next, accepted) = read(&mut snapshot, &[tx_1, tx_2]);
+        assert!(accepted.is_empty());
+        assert!(snapshot.complete(next, &ledger).is_empty());
+    }
+}
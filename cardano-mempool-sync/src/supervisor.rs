@@ -0,0 +1,26 @@
+use std::path::PathBuf;
+
+use crate::client::{Error, LocalTxMonitorClient};
+use crate::ledger::LedgerObserver;
+
+/// Everything needed to (re-)establish mempool monitoring with the node.
+pub struct LocalTxMonitorConnector {
+    path: PathBuf,
+    magic: u64,
+    ledger: LedgerObserver,
+}
+
+impl LocalTxMonitorConnector {
+    pub fn new(path: impl Into<PathBuf>, magic: u64, ledger: LedgerObserver) -> Self {
+        Self {
+            path: path.into(),
+            magic,
+            ledger,
+        }
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    pub async fn connect(&self) -> Result<LocalTxMonitorClient, Error> {
+        LocalTxMonitorClient::connect(&self.path, self.magic, self.ledger.clone()).await
+    }
+}
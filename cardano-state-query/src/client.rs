@@ -0,0 +1,137 @@
+use std::path::Path;
+
+use pallas_network::miniprotocols::handshake::RefuseReason;
+use pallas_network::miniprotocols::localstate::queries_v16;
+use pallas_network::miniprotocols::localstate::queries_v16::ProtocolParam;
+use pallas_network::miniprotocols::{
+    handshake, localstate, PROTOCOL_N2C_HANDSHAKE, PROTOCOL_N2C_STATE_QUERY,
+};
+use pallas_network::multiplexer;
+use pallas_network::multiplexer::{Bearer, RunningPlexer};
+
+pub type Epoch = u32;
+
+/// Index of the ledger era as understood by the hard fork combinator.
+pub type Era = u16;
+
+pub struct LocalStateQueryClient {
+    plexer: RunningPlexer,
+    state_query: localstate::Client,
+}
+
+impl LocalStateQueryClient {
+    #[cfg(not(target_os = "windows"))]
+    pub async fn connect(path: impl AsRef<Path>, magic: u64) -> Result<Self, Error> {
+        let bearer = Bearer::connect_unix(path).await.map_err(Error::ConnectFailure)?;
+
+        let mut mplex = multiplexer::Plexer::new(bearer);
+
+        let hs_channel = mplex.subscribe_client(PROTOCOL_N2C_HANDSHAKE);
+        let sq_channel = mplex.subscribe_client(PROTOCOL_N2C_STATE_QUERY);
+
+        let plexer = mplex.spawn();
+
+        let versions = handshake::n2c::VersionTable::v10_and_above(magic);
+        let mut client = handshake::Client::new(hs_channel);
+
+        let handshake = client
+            .handshake(versions)
+            .await
+            .map_err(Error::HandshakeProtocol)?;
+
+        if let handshake::Confirmation::Rejected(reason) = handshake {
+            return Err(Error::HandshakeRefused(reason));
+        }
+
+        Ok(Self {
+            plexer,
+            state_query: localstate::Client::new(sq_channel),
+        })
+    }
+
+    /// Query current era and epoch number.
+    pub async fn query_epoch(&mut self) -> Result<(Era, Epoch), Error> {
+        self.acquire().await?;
+        let epoch = self.current_epoch().await;
+        self.release().await?;
+        epoch
+    }
+
+    /// Query current era and epoch number along with protocol parameters effective in this epoch.
+    pub async fn query_protocol_params(&mut self) -> Result<(Era, Epoch, ProtocolParam), Error> {
+        self.acquire().await?;
+        let result = match self.current_epoch().await {
+            Ok((era, epoch)) => self.current_protocol_params(era).await.map(|pp| (era, epoch, pp)),
+            Err(err) => Err(err),
+        };
+        self.release().await?;
+        result
+    }
+
+    async fn current_epoch(&mut self) -> Result<(Era, Epoch), Error> {
+        let era = queries_v16::get_current_era(&mut self.state_query)
+            .await
+            .map_err(Error::StateQueryProtocol)?;
+        let epoch = queries_v16::get_block_epoch_number(&mut self.state_query, era)
+            .await
+            .map_err(Error::StateQueryProtocol)?;
+        Ok((era, epoch))
+    }
+
+    async fn current_protocol_params(&mut self, era: Era) -> Result<ProtocolParam, Error> {
+        queries_v16::get_current_pparams(&mut self.state_query, era)
+            .await
+            .map_err(Error::StateQueryProtocol)?
+            .pop()
+            .ok_or(Error::ProtocolParamsMissing)
+    }
+
+    async fn acquire(&mut self) -> Result<(), Error> {
+        self.state_query
+            .acquire(None)
+            .await
+            .map_err(Error::StateQueryProtocol)
+    }
+
+    async fn release(&mut self) -> Result<(), Error> {
+        self.state_query
+            .send_release()
+            .await
+            .map_err(Error::StateQueryProtocol)
+    }
+
+    pub async fn close(self) {
+        self.plexer.abort().await
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("error connecting bearer")]
+    ConnectFailure(#[source] tokio::io::Error),
+
+    #[error("handshake protocol error")]
+    HandshakeProtocol(handshake::Error),
+
+    #[error("state-query protocol error")]
+    StateQueryProtocol(localstate::ClientError),
+
+    #[error("handshake version not accepted")]
+    HandshakeRefused(RefuseReason),
+
+    #[error("node returned no protocol parameters")]
+    ProtocolParamsMissing,
+
+    #[error("protocol parameters are incomplete: {0} is missing")]
+    ProtocolParamsIncomplete(&'static str),
+}
+
+impl Error {
+    /// Whether the failure is caused by the connection and may go away once it's re-established.
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            Error::ConnectFailure(_) | Error::HandshakeProtocol(_) | Error::StateQueryProtocol(_)
+        )
+    }
+}
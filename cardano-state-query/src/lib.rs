@@ -0,0 +1,86 @@
+use std::time::Duration;
+
+use async_stream::stream;
+use futures::Stream;
+use futures_timer::Delay;
+use log::{info, warn};
+
+use cml_chain::builders::tx_builder::TransactionBuilderConfig;
+use spectrum_cardano_lib::era::SharedEra;
+use spectrum_cardano_lib::protocol_params::SharedTxBuilderConfig;
+
+use crate::client::{Epoch, Era, Error};
+use crate::protocol_params::tx_builder_config;
+use crate::supervisor::SupervisedLocalStateQueryClient;
+
+pub mod client;
+pub mod protocol_params;
+pub mod supervisor;
+
+/// Provides protocol parameters queried from the node along with the current era.
+/// Both can only change on epoch boundary, so they are cached per epoch.
+pub struct ProtocolParamsProvider {
+    client: SupervisedLocalStateQueryClient,
+    cached: Option<(Epoch, Era, TransactionBuilderConfig)>,
+}
+
+impl ProtocolParamsProvider {
+    pub fn new(client: SupervisedLocalStateQueryClient) -> Self {
+        Self { client, cached: None }
+    }
+
+    /// Get current era and protocol parameters effective in the current epoch.
+    /// Waits for the node to become reachable, fails only if its answer is unusable.
+    pub async fn current(&mut self) -> Result<(Era, TransactionBuilderConfig), Error> {
+        while let Err(err) = self.try_update().await {
+            if !err.is_transient() {
+                return Err(err);
+            }
+            warn!("Failed to retrieve protocol parameters: {}, retrying", err);
+        }
+        let (_, era, config) = self
+            .cached
+            .as_ref()
+            .expect("Params are cached after successful update");
+        Ok((*era, config.clone()))
+    }
+
+    /// Refresh protocol parameters if epoch has changed since the last query.
+    /// Returns updated era and parameters if they were refreshed.
+    pub async fn try_update(&mut self) -> Result<Option<(Era, TransactionBuilderConfig)>, Error> {
+        if let Some((cached_epoch, _, _)) = &self.cached {
+            let (_, epoch) = self.client.query_epoch().await?;
+            if epoch == *cached_epoch {
+                return Ok(None);
+            }
+        }
+        let (era, epoch, params) = self.client.query_protocol_params().await?;
+        let config = tx_builder_config(params)?;
+        info!("Protocol parameters updated in epoch {} (era {})", epoch, era);
+        self.cached = Some((epoch, era, config.clone()));
+        Ok(Some((era, config)))
+    }
+}
+
+/// Keep [SharedTxBuilderConfig] and [SharedEra] in sync with the node.
+pub fn protocol_params_sync_stream<'a>(
+    mut provider: ProtocolParamsProvider,
+    target: SharedTxBuilderConfig,
+    target_era: SharedEra,
+    poll_interval: Duration,
+) -> impl Stream<Item = ()> + 'a {
+    stream! {
+        loop {
+            match provider.try_update().await {
+                Ok(Some((era, config))) => {
+                    target_era.set(era);
+                    target.set(config);
+                }
+                Ok(None) => {}
+                Err(err) => warn!("Failed to update protocol parameters: {}", err),
+            }
+            Delay::new(poll_interval).await;
+            yield ();
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use cml_chain::builders::tx_builder::{TransactionBuilderConfig, TransactionBuilderConfigBuilder};
+use cml_chain::fees::LinearFee;
+use cml_chain::plutus::{CostModels, ExUnitPrices};
+use cml_chain::SubCoin;
+use cml_core::Int;
+use pallas_network::miniprotocols::localstate::queries_v16::{ProtocolParam, RationalNumber};
+
+use crate::client::Error;
+
+/// Project protocol parameters reported by the node onto [TransactionBuilderConfig].
+pub fn tx_builder_config(params: ProtocolParam) -> Result<TransactionBuilderConfig, Error> {
+    let fee_algo = LinearFee::new(
+        required(params.minfee_a, "minfee_a")? as u64,
+        required(params.minfee_b, "minfee_b")? as u64,
+    );
+    let execution_costs = required(params.execution_costs, "execution_costs")?;
+    let ex_unit_prices = ExUnitPrices::new(
+        sub_coin(execution_costs.mem_price),
+        sub_coin(execution_costs.step_price),
+    );
+    let cost_models = required(
+        params.cost_models_for_script_languages,
+        "cost_models_for_script_languages",
+    )?;
+    let mut cml_cost_models = CostModels::new();
+    cml_cost_models.plutus_v1 = cost_models
+        .plutus_v1
+        .map(|ops| ops.into_iter().map(Int::from).collect());
+    cml_cost_models.plutus_v2 = cost_models
+        .plutus_v2
+        .map(|ops| ops.into_iter().map(Int::from).collect());
+    cml_cost_models.plutus_v3 = cost_models
+        .plutus_v3
+        .map(|ops| ops.into_iter().map(Int::from).collect());
+    Ok(TransactionBuilderConfigBuilder::default()
+        .fee_algo(fee_algo)
+        .pool_deposit(required(params.pool_deposit, "pool_deposit")?.into())
+        .key_deposit(required(params.key_deposit, "key_deposit")?.into())
+        .max_value_size(required(params.max_value_size, "max_value_size")?)
+        .max_tx_size(required(params.max_transaction_size, "max_transaction_size")?)
+        .coins_per_utxo_byte(required(params.ada_per_utxo_byte, "ada_per_utxo_byte")?.into())
+        .ex_unit_prices(ex_unit_prices)
+        .collateral_percentage(required(params.collateral_percentage, "collateral_percentage")?)
+        .max_collateral_inputs(required(params.max_collateral_inputs, "max_collateral_inputs")?)
+        .cost_models(cml_cost_models)
+        .build()
+        .expect("All mandatory parameters are set"))
+}
+
+fn required<T>(param: Option<T>, name: &'static str) -> Result<T, Error> {
+    param.ok_or(Error::ProtocolParamsIncomplete(name))
+}
+
+fn sub_coin(r: RationalNumber) -> SubCoin {
+    SubCoin::new(r.numerator, r.denominator)
+}
+
+#[cfg(test)]
+mod tests {
+    use cml_core::Int;
+    use pallas_network::miniprotocols::localstate::queries_v16::{
+        CostMdls, ExUnitPrices, ProtocolParam, RationalNumber,
+    };
+
+    use crate::client::Error;
+    use crate::protocol_params::tx_builder_config;
+
+    fn rational(numerator: u64, denominator: u64) -> RationalNumber {
+        RationalNumber {
+            numerator,
+            denominator,
+        }
+    }
+
+    fn mainnet_params() -> ProtocolParam {
+        ProtocolParam {
+            minfee_a: Some(44),
+            minfee_b: Some(155381),
+            max_block_body_size: Some(90112),
+            max_transaction_size: Some(16384),
+            max_block_header_size: Some(1100),
+            key_deposit: Some(2000000),
+            pool_deposit: Some(500000000),
+            maximum_epoch: Some(18),
+            desired_number_of_stake_pools: Some(500),
+            pool_pledge_influence: Some(rational(3, 10)),
+            expansion_rate: Some(rational(3, 1000)),
+            treasury_growth_rate: Some(rational(1, 5)),
+            protocol_version: Some((9, 0)),
+            min_pool_cost: Some(170000000),
+            ada_per_utxo_byte: Some(4310),
+            cost_models_for_script_languages: Some(CostMdls {
+                plutus_v1: Some(vec![100, -1]),
+                plutus_v2: Some(vec![200]),
+                plutus_v3: None,
+            }),
+            execution_costs: Some(ExUnitPrices {
+                mem_price: rational(577, 10000),
+                step_price: rational(721, 10000000),
+            }),
+            max_tx_ex_units: None,
+            max_block_ex_units: None,
+            max_value_size: Some(5000),
+            collateral_percentage: Some(150),
+            max_collateral_inputs: Some(3),
+        }
+    }
+
+    #[test]
+    fn project_protocol_params_onto_builder_config() {
+        let config = tx_builder_config(mainnet_params()).unwrap();
+        assert_eq!(config.fee_algo.coefficient, 44);
+        assert_eq!(config.fee_algo.constant, 155381);
+        assert_eq!(config.key_deposit, 2000000);
+        assert_eq!(config.pool_deposit, 500000000);
+        assert_eq!(config.max_tx_size, 16384);
+        assert_eq!(config.max_value_size, 5000);
+        assert_eq!(config.coins_per_utxo_byte, 4310);
+        assert_eq!(config.ex_unit_prices.mem_price.numerator, 577);
+        assert_eq!(config.ex_unit_prices.mem_price.denominator, 10000);
+        assert_eq!(config.ex_unit_prices.step_price.numerator, 721);
+        assert_eq!(config.ex_unit_prices.step_price.denominator, 10000000);
+        assert_eq!(config._collateral_percentage, 150);
+        assert_eq!(config.max_collateral_inputs, 3);
+        assert_eq!(
+            config.cost_models.plutus_v1,
+            Some(vec![Int::from(100i64), Int::from(-1i64)])
+        );
+        assert_eq!(config.cost_models.plutus_v2, Some(vec![Int::from(200i64)]));
+        assert_eq!(config.cost_models.plutus_v3, None);
+    }
+
+    #[test]
+    fn missing_mandatory_param_is_reported() {
+        let params = ProtocolParam {
+            execution_costs: None,
+            ..mainnet_params()
+        };
+        assert!(matches!(
+            tx_builder_config(params),
+            Err(Error::ProtocolParamsIncomplete("execution_costs"))
+        ));
+    }
+}
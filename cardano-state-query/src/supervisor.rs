@@ -0,0 +1,99 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use futures_timer::Delay;
+use log::{trace, warn};
+use pallas_network::miniprotocols::localstate::queries_v16::ProtocolParam;
+
+use spectrum_cardano_lib::connection::{Backoff, ConnectionState, ReconnectPolicy, SharedConnectionState};
+
+use crate::client::{Epoch, Era, Error, LocalStateQueryClient};
+
+/// Local state query client which re-establishes the connection to the node on demand.
+/// Connection is established lazily and dropped once a query fails,
+/// attempts to reconnect are delayed with backoff until a query succeeds again.
+pub struct SupervisedLocalStateQueryClient {
+    path: PathBuf,
+    magic: u64,
+    client: Option<LocalStateQueryClient>,
+    backoff: Backoff,
+    reconnect_delay: Option<Duration>,
+    connection: SharedConnectionState,
+}
+
+impl SupervisedLocalStateQueryClient {
+    pub fn new(
+        path: impl Into<PathBuf>,
+        magic: u64,
+        reconnect_policy: ReconnectPolicy,
+        connection: SharedConnectionState,
+    ) -> Self {
+        Self {
+            path: path.into(),
+            magic,
+            client: None,
+            backoff: Backoff::new(reconnect_policy),
+            reconnect_delay: None,
+            connection,
+        }
+    }
+
+    /// Query current era and epoch number.
+    #[cfg(not(target_os = "windows"))]
+    pub async fn query_epoch(&mut self) -> Result<(Era, Epoch), Error> {
+        let result = self.connected().await.query_epoch().await;
+        self.on_query_result(&result).await;
+        result
+    }
+
+    /// Query current era and epoch number along with protocol parameters effective in this epoch.
+    #[cfg(not(target_os = "windows"))]
+    pub async fn query_protocol_params(&mut self) -> Result<(Era, Epoch, ProtocolParam), Error> {
+        let result = self.connected().await.query_protocol_params().await;
+        self.on_query_result(&result).await;
+        result
+    }
+
+    async fn on_query_result<T>(&mut self, result: &Result<T, Error>) {
+        match result {
+            Ok(_) => self.backoff.reset(),
+            Err(err) => {
+                warn!("Local state query failed: {}, dropping connection", err);
+                self.reset().await;
+            }
+        }
+    }
+
+    /// Drop current connection, the next query will establish a new one.
+    async fn reset(&mut self) {
+        if let Some(client) = self.client.take() {
+            client.close().await;
+        }
+        self.connection.set(ConnectionState::Disconnected);
+        self.reconnect_delay = Some(self.backoff.next_delay());
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    async fn connected(&mut self) -> &mut LocalStateQueryClient {
+        while self.client.is_none() {
+            if let Some(delay) = self.reconnect_delay.take() {
+                trace!("Reconnecting in {:?}", delay);
+                Delay::new(delay).await;
+            }
+            self.connection.set(ConnectionState::Connecting);
+            match LocalStateQueryClient::connect(&self.path, self.magic).await {
+                Ok(client) => {
+                    trace!("Connected to the node");
+                    self.connection.set(ConnectionState::Connected);
+                    self.client = Some(client);
+                }
+                Err(err) => {
+                    warn!("Failed to connect to the node: {}", err);
+                    self.connection.set(ConnectionState::Disconnected);
+                    self.reconnect_delay = Some(self.backoff.next_delay());
+                }
+            }
+        }
+        self.client.as_mut().unwrap()
+    }
+}
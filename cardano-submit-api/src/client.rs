@@ -8,20 +8,24 @@ use pallas_network::miniprotocols::handshake::RefuseReason;
 use pallas_network::miniprotocols::localtxsubmission::cardano_node_errors::ApplyTxError;
 use pallas_network::miniprotocols::localtxsubmission::{EraTx, NodeErrorDecoder, Response};
 use pallas_network::miniprotocols::{
-    handshake, localtxsubmission, PROTOCOL_N2C_HANDSHAKE, PROTOCOL_N2C_TX_SUBMISSION,
+    handshake, localtxsubmission, txsubmission, PROTOCOL_N2C_HANDSHAKE, PROTOCOL_N2C_TX_SUBMISSION,
 };
 use pallas_network::multiplexer;
 use pallas_network::multiplexer::{Bearer, RunningPlexer};
 
-pub struct LocalTxSubmissionClient<'a, const ERA: u16, Tx> {
+use spectrum_cardano_lib::era::SharedEra;
+
+pub struct LocalTxSubmissionClient<'a, Tx> {
     plexer: RunningPlexer,
     tx_submission: localtxsubmission::Client<'a, NodeErrorDecoder>,
+    /// Transactions are always submitted in the current era of the node.
+    era: SharedEra,
     tx: PhantomData<Tx>,
 }
 
-impl<'a, const ERA: u16, Tx> LocalTxSubmissionClient<'a, ERA, Tx> {
+impl<'a, Tx> LocalTxSubmissionClient<'a, Tx> {
     #[cfg(not(target_os = "windows"))]
-    pub async fn init(path: impl AsRef<Path>, magic: u64) -> Result<Self, Error> {
+    pub async fn init(path: impl AsRef<Path>, magic: u64, era: SharedEra) -> Result<Self, Error> {
         let bearer = Bearer::connect_unix(path).await.map_err(Error::ConnectFailure)?;
 
         let mut mplex = multiplexer::Plexer::new(bearer);
@@ -48,6 +52,7 @@ impl<'a, const ERA: u16, Tx> LocalTxSubmissionClient<'a, ERA, Tx> {
         Ok(Self {
             plexer,
             tx_submission: ts_client,
+            era,
             tx: PhantomData::default(),
         })
     }
@@ -58,10 +63,11 @@ impl<'a, const ERA: u16, Tx> LocalTxSubmissionClient<'a, ERA, Tx> {
     {
         let tx_bytes = tx.to_cbor_bytes();
         let hash = hex::encode(&blake2b256(&tx_bytes)[0..8]);
-        trace!("[{}] Going to submit TX", hash);
+        let era = self.era.get();
+        trace!("[{}] Going to submit TX in era {}", hash, era);
         let result = self
             .tx_submission
-            .submit_tx(EraTx(ERA, tx_bytes))
+            .submit_tx(EraTx(era, tx_bytes))
             .await
             .map_err(Error::TxSubmissionProtocol);
         trace!("[{}] Submit attempt finished", hash);
@@ -90,4 +96,16 @@ pub enum Error {
 
     #[error("handshake version not accepted")]
     HandshakeRefused(RefuseReason),
+
+    #[error("tx-submission2 protocol error")]
+    TxPropagationProtocol(#[source] txsubmission::Error),
+
+    #[error("malformed transaction")]
+    TxDecoding,
+
+    #[error("peer disconnected")]
+    PeerDisconnected,
+
+    #[error("no peers to connect to")]
+    NoPeers,
 }
@@ -1,6 +1,8 @@
 use cml_chain::transaction::Transaction;
 
 pub mod client;
+pub mod peer;
+pub mod supervisor;
 
 pub struct SubmitTxFailure;
 
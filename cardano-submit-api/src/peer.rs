@@ -0,0 +1,226 @@
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+
+use cml_core::serialization::Serialize;
+use cml_crypto::blake2b256;
+use log::{trace, warn};
+use pallas_network::miniprotocols::localtxsubmission::cardano_node_errors::ApplyTxError;
+use pallas_network::miniprotocols::localtxsubmission::Response;
+use pallas_network::miniprotocols::txsubmission::{EraTxBody, EraTxId, Request, TxIdAndSize};
+use pallas_network::miniprotocols::{
+    handshake, keepalive, txsubmission, PROTOCOL_N2N_HANDSHAKE, PROTOCOL_N2N_KEEP_ALIVE,
+    PROTOCOL_N2N_TX_SUBMISSION,
+};
+use pallas_network::multiplexer;
+use pallas_network::multiplexer::{Bearer, RunningPlexer};
+use tokio::net::ToSocketAddrs;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+
+use spectrum_cardano_lib::connection::keep_alive;
+use spectrum_cardano_lib::era::SharedEra;
+
+use crate::client::Error;
+
+/// Propagates transactions to a relay over the node-to-node TxSubmission2 protocol.
+/// The protocol is driven by the relay, which pulls announced transactions at its own pace,
+/// so requests of the relay are served by a background task.
+/// Unlike local submission, relays do not report whether a transaction is valid.
+pub struct PeerTxSubmissionClient<Tx> {
+    plexer: RunningPlexer,
+    keep_alive: JoinHandle<()>,
+    propagation: JoinHandle<()>,
+    outbox: mpsc::UnboundedSender<OutboundTx>,
+    /// Transactions are always submitted in the current era of the node.
+    era: SharedEra,
+    tx: PhantomData<Tx>,
+}
+
+impl<Tx> PeerTxSubmissionClient<Tx> {
+    pub async fn init(peer: impl ToSocketAddrs, magic: u64, era: SharedEra) -> Result<Self, Error> {
+        let bearer = Bearer::connect_tcp(peer).await.map_err(Error::ConnectFailure)?;
+
+        let mut mplex = multiplexer::Plexer::new(bearer);
+
+        let hs_channel = mplex.subscribe_client(PROTOCOL_N2N_HANDSHAKE);
+        let ts_channel = mplex.subscribe_client(PROTOCOL_N2N_TX_SUBMISSION);
+        let ka_channel = mplex.subscribe_client(PROTOCOL_N2N_KEEP_ALIVE);
+
+        let plexer = mplex.spawn();
+
+        let versions = handshake::n2n::VersionTable::v7_and_above(magic);
+        let mut client = handshake::Client::new(hs_channel);
+
+        let handshake = client
+            .handshake(versions)
+            .await
+            .map_err(Error::HandshakeProtocol)?;
+
+        if let handshake::Confirmation::Rejected(reason) = handshake {
+            return Err(Error::HandshakeRefused(reason));
+        }
+
+        let mut ts_client = txsubmission::Client::new(ts_channel);
+        ts_client
+            .send_init()
+            .await
+            .map_err(Error::TxPropagationProtocol)?;
+
+        let (outbox, inbox) = mpsc::unbounded_channel();
+
+        Ok(Self {
+            plexer,
+            keep_alive: keep_alive(keepalive::Client::new(ka_channel)),
+            propagation: tokio::spawn(propagate(ts_client, inbox)),
+            outbox,
+            era,
+            tx: PhantomData::default(),
+        })
+    }
+
+    /// Announce the transaction to the relay.
+    /// Resolves once the relay pulled the transaction or acknowledged it as already known.
+    pub async fn submit_tx(&mut self, tx: Tx) -> Result<Response<Vec<ApplyTxError>>, Error>
+    where
+        Tx: Serialize,
+    {
+        let tx_bytes = tx.to_cbor_bytes();
+        let tx_id = tx_id(&tx_bytes).map_err(|_| Error::TxDecoding)?;
+        let era = self.era.get();
+        trace!(
+            "[{}] Going to propagate TX in era {}",
+            hex::encode(&tx_id[0..8]),
+            era
+        );
+        let (on_propagated, propagated) = oneshot::channel();
+        self.outbox
+            .send(OutboundTx {
+                id: EraTxId(era, tx_id.to_vec()),
+                size: tx_bytes.len() as u32,
+                body: EraTxBody(era, tx_bytes),
+                on_propagated: Some(on_propagated),
+            })
+            .map_err(|_| Error::PeerDisconnected)?;
+        propagated.await.map_err(|_| Error::PeerDisconnected)?;
+        Ok(Response::Accepted)
+    }
+
+    pub async fn close(self) {
+        self.keep_alive.abort();
+        self.propagation.abort();
+        self.plexer.abort().await
+    }
+}
+
+struct OutboundTx {
+    id: EraTxId,
+    size: u32,
+    body: EraTxBody,
+    on_propagated: Option<oneshot::Sender<()>>,
+}
+
+impl OutboundTx {
+    fn propagated(&mut self) {
+        if let Some(on_propagated) = self.on_propagated.take() {
+            let _ = on_propagated.send(());
+        }
+    }
+}
+
+/// Serve requests of the relay until the connection breaks.
+/// Pending submissions fail once their responders are dropped.
+async fn propagate(mut client: txsubmission::Client, mut inbox: mpsc::UnboundedReceiver<OutboundTx>) {
+    let mut unacknowledged = VecDeque::<OutboundTx>::new();
+    loop {
+        let request = match client.next_request().await {
+            Ok(request) => request,
+            Err(err) => {
+                warn!("Relay stopped pulling transactions: {}", err);
+                break;
+            }
+        };
+        let result = match request {
+            Request::TxIds(ack, req) => {
+                acknowledge(&mut unacknowledged, ack);
+                // Blocking request is answered only when there is something to announce.
+                let Some(first) = inbox.recv().await else {
+                    break;
+                };
+                let mut announced = vec![first];
+                announced.extend(ready_txs(&mut inbox, req.saturating_sub(1)));
+                announce(&mut client, &mut unacknowledged, announced).await
+            }
+            Request::TxIdsNonBlocking(ack, req) => {
+                acknowledge(&mut unacknowledged, ack);
+                let announced = ready_txs(&mut inbox, req);
+                announce(&mut client, &mut unacknowledged, announced).await
+            }
+            Request::Txs(ids) => {
+                let mut bodies = vec![];
+                for tx in unacknowledged.iter_mut().filter(|tx| ids.contains(&tx.id)) {
+                    bodies.push(tx.body.clone());
+                    tx.propagated();
+                }
+                client.reply_txs(bodies).await
+            }
+        };
+        if let Err(err) = result {
+            warn!("Failed to reply to the relay: {}", err);
+            break;
+        }
+    }
+}
+
+/// Transactions acknowledged by the relay are either pulled already or known to it.
+fn acknowledge(unacknowledged: &mut VecDeque<OutboundTx>, ack: u16) {
+    let ack = (ack as usize).min(unacknowledged.len());
+    for mut tx in unacknowledged.drain(..ack) {
+        tx.propagated();
+    }
+}
+
+fn ready_txs(inbox: &mut mpsc::UnboundedReceiver<OutboundTx>, limit: u16) -> Vec<OutboundTx> {
+    let mut txs = vec![];
+    while txs.len() < limit as usize {
+        match inbox.try_recv() {
+            Ok(tx) => txs.push(tx),
+            Err(_) => break,
+        }
+    }
+    txs
+}
+
+async fn announce(
+    client: &mut txsubmission::Client,
+    unacknowledged: &mut VecDeque<OutboundTx>,
+    txs: Vec<OutboundTx>,
+) -> Result<(), txsubmission::Error> {
+    let ids = txs.iter().map(|tx| TxIdAndSize(tx.id.clone(), tx.size)).collect();
+    unacknowledged.extend(txs);
+    client.reply_tx_ids(ids).await
+}
+
+/// Id of a transaction is the hash of its body exactly as it is encoded in the transaction.
+fn tx_id(tx_bytes: &[u8]) -> Result<[u8; 32], minicbor::decode::Error> {
+    let mut decoder = minicbor::Decoder::new(tx_bytes);
+    decoder.array()?;
+    let body_start = decoder.position();
+    decoder.skip()?;
+    Ok(blake2b256(&tx_bytes[body_start..decoder.position()]))
+}
+
+#[cfg(test)]
+mod tests {
+    use cml_crypto::blake2b256;
+
+    use crate::peer::tx_id;
+
+    #[test]
+    fn tx_id_is_hash_of_original_body_bytes() {
+        let body = [0xa1, 0x02, 0x18, 0x64];
+        let mut tx = vec![0x84];
+        tx.extend_from_slice(&body);
+        tx.extend_from_slice(&[0xa0, 0xf5, 0xf6]);
+        assert_eq!(tx_id(&tx).unwrap(), blake2b256(&body));
+    }
+}
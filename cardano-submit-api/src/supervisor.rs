@@ -0,0 +1,114 @@
+use cml_core::serialization::Serialize;
+use futures_timer::Delay;
+use log::{trace, warn};
+use pallas_network::miniprotocols::localtxsubmission::cardano_node_errors::ApplyTxError;
+use pallas_network::miniprotocols::localtxsubmission::Response;
+
+use spectrum_cardano_lib::connection::{
+    Backoff, ConnectionState, NodeAddress, ReconnectPolicy, SharedConnectionState,
+};
+use spectrum_cardano_lib::era::SharedEra;
+
+use crate::client::{Error, LocalTxSubmissionClient};
+use crate::peer::PeerTxSubmissionClient;
+
+/// Tx submission client which re-establishes the connection to the node on demand.
+/// Connection is established lazily and retried with backoff until the node is reachable.
+pub struct SupervisedTxSubmissionClient<'a, Tx> {
+    address: NodeAddress,
+    magic: u64,
+    era: SharedEra,
+    client: Option<AnyTxSubmissionClient<'a, Tx>>,
+    backoff: Backoff,
+    connection: SharedConnectionState,
+}
+
+impl<'a, Tx> SupervisedTxSubmissionClient<'a, Tx> {
+    pub fn new(
+        address: NodeAddress,
+        magic: u64,
+        era: SharedEra,
+        reconnect_policy: ReconnectPolicy,
+        connection: SharedConnectionState,
+    ) -> Self {
+        Self {
+            address,
+            magic,
+            era,
+            client: None,
+            backoff: Backoff::new(reconnect_policy),
+            connection,
+        }
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    pub async fn submit_tx(&mut self, tx: Tx) -> Result<Response<Vec<ApplyTxError>>, Error>
+    where
+        Tx: Serialize,
+    {
+        match self.connected().await {
+            AnyTxSubmissionClient::Local(client) => client.submit_tx(tx).await,
+            AnyTxSubmissionClient::Peer(client) => client.submit_tx(tx).await,
+        }
+    }
+
+    /// Drop current connection, the next submission will establish a new one.
+    pub async fn reset(&mut self) {
+        match self.client.take() {
+            Some(AnyTxSubmissionClient::Local(client)) => client.close().await,
+            Some(AnyTxSubmissionClient::Peer(client)) => client.close().await,
+            None => {}
+        }
+        self.connection.set(ConnectionState::Disconnected);
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    async fn connected(&mut self) -> &mut AnyTxSubmissionClient<'a, Tx> {
+        while self.client.is_none() {
+            self.connection.set(ConnectionState::Connecting);
+            match self.connect().await {
+                Ok(client) => {
+                    trace!("Connected to the node");
+                    self.connection.set(ConnectionState::Connected);
+                    self.backoff.reset();
+                    self.client = Some(client);
+                }
+                Err(err) => {
+                    self.connection.set(ConnectionState::Disconnected);
+                    let delay = self.backoff.next_delay();
+                    warn!("Failed to connect to the node: {}, retrying in {:?}", err, delay);
+                    Delay::new(delay).await;
+                }
+            }
+        }
+        self.client.as_mut().unwrap()
+    }
+
+    /// Relays are tried in turn, the error of the last one is returned if none is reachable.
+    #[cfg(not(target_os = "windows"))]
+    async fn connect(&self) -> Result<AnyTxSubmissionClient<'a, Tx>, Error> {
+        match &self.address {
+            NodeAddress::Local(path) => LocalTxSubmissionClient::init(path, self.magic, self.era.clone())
+                .await
+                .map(AnyTxSubmissionClient::Local),
+            NodeAddress::Remote(peers) => {
+                let mut last_err = None;
+                for peer in peers {
+                    match PeerTxSubmissionClient::init(peer.as_str(), self.magic, self.era.clone()).await {
+                        Ok(client) => return Ok(AnyTxSubmissionClient::Peer(client)),
+                        Err(err) => {
+                            warn!("Failed to connect to {}: {}", peer, err);
+                            last_err = Some(err);
+                        }
+                    }
+                }
+                Err(last_err.unwrap_or(Error::NoPeers))
+            }
+        }
+    }
+}
+
+enum AnyTxSubmissionClient<'a, Tx> {
+    Local(LocalTxSubmissionClient<'a, Tx>),
+    Peer(PeerTxSubmissionClient<Tx>),
+}
@@ -6,7 +6,7 @@ use cml_chain::certs::{Credential, StakeCredential};
 use cml_chain::plutus::PlutusData;
 use cml_crypto::{Ed25519KeyHash, RawBytesEncoding, ScriptHash};
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
 pub enum PlutusCredential {
     PubKey(Ed25519KeyHash),
     Script(ScriptHash),
@@ -35,7 +35,7 @@ impl From<PlutusCredential> for Credential {
     }
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct InlineCredential(PlutusCredential);
 impl TryFromPData for InlineCredential {
     fn try_from_pd(data: PlutusData) -> Option<Self> {
@@ -49,7 +49,7 @@ impl TryFromPData for InlineCredential {
     }
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct PlutusAddress {
     pub payment_cred: PlutusCredential,
     pub stake_cred: Option<InlineCredential>,
@@ -0,0 +1,23 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use cml_core::Slot;
+
+/// Slot of the latest block applied to the local view of the ledger.
+/// Shared between the component following the chain and the components validating transactions.
+#[derive(Debug, Clone, Default)]
+pub struct ChainTip(Arc<AtomicU64>);
+
+impl ChainTip {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self) -> Slot {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    pub fn set(&self, slot: Slot) {
+        self.0.store(slot, Ordering::Relaxed)
+    }
+}
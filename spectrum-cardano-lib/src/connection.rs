@@ -0,0 +1,151 @@
+use std::cmp::min;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use log::warn;
+use pallas_network::miniprotocols::keepalive;
+use tokio::task::JoinHandle;
+
+/// State of a connection to the node.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, derive_more::Display, serde::Serialize)]
+pub enum ConnectionState {
+    Connecting,
+    Connected,
+    Disconnected,
+}
+
+impl From<u8> for ConnectionState {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => ConnectionState::Connected,
+            2 => ConnectionState::Disconnected,
+            _ => ConnectionState::Connecting,
+        }
+    }
+}
+
+impl From<ConnectionState> for u8 {
+    fn from(value: ConnectionState) -> Self {
+        match value {
+            ConnectionState::Connecting => 0,
+            ConnectionState::Connected => 1,
+            ConnectionState::Disconnected => 2,
+        }
+    }
+}
+
+/// Connection state shared between a supervised node client
+/// and the components which depend on the connection.
+#[derive(Debug, Clone)]
+pub struct SharedConnectionState(Arc<AtomicU8>);
+
+impl SharedConnectionState {
+    pub fn new() -> Self {
+        Self(Arc::new(AtomicU8::new(ConnectionState::Connecting.into())))
+    }
+
+    pub fn get(&self) -> ConnectionState {
+        self.0.load(Ordering::Relaxed).into()
+    }
+
+    pub fn set(&self, state: ConnectionState) {
+        self.0.store(state.into(), Ordering::Relaxed);
+    }
+}
+
+impl Default for SharedConnectionState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Where node clients connect to.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum NodeAddress {
+    /// Unix socket of a local node, spoken to over node-to-client protocols.
+    Local(PathBuf),
+    /// Relays spoken to over node-to-node protocols, tried in turn until one is reachable.
+    Remote(Vec<String>),
+}
+
+/// How broken connections to the node are re-established.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReconnectPolicy {
+    /// Delay before the first reconnection attempt.
+    pub min_delay: Duration,
+    /// Delay between attempts doubles until it reaches this value.
+    pub max_delay: Duration,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            min_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Exponentially growing delays between reconnection attempts.
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    policy: ReconnectPolicy,
+    next_delay: Duration,
+}
+
+impl Backoff {
+    pub fn new(policy: ReconnectPolicy) -> Self {
+        Self {
+            policy,
+            next_delay: policy.min_delay,
+        }
+    }
+
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self.next_delay;
+        self.next_delay = min(delay * 2, self.policy.max_delay);
+        delay
+    }
+
+    /// Start over once the connection is re-established.
+    pub fn reset(&mut self) {
+        self.next_delay = self.policy.min_delay;
+    }
+}
+
+/// Relays drop connections which stay silent for too long.
+pub fn keep_alive(mut client: keepalive::Client) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(KEEP_ALIVE_INTERVAL).await;
+            if let Err(err) = client.keepalive_roundtrip().await {
+                warn!("Keep-alive failed: {}", err);
+                break;
+            }
+        }
+    })
+}
+
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(20);
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::connection::{Backoff, ReconnectPolicy};
+
+    #[test]
+    fn backoff_doubles_up_to_max_delay() {
+        let mut backoff = Backoff::new(ReconnectPolicy {
+            min_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(5),
+        });
+        let delays = (0..4).map(|_| backoff.next_delay().as_secs()).collect::<Vec<_>>();
+        assert_eq!(delays, vec![1, 2, 4, 5]);
+        backoff.reset();
+        assert_eq!(backoff.next_delay(), Duration::from_secs(1));
+    }
+}
@@ -10,3 +10,5 @@ pub const ZERO: Coin = 0;
 pub const MIN_TX_FEE: Coin = 300000;
 
 pub const BABBAGE_ERA_ID: u16 = 5;
+
+pub const CONWAY_ERA_ID: u16 = 6;
@@ -0,0 +1,31 @@
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::sync::Arc;
+
+use crate::constants::BABBAGE_ERA_ID;
+
+/// Ledger era shared between the component tracking the node
+/// and the components which depend on the era, e.g. tx submission.
+#[derive(Debug, Clone)]
+pub struct SharedEra(Arc<AtomicU16>);
+
+impl SharedEra {
+    pub fn new(era: u16) -> Self {
+        Self(Arc::new(AtomicU16::new(era)))
+    }
+
+    /// Get the latest known era.
+    pub fn get(&self) -> u16 {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Replace current era.
+    pub fn set(&self, era: u16) {
+        self.0.store(era, Ordering::Relaxed);
+    }
+}
+
+impl Default for SharedEra {
+    fn default() -> Self {
+        Self::new(BABBAGE_ERA_ID)
+    }
+}
@@ -18,20 +18,26 @@ use crate::plutus_data::{ConstrPlutusDataExtension, PlutusDataExtension};
 use crate::types::TryFromPData;
 
 pub mod address;
+pub mod chain_tip;
 pub mod collateral;
+pub mod connection;
 pub mod constants;
 pub mod credential;
+pub mod era;
 pub mod ex_units;
 pub mod hash;
 pub mod output;
 pub mod plutus_data;
 pub mod protocol_params;
+pub mod time;
 pub mod transaction;
 pub mod types;
 pub mod value;
 
 /// Asset name bytes padded to 32-byte fixed array and tupled with the len of the original asset name.
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, derive_more::From)]
+#[derive(
+    Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, derive_more::From, serde::Serialize, Deserialize,
+)]
 pub struct AssetName(u8, [u8; 32]);
 
 impl AssetName {
@@ -111,8 +117,8 @@ impl TryFrom<Vec<u8>> for AssetName {
     }
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Deserialize)]
-#[serde(try_from = "String")]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, serde::Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
 pub struct OutputRef(TransactionHash, u64);
 
 impl OutputRef {
@@ -139,6 +145,12 @@ impl Display for OutputRef {
     }
 }
 
+impl From<OutputRef> for String {
+    fn from(value: OutputRef) -> Self {
+        value.to_string()
+    }
+}
+
 impl From<TransactionInput> for OutputRef {
     fn from(value: TransactionInput) -> Self {
         Self(value.transaction_id, value.index)
@@ -179,7 +191,7 @@ impl TryFrom<&str> for OutputRef {
 
 pub type Token = (PolicyId, AssetName);
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, serde::Serialize, Deserialize)]
 pub enum AssetClass {
     Native,
     Token(Token),
@@ -250,6 +262,8 @@ impl TryFromPData for AssetClass {
     PartialOrd(bound = ""),
     Hash(bound = "")
 )]
+#[derive(serde::Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct TaggedAssetClass<T>(AssetClass, PhantomData<T>);
 
 impl<T> TaggedAssetClass<T> {
@@ -273,6 +287,8 @@ impl<T> TryFromPData for TaggedAssetClass<T> {
 #[repr(transparent)]
 #[derive(Derivative)]
 #[derivative(Debug(bound = ""), Copy(bound = ""), Clone(bound = ""), Eq(bound = ""))]
+#[derive(serde::Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct TaggedAmount<T>(u64, PhantomData<T>);
 
 impl<T> PartialEq for TaggedAmount<T> {
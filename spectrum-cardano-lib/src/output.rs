@@ -1,14 +1,29 @@
 use cml_chain::transaction::TransactionOutput;
-use cml_multi_era::babbage::BabbageTransactionOutput;
+use cml_core::serialization::{Deserialize, Serialize};
 
-use crate::transaction::BabbageTransactionOutputExtension;
 use crate::OutputRef;
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct FinalizedTxOut(pub TransactionOutput, pub OutputRef);
 
 impl FinalizedTxOut {
-    pub fn new(out: BabbageTransactionOutput, out_ref: OutputRef) -> Self {
-        Self(out.upcast(), out_ref)
+    pub fn new(out: TransactionOutput, out_ref: OutputRef) -> Self {
+        Self(out, out_ref)
+    }
+}
+
+/// Output is kept in its original CBOR encoding.
+impl serde::Serialize for FinalizedTxOut {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serde::Serialize::serialize(&(self.0.to_cbor_bytes(), self.1), serializer)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for FinalizedTxOut {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (raw_out, out_ref) = <(Vec<u8>, OutputRef) as serde::Deserialize>::deserialize(deserializer)?;
+        TransactionOutput::from_cbor_bytes(&raw_out)
+            .map(|out| Self(out, out_ref))
+            .map_err(serde::de::Error::custom)
     }
 }
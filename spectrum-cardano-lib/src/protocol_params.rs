@@ -1,17 +1,29 @@
-use cml_chain::builders::tx_builder::{TransactionBuilder, TransactionBuilderConfigBuilder};
+use std::sync::{Arc, RwLock};
+
+use cml_chain::builders::tx_builder::{
+    TransactionBuilder, TransactionBuilderConfig, TransactionBuilderConfigBuilder,
+};
 use cml_chain::fees::LinearFee;
 use cml_chain::plutus::{CostModels, ExUnitPrices};
 use cml_chain::SubCoin;
 use cml_core::Int;
 
-const MAX_TX_SIZE: u32 = 16384;
+pub const MAX_TX_SIZE: u32 = 16384;
 const MAX_VALUE_SIZE: u32 = 5000;
 
 const COINS_PER_UTXO_BYTE: u64 = 4310;
 
-//todo: check correctness
+pub const COLLATERAL_PERCENTAGE: u32 = 150;
+
+/// Tx builder over hardcoded protocol parameters.
+/// Use only when live parameters are unavailable, e.g. in tests.
 pub fn constant_tx_builder() -> TransactionBuilder {
-    create_tx_builder_full(
+    TransactionBuilder::new(constant_tx_builder_config())
+}
+
+/// Builder config derived from hardcoded protocol parameters.
+pub fn constant_tx_builder_config() -> TransactionBuilderConfig {
+    create_tx_builder_config_full(
         LinearFee::new(44, 155381),
         500000000,
         2000000,
@@ -20,6 +32,33 @@ pub fn constant_tx_builder() -> TransactionBuilder {
     )
 }
 
+/// Protocol parameters shared between the component keeping them up to date
+/// and the components building transactions.
+#[derive(Debug, Clone)]
+pub struct SharedTxBuilderConfig(Arc<RwLock<TransactionBuilderConfig>>);
+
+impl SharedTxBuilderConfig {
+    pub fn new(config: TransactionBuilderConfig) -> Self {
+        Self(Arc::new(RwLock::new(config)))
+    }
+
+    /// Get the latest known config.
+    pub fn get(&self) -> TransactionBuilderConfig {
+        self.0.read().unwrap().clone()
+    }
+
+    /// Replace current config.
+    pub fn set(&self, config: TransactionBuilderConfig) {
+        *self.0.write().unwrap() = config;
+    }
+}
+
+impl Default for SharedTxBuilderConfig {
+    fn default() -> Self {
+        Self::new(constant_tx_builder_config())
+    }
+}
+
 pub fn constant_cost_models() -> CostModels {
     let ops_v1: [u64; 166] = [
         205665, 812, 1, 1, 1000, 571, 0, 1, 1000, 24177, 4, 1, 1000, 32, 117366, 10475, 4, 23000, 100, 23000,
@@ -52,14 +91,14 @@ pub fn constant_cost_models() -> CostModels {
     res
 }
 
-fn create_tx_builder_full(
+fn create_tx_builder_config_full(
     linear_fee: LinearFee,
     pool_deposit: u64,
     key_deposit: u64,
     max_val_size: u32,
     coins_per_utxo_byte: u64,
-) -> TransactionBuilder {
-    let cfg = TransactionBuilderConfigBuilder::default()
+) -> TransactionBuilderConfig {
+    TransactionBuilderConfigBuilder::default()
         .fee_algo(linear_fee)
         .pool_deposit(pool_deposit)
         .key_deposit(key_deposit)
@@ -70,10 +109,9 @@ fn create_tx_builder_full(
             SubCoin::new(577, 10000),
             SubCoin::new(721, 10000000),
         ))
-        .collateral_percentage(150)
+        .collateral_percentage(COLLATERAL_PERCENTAGE)
         .max_collateral_inputs(3)
         .cost_models(constant_cost_models())
         .build()
-        .unwrap();
-    TransactionBuilder::new(cfg)
+        .unwrap()
 }
@@ -0,0 +1,34 @@
+use cml_core::Slot;
+
+/// Parameters required to translate slots into POSIX time.
+/// Taken from the Shelley genesis of the network the agent is connected to.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SlotConfig {
+    pub zero_time: u64,
+    pub zero_slot: u64,
+    pub slot_length: u32,
+}
+
+impl SlotConfig {
+    /// POSIX time in milliseconds at the start of the given slot.
+    pub fn slot_to_posix_time(&self, slot: Slot) -> u64 {
+        self.zero_time + slot.saturating_sub(self.zero_slot) * self.slot_length as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::time::SlotConfig;
+
+    #[test]
+    fn slot_to_posix_time_on_mainnet() {
+        let conf = SlotConfig {
+            zero_time: 1596059091000,
+            zero_slot: 4492800,
+            slot_length: 1000,
+        };
+        assert_eq!(conf.slot_to_posix_time(4492800), 1596059091000);
+        assert_eq!(conf.slot_to_posix_time(134366175), 1725932466000);
+    }
+}
@@ -1,16 +1,23 @@
 use cml_chain::address::Address;
 use cml_chain::certs::{Credential, StakeCredential};
 use cml_chain::plutus::PlutusData;
-use cml_chain::transaction::{ConwayFormatTxOut, DatumOption, ScriptRef, Transaction, TransactionOutput};
+use cml_chain::transaction::{
+    ConwayFormatTxOut, DatumOption, ScriptRef, Transaction, TransactionBody, TransactionInput,
+    TransactionOutput,
+};
 use cml_chain::Value;
+use cml_core::serialization::Deserialize;
 use cml_crypto::{ScriptHash, TransactionHash};
-use cml_multi_era::babbage::{BabbageFormatTxOut, BabbageScriptRef, BabbageTransactionOutput};
+use cml_multi_era::babbage::{
+    BabbageScriptRef, BabbageTransaction, BabbageTransactionBody, BabbageTransactionOutput,
+};
 use derive_more::From;
 use std::ops::Deref;
 
 use spectrum_offchain::tx_hash::CanonicalHash;
 
 use crate::address::AddressExtension;
+use crate::constants::{BABBAGE_ERA_ID, CONWAY_ERA_ID};
 use crate::hash::hash_transaction_canonical;
 use crate::AssetClass;
 
@@ -43,7 +50,7 @@ pub trait TransactionOutputExtension {
     fn update_payment_cred(&mut self, cred: StakeCredential);
     fn update_address(&mut self, addr: Address);
     fn update_value(&mut self, value: Value);
-    fn script_ref(&self) -> Option<&BabbageScriptRef>;
+    fn script_ref(&self) -> Option<&ScriptRef>;
     fn sub_asset(&mut self, asset: AssetClass, amount: u64) {
         let updated_value = self.value().checked_sub(&asset.into_value(amount)).unwrap();
         *self.value_mut() = updated_value;
@@ -54,92 +61,6 @@ pub trait TransactionOutputExtension {
     }
 }
 
-impl TransactionOutputExtension for BabbageTransactionOutput {
-    fn address(&self) -> &Address {
-        match self {
-            Self::AlonzoFormatTxOut(tx_out) => &tx_out.address,
-            Self::BabbageFormatTxOut(tx_out) => &tx_out.address,
-        }
-    }
-    fn value(&self) -> &Value {
-        match self {
-            Self::AlonzoFormatTxOut(tx_out) => &tx_out.amount,
-            Self::BabbageFormatTxOut(tx_out) => &tx_out.amount,
-        }
-    }
-    fn value_mut(&mut self) -> &mut Value {
-        match self {
-            Self::AlonzoFormatTxOut(tx_out) => &mut tx_out.amount,
-            Self::BabbageFormatTxOut(tx_out) => &mut tx_out.amount,
-        }
-    }
-    fn datum(&self) -> Option<DatumOption> {
-        match self {
-            Self::AlonzoFormatTxOut(tx_out) => tx_out.datum_hash.map(DatumOption::new_hash).clone(),
-            Self::BabbageFormatTxOut(tx_out) => tx_out.datum_option.clone(),
-        }
-    }
-    fn data_mut(&mut self) -> Option<&mut PlutusData> {
-        match self {
-            Self::BabbageFormatTxOut(BabbageFormatTxOut {
-                datum_option: Some(DatumOption::Datum { datum, .. }),
-                ..
-            }) => Some(datum),
-            _ => None,
-        }
-    }
-    fn null_datum(&mut self) {
-        match self {
-            Self::AlonzoFormatTxOut(tx_out) => {
-                tx_out.datum_hash.take();
-            }
-            Self::BabbageFormatTxOut(tx_out) => {
-                tx_out.datum_option.take();
-            }
-        }
-    }
-    fn into_datum(self) -> Option<DatumOption> {
-        match self {
-            Self::AlonzoFormatTxOut(tx_out) => tx_out.datum_hash.map(DatumOption::new_hash),
-            Self::BabbageFormatTxOut(tx_out) => tx_out.datum_option,
-        }
-    }
-    fn script_hash(&self) -> Option<ScriptHash> {
-        match self {
-            Self::AlonzoFormatTxOut(tx_out) => tx_out.address.script_hash(),
-            Self::BabbageFormatTxOut(tx_out) => tx_out.address.script_hash(),
-        }
-    }
-    fn update_payment_cred(&mut self, cred: StakeCredential) {
-        match self {
-            Self::AlonzoFormatTxOut(tx_out) => tx_out.address.update_payment_cred(cred),
-            Self::BabbageFormatTxOut(tx_out) => tx_out.address.update_payment_cred(cred),
-        }
-    }
-    fn update_address(&mut self, addr: Address) {
-        match self {
-            BabbageTransactionOutput::AlonzoFormatTxOut(tx_out) => tx_out.address = addr,
-            BabbageTransactionOutput::BabbageFormatTxOut(tx_out) => tx_out.address = addr,
-        }
-    }
-    fn update_value(&mut self, value: Value) {
-        match self {
-            Self::AlonzoFormatTxOut(ref mut out) => {
-                out.amount = value;
-            }
-            Self::BabbageFormatTxOut(ref mut out) => {
-                out.amount = value;
-            }
-        }
-    }
-    fn script_ref(&self) -> Option<&BabbageScriptRef> {
-        match self {
-            Self::AlonzoFormatTxOut(_) => None,
-            Self::BabbageFormatTxOut(tx_out) => tx_out.script_reference.as_ref(),
-        }
-    }
-}
-
 impl TransactionOutputExtension for TransactionOutput {
     fn address(&self) -> &Address {
         match self {
@@ -218,8 +139,11 @@ impl TransactionOutputExtension for TransactionOutput {
             }
         }
     }
-    fn script_ref(&self) -> Option<&BabbageScriptRef> {
-        None
+    fn script_ref(&self) -> Option<&ScriptRef> {
+        match self {
+            Self::AlonzoFormatTxOut(_) => None,
+            Self::ConwayFormatTxOut(tx_out) => tx_out.script_reference.as_ref(),
+        }
     }
 }
 
@@ -283,3 +207,57 @@ impl BabbageTransactionOutputExtension for BabbageTransactionOutput {
         }
     }
 }
+
+/// Era-agnostic view of a ledger transaction.
+/// Outputs are upcast to the latest era, while the hash is computed over the original body,
+/// so it always matches the ID of the transaction on-chain.
+#[derive(Debug, Clone)]
+pub struct TxViewMut {
+    pub hash: TransactionHash,
+    pub inputs: Vec<TransactionInput>,
+    pub outputs: Vec<TransactionOutput>,
+}
+
+impl TxViewMut {
+    /// Decode transaction serialized in the given era.
+    /// Returns `None` for eras preceding Babbage or malformed bytes.
+    pub fn from_era_bytes(era: u16, bytes: &[u8]) -> Option<Self> {
+        match era {
+            BABBAGE_ERA_ID => BabbageTransaction::from_cbor_bytes(bytes).ok().map(Self::from),
+            CONWAY_ERA_ID => Transaction::from_cbor_bytes(bytes).ok().map(Self::from),
+            _ => None,
+        }
+    }
+}
+
+impl From<BabbageTransactionBody> for TxViewMut {
+    fn from(body: BabbageTransactionBody) -> Self {
+        Self {
+            hash: hash_transaction_canonical(&body),
+            inputs: body.inputs,
+            outputs: body.outputs.into_iter().map(|out| out.upcast()).collect(),
+        }
+    }
+}
+
+impl From<BabbageTransaction> for TxViewMut {
+    fn from(tx: BabbageTransaction) -> Self {
+        Self::from(tx.body)
+    }
+}
+
+impl From<TransactionBody> for TxViewMut {
+    fn from(body: TransactionBody) -> Self {
+        Self {
+            hash: hash_transaction_canonical(&body),
+            inputs: body.inputs,
+            outputs: body.outputs,
+        }
+    }
+}
+
+impl From<Transaction> for TxViewMut {
+    fn from(tx: Transaction) -> Self {
+        Self::from(tx.body)
+    }
+}
@@ -13,10 +13,10 @@ use crate::deployment::ProtocolValidator::{
     StableFnPoolT2TRedeem,
 };
 use crate::deployment::{DeployedScriptInfo, DeployedValidator};
+use crate::script_eval::SlotConfig;
 use bloom_offchain::execution_engine::bundled::Bundled;
-use cml_chain::builders::tx_builder::SignedTxBuilder;
+use cml_chain::builders::tx_builder::{SignedTxBuilder, TransactionBuilderConfig};
 use cml_crypto::ScriptHash;
-use cml_multi_era::babbage::BabbageTransactionOutput;
 use spectrum_cardano_lib::collateral::Collateral;
 use spectrum_cardano_lib::output::FinalizedTxOut;
 use spectrum_cardano_lib::{NetworkId, OutputRef};
@@ -38,6 +38,8 @@ where
         + Has<Collateral>
         + Has<NetworkId>
         + Has<OperatorRewardAddress>
+        + Has<SlotConfig>
+        + Has<TransactionBuilderConfig>
         + Has<DeployedValidator<{ BalanceFnPoolV1 as u8 }>>
         + Has<DeployedValidator<{ BalanceFnPoolV2 as u8 }>>
         + Has<DeployedValidator<{ BalanceFnPoolDeposit as u8 }>>
@@ -1,11 +1,11 @@
 use std::fmt::{Display, Formatter};
 use std::hash::{Hash, Hasher};
 
-use cml_chain::builders::tx_builder::SignedTxBuilder;
+use cml_chain::builders::tx_builder::{SignedTxBuilder, TransactionBuilderConfig};
 use cml_chain::plutus::{ConstrPlutusData, PlutusData};
+use cml_chain::transaction::TransactionOutput;
 use cml_chain::utils::BigInteger;
 use cml_crypto::ScriptHash;
-use cml_multi_era::babbage::BabbageTransactionOutput;
 use futures::future::Either::Right;
 
 use bloom_offchain::execution_engine::bundled::Bundled;
@@ -34,6 +34,7 @@ use crate::deployment::ProtocolValidator::{
     ConstFnPoolV2, StableFnPoolT2T, StableFnPoolT2TDeposit, StableFnPoolT2TRedeem,
 };
 use crate::deployment::{DeployedScriptInfo, DeployedValidator};
+use crate::script_eval::SlotConfig;
 use spectrum_cardano_lib::{NetworkId, OutputRef};
 use spectrum_offchain::executor::RunOrderError::Fatal;
 
@@ -158,7 +159,7 @@ impl SpecializedOrder for ClassicalAMMOrder {
     }
 }
 
-impl<Ctx> TryFromLedger<BabbageTransactionOutput, Ctx> for ClassicalAMMOrder
+impl<Ctx> TryFromLedger<TransactionOutput, Ctx> for ClassicalAMMOrder
 where
     Ctx: Has<OutputRef>
         + Has<DeployedScriptInfo<{ ConstFnFeeSwitchPoolSwap as u8 }>>
@@ -174,7 +175,7 @@ where
         + Has<DepositOrderBounds>
         + Has<RedeemOrderBounds>,
 {
-    fn try_from_ledger(repr: &BabbageTransactionOutput, ctx: &Ctx) -> Option<Self> {
+    fn try_from_ledger(repr: &TransactionOutput, ctx: &Ctx) -> Option<Self> {
         ClassicalOnChainLimitSwap::try_from_ledger(repr, ctx)
             .map(|swap| ClassicalAMMOrder::Swap(swap))
             .or_else(|| {
@@ -197,6 +198,8 @@ where
         + Has<Collateral>
         + Has<NetworkId>
         + Has<OperatorRewardAddress>
+        + Has<SlotConfig>
+        + Has<TransactionBuilderConfig>
         + Has<DeployedValidator<{ ConstFnPoolV1 as u8 }>>
         + Has<DeployedValidator<{ ConstFnPoolV2 as u8 }>>
         + Has<DeployedValidator<{ ConstFnFeeSwitchPoolSwap as u8 }>>
@@ -1,9 +1,11 @@
 use std::fmt::{Display, Formatter, Write};
 
+use bloom_offchain::execution_engine::liquidity_book::routing::AssetPair;
 use bloom_offchain::execution_engine::liquidity_book::side::Side;
 use spectrum_cardano_lib::AssetClass;
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(from = "(AssetClass, AssetClass)")]
 pub struct PairId(AssetClass, AssetClass);
 
 impl PairId {
@@ -14,6 +16,24 @@ impl PairId {
     }
 }
 
+impl From<(AssetClass, AssetClass)> for PairId {
+    fn from((x, y): (AssetClass, AssetClass)) -> Self {
+        Self::canonical(x, y)
+    }
+}
+
+impl AssetPair for PairId {
+    type Asset = AssetClass;
+
+    fn assets(&self) -> (AssetClass, AssetClass) {
+        (self.0, self.1)
+    }
+
+    fn of(x: AssetClass, y: AssetClass) -> Self {
+        Self::canonical(x, y)
+    }
+}
+
 /// Determine side of a trade relatively to canonical pair.
 pub fn side_of(input: AssetClass, output: AssetClass) -> Side {
     let xs = order_canonical(input, output);
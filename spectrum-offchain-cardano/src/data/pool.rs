@@ -5,7 +5,8 @@ use cml_chain::builders::input_builder::SingleInputBuilder;
 use cml_chain::builders::output_builder::SingleOutputBuilderResult;
 use cml_chain::builders::redeemer_builder::RedeemerWitnessKey;
 use cml_chain::builders::tx_builder::{
-    ChangeSelectionAlgo, SignedTxBuilder, TransactionUnspentOutput, TxBuilderError,
+    ChangeSelectionAlgo, SignedTxBuilder, TransactionBuilder, TransactionBuilderConfig,
+    TransactionUnspentOutput, TxBuilderError,
 };
 use cml_chain::builders::witness_builder::{PartialPlutusWitness, PlutusScriptWitness};
 use cml_chain::plutus::{PlutusData, RedeemerTag};
@@ -14,8 +15,7 @@ use cml_chain::utils::BigInteger;
 use cml_chain::{Coin, PolicyId};
 use cml_core::serialization::Serialize;
 
-use cml_multi_era::babbage::BabbageTransactionOutput;
-use log::info;
+use log::{info, warn};
 
 use bloom_offchain::execution_engine::bundled::Bundled;
 use bloom_offchain::execution_engine::liquidity_book::core::{MakeInProgress, Next, Unit};
@@ -28,8 +28,7 @@ use spectrum_cardano_lib::collateral::Collateral;
 use spectrum_cardano_lib::ex_units::ExUnits;
 use spectrum_cardano_lib::hash::hash_transaction_canonical;
 use spectrum_cardano_lib::output::FinalizedTxOut;
-use spectrum_cardano_lib::protocol_params::constant_tx_builder;
-use spectrum_cardano_lib::{AssetClass, OutputRef, TaggedAmount, Token};
+use spectrum_cardano_lib::{AssetClass, NetworkId, OutputRef, TaggedAmount, Token};
 use spectrum_offchain::data::event::Predicted;
 use spectrum_offchain::data::{Has, Stable, Tradable};
 use spectrum_offchain::executor::RunOrderError;
@@ -51,6 +50,7 @@ use crate::deployment::ProtocolValidator::{
     ConstFnPoolFeeSwitchV2, ConstFnPoolV1, ConstFnPoolV2, StableFnPoolT2T,
 };
 use crate::deployment::{DeployedScriptInfo, RequiresValidator};
+use crate::script_eval::{evaluate_ex_units, set_evaluated_ex_units, SlotConfig};
 
 pub struct Rx;
 
@@ -209,14 +209,14 @@ impl CFMMPoolAction {
     }
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Debug, serde::Deserialize)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PoolBounds {
     pub min_n2t_lovelace: u64,
     pub min_t2t_lovelace: u64,
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
 pub enum AnyPool {
     PureCFMM(ConstFnPool),
     BalancedCFMM(BalancePool),
@@ -314,7 +314,7 @@ impl MarketMaker for AnyPool {
     }
 }
 
-impl<C> TryFromLedger<BabbageTransactionOutput, C> for AnyPool
+impl<C> TryFromLedger<TransactionOutput, C> for AnyPool
 where
     C: Has<DeployedScriptInfo<{ ConstFnPoolV1 as u8 }>>
         + Has<DeployedScriptInfo<{ ConstFnPoolV2 as u8 }>>
@@ -326,7 +326,7 @@ where
         + Has<DeployedScriptInfo<{ StableFnPoolT2T as u8 }>>
         + Has<PoolBounds>,
 {
-    fn try_from_ledger(repr: &BabbageTransactionOutput, ctx: &C) -> Option<Self> {
+    fn try_from_ledger(repr: &TransactionOutput, ctx: &C) -> Option<Self> {
         ConstFnPool::try_from_ledger(repr, ctx)
             .map(PureCFMM)
             .or_else(|| BalancePool::try_from_ledger(repr, ctx).map(BalancedCFMM))
@@ -452,7 +452,12 @@ where
     <Pool as ApplyOrder<Order>>::Result: IntoLedger<TransactionOutput, Ctx>,
     Order: Has<OnChainOrderId> + RequiresValidator<Ctx> + Clone + Debug,
     Order: Into<CFMMPoolAction>,
-    Ctx: Clone + Has<Collateral> + Has<OperatorRewardAddress>,
+    Ctx: Clone
+        + Has<Collateral>
+        + Has<NetworkId>
+        + Has<OperatorRewardAddress>
+        + Has<SlotConfig>
+        + Has<TransactionBuilderConfig>,
 {
     let Bundled(pool, FinalizedTxOut(pool_utxo, pool_ref)) = pool_bundle.clone();
     let Bundled(order, FinalizedTxOut(order_utxo, order_ref)) = order_bundle.clone();
@@ -504,10 +509,19 @@ where
         .plutus_script_inline_datum(pool_script, Vec::new())
         .unwrap();
 
-    let mut tx_builder = constant_tx_builder();
+    let tx_builder_config = ctx.select::<TransactionBuilderConfig>();
+    let collateral = ctx.select::<Collateral>();
+    let resolved_utxos = vec![
+        TransactionUnspentOutput::new(pool_ref.into(), pool_utxo.clone()),
+        TransactionUnspentOutput::new(order_ref.into(), order_utxo.clone()),
+        order_validator.reference_utxo.clone(),
+        pool_validator.reference_utxo.clone(),
+        collateral.clone().into(),
+    ];
+    let mut tx_builder = TransactionBuilder::new(tx_builder_config.clone());
 
     tx_builder
-        .add_collateral(ctx.select::<Collateral>().into())
+        .add_collateral(collateral.into())
         .map_err(|err| RunOrderError::from_cml_error(err, order_bundle.clone()))?;
 
     tx_builder.add_reference_input(order_validator.reference_utxo);
@@ -537,11 +551,26 @@ where
         .add_output(SingleOutputBuilderResult::new(user_out.into_ledger(ctx.clone())))
         .map_err(|err| RunOrderError::from_cml_error(err, order_bundle.clone()))?;
 
+    let change_address = ctx.select::<OperatorRewardAddress>().into();
+    // Static budgets are kept as a fallback in case local evaluation isn't possible.
+    match evaluate_ex_units(
+        &tx_builder,
+        &change_address,
+        &resolved_utxos,
+        &tx_builder_config.cost_models,
+        ctx.select::<SlotConfig>(),
+    ) {
+        Ok(evaluated) => {
+            set_evaluated_ex_units(&mut tx_builder, &evaluated);
+        }
+        Err(err) if err.is_script_failure() => {
+            return Err(RunOrderError::Fatal(err.to_string(), order_bundle));
+        }
+        Err(err) => warn!(target: "offchain", "Failed to evaluate scripts of order {}: {}", order_ref, err),
+    }
+
     let tx = wrap_cml_action(
-        tx_builder.build(
-            ChangeSelectionAlgo::Default,
-            &ctx.select::<OperatorRewardAddress>().into(),
-        ),
+        tx_builder.build(ChangeSelectionAlgo::Default, &change_address),
         Bundled(order, FinalizedTxOut(order_utxo, order_ref)),
     )?;
 
@@ -9,8 +9,9 @@ use crate::deployment::ProtocolValidator::{
     ConstFnPoolSwap, ConstFnPoolV1, ConstFnPoolV2, StableFnPoolT2T, StableFnPoolT2TDeposit,
     StableFnPoolT2TRedeem,
 };
+use crate::script_eval::SlotConfig;
 use bloom_offchain::execution_engine::bundled::Bundled;
-use cml_chain::builders::tx_builder::SignedTxBuilder;
+use cml_chain::builders::tx_builder::{SignedTxBuilder, TransactionBuilderConfig};
 use spectrum_cardano_lib::collateral::Collateral;
 use spectrum_cardano_lib::output::FinalizedTxOut;
 use spectrum_cardano_lib::NetworkId;
@@ -27,6 +28,8 @@ where
         + Has<Collateral>
         + Has<NetworkId>
         + Has<OperatorRewardAddress>
+        + Has<SlotConfig>
+        + Has<TransactionBuilderConfig>
         + Has<DeployedValidator<{ BalanceFnPoolV1 as u8 }>>
         + Has<DeployedValidator<{ BalanceFnPoolDeposit as u8 }>>
         + Has<DeployedValidator<{ BalanceFnPoolRedeem as u8 }>>
@@ -4,14 +4,14 @@ use std::marker::PhantomData;
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use cml_multi_era::babbage::{BabbageTransaction, BabbageTransactionOutput};
+use cml_chain::transaction::TransactionOutput;
 use futures::{Sink, SinkExt};
 use log::trace;
 use tokio::sync::Mutex;
 
 use cardano_chain_sync::data::LedgerTxEvent;
 use cardano_mempool_sync::data::MempoolUpdate;
-use spectrum_cardano_lib::hash::hash_transaction_canonical;
+use spectrum_cardano_lib::transaction::TxViewMut;
 use spectrum_cardano_lib::OutputRef;
 use spectrum_offchain::box_resolver::persistence::EntityRepo;
 use spectrum_offchain::combinators::Ior;
@@ -45,16 +45,16 @@ where
 
 async fn extract_transitions<TEntity, TRepo>(
     entities: Arc<Mutex<TRepo>>,
-    tx: BabbageTransaction,
+    tx: TxViewMut,
 ) -> Vec<Ior<TEntity, TEntity>>
 where
-    TEntity: EntitySnapshot + TryFromLedger<BabbageTransactionOutput, OutputRef> + Clone,
+    TEntity: EntitySnapshot + TryFromLedger<TransactionOutput, OutputRef> + Clone,
     TEntity::StableId: Clone,
     TEntity::Version: From<OutputRef> + Copy,
     TRepo: EntityRepo<TEntity>,
 {
     let mut consumed_entities = HashMap::<TEntity::StableId, TEntity>::new();
-    for i in &tx.body.inputs {
+    for i in &tx.inputs {
         let state_id = TEntity::Version::from(OutputRef::from((i.transaction_id, i.index)));
         let entities = entities.lock().await;
         if entities.may_exist(state_id).await {
@@ -65,8 +65,8 @@ where
         }
     }
     let mut created_entities = HashMap::<TEntity::StableId, TEntity>::new();
-    let tx_hash = hash_transaction_canonical(&tx.body);
-    for (i, o) in tx.body.outputs.iter().enumerate() {
+    let tx_hash = tx.hash;
+    for (i, o) in tx.outputs.iter().enumerate() {
         let o_ref = OutputRef::from((tx_hash, i as u64));
         if let Some(entity) = TEntity::try_from_ledger(o, &o_ref) {
             let entity_id = entity.stable_id();
@@ -87,21 +87,18 @@ where
 }
 
 #[async_trait(?Send)]
-impl<TSink, TEntity, TRepo> EventHandler<LedgerTxEvent<BabbageTransaction>>
+impl<TSink, TEntity, TRepo> EventHandler<LedgerTxEvent<TxViewMut>>
     for ConfirmedUpdateHandler<TSink, TEntity, TRepo>
 where
     TSink: Sink<Channel<StateUpdate<TEntity>>> + Unpin,
-    TEntity: EntitySnapshot + TryFromLedger<BabbageTransactionOutput, OutputRef> + Clone + Debug,
+    TEntity: EntitySnapshot + TryFromLedger<TransactionOutput, OutputRef> + Clone + Debug,
     TEntity::StableId: Clone,
     TEntity::Version: From<OutputRef> + Copy,
     TRepo: EntityRepo<TEntity>,
 {
-    async fn try_handle(
-        &mut self,
-        ev: LedgerTxEvent<BabbageTransaction>,
-    ) -> Option<LedgerTxEvent<BabbageTransaction>> {
+    async fn try_handle(&mut self, ev: LedgerTxEvent<TxViewMut>) -> Option<LedgerTxEvent<TxViewMut>> {
         let res = match ev {
-            LedgerTxEvent::TxApplied { tx, slot } => {
+            LedgerTxEvent::TxApplied { tx, slot, position } => {
                 let transitions = extract_transitions(Arc::clone(&self.entities), tx.clone()).await;
                 let num_transitions = transitions.len();
                 let is_success = num_transitions > 0;
@@ -115,7 +112,7 @@ where
                     trace!(target: "offchain_lm", "[{}] entities parsed from applied tx", num_transitions);
                     None
                 } else {
-                    Some(LedgerTxEvent::TxApplied { tx, slot })
+                    Some(LedgerTxEvent::TxApplied { tx, slot, position })
                 }
             }
             LedgerTxEvent::TxUnapplied(tx) => {
@@ -137,6 +134,9 @@ where
                     Some(LedgerTxEvent::TxUnapplied(tx))
                 }
             }
+            ev @ (LedgerTxEvent::BlockCompleted(_)
+            | LedgerTxEvent::BlockRolledBack(_)
+            | LedgerTxEvent::RollbackIncomplete(_)) => Some(ev),
         };
         let _ = self.topic.flush().await;
         res
@@ -166,19 +166,16 @@ where
 }
 
 #[async_trait(?Send)]
-impl<TSink, TEntity, TRepo> EventHandler<MempoolUpdate<BabbageTransaction>>
+impl<TSink, TEntity, TRepo> EventHandler<MempoolUpdate<TxViewMut>>
     for UnconfirmedUpdateHandler<TSink, TEntity, TRepo>
 where
     TSink: Sink<Channel<StateUpdate<TEntity>>> + Unpin,
-    TEntity: EntitySnapshot + TryFromLedger<BabbageTransactionOutput, OutputRef> + Clone + Debug,
+    TEntity: EntitySnapshot + TryFromLedger<TransactionOutput, OutputRef> + Clone + Debug,
     TEntity::StableId: Clone,
     TEntity::Version: From<OutputRef> + Copy,
     TRepo: EntityRepo<TEntity>,
 {
-    async fn try_handle(
-        &mut self,
-        ev: MempoolUpdate<BabbageTransaction>,
-    ) -> Option<MempoolUpdate<BabbageTransaction>> {
+    async fn try_handle(&mut self, ev: MempoolUpdate<TxViewMut>) -> Option<MempoolUpdate<TxViewMut>> {
         let res = match ev {
             MempoolUpdate::TxAccepted(tx) => {
                 let transitions = extract_transitions(Arc::clone(&self.entities), tx.clone()).await;
@@ -195,6 +192,41 @@ where
                     Some(MempoolUpdate::TxAccepted(tx))
                 }
             }
+            MempoolUpdate::TxDropped(tx) => {
+                let transitions = extract_transitions(Arc::clone(&self.entities), tx.clone()).await;
+                let is_success = !transitions.is_empty();
+                for tr in transitions {
+                    let _ = self
+                        .topic
+                        .feed(Channel::Mempool(Unconfirmed(StateUpdate::TransitionRollback(
+                            tr.swap(),
+                        ))))
+                        .await;
+                }
+                if is_success {
+                    None
+                } else {
+                    Some(MempoolUpdate::TxDropped(tx))
+                }
+            }
+            MempoolUpdate::TxRejected(tx) => {
+                let transitions = extract_transitions(Arc::clone(&self.entities), tx.clone()).await;
+                let is_success = !transitions.is_empty();
+                // Consumed states are not restored as they were spent by a competing TX.
+                for produced in transitions.into_iter().filter_map(|tr| tr.right()) {
+                    let _ = self
+                        .topic
+                        .feed(Channel::Mempool(Unconfirmed(StateUpdate::TransitionRollback(
+                            Ior::Left(produced),
+                        ))))
+                        .await;
+                }
+                if is_success {
+                    None
+                } else {
+                    Some(MempoolUpdate::TxRejected(tx))
+                }
+            }
         };
         let _ = self.topic.flush().await;
         res
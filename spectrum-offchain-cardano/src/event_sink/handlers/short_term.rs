@@ -2,14 +2,14 @@ use std::marker::PhantomData;
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use cml_multi_era::babbage::{BabbageTransaction, BabbageTransactionOutput};
+use cml_chain::transaction::TransactionOutput;
 use futures::{Sink, SinkExt};
 use log::info;
 use tokio::sync::Mutex;
 
 use cardano_chain_sync::data::LedgerTxEvent;
 use cardano_mempool_sync::data::MempoolUpdate;
-use spectrum_cardano_lib::hash::hash_transaction_canonical;
+use spectrum_cardano_lib::transaction::TxViewMut;
 use spectrum_cardano_lib::OutputRef;
 use spectrum_offchain::data::order::{OrderLink, OrderUpdate, SpecializedOrder};
 use spectrum_offchain::event_sink::event_handler::EventHandler;
@@ -35,16 +35,16 @@ impl<TSink, TOrd, TRegistry> ClassicalOrderUpdatesHandler<TSink, TOrd, TRegistry
         }
     }
 
-    async fn handle_applied_tx<F, R>(&mut self, tx: BabbageTransaction, on_failure: F) -> Option<R>
+    async fn handle_applied_tx<F, R>(&mut self, tx: TxViewMut, on_failure: F) -> Option<R>
     where
         TSink: Sink<OrderUpdate<TOrd, OrderLink<TOrd>>> + Unpin,
-        TOrd: SpecializedOrder + TryFromLedger<BabbageTransactionOutput, OutputRef>,
+        TOrd: SpecializedOrder + TryFromLedger<TransactionOutput, OutputRef>,
         TOrd::TOrderId: From<OutputRef> + Copy,
         TRegistry: HotOrderRegistry<TOrd>,
-        F: FnOnce(BabbageTransaction) -> R,
+        F: FnOnce(TxViewMut) -> R,
     {
         let mut is_success = false;
-        for i in &tx.body.inputs {
+        for i in &tx.inputs {
             let maybe_order_link = {
                 let order_id = TOrd::TOrderId::from(OutputRef::from((i.transaction_id, i.index)));
                 let mut registry = self.registry.lock().await;
@@ -57,9 +57,9 @@ impl<TSink, TOrd, TRegistry> ClassicalOrderUpdatesHandler<TSink, TOrd, TRegistry
             }
         }
         if !is_success {
-            let tx_hash = hash_transaction_canonical(&tx.body);
+            let tx_hash = tx.hash;
             // no point in searching for new orders in execution tx
-            for (i, o) in tx.body.outputs.iter().enumerate() {
+            for (i, o) in tx.outputs.iter().enumerate() {
                 let o_ref = OutputRef::from((tx_hash, i as u64));
                 if let Some(order) = TOrd::try_from_ledger(o, &o_ref) {
                     is_success = true;
@@ -81,19 +81,17 @@ impl<TSink, TOrd, TRegistry> ClassicalOrderUpdatesHandler<TSink, TOrd, TRegistry
         Some(on_failure(tx))
     }
 
-    async fn handle_unapplied_tx(
-        &mut self,
-        tx: BabbageTransaction,
-    ) -> Option<LedgerTxEvent<BabbageTransaction>>
+    async fn handle_unapplied_tx<F, R>(&mut self, tx: TxViewMut, on_failure: F) -> Option<R>
     where
         TSink: Sink<OrderUpdate<TOrd, OrderLink<TOrd>>> + Unpin,
-        TOrd: SpecializedOrder + TryFromLedger<BabbageTransactionOutput, OutputRef>,
+        TOrd: SpecializedOrder + TryFromLedger<TransactionOutput, OutputRef>,
         TOrd::TOrderId: From<OutputRef> + Copy,
         TRegistry: HotOrderRegistry<TOrd>,
+        F: FnOnce(TxViewMut) -> R,
     {
         let mut is_success = false;
-        let tx_hash = hash_transaction_canonical(&tx.body);
-        for (i, _) in tx.body.outputs.iter().enumerate() {
+        let tx_hash = tx.hash;
+        for (i, _) in tx.outputs.iter().enumerate() {
             let maybe_order_link = {
                 let o_ref = OutputRef::from((tx_hash, i as u64));
                 let order_id = TOrd::TOrderId::from(o_ref);
@@ -109,29 +107,29 @@ impl<TSink, TOrd, TRegistry> ClassicalOrderUpdatesHandler<TSink, TOrd, TRegistry
         if is_success {
             return None;
         }
-        Some(LedgerTxEvent::TxUnapplied(tx))
+        Some(on_failure(tx))
     }
 }
 
 #[async_trait(? Send)]
-impl<TSink, TOrd, TRegistry> EventHandler<LedgerTxEvent<BabbageTransaction>>
+impl<TSink, TOrd, TRegistry> EventHandler<LedgerTxEvent<TxViewMut>>
     for ClassicalOrderUpdatesHandler<TSink, TOrd, TRegistry>
 where
     TSink: Sink<OrderUpdate<TOrd, OrderLink<TOrd>>> + Unpin,
-    TOrd: SpecializedOrder + TryFromLedger<BabbageTransactionOutput, OutputRef>,
+    TOrd: SpecializedOrder + TryFromLedger<TransactionOutput, OutputRef>,
     TOrd::TOrderId: From<OutputRef> + Copy,
     TRegistry: HotOrderRegistry<TOrd>,
 {
-    async fn try_handle(
-        &mut self,
-        ev: LedgerTxEvent<BabbageTransaction>,
-    ) -> Option<LedgerTxEvent<BabbageTransaction>> {
+    async fn try_handle(&mut self, ev: LedgerTxEvent<TxViewMut>) -> Option<LedgerTxEvent<TxViewMut>> {
         let res = match ev {
-            LedgerTxEvent::TxApplied { tx, slot } => {
-                self.handle_applied_tx(tx.clone(), |tx| LedgerTxEvent::TxApplied { tx, slot })
+            LedgerTxEvent::TxApplied { tx, slot, position } => {
+                self.handle_applied_tx(tx.clone(), |tx| LedgerTxEvent::TxApplied { tx, slot, position })
                     .await
             }
-            LedgerTxEvent::TxUnapplied(tx) => self.handle_unapplied_tx(tx).await,
+            LedgerTxEvent::TxUnapplied(tx) => self.handle_unapplied_tx(tx, LedgerTxEvent::TxUnapplied).await,
+            ev @ (LedgerTxEvent::BlockCompleted(_)
+            | LedgerTxEvent::BlockRolledBack(_)
+            | LedgerTxEvent::RollbackIncomplete(_)) => Some(ev),
         };
         let _ = self.topic.flush().await;
         res
@@ -139,20 +137,19 @@ where
 }
 
 #[async_trait(? Send)]
-impl<TSink, TOrd, TRegistry> EventHandler<MempoolUpdate<BabbageTransaction>>
+impl<TSink, TOrd, TRegistry> EventHandler<MempoolUpdate<TxViewMut>>
     for ClassicalOrderUpdatesHandler<TSink, TOrd, TRegistry>
 where
     TSink: Sink<OrderUpdate<TOrd, OrderLink<TOrd>>> + Unpin,
-    TOrd: SpecializedOrder + TryFromLedger<BabbageTransactionOutput, OutputRef>,
+    TOrd: SpecializedOrder + TryFromLedger<TransactionOutput, OutputRef>,
     TOrd::TOrderId: From<OutputRef> + Copy,
     TRegistry: HotOrderRegistry<TOrd>,
 {
-    async fn try_handle(
-        &mut self,
-        ev: MempoolUpdate<BabbageTransaction>,
-    ) -> Option<MempoolUpdate<BabbageTransaction>> {
+    async fn try_handle(&mut self, ev: MempoolUpdate<TxViewMut>) -> Option<MempoolUpdate<TxViewMut>> {
         let res = match ev {
             MempoolUpdate::TxAccepted(tx) => self.handle_applied_tx(tx, MempoolUpdate::TxAccepted).await,
+            MempoolUpdate::TxDropped(tx) => self.handle_unapplied_tx(tx, MempoolUpdate::TxDropped).await,
+            MempoolUpdate::TxRejected(tx) => self.handle_unapplied_tx(tx, MempoolUpdate::TxRejected).await,
         };
         let _ = self.topic.flush().await;
         res
@@ -5,10 +5,13 @@ pub mod data;
 pub mod deployment;
 pub mod event_sink;
 mod fees;
+mod metrics;
 pub mod node;
 pub mod parametrized_validators;
 pub mod pool_math;
 pub mod prover;
 pub mod script;
+pub mod script_eval;
 pub mod tx_submission;
+pub mod tx_validation;
 pub mod utxo;
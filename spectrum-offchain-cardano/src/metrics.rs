@@ -0,0 +1,106 @@
+use std::fmt::Debug;
+use std::time::Duration;
+
+use lazy_static::lazy_static;
+use prometheus::{register_histogram, register_int_counter_vec, Histogram, IntCounterVec};
+
+use crate::tx_submission::{RejectReasons, SubmissionResult};
+
+lazy_static! {
+    static ref TX_SUBMISSIONS: IntCounterVec = register_int_counter_vec!(
+        "tx_submissions_total",
+        "Number of transactions submitted to the node, by result",
+        &["result"]
+    )
+    .unwrap();
+    static ref TX_REJECT_REASONS: IntCounterVec = register_int_counter_vec!(
+        "tx_reject_reasons_total",
+        "Number of ledger errors the node reported for rejected transactions, by error",
+        &["reason"]
+    )
+    .unwrap();
+    static ref TX_SUBMISSION_LATENCY: Histogram = register_histogram!(
+        "tx_submission_latency_seconds",
+        "Time it takes the node to respond to a submitted transaction"
+    )
+    .unwrap();
+}
+
+pub(crate) fn on_tx_submitted(result: &SubmissionResult, latency: Duration) {
+    TX_SUBMISSION_LATENCY.observe(latency.as_secs_f64());
+    match result {
+        SubmissionResult::Ok => TX_SUBMISSIONS.with_label_values(&["accepted"]).inc(),
+        SubmissionResult::TxRejected { errors } => {
+            TX_SUBMISSIONS.with_label_values(&["rejected"]).inc();
+            on_tx_rejected(errors);
+        }
+    }
+}
+
+fn on_tx_rejected(errors: &RejectReasons) {
+    let reasons = errors
+        .0
+        .iter()
+        .flat_map(|err| err.node_errors.iter().map(error_label))
+        .collect::<Vec<_>>();
+    if reasons.is_empty() {
+        TX_REJECT_REASONS.with_label_values(&["Unknown"]).inc();
+    }
+    for reason in reasons {
+        TX_REJECT_REASONS.with_label_values(&[&reason]).inc();
+    }
+}
+
+/// Name of the innermost constructor of a nested ledger error,
+/// e.g. `BadInputsUtxo` for `UtxowFailure(UtxoFailure(..(BadInputsUtxo([..]))))`.
+/// Keeps the label set bounded while still pointing at the actual failure.
+fn error_label<E: Debug>(err: &E) -> String {
+    let repr = format!("{:?}", err);
+    let mut label = "";
+    for segment in repr.split('(') {
+        let segment = segment.trim_start();
+        let name_len = segment
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(segment.len());
+        if name_len == 0 {
+            break;
+        }
+        label = &segment[..name_len];
+        if name_len < segment.len() {
+            break;
+        }
+    }
+    if label.is_empty() {
+        "Unknown".to_string()
+    } else {
+        label.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::metrics::error_label;
+
+    #[derive(Debug)]
+    enum Inner {
+        BadInputsUtxo(Vec<u8>),
+        ValueNotConserved { consumed: u64 },
+    }
+
+    #[derive(Debug)]
+    enum Outer {
+        UtxoFailure(Inner),
+    }
+
+    #[test]
+    fn innermost_constructor_is_used_as_label() {
+        assert_eq!(
+            error_label(&Outer::UtxoFailure(Inner::BadInputsUtxo(vec![1]))),
+            "BadInputsUtxo"
+        );
+        assert_eq!(
+            error_label(&Outer::UtxoFailure(Inner::ValueNotConserved { consumed: 1 })),
+            "ValueNotConserved"
+        );
+    }
+}
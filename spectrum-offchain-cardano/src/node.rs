@@ -1,6 +1,53 @@
+use std::time::Duration;
+
+use spectrum_cardano_lib::connection::{NodeAddress, ReconnectPolicy, SharedConnectionState};
+
 #[derive(serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct NodeConfig<'a> {
-    pub path: &'a str,
+    /// Local node socket. Optional for chain-sync and tx submission when `peers` are given,
+    /// but local state query and mempool monitoring have no node-to-node counterpart,
+    /// so agents which need protocol parameters refuse to start without it.
+    #[serde(default, borrow)]
+    pub path: Option<&'a str>,
     pub magic: u64,
+    /// How broken connections to the node are re-established.
+    #[serde(default)]
+    pub reconnect: ReconnectPolicy,
+    /// Relays (`host:port`) to follow the chain and propagate transactions through
+    /// over node-to-node protocols instead of the local socket.
+    #[serde(default)]
+    pub peers: Vec<String>,
+    /// Relays accept TXs without validating them, a TX propagated through relays
+    /// is considered failed unless it's included in a block within this time.
+    #[serde(default = "default_inclusion_timeout")]
+    pub inclusion_timeout: Duration,
+}
+
+fn default_inclusion_timeout() -> Duration {
+    Duration::from_secs(300)
+}
+
+impl<'a> NodeConfig<'a> {
+    /// Address chain-sync and tx submission connect to.
+    pub fn address(&self) -> NodeAddress {
+        if self.peers.is_empty() {
+            NodeAddress::Local(
+                self.path
+                    .expect("Either node `path` or `peers` must be configured")
+                    .into(),
+            )
+        } else {
+            NodeAddress::Remote(self.peers.clone())
+        }
+    }
+}
+
+/// States of connections of node clients, shared with the components which report them.
+#[derive(Debug, Clone, Default)]
+pub struct NodeConnections {
+    pub chain_sync: SharedConnectionState,
+    pub mempool_sync: SharedConnectionState,
+    pub tx_submission: SharedConnectionState,
+    pub state_query: SharedConnectionState,
 }
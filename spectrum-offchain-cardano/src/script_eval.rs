@@ -0,0 +1,260 @@
+use std::fmt::{Display, Formatter};
+
+use cml_chain::address::Address;
+use cml_chain::builders::redeemer_builder::RedeemerWitnessKey;
+use cml_chain::builders::tx_builder::{ChangeSelectionAlgo, TransactionBuilder, TransactionUnspentOutput};
+use cml_chain::plutus::{CostModels, LegacyRedeemer, RedeemerTag};
+use cml_core::serialization::{Deserialize, Serialize};
+use uplc::tx::error::Error as UplcError;
+use uplc::tx::eval_phase_two_raw;
+
+use algebra_core::monoid::Monoid;
+use spectrum_cardano_lib::ex_units::ExUnits;
+pub use spectrum_cardano_lib::time::SlotConfig;
+
+/// Budget scripts are evaluated against. It is deliberately far above protocol limits
+/// so that an oversized transaction is measured rather than rejected.
+const EVAL_BUDGET: ExUnits = ExUnits {
+    mem: 140_000_000,
+    steps: 100_000_000_000,
+};
+
+#[derive(Debug, Clone)]
+pub enum ScriptEvalError {
+    /// Transaction draft could not be built.
+    Draft(String),
+    /// Evaluator could not process the transaction, says nothing about validity of the scripts.
+    Unavailable(String),
+    /// One of the scripts failed or exhausted the budget.
+    /// Carries the redeemer of the failed script when the evaluator reports it.
+    Eval {
+        redeemer: Option<RedeemerWitnessKey>,
+        reason: String,
+    },
+}
+
+impl ScriptEvalError {
+    /// Whether the transaction is known to fail phase-two validation.
+    pub fn is_script_failure(&self) -> bool {
+        matches!(self, ScriptEvalError::Eval { .. })
+    }
+
+    /// Redeemer of the script which failed, if known.
+    pub fn failed_redeemer(&self) -> Option<RedeemerWitnessKey> {
+        match self {
+            ScriptEvalError::Eval { redeemer, .. } => redeemer.clone(),
+            _ => None,
+        }
+    }
+}
+
+impl From<UplcError> for ScriptEvalError {
+    fn from(err: UplcError) -> Self {
+        match err {
+            UplcError::Machine(..) => ScriptEvalError::Eval {
+                redeemer: None,
+                reason: format!("{:?}", err),
+            },
+            UplcError::RedeemerError {
+                ref tag,
+                index,
+                err: ref cause,
+            } if matches!(**cause, UplcError::Machine(..)) => ScriptEvalError::Eval {
+                redeemer: parse_redeemer_tag(tag).map(|tag| RedeemerWitnessKey::new(tag, index as u64)),
+                reason: format!("{:?}", err),
+            },
+            _ => ScriptEvalError::Unavailable(format!("{:?}", err)),
+        }
+    }
+}
+
+/// The evaluator reports the purpose of the failed redeemer by name.
+fn parse_redeemer_tag(tag: &str) -> Option<RedeemerTag> {
+    match tag {
+        "Spend" => Some(RedeemerTag::Spend),
+        "Mint" => Some(RedeemerTag::Mint),
+        "Cert" => Some(RedeemerTag::Cert),
+        "Reward" => Some(RedeemerTag::Reward),
+        "Vote" => Some(RedeemerTag::Voting),
+        "Propose" => Some(RedeemerTag::Proposing),
+        _ => None,
+    }
+}
+
+impl Display for ScriptEvalError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScriptEvalError::Draft(err) => f.write_str(format!("Failed to draft tx: {}", err).as_str()),
+            ScriptEvalError::Unavailable(err) => {
+                f.write_str(format!("Failed to evaluate scripts: {}", err).as_str())
+            }
+            ScriptEvalError::Eval { reason, .. } => {
+                f.write_str(format!("Script evaluation failed: {}", reason).as_str())
+            }
+        }
+    }
+}
+
+/// Run all scripts of the transaction being built in [txb] locally.
+/// [utxos] must resolve every input, reference input and collateral of the transaction.
+pub fn evaluate_ex_units(
+    txb: &TransactionBuilder,
+    change_address: &Address,
+    utxos: &[TransactionUnspentOutput],
+    cost_models: &CostModels,
+    slot_config: SlotConfig,
+) -> Result<Vec<(RedeemerWitnessKey, ExUnits)>, ScriptEvalError> {
+    let draft_tx = txb
+        .build_for_evaluation(ChangeSelectionAlgo::Default, change_address)
+        .map_err(|err| ScriptEvalError::Draft(format!("{:?}", err)))?
+        .draft_tx()
+        .map_err(|err| ScriptEvalError::Draft(format!("{:?}", err)))?;
+    let resolved_utxos = utxos
+        .iter()
+        .map(|utxo| (utxo.input.to_cbor_bytes(), utxo.output.to_cbor_bytes()))
+        .collect::<Vec<_>>();
+    let redeemers = eval_phase_two_raw(
+        &draft_tx.to_cbor_bytes(),
+        &resolved_utxos,
+        Some(cost_models.to_cbor_bytes().as_slice()),
+        (EVAL_BUDGET.steps, EVAL_BUDGET.mem),
+        (
+            slot_config.zero_time,
+            slot_config.zero_slot,
+            slot_config.slot_length,
+        ),
+        false,
+        |_| (),
+    )
+    .map_err(ScriptEvalError::from)?;
+    redeemers
+        .into_iter()
+        .map(|raw| {
+            let redeemer = LegacyRedeemer::from_cbor_bytes(&raw)
+                .map_err(|err| ScriptEvalError::Unavailable(format!("{:?}", err)))?;
+            let ex_units = ExUnits {
+                mem: redeemer.ex_units.mem,
+                steps: redeemer.ex_units.steps,
+            };
+            Ok((RedeemerWitnessKey::new(redeemer.tag, redeemer.index), ex_units))
+        })
+        .collect()
+}
+
+/// Replace budgets of redeemers in [txb] with evaluated ones.
+/// Returns total ExUnits consumed by the transaction.
+pub fn set_evaluated_ex_units(
+    txb: &mut TransactionBuilder,
+    evaluated: &[(RedeemerWitnessKey, ExUnits)],
+) -> ExUnits {
+    let mut total = ExUnits::empty();
+    for (key, ex_units) in evaluated {
+        txb.set_exunits(key.clone(), (*ex_units).into());
+        total += *ex_units;
+    }
+    total
+}
+
+#[cfg(test)]
+mod tests {
+    use cml_chain::address::{Address, EnterpriseAddress};
+    use cml_chain::builders::input_builder::SingleInputBuilder;
+    use cml_chain::builders::output_builder::SingleOutputBuilderResult;
+    use cml_chain::builders::redeemer_builder::RedeemerWitnessKey;
+    use cml_chain::builders::tx_builder::{TransactionBuilder, TransactionUnspentOutput};
+    use cml_chain::builders::witness_builder::{PartialPlutusWitness, PlutusScriptWitness};
+    use cml_chain::certs::StakeCredential;
+    use cml_chain::plutus::{PlutusData, PlutusScript, PlutusV2Script, RedeemerTag};
+    use cml_chain::transaction::{DatumOption, TransactionInput, TransactionOutput};
+    use cml_chain::utils::BigInteger;
+    use cml_chain::Value;
+    use cml_crypto::{Ed25519KeyHash, TransactionHash};
+
+    use spectrum_cardano_lib::ex_units::ExUnits;
+    use spectrum_cardano_lib::protocol_params::{constant_cost_models, constant_tx_builder_config};
+
+    use crate::script_eval::{evaluate_ex_units, ScriptEvalError, SlotConfig};
+
+    /// `(program 1.0.0 (lam _ (lam _ (lam _ (con unit ())))))`
+    const ALWAYS_SUCCEEDS: &str = "46010000222499";
+    /// `(program 1.0.0 (lam _ (lam _ (lam _ (error)))))`
+    const ALWAYS_FAILS: &str = "46010000222601";
+
+    const MAINNET_SLOT_CONFIG: SlotConfig = SlotConfig {
+        zero_time: 1596059091000,
+        zero_slot: 4492800,
+        slot_length: 1000,
+    };
+
+    fn pub_key_address() -> Address {
+        EnterpriseAddress::new(1, StakeCredential::new_pub_key(Ed25519KeyHash::from([0u8; 28]))).to_address()
+    }
+
+    fn utxo(ix: u64, address: Address, datum: Option<DatumOption>) -> TransactionUnspentOutput {
+        TransactionUnspentOutput::new(
+            TransactionInput::new(TransactionHash::from([0u8; 32]), ix),
+            TransactionOutput::new(address, Value::from(10_000_000), datum, None),
+        )
+    }
+
+    /// Conway transaction spending an output locked by [script].
+    fn evaluate_spending_of(script: &str) -> Result<Vec<(RedeemerWitnessKey, ExUnits)>, ScriptEvalError> {
+        let script = PlutusV2Script::new(hex::decode(script).unwrap());
+        let script_address =
+            EnterpriseAddress::new(1, StakeCredential::new_script(script.hash())).to_address();
+        let datum = DatumOption::new_datum(PlutusData::new_integer(BigInteger::from(0)));
+        let locked = utxo(0, script_address, Some(datum));
+        let collateral = utxo(1, pub_key_address(), None);
+        let mut txb = TransactionBuilder::new(constant_tx_builder_config());
+        let witness = PartialPlutusWitness::new(
+            PlutusScriptWitness::Script(PlutusScript::PlutusV2(script)),
+            PlutusData::new_list(vec![]),
+        );
+        txb.add_input(
+            SingleInputBuilder::new(locked.input.clone(), locked.output.clone())
+                .plutus_script_inline_datum(witness, vec![])
+                .unwrap(),
+        )
+        .unwrap();
+        txb.add_collateral(
+            SingleInputBuilder::new(collateral.input.clone(), collateral.output.clone())
+                .payment_key()
+                .unwrap(),
+        )
+        .unwrap();
+        txb.add_output(SingleOutputBuilderResult::new(TransactionOutput::new(
+            pub_key_address(),
+            Value::from(5_000_000),
+            None,
+            None,
+        )))
+        .unwrap();
+        evaluate_ex_units(
+            &txb,
+            &pub_key_address(),
+            &[locked, collateral],
+            &constant_cost_models(),
+            MAINNET_SLOT_CONFIG,
+        )
+    }
+
+    #[test]
+    fn evaluates_conway_tx() {
+        let evaluated = evaluate_spending_of(ALWAYS_SUCCEEDS).unwrap();
+        assert_eq!(evaluated.len(), 1);
+        let (key, ex_units) = &evaluated[0];
+        assert_eq!(key.tag, RedeemerTag::Spend);
+        assert_eq!(key.index, 0);
+        assert!(ex_units.mem > 0 && ex_units.steps > 0);
+    }
+
+    #[test]
+    fn failing_script_is_reported() {
+        let err = evaluate_spending_of(ALWAYS_FAILS).unwrap_err();
+        assert!(err.is_script_failure(), "{}", err);
+        assert_eq!(
+            err.failed_redeemer(),
+            Some(RedeemerWitnessKey::new(RedeemerTag::Spend, 0))
+        );
+    }
+}
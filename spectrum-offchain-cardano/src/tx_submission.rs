@@ -1,6 +1,7 @@
 use std::collections::HashSet;
 use std::fmt::{Display, Formatter};
 use std::ops::Deref;
+use std::time::{Duration, Instant};
 
 use async_stream::stream;
 use cml_core::serialization::Serialize;
@@ -15,58 +16,100 @@ use pallas_network::miniprotocols::localtxsubmission::cardano_node_errors::{
 use pallas_network::miniprotocols::localtxsubmission::Response;
 use pallas_network::multiplexer;
 
-use cardano_submit_api::client::{Error, LocalTxSubmissionClient};
+use cardano_mempool_sync::ledger::LedgerObserver;
+use cardano_submit_api::client::Error;
+use cardano_submit_api::supervisor::SupervisedTxSubmissionClient;
+use cml_chain::plutus::RedeemerTag;
+use cml_chain::transaction::Transaction;
+use cml_crypto::{ScriptHash, TransactionHash};
 use pallas_primitives::conway::Value;
+use spectrum_cardano_lib::connection::{NodeAddress, SharedConnectionState};
+use spectrum_cardano_lib::era::SharedEra;
+use spectrum_cardano_lib::output::FinalizedTxOut;
+use spectrum_cardano_lib::transaction::TransactionOutputExtension;
 use spectrum_cardano_lib::OutputRef;
+use spectrum_offchain::executor::{AttributeRejection, TxSubmissionError};
 use spectrum_offchain::network::Network;
 use spectrum_offchain::tx_hash::CanonicalHash;
 
+use crate::metrics;
 use crate::node::NodeConfig;
 
-pub struct TxSubmissionAgent<'a, const ERA: u16, TxAdapter, Tx> {
-    client: LocalTxSubmissionClient<'a, ERA, Tx>,
+pub struct TxSubmissionAgent<'a, TxAdapter, Tx> {
+    client: SupervisedTxSubmissionClient<'a, Tx>,
     mailbox: mpsc::Receiver<SubmitTx<TxAdapter>>,
-    node_config: NodeConfig<'a>,
+    /// Set when TXs are propagated through relays.
+    inclusion: Option<InclusionWatch>,
 }
 
-impl<'a, const ERA: u16, TxAdapter, Tx> TxSubmissionAgent<'a, ERA, TxAdapter, Tx> {
-    pub async fn new(
+impl<'a, TxAdapter, Tx> TxSubmissionAgent<'a, TxAdapter, Tx> {
+    /// Connection to the node is established on first submission and re-established whenever it breaks.
+    /// [ledger] is consulted on the fate of TXs propagated through relays.
+    pub fn new(
         node_config: NodeConfig<'a>,
         buffer_size: usize,
-    ) -> Result<(Self, TxSubmissionChannel<ERA, TxAdapter>), Error> {
-        let tx_submission_client = LocalTxSubmissionClient::init(node_config.path, node_config.magic).await?;
+        era: SharedEra,
+        connection: SharedConnectionState,
+        ledger: LedgerObserver,
+    ) -> (Self, TxSubmissionChannel<TxAdapter>) {
+        let address = node_config.address();
+        let inclusion = matches!(address, NodeAddress::Remote(_)).then(|| InclusionWatch {
+            ledger,
+            timeout: node_config.inclusion_timeout,
+        });
+        let tx_submission_client = SupervisedTxSubmissionClient::new(
+            address,
+            node_config.magic,
+            era,
+            node_config.reconnect,
+            connection,
+        );
         let (snd, recv) = mpsc::channel(buffer_size);
         let agent = Self {
             client: tx_submission_client,
             mailbox: recv,
-            node_config,
+            inclusion,
         };
-        Ok((agent, TxSubmissionChannel(snd)))
+        (agent, TxSubmissionChannel(snd))
     }
 
-    pub fn recover(&mut self) {
-        self.client.unsafe_reset();
+    /// Start over with a new connection once the state of the protocol is unknown.
+    pub async fn recover(&mut self) {
+        self.client.reset().await;
     }
+}
+
+/// Relays don't report whether a TX is valid, it's only known to be once included in a block.
+#[derive(Clone)]
+struct InclusionWatch {
+    ledger: LedgerObserver,
+    timeout: Duration,
+}
 
-    pub async fn restarted(self) -> Result<Self, Error> {
-        let TxSubmissionAgent {
-            client,
-            mailbox,
-            node_config,
-        } = self;
-        client.close().await;
-        let new_tx_submission_client =
-            LocalTxSubmissionClient::init(node_config.path, node_config.magic).await?;
-        Ok(Self {
-            client: new_tx_submission_client,
-            mailbox,
-            node_config,
-        })
+impl InclusionWatch {
+    /// Wait until the TX is observed in a block, it's considered rejected for an unknown reason otherwise.
+    async fn wait(self, tx_hash: TransactionHash) -> SubmissionResult {
+        let deadline = Instant::now() + self.timeout;
+        while Instant::now() < deadline {
+            if self.ledger.is_included(&tx_hash) {
+                return SubmissionResult::Ok;
+            }
+            tokio::time::sleep(INCLUSION_POLL_INTERVAL).await;
+        }
+        warn!(
+            "TX {} was not included in a block within {:?}",
+            tx_hash, self.timeout
+        );
+        SubmissionResult::TxRejected {
+            errors: vec![].into(),
+        }
     }
 }
 
+const INCLUSION_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
 #[derive(Clone)]
-pub struct TxSubmissionChannel<const ERA: u16, Tx>(mpsc::Sender<SubmitTx<Tx>>);
+pub struct TxSubmissionChannel<Tx>(mpsc::Sender<SubmitTx<Tx>>);
 
 pub struct SubmitTx<Tx>(Tx, oneshot::Sender<SubmissionResult>);
 
@@ -87,12 +130,11 @@ impl From<SubmissionResult> for Result<(), RejectReasons> {
 
 const MAX_SUBMIT_ATTEMPTS: usize = 3;
 
-pub fn tx_submission_agent_stream<'a, const ERA: u16, TxAdapter, Tx>(
-    mut agent: TxSubmissionAgent<'a, ERA, TxAdapter, Tx>,
+pub fn tx_submission_agent_stream<'a, TxAdapter, Tx>(
+    mut agent: TxSubmissionAgent<'a, TxAdapter, Tx>,
 ) -> impl Stream<Item = ()> + 'a
 where
-    TxAdapter: Deref<Target = Tx> + CanonicalHash + 'a,
-    TxAdapter::Hash: Display,
+    TxAdapter: Deref<Target = Tx> + CanonicalHash<Hash = TransactionHash> + 'a,
     Tx: Serialize + Clone + 'a,
 {
     stream! {
@@ -100,35 +142,50 @@ where
             let SubmitTx(tx, on_resp) = agent.mailbox.select_next_some().await;
             let mut attempts_done = 0;
             let tx_hash = tx.canonical_hash();
+            let submitted_at = Instant::now();
+            let respond = move |on_resp: oneshot::Sender<SubmissionResult>, result: SubmissionResult| {
+                metrics::on_tx_submitted(&result, submitted_at.elapsed());
+                on_resp.send(result).expect("Responder was dropped");
+            };
             loop {
                 match agent.client.submit_tx((*tx).clone()).await {
-                    Ok(Response::Accepted) => on_resp.send(SubmissionResult::Ok).expect("Responder was dropped"),
+                    Ok(Response::Accepted) => match agent.inclusion.clone() {
+                        Some(inclusion) => {
+                            tokio::spawn(async move {
+                                respond(on_resp, inclusion.wait(tx_hash).await);
+                            });
+                        }
+                        None => respond(on_resp, SubmissionResult::Ok),
+                    },
                     Ok(Response::Rejected(errors)) => {
                         trace!("TX {} was rejected due to error: {:?}", tx_hash, errors);
-                        on_resp.send(SubmissionResult::TxRejected{errors:  RejectReasons(errors)}).expect("Responder was dropped");
+                        respond(on_resp, SubmissionResult::TxRejected{errors:  RejectReasons(errors)});
                     },
-                    Err(Error::TxSubmissionProtocol(err)) => {
+                    Err(Error::TxSubmissionProtocol(localtxsubmission::Error::ChannelError(
+                        multiplexer::Error::Decoding(_),
+                    ))) => {
                         trace!("Failed to submit TX {}: {}", tx_hash, hex::encode(tx.to_cbor_bytes()));
-                        match err {
-                            localtxsubmission::Error::ChannelError(multiplexer::Error::Decoding(_)) => {
-                                warn!("TX {} was likely rejected, reason unknown. Trying to recover.", tx_hash);
-                                agent.recover();
-                                on_resp.send(SubmissionResult::TxRejected{errors: vec![].into()}).expect("Responder was dropped");
-                            }
-                            retryable_err => {
-                                trace!("Failed to submit TX {}: protocol returned error: {}", tx_hash, retryable_err);
-                                if attempts_done < MAX_SUBMIT_ATTEMPTS {
-                                    trace!("Retrying");
-                                    attempts_done += 1;
-                                } else {
-                                    trace!("Restarting TxSubmissionProtocol");
-                                    agent = agent.restarted().await.expect("Failed to restart TxSubmissionProtocol");
-                                }
-                                continue;
-                            },
-                        };
-                    },
-                    Err(err) => panic!("Cannot submit TX {} due to {}", tx_hash, err),
+                        warn!("TX {} was likely rejected, reason unknown. Trying to recover.", tx_hash);
+                        agent.recover().await;
+                        respond(on_resp, SubmissionResult::TxRejected{errors: vec![].into()});
+                    }
+                    Err(Error::TxDecoding) => {
+                        warn!("TX {} is malformed", tx_hash);
+                        respond(on_resp, SubmissionResult::TxRejected{errors: vec![].into()});
+                    }
+                    Err(err) => {
+                        trace!("Failed to submit TX {}: {}", tx_hash, err);
+                        if attempts_done < MAX_SUBMIT_ATTEMPTS {
+                            trace!("Retrying");
+                            attempts_done += 1;
+                        } else {
+                            // Connection is likely broken, TX is retried once it is re-established.
+                            warn!("Failed to submit TX {} after {} attempts, reconnecting", tx_hash, attempts_done);
+                            agent.recover().await;
+                            attempts_done = 0;
+                        }
+                        continue;
+                    }
                 }
                 break;
             }
@@ -167,7 +224,7 @@ impl TryFrom<RejectReasons> for HashSet<OutputRef> {
 }
 
 #[async_trait::async_trait]
-impl<const ERA: u16, Tx> Network<Tx, RejectReasons> for TxSubmissionChannel<ERA, Tx>
+impl<Tx> Network<Tx, RejectReasons> for TxSubmissionChannel<Tx>
 where
     Tx: Send,
 {
@@ -181,3 +238,320 @@ where
 #[derive(Debug, Clone, derive_more::Display, derive_more::From)]
 #[display(fmt = "RejectReasons: {:?}", "_0")]
 pub struct RejectReasons(pub Vec<ApplyTxError>);
+
+/// Parts of the rejected transaction errors are attributed against.
+#[derive(Debug, Clone)]
+pub struct RejectedTx {
+    /// Inputs in the order the ledger indexes them.
+    inputs: Vec<OutputRef>,
+    /// Tags and indexes of all redeemers.
+    redeemers: Vec<(RedeemerTag, u64)>,
+}
+
+impl From<&Transaction> for RejectedTx {
+    fn from(tx: &Transaction) -> Self {
+        let mut inputs = tx
+            .body
+            .inputs
+            .iter()
+            .map(|input| OutputRef::from(input.clone()))
+            .collect::<Vec<_>>();
+        inputs.sort();
+        let redeemers = tx
+            .witness_set
+            .redeemers
+            .iter()
+            .flatten()
+            .map(|redeemer| (redeemer.tag, redeemer.index))
+            .collect();
+        Self { inputs, redeemers }
+    }
+}
+
+impl RejectedTx {
+    /// Index of the redeemer spending [input], if it's spent by a script.
+    fn spending_redeemer_index(&self, input: &OutputRef) -> Option<u64> {
+        let index = self.inputs.iter().position(|i| i == input)? as u64;
+        self.redeemers
+            .iter()
+            .any(|(tag, ix)| *tag == RedeemerTag::Spend && *ix == index)
+            .then_some(index)
+    }
+
+    /// Node doesn't point at the failed redeemer, it's only known when there is a single one.
+    fn script_failure(&self) -> TxSubmissionError<OutputRef> {
+        match self.redeemers.as_slice() {
+            [(tag, index)] => TxSubmissionError::ScriptFailure {
+                redeemer_index: Some(*index),
+                input: if *tag == RedeemerTag::Spend {
+                    self.inputs.get(*index as usize).copied()
+                } else {
+                    None
+                },
+            },
+            _ => TxSubmissionError::ScriptFailure {
+                redeemer_index: None,
+                input: None,
+            },
+        }
+    }
+}
+
+impl RejectReasons {
+    /// Decode errors reported by the node for [tx] into typed submission errors.
+    pub fn decode(self, tx: RejectedTx) -> TxRejected {
+        let mut missing_inputs = HashSet::new();
+        let mut failed_scripts = HashSet::new();
+        let mut errors = vec![];
+        for ApplyTxError { node_errors } in self.0 {
+            for error in node_errors {
+                match error {
+                    ShelleyLedgerPredFailure::UtxowFailure(BabbageUtxowPredFailure::UtxoFailure(
+                        BabbageUtxoPredFailure::AlonzoInBabbageUtxoPredFailure(
+                            AlonzoUtxoPredFailure::BadInputsUtxo(inputs),
+                        ),
+                    )) => missing_inputs.extend(
+                        inputs
+                            .into_iter()
+                            .map(|TxInput { tx_hash, index }| OutputRef::new((*tx_hash).into(), index)),
+                    ),
+                    ShelleyLedgerPredFailure::UtxowFailure(
+                        BabbageUtxowPredFailure::AlonzoInBabbageUtxowPredFailure(
+                            AlonzoUtxowPredFailure::ShelleyInAlonzoUtxowPredFailure(
+                                ShelleyUtxowPredFailure::ScriptWitnessNotValidatingUTXOW(scripts),
+                            ),
+                        ),
+                    ) => failed_scripts.extend(scripts.into_iter().map(|hash| ScriptHash::from(*hash))),
+                    other => errors.push(decode_failure(other, &tx)),
+                }
+            }
+        }
+        if !missing_inputs.is_empty() {
+            errors.insert(0, TxSubmissionError::MissingInputs(missing_inputs));
+        }
+        if errors.is_empty() && failed_scripts.is_empty() {
+            errors.push(TxSubmissionError::Unknown {
+                info: "Node gave no reason".to_string(),
+            });
+        }
+        TxRejected {
+            errors,
+            failed_scripts,
+            tx,
+        }
+    }
+}
+
+fn decode_failure(error: ShelleyLedgerPredFailure, tx: &RejectedTx) -> TxSubmissionError<OutputRef> {
+    match error {
+        ShelleyLedgerPredFailure::UtxowFailure(BabbageUtxowPredFailure::UtxoFailure(
+            BabbageUtxoPredFailure::AlonzoInBabbageUtxoPredFailure(failure),
+        )) => match failure {
+            // Phase-2 failures are reported without the failed script.
+            AlonzoUtxoPredFailure::UtxosFailure(_) => tx.script_failure(),
+            AlonzoUtxoPredFailure::FeeTooSmallUTxO(..) => TxSubmissionError::FeeTooSmall,
+            AlonzoUtxoPredFailure::ValueNotConservedUTxO(..) => TxSubmissionError::ValueNotConserved,
+            AlonzoUtxoPredFailure::OutsideValidityIntervalUTxO(..) => {
+                TxSubmissionError::OutsideValidityInterval
+            }
+            AlonzoUtxoPredFailure::InsufficientCollateral(..)
+            | AlonzoUtxoPredFailure::NoCollateralInputs
+            | AlonzoUtxoPredFailure::TooManyCollateralInputs(..)
+            | AlonzoUtxoPredFailure::CollateralContainsNonADA(..) => TxSubmissionError::CollateralIssue,
+            AlonzoUtxoPredFailure::ExUnitsTooBigUTxO(..) => TxSubmissionError::ExUnitsTooBig,
+            AlonzoUtxoPredFailure::MaxTxSizeUTxO(..) => TxSubmissionError::TxTooLarge,
+            other => TxSubmissionError::Unknown {
+                info: format!("{:?}", other),
+            },
+        },
+        ShelleyLedgerPredFailure::UtxowFailure(BabbageUtxowPredFailure::UtxoFailure(
+            BabbageUtxoPredFailure::IncorrectTotalCollateralField(..),
+        )) => TxSubmissionError::CollateralIssue,
+        other => TxSubmissionError::Unknown {
+            info: format!("{:?}", other),
+        },
+    }
+}
+
+/// Typed reasons of transaction rejection.
+#[derive(Debug, Clone)]
+pub struct TxRejected {
+    errors: Vec<TxSubmissionError<OutputRef>>,
+    /// Scripts which didn't validate, inputs they guard are known once spent outputs are resolved.
+    failed_scripts: HashSet<ScriptHash>,
+    tx: RejectedTx,
+}
+
+impl Display for TxRejected {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "TxRejected: {:?}, failed scripts: {:?}",
+            self.errors, self.failed_scripts
+        )
+    }
+}
+
+impl AttributeRejection<FinalizedTxOut, OutputRef> for TxRejected {
+    fn attribute(self, spent: &[FinalizedTxOut]) -> Vec<TxSubmissionError<OutputRef>> {
+        let mut errors = self.errors;
+        if !self.failed_scripts.is_empty() {
+            let failed_inputs = spent
+                .iter()
+                .filter(|FinalizedTxOut(out, _)| {
+                    out.script_hash()
+                        .map_or(false, |hash| self.failed_scripts.contains(&hash))
+                })
+                .map(|FinalizedTxOut(_, oref)| TxSubmissionError::ScriptFailure {
+                    redeemer_index: self.tx.spending_redeemer_index(oref),
+                    input: Some(*oref),
+                })
+                .collect::<Vec<_>>();
+            if failed_inputs.is_empty() {
+                // Failed scripts don't guard any of the spent outputs, e.g. minting policies.
+                errors.push(TxSubmissionError::ScriptFailure {
+                    redeemer_index: None,
+                    input: None,
+                });
+            } else {
+                errors.extend(failed_inputs);
+            }
+        }
+        errors
+    }
+}
+
+/// [TxSubmissionChannel] which decodes rejection reasons against the submitted transaction.
+#[derive(Clone)]
+pub struct TypedTxSubmissionChannel<Tx>(pub TxSubmissionChannel<Tx>);
+
+#[async_trait::async_trait]
+impl<Tx> Network<Tx, TxRejected> for TypedTxSubmissionChannel<Tx>
+where
+    Tx: Deref<Target = Transaction> + Send,
+{
+    async fn submit_tx(&mut self, tx: Tx) -> Result<(), TxRejected> {
+        let rejected_tx = RejectedTx::from(tx.deref());
+        self.0
+            .submit_tx(tx)
+            .await
+            .map_err(|reasons| reasons.decode(rejected_tx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use cml_chain::address::EnterpriseAddress;
+    use cml_chain::certs::StakeCredential;
+    use cml_chain::plutus::RedeemerTag;
+    use cml_chain::transaction::TransactionOutput;
+    use cml_chain::Value;
+    use cml_crypto::{ScriptHash, TransactionHash};
+    use pallas_network::miniprotocols::localtxsubmission::cardano_node_errors::ApplyTxError;
+
+    use spectrum_cardano_lib::output::FinalizedTxOut;
+    use spectrum_cardano_lib::OutputRef;
+    use spectrum_offchain::executor::{AttributeRejection, TxSubmissionError};
+
+    use crate::tx_submission::{RejectReasons, RejectedTx};
+
+    /// `UtxowFailure (UtxoFailure (AlonzoInBabbageUtxoPredFailure (BadInputsUtxo {11..11#1})))`
+    const BAD_INPUTS: &str =
+        "81820082028201820081825820111111111111111111111111111111111111111111111111111111111111111101";
+    /// `UtxowFailure (UtxoFailure (AlonzoInBabbageUtxoPredFailure (FeeTooSmallUTxO 172196 178400)))`
+    const FEE_TOO_SMALL: &str = "8182008202820183041a0002a0a41a0002b8e0";
+    /// `UtxowFailure (AlonzoInBabbageUtxowPredFailure (ShelleyInAlonzoUtxowPredFailure
+    ///   (ScriptWitnessNotValidatingUTXOW {22..22})))`
+    const SCRIPT_WITNESS_NOT_VALIDATING: &str =
+        "81820082018200820381581c22222222222222222222222222222222222222222222222222222222";
+    /// `UtxowFailure (UtxoFailure (AlonzoInBabbageUtxoPredFailure TriesToForgeADA))`
+    const TRIES_TO_FORGE_ADA: &str = "81820082028201810b";
+
+    fn reasons(payload: &str) -> RejectReasons {
+        let raw = hex::decode(payload).unwrap();
+        RejectReasons(vec![minicbor::decode::<ApplyTxError>(&raw).unwrap()])
+    }
+
+    fn oref(ix: u64) -> OutputRef {
+        OutputRef::new(TransactionHash::from([0u8; 32]), ix)
+    }
+
+    fn locked_by(script: ScriptHash, oref: OutputRef) -> FinalizedTxOut {
+        let addr = EnterpriseAddress::new(0, StakeCredential::new_script(script)).to_address();
+        FinalizedTxOut(
+            TransactionOutput::new(addr, Value::from(2_000_000), None, None),
+            oref,
+        )
+    }
+
+    /// Three inputs, the first two are spent by scripts.
+    fn rejected_tx() -> RejectedTx {
+        RejectedTx {
+            inputs: vec![oref(0), oref(1), oref(2)],
+            redeemers: vec![(RedeemerTag::Spend, 0), (RedeemerTag::Spend, 1)],
+        }
+    }
+
+    #[test]
+    fn decode_bad_inputs() {
+        let errors = reasons(BAD_INPUTS).decode(rejected_tx()).attribute(&[]);
+        let missing = OutputRef::new(TransactionHash::from([0x11u8; 32]), 1);
+        assert_eq!(
+            errors,
+            vec![TxSubmissionError::MissingInputs(HashSet::from([missing]))]
+        );
+    }
+
+    #[test]
+    fn decode_fee_too_small() {
+        let errors = reasons(FEE_TOO_SMALL).decode(rejected_tx()).attribute(&[]);
+        assert_eq!(errors, vec![TxSubmissionError::FeeTooSmall]);
+    }
+
+    #[test]
+    fn failed_script_is_attributed_to_inputs_it_guards() {
+        let failed = ScriptHash::from([0x22u8; 28]);
+        let other = ScriptHash::from([0x33u8; 28]);
+        let spent = [locked_by(other, oref(0)), locked_by(failed, oref(1))];
+        let errors = reasons(SCRIPT_WITNESS_NOT_VALIDATING)
+            .decode(rejected_tx())
+            .attribute(&spent);
+        assert_eq!(
+            errors,
+            vec![TxSubmissionError::ScriptFailure {
+                redeemer_index: Some(1),
+                input: Some(oref(1)),
+            }]
+        );
+    }
+
+    #[test]
+    fn failed_script_guarding_no_spent_input_is_not_attributed() {
+        let other = ScriptHash::from([0x33u8; 28]);
+        let spent = [locked_by(other, oref(0))];
+        let errors = reasons(SCRIPT_WITNESS_NOT_VALIDATING)
+            .decode(rejected_tx())
+            .attribute(&spent);
+        assert_eq!(
+            errors,
+            vec![TxSubmissionError::ScriptFailure {
+                redeemer_index: None,
+                input: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn unrecognized_failure_is_kept_as_unknown() {
+        let errors = reasons(TRIES_TO_FORGE_ADA).decode(rejected_tx()).attribute(&[]);
+        assert!(matches!(errors.as_slice(), [TxSubmissionError::Unknown { .. }]));
+    }
+
+    #[test]
+    fn empty_rejection_is_unknown() {
+        let errors = RejectReasons(vec![]).decode(rejected_tx()).attribute(&[]);
+        assert!(matches!(errors.as_slice(), [TxSubmissionError::Unknown { .. }]));
+    }
+}
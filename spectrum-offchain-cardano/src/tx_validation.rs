@@ -0,0 +1,467 @@
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+
+use cml_chain::builders::tx_builder::{TransactionBuilderConfig, TransactionUnspentOutput};
+use cml_chain::min_ada::min_ada_required;
+use cml_chain::transaction::Transaction;
+use cml_chain::Value;
+use cml_core::serialization::Serialize;
+use cml_crypto::Ed25519KeyHash;
+
+use spectrum_cardano_lib::chain_tip::ChainTip;
+use spectrum_cardano_lib::collateral::Collateral;
+use spectrum_cardano_lib::hash::hash_transaction_canonical;
+use spectrum_cardano_lib::output::FinalizedTxOut;
+use spectrum_cardano_lib::transaction::{OutboundTransaction, TransactionOutputExtension};
+use spectrum_cardano_lib::OutputRef;
+use spectrum_offchain::data::Has;
+use spectrum_offchain::tx_validator::{Remedy, TxValidator};
+
+/// Reasons a transaction would fail phase-1 validation on the ledger.
+#[derive(Debug, Clone)]
+pub enum TxValidationError {
+    TxTooLarge {
+        size: usize,
+        max_size: usize,
+    },
+    OutputBelowMinUtxo {
+        output: OutputRef,
+        coin: u64,
+        min_coin: u64,
+    },
+    UnresolvedInput(OutputRef),
+    /// Sum of values overflows, so the balance can't be checked.
+    ValueOverflow {
+        spent: HashSet<OutputRef>,
+    },
+    ValueNotConserved {
+        consumed: Value,
+        produced: Value,
+    },
+    InsufficientCollateral {
+        provided: u64,
+        required: u64,
+    },
+    MissingRequiredSigners {
+        signers: Vec<Ed25519KeyHash>,
+    },
+    OutsideValidityInterval {
+        slot: u64,
+        valid_from: Option<u64>,
+        valid_until: Option<u64>,
+    },
+}
+
+impl Display for TxValidationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TxValidationError::TxTooLarge { size, max_size } => {
+                write!(f, "Tx size {} exceeds max size {}", size, max_size)
+            }
+            TxValidationError::OutputBelowMinUtxo {
+                output,
+                coin,
+                min_coin,
+            } => {
+                write!(
+                    f,
+                    "Output {} holds {} lovelace, min required is {}",
+                    output, coin, min_coin
+                )
+            }
+            TxValidationError::UnresolvedInput(input) => write!(f, "Input {} is unresolved", input),
+            TxValidationError::ValueOverflow { .. } => f.write_str("Value overflow"),
+            TxValidationError::ValueNotConserved {
+                consumed, produced, ..
+            } => {
+                write!(
+                    f,
+                    "Value not conserved, consumed: {:?}, produced: {:?}",
+                    consumed, produced
+                )
+            }
+            TxValidationError::InsufficientCollateral {
+                provided, required, ..
+            } => {
+                write!(f, "Collateral {} is below required {}", provided, required)
+            }
+            TxValidationError::MissingRequiredSigners { signers, .. } => {
+                write!(f, "Missing signatures of {:?}", signers)
+            }
+            TxValidationError::OutsideValidityInterval {
+                slot,
+                valid_from,
+                valid_until,
+            } => write!(
+                f,
+                "Slot {} is outside of validity interval [{:?}, {:?})",
+                slot, valid_from, valid_until
+            ),
+        }
+    }
+}
+
+impl From<TxValidationError> for Remedy<OutputRef> {
+    fn from(err: TxValidationError) -> Self {
+        match err {
+            TxValidationError::TxTooLarge { .. } => Remedy::Shrink,
+            TxValidationError::OutputBelowMinUtxo { output, .. } => Remedy::Exclude(HashSet::from([output])),
+            TxValidationError::UnresolvedInput(input) => Remedy::Exclude(HashSet::from([input])),
+            // Values of the entities involved don't add up, so they are kept out until the fault is found.
+            TxValidationError::ValueOverflow { spent } => Remedy::Exclude(spent),
+            // Balancing, collateral and signing are up to the operator, entities are not to blame.
+            TxValidationError::ValueNotConserved { .. }
+            | TxValidationError::InsufficientCollateral { .. }
+            | TxValidationError::MissingRequiredSigners { .. } => Remedy::RetryLater,
+            // Time-bound takers are evicted by the book itself as time goes.
+            TxValidationError::OutsideValidityInterval { .. } => Remedy::RetryLater,
+        }
+    }
+}
+
+/// Phase-1 ledger rules checked locally.
+#[derive(Debug, Copy, Clone)]
+pub struct CardanoTxValidator;
+
+impl<Ctx> TxValidator<OutboundTransaction<Transaction>, FinalizedTxOut, Ctx> for CardanoTxValidator
+where
+    Ctx: Has<TransactionBuilderConfig> + Has<Collateral> + Has<ChainTip>,
+{
+    type Error = TxValidationError;
+
+    fn validate(
+        &self,
+        tx: &OutboundTransaction<Transaction>,
+        inputs: &[FinalizedTxOut],
+        ctx: &Ctx,
+    ) -> Result<(), Self::Error> {
+        let config = ctx.select::<TransactionBuilderConfig>();
+        check_size(tx, config.max_tx_size)?;
+        check_min_utxo(tx, config.coins_per_utxo_byte)?;
+        check_value_conservation(tx, inputs)?;
+        check_collateral(
+            tx,
+            ctx.select::<Collateral>().into(),
+            config._collateral_percentage,
+        )?;
+        check_required_signers(tx)?;
+        check_validity_interval(tx, ctx.select::<ChainTip>().get())
+    }
+}
+
+fn spent_inputs(tx: &Transaction) -> HashSet<OutputRef> {
+    tx.body.inputs.iter().cloned().map(OutputRef::from).collect()
+}
+
+fn check_size(tx: &Transaction, max_tx_size: u32) -> Result<(), TxValidationError> {
+    let size = tx.to_cbor_bytes().len();
+    let max_size = max_tx_size as usize;
+    if size > max_size {
+        return Err(TxValidationError::TxTooLarge { size, max_size });
+    }
+    Ok(())
+}
+
+fn check_min_utxo(tx: &Transaction, coins_per_utxo_byte: u64) -> Result<(), TxValidationError> {
+    let tx_hash = hash_transaction_canonical(&tx.body);
+    for (ix, output) in tx.body.outputs.iter().enumerate() {
+        let coin = output.value().coin;
+        let min_coin = min_ada_required(output, coins_per_utxo_byte).unwrap_or(u64::MAX);
+        if coin < min_coin {
+            return Err(TxValidationError::OutputBelowMinUtxo {
+                output: OutputRef::new(tx_hash, ix as u64),
+                coin,
+                min_coin,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Minted tokens count as consumed, burned ones as produced.
+/// Deposits and refunds are not taken into account as operator's transactions don't carry certificates.
+fn check_value_conservation(tx: &Transaction, inputs: &[FinalizedTxOut]) -> Result<(), TxValidationError> {
+    let overflow = |_| TxValidationError::ValueOverflow {
+        spent: spent_inputs(tx),
+    };
+    let mut consumed = Value::zero();
+    for input in &tx.body.inputs {
+        let input_ref = OutputRef::from(input.clone());
+        let FinalizedTxOut(output, _) = inputs
+            .iter()
+            .find(|FinalizedTxOut(_, oref)| *oref == input_ref)
+            .ok_or(TxValidationError::UnresolvedInput(input_ref))?;
+        consumed = consumed.checked_add(output.value()).map_err(overflow)?;
+    }
+    if let Some(withdrawals) = &tx.body.withdrawals {
+        for amount in withdrawals.values() {
+            consumed = consumed.checked_add(&Value::from(*amount)).map_err(overflow)?;
+        }
+    }
+    let mut produced = Value::from(tx.body.fee);
+    for output in &tx.body.outputs {
+        produced = produced.checked_add(output.value()).map_err(overflow)?;
+    }
+    if let Some(mint) = &tx.body.mint {
+        consumed = consumed
+            .checked_add(&Value::new(0, mint.as_positive_multiasset()))
+            .map_err(overflow)?;
+        produced = produced
+            .checked_add(&Value::new(0, mint.as_negative_multiasset()))
+            .map_err(overflow)?;
+    }
+    let conserved = consumed
+        .checked_sub(&produced)
+        .map_or(false, |remainder| remainder.is_zero());
+    if !conserved {
+        return Err(TxValidationError::ValueNotConserved { consumed, produced });
+    }
+    Ok(())
+}
+
+fn check_collateral(
+    tx: &Transaction,
+    collateral: TransactionUnspentOutput,
+    collateral_percentage: u32,
+) -> Result<(), TxValidationError> {
+    if tx.body.collateral_inputs.is_none() {
+        return Ok(());
+    }
+    let provided = tx.body.total_collateral.unwrap_or_else(|| {
+        let returned = tx
+            .body
+            .collateral_return
+            .as_ref()
+            .map_or(0, |output| output.value().coin);
+        collateral.output.value().coin.saturating_sub(returned)
+    });
+    let required = (tx.body.fee * collateral_percentage as u64).div_ceil(100);
+    if provided < required {
+        return Err(TxValidationError::InsufficientCollateral { provided, required });
+    }
+    Ok(())
+}
+
+fn check_required_signers(tx: &Transaction) -> Result<(), TxValidationError> {
+    let signed_by = tx
+        .witness_set
+        .vkeywitnesses
+        .iter()
+        .flatten()
+        .map(|witness| witness.vkey.hash())
+        .collect::<HashSet<_>>();
+    let missing = tx
+        .body
+        .required_signers
+        .iter()
+        .flatten()
+        .filter(|signer| !signed_by.contains(signer))
+        .cloned()
+        .collect::<Vec<_>>();
+    if !missing.is_empty() {
+        return Err(TxValidationError::MissingRequiredSigners { signers: missing });
+    }
+    Ok(())
+}
+
+/// The transaction is checked against the slot of the latest block the agent has seen,
+/// which is the ledger state it's going to be applied to.
+fn check_validity_interval(tx: &Transaction, slot: u64) -> Result<(), TxValidationError> {
+    let valid_from = tx.body.validity_interval_start;
+    let valid_until = tx.body.ttl;
+    let started = valid_from.map_or(true, |start| slot >= start);
+    let expired = valid_until.map_or(false, |ttl| slot >= ttl);
+    if !started || expired {
+        return Err(TxValidationError::OutsideValidityInterval {
+            slot,
+            valid_from,
+            valid_until,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use cml_chain::address::{Address, EnterpriseAddress};
+    use cml_chain::assets::{AssetName, Mint, MultiAsset};
+    use cml_chain::builders::tx_builder::TransactionUnspentOutput;
+    use cml_chain::certs::StakeCredential;
+    use cml_chain::transaction::{
+        Transaction, TransactionBody, TransactionInput, TransactionOutput, TransactionWitnessSet,
+    };
+    use cml_chain::{PolicyId, Value};
+    use cml_crypto::{Ed25519KeyHash, TransactionHash};
+
+    use spectrum_cardano_lib::output::FinalizedTxOut;
+    use spectrum_cardano_lib::OutputRef;
+    use spectrum_offchain::tx_validator::Remedy;
+
+    use crate::tx_validation::{
+        check_collateral, check_required_signers, check_size, check_validity_interval,
+        check_value_conservation, TxValidationError,
+    };
+
+    fn address() -> Address {
+        EnterpriseAddress::new(0, StakeCredential::new_pub_key(Ed25519KeyHash::from([0u8; 28]))).to_address()
+    }
+
+    fn utxo(ix: u64, value: Value) -> FinalizedTxOut {
+        FinalizedTxOut(
+            TransactionOutput::new(address(), value, None, None),
+            OutputRef::new(TransactionHash::from([0u8; 32]), ix),
+        )
+    }
+
+    fn tx(inputs: &[FinalizedTxOut], outputs: Vec<Value>, fee: u64) -> Transaction {
+        let body = TransactionBody::new(
+            inputs
+                .iter()
+                .map(|FinalizedTxOut(_, oref)| TransactionInput::from(*oref))
+                .collect(),
+            outputs
+                .into_iter()
+                .map(|value| TransactionOutput::new(address(), value, None, None))
+                .collect(),
+            fee,
+        );
+        Transaction::new(body, TransactionWitnessSet::new(), true, None)
+    }
+
+    fn token(amount: u64) -> Value {
+        let mut ma = MultiAsset::new();
+        ma.set(
+            PolicyId::from([1u8; 28]),
+            AssetName::utf8_unsafe("tkn".into()),
+            amount,
+        );
+        Value::new(0, ma)
+    }
+
+    #[test]
+    fn balanced_tx_is_valid() {
+        let inputs = [utxo(0, 3_000_000.into()), utxo(1, 2_000_000.into())];
+        let tx = tx(&inputs, vec![4_800_000.into()], 200_000);
+        assert!(check_value_conservation(&tx, &inputs).is_ok());
+    }
+
+    #[test]
+    fn unbalanced_tx_blames_no_entity() {
+        let inputs = [utxo(0, 3_000_000.into()), utxo(1, 2_000_000.into())];
+        let tx = tx(&inputs, vec![4_900_000.into()], 200_000);
+        let err = check_value_conservation(&tx, &inputs).unwrap_err();
+        assert!(matches!(err, TxValidationError::ValueNotConserved { .. }));
+        assert_eq!(Remedy::from(err), Remedy::RetryLater);
+    }
+
+    #[test]
+    fn input_missing_among_resolved_ones_is_excluded() {
+        let inputs = [utxo(0, 3_000_000.into()), utxo(1, 2_000_000.into())];
+        let tx = tx(&inputs, vec![4_800_000.into()], 200_000);
+        let err = check_value_conservation(&tx, &inputs[..1]).unwrap_err();
+        assert_eq!(Remedy::from(err), Remedy::Exclude(HashSet::from([inputs[1].1])));
+    }
+
+    #[test]
+    fn overflowing_value_is_reported() {
+        let inputs = [utxo(0, u64::MAX.into()), utxo(1, 1.into())];
+        let tx = tx(&inputs, vec![], 0);
+        assert!(matches!(
+            check_value_conservation(&tx, &inputs),
+            Err(TxValidationError::ValueOverflow { .. })
+        ));
+    }
+
+    #[test]
+    fn minted_tokens_are_balanced() {
+        let inputs = [utxo(0, 3_000_000.into())];
+        let mut mint = Mint::new();
+        mint.set(
+            PolicyId::from([1u8; 28]),
+            AssetName::utf8_unsafe("tkn".into()),
+            100,
+        );
+        let produced = Value::from(2_800_000).checked_add(&token(100)).unwrap();
+        let mut minting_tx = tx(&inputs, vec![produced], 200_000);
+        minting_tx.body.mint = Some(mint.clone());
+        assert!(check_value_conservation(&minting_tx, &inputs).is_ok());
+
+        let overminted = Value::from(2_800_000).checked_add(&token(101)).unwrap();
+        let mut overminting_tx = tx(&inputs, vec![overminted], 200_000);
+        overminting_tx.body.mint = Some(mint);
+        assert!(matches!(
+            check_value_conservation(&overminting_tx, &inputs),
+            Err(TxValidationError::ValueNotConserved { .. })
+        ));
+    }
+
+    #[test]
+    fn burned_tokens_are_balanced() {
+        let inputs = [utxo(0, Value::from(3_000_000).checked_add(&token(100)).unwrap())];
+        let mut burn = Mint::new();
+        burn.set(
+            PolicyId::from([1u8; 28]),
+            AssetName::utf8_unsafe("tkn".into()),
+            -100,
+        );
+        let mut burning_tx = tx(&inputs, vec![2_800_000.into()], 200_000);
+        burning_tx.body.mint = Some(burn);
+        assert!(check_value_conservation(&burning_tx, &inputs).is_ok());
+    }
+
+    #[test]
+    fn size_is_checked_against_configured_limit() {
+        let inputs = [utxo(0, 3_000_000.into())];
+        let tx = tx(&inputs, vec![2_800_000.into()], 200_000);
+        assert!(check_size(&tx, 16384).is_ok());
+        let err = check_size(&tx, 16).unwrap_err();
+        assert!(matches!(err, TxValidationError::TxTooLarge { max_size: 16, .. }));
+        assert_eq!(Remedy::from(err), Remedy::Shrink);
+    }
+
+    #[test]
+    fn collateral_is_checked_against_configured_percentage() {
+        let inputs = [utxo(0, 3_000_000.into())];
+        let mut tx = tx(&inputs, vec![2_800_000.into()], 200_000);
+        let FinalizedTxOut(collateral_out, collateral_ref) = utxo(7, 300_000.into());
+        let collateral_in = TransactionInput::from(collateral_ref);
+        tx.body.collateral_inputs = Some(vec![collateral_in.clone()]);
+        let collateral = TransactionUnspentOutput::new(collateral_in, collateral_out);
+        assert!(check_collateral(&tx, collateral.clone(), 150).is_ok());
+        let err = check_collateral(&tx, collateral, 200).unwrap_err();
+        assert!(matches!(
+            err,
+            TxValidationError::InsufficientCollateral {
+                provided: 300_000,
+                required: 400_000,
+                ..
+            }
+        ));
+        assert_eq!(Remedy::from(err), Remedy::RetryLater);
+    }
+
+    #[test]
+    fn missing_signature_blames_no_entity() {
+        let inputs = [utxo(0, 3_000_000.into())];
+        let mut tx = tx(&inputs, vec![2_800_000.into()], 200_000);
+        assert!(check_required_signers(&tx).is_ok());
+        tx.body.required_signers = Some(vec![Ed25519KeyHash::from([2u8; 28])]);
+        let err = check_required_signers(&tx).unwrap_err();
+        assert_eq!(Remedy::from(err), Remedy::RetryLater);
+    }
+
+    #[test]
+    fn validity_interval_is_checked_against_chain_tip() {
+        let inputs = [utxo(0, 3_000_000.into())];
+        let mut tx = tx(&inputs, vec![2_800_000.into()], 200_000);
+        tx.body.validity_interval_start = Some(100);
+        tx.body.ttl = Some(200);
+        assert!(check_validity_interval(&tx, 99).is_err());
+        assert!(check_validity_interval(&tx, 100).is_ok());
+        assert!(check_validity_interval(&tx, 199).is_ok());
+        let err = check_validity_interval(&tx, 200).unwrap_err();
+        assert_eq!(Remedy::from(err), Remedy::RetryLater);
+    }
+}
@@ -21,6 +21,7 @@ use crate::circular_filter::CircularFilter;
 use crate::data::order::{PendingOrder, ProgressingOrder, SpecializedOrder, SuspendedOrder, UniqueOrder};
 use crate::data::Has;
 use crate::maker::Maker;
+use crate::metrics;
 
 pub mod data;
 pub mod persistence;
@@ -48,6 +49,8 @@ where
     fn soft_evict<'a>(&mut self, ord: TOrd::TOrderId)
     where
         TOrd: 'a;
+    /// All orders currently held in backlog, best first.
+    fn orders(&self) -> Vec<TOrd>;
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Into, From)]
@@ -97,6 +100,7 @@ where
             self.queue.push(id, wt);
             self.store.insert(id, ord);
             self.capacity -= 1;
+            metrics::on_hot_backlog_put();
         }
     }
 
@@ -104,6 +108,7 @@ where
         while let Some((oid, _)) = self.queue.pop() {
             if let Some(ord) = self.store.remove(&oid) {
                 self.capacity += 1;
+                metrics::on_hot_backlog_removed();
                 return Some(ord);
             }
         }
@@ -124,6 +129,7 @@ where
         self.soft_evicted_orders.remove(&ord);
         if self.store.remove(&ord).is_some() {
             self.capacity += 1;
+            metrics::on_hot_backlog_removed();
         }
     }
 
@@ -133,6 +139,14 @@ where
     {
         self.soft_evicted_orders.add(ord);
     }
+
+    fn orders(&self) -> Vec<TOrd> {
+        self.queue
+            .clone()
+            .into_sorted_iter()
+            .filter_map(|(oid, _)| self.store.get(&oid).cloned())
+            .collect()
+    }
 }
 
 /// Backlog manages orders on all stages of their life.
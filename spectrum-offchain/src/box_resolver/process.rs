@@ -11,9 +11,9 @@ use crate::data::event::{Channel, Confirmed, Predicted, StateUpdate, Unconfirmed
 use crate::data::EntitySnapshot;
 use crate::partitioning::Partitioned;
 
-pub fn pool_tracking_stream<'a, const N: usize, S, Repo, Pool>(
+pub fn pool_tracking_stream<'a, S, Repo, Pool>(
     upstream: S,
-    pools: Partitioned<N, Pool::StableId, Arc<Mutex<Repo>>>,
+    pools: Partitioned<Pool::StableId, Arc<Mutex<Repo>>>,
 ) -> impl Stream<Item = ()> + 'a
 where
     S: Stream<Item = Channel<StateUpdate<Pool>>> + 'a,
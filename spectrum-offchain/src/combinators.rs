@@ -38,6 +38,13 @@ impl<O1, O2> Ior<O1, O2> {
             Ior::Both(lh, rh) => Ior::Both(lhf(lh), rhf(rh)),
         }
     }
+
+    pub fn right(self) -> Option<O2> {
+        match self {
+            Ior::Left(_) => None,
+            Ior::Right(rh) | Ior::Both(_, rh) => Some(rh),
+        }
+    }
 }
 
 impl<O1, O2> TryFrom<(Option<O1>, Option<O2>)> for Ior<O1, O2> {
@@ -99,7 +99,7 @@ where
 
 /// A baked entity [T] paired with a computed version [V],
 /// i.e. [T] can no longer be modified.
-#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug, serde::Serialize, serde::Deserialize)]
 pub struct Baked<T, V> {
     pub entity: T,
     pub version: V,
@@ -1,5 +1,7 @@
+use std::collections::HashSet;
 use std::fmt::Debug;
 use std::fmt::Display;
+use std::hash::Hash;
 use std::marker::PhantomData;
 use std::sync::{Arc, Once};
 use std::time::Duration;
@@ -20,7 +22,7 @@ use crate::data::event::{Predicted, Traced};
 use crate::data::order::SpecializedOrder;
 use crate::data::EntitySnapshot;
 use crate::executor::RunOrderError::{Fatal, NonFatal};
-use crate::executor::TxSubmissionError::{OrderUtxoIsSpent, PoolUtxoIsSpent, UnknownError};
+use crate::executor::TxSubmissionError::{MissingInputs, ScriptFailure};
 use crate::network::Network;
 use crate::tx_prover::TxProver;
 
@@ -106,56 +108,51 @@ impl<Net, Backlog, Pools, Prover, Ctx, Ord, Pool, TxCandidate, Tx, Err>
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
-pub enum TxSubmissionError {
-    PoolUtxoIsSpent,
-    OrderUtxoIsSpent,
-    UnknownError { info: String },
+/// Typed reason of transaction rejection by the network.
+/// Inputs are identified by versions [V] of entities they carry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TxSubmissionError<V: Eq + Hash> {
+    /// Inputs are already spent or never existed.
+    MissingInputs(HashSet<V>),
+    /// Script failed. Failing redeemer and the input it unlocks are set when they can be told apart.
+    ScriptFailure {
+        redeemer_index: Option<u64>,
+        input: Option<V>,
+    },
+    FeeTooSmall,
+    ValueNotConserved,
+    OutsideValidityInterval,
+    /// Collateral is missing, insufficient or malformed.
+    CollateralIssue,
+    ExUnitsTooBig,
+    TxTooLarge,
+    Unknown {
+        info: String,
+    },
 }
 
-// Temporal solution for handling errors from cardano node socket.
-// Based on attempt of extracting badInputsUtxo error from Err
-// display instance. In case of submission through socket - display
-// instance is just hex encoded string with error response.
-// Due to impossibility of guarantee that all '25820 + 32 bytes'
-// patterns represent badInputsUtxo case - we can not get error
-// directly after parsing response
-fn process_tx_rejected_error<'a, Err: Display, PoolVersion: Display, OrderVersion: Display>(
-    tx_error: Err,
-    prev_pool_version: PoolVersion,
-    order_version: OrderVersion,
-) -> Vec<TxSubmissionError> {
-    let prefix: String = "25820".to_string(); // prefix of cbor set start?
-    let parsed_error = format!("{}", tx_error);
-    // string like: 25820 + tx_hash_without_index
-    let pool_tx = prefix.clone()
-        + (prev_pool_version.to_string().split("#").collect::<Vec<&str>>())
-            .first()
-            .unwrap();
-    let order_tx = prefix.clone()
-        + (order_version.to_string().split("#").collect::<Vec<&str>>())
-            .first()
-            .unwrap();
-
-    let patterns: [(String, TxSubmissionError); 2] =
-        [(pool_tx, PoolUtxoIsSpent), (order_tx, OrderUtxoIsSpent)];
-
-    let parsed_errors = patterns
-        .iter()
-        .flat_map(|(tx_pattern, error)| {
-            if parsed_error.contains(tx_pattern) {
-                Some(error.clone())
-            } else {
-                None
-            }
-        })
-        .collect();
-
-    if parsed_error.len() > 0 {
-        parsed_errors
-    } else {
-        // case when errors are not PoolUtxoIsSpent or OrderUtxoIsSpent
-        vec![UnknownError { info: parsed_error }]
+impl<V: Eq + Hash> TxSubmissionError<V> {
+    /// Whether input carrying [version] is already spent.
+    pub fn is_missing(&self, version: &V) -> bool {
+        matches!(self, MissingInputs(inputs) if inputs.contains(version))
+    }
+
+    /// Whether script guarding input carrying [version] failed.
+    pub fn is_script_failure_of(&self, version: &V) -> bool {
+        matches!(self, ScriptFailure { input: Some(input), .. } if input == version)
+    }
+}
+
+/// Rejection whose reasons are attributed to the outputs spent by the rejected transaction.
+/// Some of the reasons point at scripts rather than at inputs, so they can only be told apart
+/// once the spent outputs are known.
+pub trait AttributeRejection<Bearer, V: Eq + Hash> {
+    fn attribute(self, spent: &[Bearer]) -> Vec<TxSubmissionError<V>>;
+}
+
+impl<Bearer, V: Eq + Hash> AttributeRejection<Bearer, V> for Vec<TxSubmissionError<V>> {
+    fn attribute(self, _: &[Bearer]) -> Vec<TxSubmissionError<V>> {
+        self
     }
 }
 
@@ -164,8 +161,8 @@ impl<Net, Backlog, Pools, Prover, Ctx, Ord, Pool, TxCandidate, Tx, Err> Executor
     for HotOrderExecutor<Net, Backlog, Pools, Prover, Ctx, Ord, Pool, TxCandidate, Tx, Err>
 where
     Ord: SpecializedOrder + Clone + Display,
-    <Ord as SpecializedOrder>::TOrderId: Clone + Display,
-    Pool: EntitySnapshot + RunOrder<Ord, Ctx, TxCandidate> + Clone,
+    <Ord as SpecializedOrder>::TOrderId: Clone + Eq + Hash + Debug + Display,
+    Pool: EntitySnapshot<Version = Ord::TOrderId> + RunOrder<Ord, Ctx, TxCandidate> + Clone,
     Pool::StableId: Copy,
     Ord::TPoolId: IsEqual<Pool::StableId> + Display,
     Net: Network<Tx, Err>,
@@ -173,7 +170,7 @@ where
     Pools: EntityRepo<Pool>,
     Prover: TxProver<TxCandidate, Tx>,
     Ctx: Clone,
-    Err: Display,
+    Err: Into<Vec<TxSubmissionError<Ord::TOrderId>>>,
     Tx: Serialize,
 {
     async fn try_execute_next(&mut self) -> bool {
@@ -194,14 +191,17 @@ where
                         let mut entity_repo = self.pool_repo.lock().await;
                         let tx = self.prover.prove(tx_candidate);
                         if let Err(err) = self.network.submit_tx(tx).await {
-                            let errors =
-                                process_tx_rejected_error(err, entity.clone().version(), ord.get_self_ref());
+                            let errors: Vec<TxSubmissionError<Ord::TOrderId>> = err.into();
                             warn!("Failed to submit TX. Errors {:?}", errors);
-                            if !errors.is_empty() {
-                                if errors.contains(&PoolUtxoIsSpent) && errors.contains(&OrderUtxoIsSpent) {
-                                    entity_repo.invalidate(pool_state_id, pool_id).await;
-                                } else if errors.contains(&PoolUtxoIsSpent) {
-                                    entity_repo.invalidate(pool_state_id, pool_id).await;
+                            let order_id = ord.get_self_ref();
+                            let pool_is_spent = errors.iter().any(|e| e.is_missing(&pool_state_id));
+                            let pool_failed = errors.iter().any(|e| e.is_script_failure_of(&pool_state_id));
+                            let order_is_spent = errors.iter().any(|e| e.is_missing(&order_id));
+                            let order_failed = errors.iter().any(|e| e.is_script_failure_of(&order_id));
+                            if pool_is_spent || pool_failed {
+                                entity_repo.invalidate(pool_state_id, pool_id).await;
+                                // Order is retried against the actual state of the pool.
+                                if !order_is_spent && !order_failed {
                                     self.backlog.lock().await.put(ord);
                                 }
                             }
@@ -8,9 +8,11 @@ pub mod event_sink;
 pub mod executor;
 pub mod ledger;
 pub mod maker;
+mod metrics;
 pub mod network;
 pub mod partitioning;
-pub(crate) mod rocks;
+pub mod rocks;
 pub mod streaming;
 pub mod tx_hash;
 pub mod tx_prover;
+pub mod tx_validator;
@@ -2,3 +2,13 @@
 pub trait Maker<T> {
     fn make(ctx: &T) -> Self;
 }
+
+/// Update settings of [Self] in place given (possibly changed) context [T].
+pub trait Reconfigure<T> {
+    fn reconfigure(&mut self, ctx: &T);
+}
+
+/// Narrow context [Self] down to the given key [K], e.g. to settings of a particular pair.
+pub trait Scoped<K> {
+    fn scoped(&self, key: &K) -> Self;
+}
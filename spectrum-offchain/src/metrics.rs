@@ -0,0 +1,18 @@
+use lazy_static::lazy_static;
+use prometheus::{register_int_gauge, IntGauge};
+
+lazy_static! {
+    static ref HOT_BACKLOG_ORDERS: IntGauge = register_int_gauge!(
+        "hot_backlog_orders",
+        "Number of orders currently held in hot backlogs"
+    )
+    .unwrap();
+}
+
+pub(crate) fn on_hot_backlog_put() {
+    HOT_BACKLOG_ORDERS.inc();
+}
+
+pub(crate) fn on_hot_backlog_removed() {
+    HOT_BACKLOG_ORDERS.dec();
+}
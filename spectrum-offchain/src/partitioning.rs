@@ -1,44 +1,49 @@
 use std::collections::hash_map::DefaultHasher;
-use std::fmt::Debug;
 use std::hash::{Hash, Hasher};
 use std::marker::PhantomData;
 
 /// Partitioned resource `R`.
-/// `K` - partitioning key;
-/// `N` - number of partitions.
+/// `K` - partitioning key.
 #[derive(Clone)]
-pub struct Partitioned<const N: usize, K, R> {
-    inner: [R; N],
+pub struct Partitioned<K, R> {
+    inner: Vec<R>,
     pd: PhantomData<K>,
 }
 
-impl<const N: usize, K, R> Partitioned<N, K, R>
+impl<K, R> Partitioned<K, R>
 where
     K: Hash,
 {
-    pub fn new(partitions: [R; N]) -> Self {
+    pub fn new<const N: usize>(partitions: [R; N]) -> Self {
+        Self::new_unsafe(Vec::from(partitions))
+    }
+
+    /// Number of partitions is only known at runtime.
+    /// Panics if `partitions` is empty.
+    pub fn new_unsafe(partitions: Vec<R>) -> Self {
+        assert!(!partitions.is_empty(), "At least one partition is required");
         Self {
             inner: partitions,
             pd: PhantomData::default(),
         }
     }
 
-    pub fn new_unsafe(partitions: Vec<R>) -> Self
-    where
-        R: Debug,
-    {
-        Self {
-            inner: <[R; N]>::try_from(partitions).unwrap(),
-            pd: PhantomData::default(),
-        }
+    pub fn num_partitions(&self) -> usize {
+        self.inner.len()
     }
 
     pub fn get(&self, key: K) -> &R {
-        &self.inner[(hash_partitioning_key(key) % N as u64) as usize]
+        let ix = self.partition_index(key);
+        &self.inner[ix]
     }
 
     pub fn get_mut(&mut self, key: K) -> &mut R {
-        &mut self.inner[(hash_partitioning_key(key) % N as u64) as usize]
+        let ix = self.partition_index(key);
+        &mut self.inner[ix]
+    }
+
+    fn partition_index(&self, key: K) -> usize {
+        (hash_partitioning_key(key) % self.inner.len() as u64) as usize
     }
 }
 
@@ -0,0 +1,20 @@
+use std::collections::HashSet;
+
+/// Local checks a transaction has to pass before it is submitted to the network.
+pub trait TxValidator<Tx, Bearer, Ctx> {
+    type Error;
+    /// Validate [tx] spending [inputs].
+    fn validate(&self, tx: &Tx, inputs: &[Bearer], ctx: &Ctx) -> Result<(), Self::Error>;
+}
+
+/// How to deal with operations of a transaction which didn't pass validation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Remedy<V> {
+    /// Entities to blame, identified either by spent versions or by versions produced by the transaction.
+    /// Takers among them are stashed, makers are dropped.
+    Exclude(HashSet<V>),
+    /// Transaction has to be built out of fewer operations.
+    Shrink,
+    /// The same operations may succeed later, e.g. once time or protocol parameters change.
+    RetryLater,
+}
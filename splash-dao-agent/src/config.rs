@@ -0,0 +1,92 @@
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use cml_chain::PolicyId;
+use cml_core::Slot;
+use cml_crypto::ScriptHash;
+
+use cardano_chain_sync::client::Point;
+use cardano_chain_sync::retention::RetentionPolicy;
+use cardano_explorer::CardanoNetworkConfig;
+use spectrum_cardano_lib::time::SlotConfig;
+use spectrum_cardano_lib::{AssetName, NetworkId, OutputRef, Token};
+use spectrum_offchain_cardano::creds::OperatorRewardAddress;
+use spectrum_offchain_cardano::node::NodeConfig;
+
+#[derive(serde::Deserialize)]
+#[serde(bound = "'de: 'a")]
+#[serde(rename_all = "camelCase")]
+pub struct AppConfig<'a> {
+    pub chain_sync: ChainSyncConfig<'a>,
+    pub node: NodeConfig<'a>,
+    pub tx_submission_buffer_size: usize,
+    pub operator_key: &'a str, //todo: store encrypted
+    pub operator_reward_address: OperatorRewardAddress,
+    pub network_id: NetworkId,
+    /// Used to derive time of blocks.
+    pub slot_config: SlotConfig,
+    pub explorer: CardanoNetworkConfig,
+    pub state_projection_db_path: &'a str,
+    pub protocol_params_poll_interval: Duration,
+    /// Predicted states not confirmed within this time are discarded.
+    pub prediction_ttl: Duration,
+    /// Address to accept voting orders at.
+    pub voting_api_endpoint: SocketAddr,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChainSyncConfig<'a> {
+    pub starting_point: Point,
+    pub replay_from_point: Option<Point>,
+    pub disable_rollbacks_until: Slot,
+    pub db_path: &'a str,
+    /// Cached blocks are kept forever if not set.
+    pub retention: Option<RetentionPolicy>,
+}
+
+#[derive(serde::Deserialize, Copy, Clone)]
+#[serde(try_from = "DeployedTokenRepr")]
+pub struct DeployedToken(pub Token);
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DeployedTokenRepr {
+    policy_id: PolicyId,
+    asset_name: String,
+}
+
+impl TryFrom<DeployedTokenRepr> for DeployedToken {
+    type Error = &'static str;
+    fn try_from(repr: DeployedTokenRepr) -> Result<Self, Self::Error> {
+        AssetName::try_from_hex(&repr.asset_name)
+            .map(|name| DeployedToken((repr.policy_id, name)))
+            .ok_or("Invalid asset name")
+    }
+}
+
+/// On-chain deployment of the DAO protocol.
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DaoDeployment {
+    pub splash_policy: PolicyId,
+    pub inflation_box: DeployedToken,
+    pub inflation_box_ref_script: OutputRef,
+    pub poll_factory: DeployedToken,
+    pub poll_factory_ref_script: OutputRef,
+    pub gov_witness_script_hash: ScriptHash,
+    pub wpoll_auth_policy: PolicyId,
+    pub wpoll_auth_ref_script: OutputRef,
+    pub farm_auth_policy: PolicyId,
+    pub farm_auth_ref_script: OutputRef,
+    pub factory_auth_policy: PolicyId,
+    pub ve_factory_auth_policy: PolicyId,
+    pub voting_escrow_ref_script: OutputRef,
+    pub weighting_power_ref_script: OutputRef,
+    pub perm_manager_box: DeployedToken,
+    pub perm_manager_box_ref_script: OutputRef,
+    pub edao_msig_policy: PolicyId,
+    pub perm_manager_auth_policy: PolicyId,
+    pub gt_policy: PolicyId,
+    pub genesis_epoch_start_time: u64,
+}
@@ -0,0 +1,346 @@
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+use clap::Parser;
+use cml_chain::address::RewardAddress;
+use cml_chain::builders::tx_builder::TransactionUnspentOutput;
+use cml_chain::transaction::Transaction;
+use futures::stream::select_all;
+use futures::StreamExt;
+use log::info;
+use tokio::sync::{broadcast, Mutex};
+use tracing_subscriber::fmt::Subscriber;
+
+use cardano_chain_sync::cache::LedgerCacheRocksDB;
+use cardano_chain_sync::chain_sync_stream;
+use cardano_chain_sync::data::LedgerTxEvent;
+use cardano_chain_sync::event_source::ledger_transactions;
+use cardano_chain_sync::retention::{pruning_stream, RetentionPolicy};
+use cardano_chain_sync::supervisor::ChainSyncConnector;
+use cardano_explorer::{AnyCardanoNetwork, CardanoNetwork};
+use cardano_mempool_sync::ledger::LedgerObserver;
+use cardano_state_query::supervisor::SupervisedLocalStateQueryClient;
+use cardano_state_query::{protocol_params_sync_stream, ProtocolParamsProvider};
+use spectrum_cardano_lib::era::SharedEra;
+use spectrum_cardano_lib::protocol_params::SharedTxBuilderConfig;
+use spectrum_cardano_lib::transaction::{OutboundTransaction, TxViewMut};
+use spectrum_cardano_lib::OutputRef;
+use spectrum_offchain::event_sink::event_handler::EventHandler;
+use spectrum_offchain::event_sink::process_events;
+use spectrum_offchain::rocks::RocksConfig;
+use spectrum_offchain::streaming::boxed;
+use spectrum_offchain_cardano::collateral::pull_collateral;
+use spectrum_offchain_cardano::creds::operator_creds;
+use spectrum_offchain_cardano::node::NodeConnections;
+use spectrum_offchain_cardano::prover::operator::OperatorProver;
+use spectrum_offchain_cardano::tx_submission::{tx_submission_agent_stream, TxSubmissionAgent};
+use splash_dao_offchain::backlog::InMemoryBacklog;
+use splash_dao_offchain::entities::offchain::voting_order::VotingOrder;
+use splash_dao_offchain::entities::onchain::inflation_box::InflationBoxId;
+use splash_dao_offchain::entities::onchain::permission_manager::PermManagerId;
+use splash_dao_offchain::entities::onchain::poll_factory::PollFactoryId;
+use splash_dao_offchain::entities::onchain::smart_farm::FarmId;
+use splash_dao_offchain::entities::onchain::voting_escrow::VotingEscrowId;
+use splash_dao_offchain::entities::onchain::weighting_poll::WeightingPollId;
+use splash_dao_offchain::event_sink::ProjectionUpdateHandler;
+use splash_dao_offchain::protocol_config::ProtocolConfig;
+use splash_dao_offchain::routine::{routine, Routine};
+use splash_dao_offchain::routines::inflation::actions::CardanoInflationActions;
+use splash_dao_offchain::routines::inflation::Behaviour;
+use splash_dao_offchain::state_projection::rocksdb::{open_projection_db, StateProjectionRocksDB};
+use splash_dao_offchain::time::NetworkTimeSource;
+use splash_dao_offchain::GenesisEpochStartTime;
+
+use crate::config::{AppConfig, DaoDeployment};
+use crate::voting_api::voting_api_stream;
+
+mod config;
+mod voting_api;
+
+const INFLATION_BOX_NS: &str = "inflation_box";
+const POLL_FACTORY_NS: &str = "poll_factory";
+const WEIGHTING_POLL_NS: &str = "weighting_poll";
+const VOTING_ESCROW_NS: &str = "voting_escrow";
+const SMART_FARM_NS: &str = "smart_farm";
+const PERM_MANAGER_NS: &str = "perm_manager";
+
+#[tokio::main(flavor = "multi_thread", worker_threads = 4)]
+async fn main() {
+    let subscriber = Subscriber::new();
+    tracing::subscriber::set_global_default(subscriber).expect("setting tracing default failed");
+    let args = AppArgs::parse();
+    let raw_config = std::fs::read_to_string(args.config_path).expect("Cannot load configuration file");
+    let config: AppConfig = serde_json::from_str(&raw_config).expect("Invalid configuration file");
+
+    let raw_deployment = std::fs::read_to_string(args.deployment_path).expect("Cannot load deployment file");
+    let deployment: DaoDeployment = serde_json::from_str(&raw_deployment).expect("Invalid deployment file");
+
+    log4rs::init_file(args.log4rs_path, Default::default()).unwrap();
+
+    info!("Starting DAO Agent ..");
+
+    let rollback_in_progress = Arc::new(AtomicBool::new(false));
+
+    let explorer = AnyCardanoNetwork::new(config.explorer.clone(), config.network_id.into())
+        .await
+        .expect("Explorer instantiation failed");
+
+    let chain_sync_cache = Arc::new(Mutex::new(
+        LedgerCacheRocksDB::new(config.chain_sync.db_path).with_archive(
+            config
+                .chain_sync
+                .retention
+                .as_ref()
+                .and_then(RetentionPolicy::archive),
+        ),
+    ));
+    let node_connections = NodeConnections::default();
+    let reconnect_policy = config.node.reconnect;
+    let chain_sync = ChainSyncConnector::new(
+        Arc::clone(&chain_sync_cache),
+        config.node.address(),
+        config.node.magic,
+        config.chain_sync.starting_point,
+    );
+
+    // n2c clients:
+    let node_path = config
+        .node
+        .path
+        .expect("Local node socket is required to query protocol parameters");
+    let state_query = SupervisedLocalStateQueryClient::new(
+        node_path,
+        config.node.magic,
+        reconnect_policy,
+        node_connections.state_query.clone(),
+    );
+    let mut protocol_params_provider = ProtocolParamsProvider::new(state_query);
+    let (current_era, current_tx_builder_config) = protocol_params_provider
+        .current()
+        .await
+        .expect("Couldn't retrieve protocol parameters");
+    let era = SharedEra::new(current_era);
+    let tx_builder_config = SharedTxBuilderConfig::new(current_tx_builder_config);
+
+    let node_magic = config.node.magic;
+    // Tx submission consults the ledger on the fate of txs propagated through relays.
+    let ledger_observer = LedgerObserver::default();
+    let (tx_submission_agent, tx_submission_channel) =
+        TxSubmissionAgent::<OutboundTransaction<Transaction>, Transaction>::new(
+            config.node,
+            config.tx_submission_buffer_size,
+            era.clone(),
+            node_connections.tx_submission.clone(),
+            ledger_observer.clone(),
+        );
+
+    // prepare upstreams
+    let tx_submission_stream = tx_submission_agent_stream(tx_submission_agent);
+    let protocol_params_stream = protocol_params_sync_stream(
+        protocol_params_provider,
+        tx_builder_config.clone(),
+        era,
+        config.protocol_params_poll_interval,
+    );
+
+    let (operator_sk, operator_pkh, _) = operator_creds(config.operator_key);
+
+    let collateral = pull_collateral(operator_pkh, &explorer)
+        .await
+        .expect("Couldn't retrieve collateral");
+
+    let reward_address = RewardAddress::from_address(&config.operator_reward_address.0)
+        .expect("Operator reward address must be a reward address");
+
+    let protocol_config = ProtocolConfig {
+        operator_sk: config.operator_key.to_string(),
+        node_magic,
+        reward_address,
+        collateral,
+        tx_builder_config,
+        splash_policy: deployment.splash_policy,
+        inflation_box_id: InflationBoxId::from(deployment.inflation_box.0),
+        inflation_box_ref_script: pull_ref_script(deployment.inflation_box_ref_script, &explorer).await,
+        poll_factory_id: PollFactoryId::from(deployment.poll_factory.0),
+        poll_factory_ref_script: pull_ref_script(deployment.poll_factory_ref_script, &explorer).await,
+        gov_witness_script_hash: deployment.gov_witness_script_hash,
+        wpoll_auth_policy: deployment.wpoll_auth_policy,
+        wpoll_auth_ref_script: pull_ref_script(deployment.wpoll_auth_ref_script, &explorer).await,
+        farm_auth_policy: deployment.farm_auth_policy,
+        farm_auth_ref_script: pull_ref_script(deployment.farm_auth_ref_script, &explorer).await,
+        factory_auth_policy: deployment.factory_auth_policy,
+        ve_factory_auth_policy: deployment.ve_factory_auth_policy,
+        voting_escrow_ref_script: pull_ref_script(deployment.voting_escrow_ref_script, &explorer).await,
+        weighting_power_ref_script: pull_ref_script(deployment.weighting_power_ref_script, &explorer).await,
+        perm_manager_box_id: PermManagerId::from(deployment.perm_manager_box.0),
+        perm_manager_box_ref_script: pull_ref_script(deployment.perm_manager_box_ref_script, &explorer).await,
+        edao_msig_policy: deployment.edao_msig_policy,
+        perm_manager_auth_policy: deployment.perm_manager_auth_policy,
+        gt_policy: deployment.gt_policy,
+        genesis_time: GenesisEpochStartTime::from(deployment.genesis_epoch_start_time),
+    };
+    // Protocol config is shared by all components for the whole lifetime of the agent.
+    let protocol_config: &'static ProtocolConfig = Box::leak(Box::new(protocol_config));
+
+    let projection_db = open_projection_db(RocksConfig {
+        db_path: config.state_projection_db_path.to_string(),
+    });
+
+    let handlers_ledger: Vec<Box<dyn EventHandler<LedgerTxEvent<TxViewMut>>>> = vec![
+        Box::new(ProjectionUpdateHandler::new(StateProjectionRocksDB::<
+            InflationBoxId,
+            _,
+        >::new(
+            projection_db.clone(),
+            INFLATION_BOX_NS,
+            protocol_config,
+        ))),
+        Box::new(ProjectionUpdateHandler::new(StateProjectionRocksDB::<
+            PollFactoryId,
+            _,
+        >::new(
+            projection_db.clone(),
+            POLL_FACTORY_NS,
+            protocol_config,
+        ))),
+        Box::new(ProjectionUpdateHandler::new(StateProjectionRocksDB::<
+            WeightingPollId,
+            _,
+        >::new(
+            projection_db.clone(),
+            WEIGHTING_POLL_NS,
+            protocol_config,
+        ))),
+        Box::new(ProjectionUpdateHandler::new(StateProjectionRocksDB::<
+            VotingEscrowId,
+            _,
+        >::new(
+            projection_db.clone(),
+            VOTING_ESCROW_NS,
+            protocol_config,
+        ))),
+        Box::new(ProjectionUpdateHandler::new(
+            StateProjectionRocksDB::<FarmId, _>::new(projection_db.clone(), SMART_FARM_NS, protocol_config),
+        )),
+        Box::new(ProjectionUpdateHandler::new(StateProjectionRocksDB::<
+            PermManagerId,
+            _,
+        >::new(
+            projection_db.clone(),
+            PERM_MANAGER_NS,
+            protocol_config,
+        ))),
+    ];
+
+    let prover = OperatorProver::new(&operator_sk);
+    let prediction_ttl = config.prediction_ttl;
+    let backlog = InMemoryBacklog::<VotingOrder>::new();
+    let voting_api_stream = voting_api_stream(config.voting_api_endpoint, backlog.clone());
+    let behaviour = Behaviour::new(
+        StateProjectionRocksDB::<InflationBoxId, _>::new(
+            projection_db.clone(),
+            INFLATION_BOX_NS,
+            protocol_config,
+        )
+        .with_prediction_ttl(prediction_ttl),
+        StateProjectionRocksDB::<PollFactoryId, _>::new(
+            projection_db.clone(),
+            POLL_FACTORY_NS,
+            protocol_config,
+        )
+        .with_prediction_ttl(prediction_ttl),
+        StateProjectionRocksDB::<WeightingPollId, _>::new(
+            projection_db.clone(),
+            WEIGHTING_POLL_NS,
+            protocol_config,
+        )
+        .with_prediction_ttl(prediction_ttl),
+        StateProjectionRocksDB::<VotingEscrowId, _>::new(
+            projection_db.clone(),
+            VOTING_ESCROW_NS,
+            protocol_config,
+        )
+        .with_prediction_ttl(prediction_ttl),
+        StateProjectionRocksDB::<FarmId, _>::new(projection_db.clone(), SMART_FARM_NS, protocol_config)
+            .with_prediction_ttl(prediction_ttl),
+        StateProjectionRocksDB::<PermManagerId, _>::new(projection_db, PERM_MANAGER_NS, protocol_config)
+            .with_prediction_ttl(prediction_ttl),
+        backlog,
+        NetworkTimeSource,
+        CardanoInflationActions::new(protocol_config),
+        protocol_config,
+        tx_submission_channel,
+        prover,
+    );
+    let inflation_routine_stream = routine(Routine::new(behaviour));
+
+    let (signal_tip_reached_snd, _) = broadcast::channel(1);
+
+    let ledger_cache_pruning_stream = config
+        .chain_sync
+        .retention
+        .map(|policy| boxed(pruning_stream(Arc::clone(&chain_sync_cache), policy)));
+
+    let ledger_stream = Box::pin(ledger_transactions(
+        chain_sync_cache,
+        chain_sync_stream(
+            chain_sync,
+            reconnect_policy,
+            node_connections.chain_sync.clone(),
+            signal_tip_reached_snd,
+        ),
+        config.chain_sync.disable_rollbacks_until,
+        config.chain_sync.replay_from_point,
+        rollback_in_progress,
+        config.slot_config,
+    ))
+    .await
+    .inspect(move |ev| match ev {
+        LedgerTxEvent::TxApplied { tx, .. } => ledger_observer.on_tx_applied(tx.hash),
+        LedgerTxEvent::TxUnapplied(tx) => ledger_observer.on_tx_unapplied(tx.hash),
+        LedgerTxEvent::BlockCompleted(_) => ledger_observer.on_block_completed(),
+        LedgerTxEvent::BlockRolledBack(_) | LedgerTxEvent::RollbackIncomplete(_) => {}
+    });
+
+    let process_ledger_events_stream = process_events(ledger_stream, handlers_ledger);
+
+    let mut app = select_all(
+        vec![
+            boxed(process_ledger_events_stream),
+            boxed(inflation_routine_stream),
+            boxed(tx_submission_stream),
+            boxed(protocol_params_stream),
+            boxed(voting_api_stream),
+        ]
+        .into_iter()
+        .chain(ledger_cache_pruning_stream),
+    );
+
+    loop {
+        app.select_next_some().await;
+    }
+}
+
+async fn pull_ref_script<Net: CardanoNetwork>(oref: OutputRef, explorer: &Net) -> TransactionUnspentOutput {
+    explorer
+        .utxo_by_ref(oref)
+        .await
+        .expect(format!("Reference UTxO {} from deployment not found", oref).as_str())
+}
+
+#[derive(Parser)]
+#[command(name = "splash-dao-agent")]
+#[command(author = "Spectrum Labs")]
+#[command(version = "1.0.0")]
+#[command(about = "Splash DAO Agent", long_about = None)]
+struct AppArgs {
+    /// Path to the JSON configuration file.
+    #[arg(long, short)]
+    config_path: String,
+    /// Path to the deployment JSON configuration file .
+    #[arg(long, short)]
+    deployment_path: String,
+    /// Path to the log4rs YAML configuration file.
+    #[arg(long, short)]
+    log4rs_path: String,
+}
@@ -0,0 +1,167 @@
+use std::convert::Infallible;
+use std::net::SocketAddr;
+
+use cml_chain::PolicyId;
+use cml_crypto::ScriptHash;
+use futures::{stream, Stream};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use log::{error, info, trace};
+
+use spectrum_offchain::backlog::ResilientBacklog;
+use spectrum_offchain::data::order::PendingOrder;
+use splash_dao_offchain::backlog::InMemoryBacklog;
+use splash_dao_offchain::entities::offchain::voting_order::{VotingOrder, VotingOrderId};
+use splash_dao_offchain::entities::onchain::smart_farm::FarmId;
+use splash_dao_offchain::entities::onchain::voting_escrow::VotingEscrowId;
+
+use crate::config::DeployedToken;
+
+/// Accepts voting orders at `POST /votes` and puts them into the backlog.
+pub fn voting_api_stream(addr: SocketAddr, backlog: InMemoryBacklog<VotingOrder>) -> impl Stream<Item = ()> {
+    stream::once(async move {
+        let make_svc = make_service_fn(move |_| {
+            let backlog = backlog.clone();
+            async move { Ok::<_, Infallible>(service_fn(move |req| handle(req, backlog.clone()))) }
+        });
+        info!("Serving voting API at {}", addr);
+        if let Err(err) = Server::bind(&addr).serve(make_svc).await {
+            error!("Voting API server failed: {}", err);
+        }
+    })
+}
+
+async fn handle(
+    req: Request<Body>,
+    backlog: InMemoryBacklog<VotingOrder>,
+) -> Result<Response<Body>, Infallible> {
+    if req.method() != Method::POST || req.uri().path() != "/votes" {
+        return Ok(respond(StatusCode::NOT_FOUND));
+    }
+    let order = match hyper::body::to_bytes(req.into_body()).await {
+        Ok(body) => parse_order(&body),
+        Err(_) => None,
+    };
+    match order {
+        Some(order) => {
+            trace!("Voting order {:?} accepted", order.id);
+            backlog
+                .put(PendingOrder {
+                    order,
+                    timestamp: chrono::Utc::now().timestamp(),
+                })
+                .await;
+            Ok(respond(StatusCode::ACCEPTED))
+        }
+        None => Ok(respond(StatusCode::BAD_REQUEST)),
+    }
+}
+
+fn parse_order(body: &[u8]) -> Option<VotingOrder> {
+    serde_json::from_slice::<VotingOrderRepr>(body)
+        .ok()?
+        .try_into()
+        .ok()
+}
+
+fn respond(status: StatusCode) -> Response<Body> {
+    let mut resp = Response::new(Body::empty());
+    *resp.status_mut() = status;
+    resp
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VotingOrderRepr {
+    voting_escrow: DeployedToken,
+    /// Distinguishes consecutive orders of the same voting escrow.
+    nonce: u64,
+    /// Pairs of (farm id, weight).
+    distribution: Vec<(u64, u64)>,
+    /// Hex-encoded signature of the voting escrow owner.
+    proof: String,
+    witness: ScriptHash,
+    version: u32,
+    proposal_auth_policy: PolicyId,
+}
+
+impl TryFrom<VotingOrderRepr> for VotingOrder {
+    type Error = hex::FromHexError;
+    fn try_from(repr: VotingOrderRepr) -> Result<Self, Self::Error> {
+        Ok(VotingOrder {
+            id: VotingOrderId::from((VotingEscrowId::from(repr.voting_escrow.0), repr.nonce)),
+            distribution: repr
+                .distribution
+                .into_iter()
+                .map(|(farm, weight)| (FarmId(farm), weight))
+                .collect(),
+            proof: hex::decode(repr.proof)?,
+            witness: repr.witness,
+            version: repr.version,
+            proposal_auth_policy: repr.proposal_auth_policy,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::{Body, Method, Request, StatusCode};
+
+    use spectrum_offchain::backlog::ResilientBacklog;
+    use splash_dao_offchain::backlog::InMemoryBacklog;
+    use splash_dao_offchain::entities::onchain::smart_farm::FarmId;
+
+    use crate::voting_api::{handle, parse_order};
+
+    const ORDER: &str = r#"{
+        "votingEscrow": {
+            "policyId": "a1b2c3d4e5f6a1b2c3d4e5f6a1b2c3d4e5f6a1b2c3d4e5f6a1b2c3d4",
+            "assetName": "01"
+        },
+        "nonce": 3,
+        "distribution": [[0, 70], [1, 30]],
+        "proof": "deadbeef",
+        "witness": "b1b2c3d4e5f6a1b2c3d4e5f6a1b2c3d4e5f6a1b2c3d4e5f6a1b2c3d4",
+        "version": 1,
+        "proposalAuthPolicy": "c1b2c3d4e5f6a1b2c3d4e5f6a1b2c3d4e5f6a1b2c3d4e5f6a1b2c3d4"
+    }"#;
+
+    #[test]
+    fn parse_voting_order() {
+        let order = parse_order(ORDER.as_bytes()).unwrap();
+        assert_eq!(order.distribution, vec![(FarmId(0), 70), (FarmId(1), 30)]);
+        assert_eq!(order.proof, vec![0xde, 0xad, 0xbe, 0xef]);
+        assert_eq!(order.version, 1);
+    }
+
+    #[test]
+    fn order_with_malformed_proof_is_rejected() {
+        assert!(parse_order(ORDER.replace("deadbeef", "xyz").as_bytes()).is_none());
+    }
+
+    #[tokio::test]
+    async fn accepted_order_lands_in_backlog() {
+        let backlog = InMemoryBacklog::new();
+        let req = Request::builder()
+            .method(Method::POST)
+            .uri("/votes")
+            .body(Body::from(ORDER))
+            .unwrap();
+        let resp = handle(req, backlog.clone()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::ACCEPTED);
+        assert!(backlog.try_pop().await.is_some());
+    }
+
+    #[tokio::test]
+    async fn malformed_order_is_rejected() {
+        let backlog = InMemoryBacklog::new();
+        let req = Request::builder()
+            .method(Method::POST)
+            .uri("/votes")
+            .body(Body::from("{}"))
+            .unwrap();
+        let resp = handle(req, backlog.clone()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        assert!(backlog.try_pop().await.is_none());
+    }
+}
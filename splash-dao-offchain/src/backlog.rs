@@ -0,0 +1,93 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use tokio::sync::Mutex;
+
+use spectrum_offchain::backlog::ResilientBacklog;
+use spectrum_offchain::data::order::{PendingOrder, ProgressingOrder, UniqueOrder};
+
+/// Simple FIFO backlog. Orders are lost on restart.
+#[derive(Clone)]
+pub struct InMemoryBacklog<TOrd> {
+    queue: Arc<Mutex<VecDeque<TOrd>>>,
+}
+
+impl<TOrd> InMemoryBacklog<TOrd> {
+    pub fn new() -> Self {
+        Self {
+            queue: Arc::new(Mutex::new(VecDeque::new())),
+        }
+    }
+}
+
+impl<TOrd> Default for InMemoryBacklog<TOrd> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl<TOrd> ResilientBacklog<TOrd> for InMemoryBacklog<TOrd>
+where
+    TOrd: UniqueOrder + Clone + Send + Sync,
+    TOrd::TOrderId: Send + Sync,
+{
+    async fn put<'a>(&self, ord: PendingOrder<TOrd>)
+    where
+        TOrd: 'a,
+    {
+        let mut queue = self.queue.lock().await;
+        let id = ord.order.get_self_ref();
+        if !queue.iter().any(|o| o.get_self_ref() == id) {
+            queue.push_back(ord.order);
+        }
+    }
+
+    async fn suspend<'a>(&self, ord: TOrd) -> bool
+    where
+        TOrd: 'a,
+    {
+        self.queue.lock().await.push_back(ord);
+        true
+    }
+
+    async fn check_later<'a>(&self, _ord: ProgressingOrder<TOrd>) -> bool
+    where
+        TOrd: 'a,
+    {
+        false
+    }
+
+    async fn try_pop(&self) -> Option<TOrd> {
+        self.queue.lock().await.pop_front()
+    }
+
+    async fn exists<'a>(&self, ord_id: TOrd::TOrderId) -> bool
+    where
+        TOrd::TOrderId: 'a,
+    {
+        self.queue.lock().await.iter().any(|o| o.get_self_ref() == ord_id)
+    }
+
+    async fn remove<'a>(&self, ord_id: TOrd::TOrderId)
+    where
+        TOrd::TOrderId: 'a + Clone,
+    {
+        self.queue.lock().await.retain(|o| o.get_self_ref() != ord_id);
+    }
+
+    async fn recharge<'a>(&self, ord: TOrd)
+    where
+        TOrd: 'a,
+    {
+        self.queue.lock().await.push_front(ord);
+    }
+
+    async fn find_orders<F: Fn(&TOrd) -> bool + Send + 'static>(&self, f: F) -> Vec<TOrd>
+    where
+        F: Fn(&TOrd) -> bool + Send + 'static,
+    {
+        self.queue.lock().await.iter().filter(|o| f(o)).cloned().collect()
+    }
+}
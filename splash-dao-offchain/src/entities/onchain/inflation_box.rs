@@ -1,26 +1,52 @@
 use cml_chain::plutus::{ExUnits, PlutusData};
 
+use cml_chain::transaction::TransactionOutput;
+use cml_chain::utils::BigInteger;
 use cml_chain::PolicyId;
 use cml_crypto::{RawBytesEncoding, ScriptHash};
-use spectrum_cardano_lib::plutus_data::IntoPlutusData;
-use spectrum_cardano_lib::{TaggedAmount, Token};
-use spectrum_offchain::data::{EntitySnapshot, Identifier, Stable};
+use derive_more::From;
+use spectrum_cardano_lib::plutus_data::{DatumExtension, IntoPlutusData, PlutusDataExtension};
+use spectrum_cardano_lib::transaction::TransactionOutputExtension;
+use spectrum_cardano_lib::value::ValueExtension;
+use spectrum_cardano_lib::{AssetClass, AssetName, TaggedAmount, Token};
+use spectrum_offchain::data::{EntitySnapshot, Has, Identifier, Stable};
+use spectrum_offchain::ledger::TryFromLedger;
 use spectrum_offchain_cardano::parametrized_validators::apply_params_validator;
-use uplc_pallas_codec::utils::{Int, PlutusBytes};
 
 use crate::assets::Splash;
-use crate::constants::INFLATION_SCRIPT;
+use crate::constants::{INFLATION_SCRIPT, SPLASH_NAME};
+use crate::protocol_config::{SplashPolicy, WPAuthPolicy};
 use crate::routines::inflation::InflationBoxSnapshot;
+use crate::state_projection::ProjectionKey;
 use crate::time::{epoch_end, NetworkTime, ProtocolEpoch};
 use crate::{constants, GenesisEpochStartTime};
 
-#[derive(Copy, Clone, PartialEq, Eq, Ord, PartialOrd)]
+#[derive(Copy, Clone, PartialEq, Eq, Ord, PartialOrd, From)]
 pub struct InflationBoxId(Token);
 
 impl Identifier for InflationBoxId {
     type For = InflationBoxSnapshot;
 }
 
+impl ProjectionKey for InflationBoxId {
+    fn projection_key(&self) -> Vec<u8> {
+        self.0.projection_key()
+    }
+}
+
+impl<Ctx> TryFromLedger<TransactionOutput, Ctx> for InflationBoxId
+where
+    Ctx: Has<InflationBoxId>,
+{
+    fn try_from_ledger(repr: &TransactionOutput, ctx: &Ctx) -> Option<Self> {
+        let id = ctx.select::<InflationBoxId>();
+        if repr.value().amount_of(AssetClass::Token(id.0)).unwrap_or(0) > 0 {
+            return Some(id);
+        }
+        None
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 pub struct InflationBox {
     pub last_processed_epoch: ProtocolEpoch,
@@ -62,6 +88,25 @@ pub fn emission_rate(epoch: ProtocolEpoch) -> TaggedAmount<Splash> {
     })
 }
 
+impl<Ctx> TryFromLedger<TransactionOutput, Ctx> for InflationBox
+where
+    Ctx: Has<InflationBoxId> + Has<SplashPolicy> + Has<WPAuthPolicy>,
+{
+    fn try_from_ledger(repr: &TransactionOutput, ctx: &Ctx) -> Option<Self> {
+        InflationBoxId::try_from_ledger(repr, ctx)?;
+        let last_processed_epoch = repr.clone().into_datum()?.into_pd()?.into_u64()? as ProtocolEpoch;
+        let splash = AssetClass::Token((
+            ctx.select::<SplashPolicy>().0,
+            AssetName::utf8_unsafe(SPLASH_NAME.to_string()),
+        ));
+        Some(InflationBox {
+            last_processed_epoch,
+            splash_reserves: TaggedAmount::new(repr.value().amount_of(splash).unwrap_or(0)),
+            wp_auth_policy: ctx.select::<WPAuthPolicy>().0,
+        })
+    }
+}
+
 impl Stable for InflationBox {
     type StableId = PolicyId;
     fn stable_id(&self) -> Self::StableId {
@@ -88,11 +133,70 @@ pub fn compute_inflation_box_script_hash(
     weighting_power_policy: PolicyId,
     zeroth_epoch_start: u64,
 ) -> ScriptHash {
-    let params_pd = uplc::PlutusData::Array(vec![
-        uplc::PlutusData::BoundedBytes(PlutusBytes::from(splash_policy.to_raw_bytes().to_vec())),
-        uplc::PlutusData::BoundedBytes(PlutusBytes::from(wp_auth_policy.to_raw_bytes().to_vec())),
-        uplc::PlutusData::BoundedBytes(PlutusBytes::from(weighting_power_policy.to_raw_bytes().to_vec())),
-        uplc::PlutusData::BigInt(uplc::BigInt::Int(Int::from(zeroth_epoch_start as i64))),
+    let params_pd = PlutusData::new_list(vec![
+        PlutusData::new_bytes(splash_policy.to_raw_bytes().to_vec()),
+        PlutusData::new_bytes(wp_auth_policy.to_raw_bytes().to_vec()),
+        PlutusData::new_bytes(weighting_power_policy.to_raw_bytes().to_vec()),
+        PlutusData::new_integer(BigInteger::from(zeroth_epoch_start)),
     ]);
     apply_params_validator(params_pd, INFLATION_SCRIPT)
 }
+
+#[cfg(test)]
+mod tests {
+    use cml_chain::plutus::PlutusData;
+    use type_equalities::IsEqual;
+
+    use spectrum_cardano_lib::AssetName;
+    use spectrum_offchain::data::Has;
+    use spectrum_offchain::ledger::TryFromLedger;
+
+    use crate::constants::SPLASH_NAME;
+    use crate::entities::onchain::inflation_box::{InflationBox, InflationBoxId};
+    use crate::entities::onchain::tests::{dummy_policy_id, output_with};
+    use crate::protocol_config::{SplashPolicy, WPAuthPolicy};
+
+    struct Context;
+
+    impl Has<InflationBoxId> for Context {
+        fn select<U: IsEqual<InflationBoxId>>(&self) -> InflationBoxId {
+            InflationBoxId::from((dummy_policy_id(1), AssetName::utf8_unsafe("ibox".to_string())))
+        }
+    }
+
+    impl Has<SplashPolicy> for Context {
+        fn select<U: IsEqual<SplashPolicy>>(&self) -> SplashPolicy {
+            SplashPolicy(dummy_policy_id(2))
+        }
+    }
+
+    impl Has<WPAuthPolicy> for Context {
+        fn select<U: IsEqual<WPAuthPolicy>>(&self) -> WPAuthPolicy {
+            WPAuthPolicy(dummy_policy_id(3))
+        }
+    }
+
+    #[test]
+    fn parse_inflation_box() {
+        let out = output_with(
+            &[
+                (dummy_policy_id(1), b"ibox".to_vec(), 1),
+                (dummy_policy_id(2), SPLASH_NAME.as_bytes().to_vec(), 1_000_000),
+            ],
+            Some(PlutusData::new_integer(7u64.into())),
+        );
+        let ibox = InflationBox::try_from_ledger(&out, &Context).unwrap();
+        assert_eq!(ibox.last_processed_epoch, 7);
+        assert_eq!(ibox.splash_reserves.untag(), 1_000_000);
+        assert_eq!(ibox.wp_auth_policy, dummy_policy_id(3));
+    }
+
+    #[test]
+    fn output_without_identifier_is_not_inflation_box() {
+        let out = output_with(
+            &[(dummy_policy_id(2), SPLASH_NAME.as_bytes().to_vec(), 1_000_000)],
+            Some(PlutusData::new_integer(7u64.into())),
+        );
+        assert!(InflationBox::try_from_ledger(&out, &Context).is_none());
+    }
+}
@@ -1,6 +1,57 @@
+use cml_chain::{PolicyId, Value};
+
+use spectrum_cardano_lib::{AssetName, Token};
+
 pub mod inflation_box;
 pub mod permission_manager;
 pub mod poll_factory;
 pub mod smart_farm;
 pub mod voting_escrow;
 pub mod weighting_poll;
+
+/// Find a token issued under the given policy in the given value.
+pub fn find_token(value: &Value, policy: PolicyId) -> Option<Token> {
+    value
+        .multiasset
+        .iter()
+        .find(|(pol, _)| **pol == policy)
+        .and_then(|(_, names)| names.iter().find(|(_, amt)| **amt > 0))
+        .map(|(name, _)| (policy, AssetName::from(name.clone())))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use cml_chain::address::EnterpriseAddress;
+    use cml_chain::assets::MultiAsset;
+    use cml_chain::certs::StakeCredential;
+    use cml_chain::plutus::PlutusData;
+    use cml_chain::transaction::{DatumOption, TransactionOutput};
+    use cml_chain::{PolicyId, Value};
+
+    pub(crate) fn dummy_policy_id(val: u8) -> PolicyId {
+        PolicyId::from([val; 28])
+    }
+
+    /// Output holding the given tokens along with an inline datum.
+    pub(crate) fn output_with(
+        tokens: &[(PolicyId, Vec<u8>, u64)],
+        datum: Option<PlutusData>,
+    ) -> TransactionOutput {
+        let mut ma = MultiAsset::new();
+        for (policy, name, amount) in tokens {
+            ma.set(
+                *policy,
+                cml_chain::assets::AssetName::new(name.clone()).unwrap(),
+                *amount,
+            );
+        }
+        let address =
+            EnterpriseAddress::new(0, StakeCredential::new_script(dummy_policy_id(0xff))).to_address();
+        TransactionOutput::new(
+            address,
+            Value::new(5_000_000, ma),
+            datum.map(DatumOption::new_datum),
+            None,
+        )
+    }
+}
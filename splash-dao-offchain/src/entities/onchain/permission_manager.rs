@@ -1,12 +1,21 @@
 use std::fmt::Formatter;
 
-use cml_chain::{plutus::ExUnits, PolicyId};
+use cml_chain::transaction::TransactionOutput;
+use cml_chain::{
+    plutus::{ExUnits, PlutusData},
+    PolicyId,
+};
 use cml_crypto::RawBytesEncoding;
 use derive_more::From;
-use spectrum_cardano_lib::Token;
-use spectrum_offchain::data::{Identifier, Stable};
+use spectrum_cardano_lib::transaction::TransactionOutputExtension;
+use spectrum_cardano_lib::value::ValueExtension;
+use spectrum_cardano_lib::{AssetClass, Token};
+use spectrum_offchain::data::{Has, Identifier, Stable};
+use spectrum_offchain::ledger::TryFromLedger;
 use spectrum_offchain_cardano::parametrized_validators::apply_params_validator;
 
+use crate::protocol_config::{EDaoMSigAuthPolicy, PermManagerAuthPolicy};
+use crate::state_projection::ProjectionKey;
 use crate::{constants::PERM_MANAGER_SCRIPT, routines::inflation::PermManagerSnapshot};
 
 #[derive(Copy, Clone, PartialEq, Eq, Ord, PartialOrd, From)]
@@ -16,6 +25,25 @@ impl Identifier for PermManagerId {
     type For = PermManagerSnapshot;
 }
 
+impl ProjectionKey for PermManagerId {
+    fn projection_key(&self) -> Vec<u8> {
+        self.0.projection_key()
+    }
+}
+
+impl<Ctx> TryFromLedger<TransactionOutput, Ctx> for PermManagerId
+where
+    Ctx: Has<PermManagerId>,
+{
+    fn try_from_ledger(repr: &TransactionOutput, ctx: &Ctx) -> Option<Self> {
+        let id = ctx.select::<PermManagerId>();
+        if repr.value().amount_of(AssetClass::Token(id.0)).unwrap_or(0) > 0 {
+            return Some(id);
+        }
+        None
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct PermManager {
     pub stable_id: PermManagerStableId,
@@ -37,6 +65,21 @@ impl std::fmt::Display for PermManagerStableId {
     }
 }
 
+impl<Ctx> TryFromLedger<TransactionOutput, Ctx> for PermManager
+where
+    Ctx: Has<PermManagerId> + Has<EDaoMSigAuthPolicy> + Has<PermManagerAuthPolicy>,
+{
+    fn try_from_ledger(repr: &TransactionOutput, ctx: &Ctx) -> Option<Self> {
+        PermManagerId::try_from_ledger(repr, ctx)?;
+        Some(PermManager {
+            stable_id: PermManagerStableId {
+                edao_msig_policy: ctx.select::<EDaoMSigAuthPolicy>().0,
+                perm_manager_auth_policy: ctx.select::<PermManagerAuthPolicy>().0,
+            },
+        })
+    }
+}
+
 impl Stable for PermManager {
     type StableId = PermManagerStableId;
     fn stable_id(&self) -> Self::StableId {
@@ -51,13 +94,9 @@ pub fn compute_perm_manager_policy_id(
     edao_msig_policy: PolicyId,
     perm_manager_auth_policy: PolicyId,
 ) -> PolicyId {
-    let params_pd = uplc::PlutusData::Array(vec![
-        uplc::PlutusData::BoundedBytes(uplc_pallas_codec::utils::PlutusBytes::from(
-            edao_msig_policy.to_raw_bytes().to_vec(),
-        )),
-        uplc::PlutusData::BoundedBytes(uplc_pallas_codec::utils::PlutusBytes::from(
-            perm_manager_auth_policy.to_raw_bytes().to_vec(),
-        )),
+    let params_pd = PlutusData::new_list(vec![
+        PlutusData::new_bytes(edao_msig_policy.to_raw_bytes().to_vec()),
+        PlutusData::new_bytes(perm_manager_auth_policy.to_raw_bytes().to_vec()),
     ]);
     apply_params_validator(params_pd, PERM_MANAGER_SCRIPT)
 }
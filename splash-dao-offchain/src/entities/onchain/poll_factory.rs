@@ -8,24 +8,44 @@ use spectrum_cardano_lib::plutus_data::{ConstrPlutusDataExtension, IntoPlutusDat
 use spectrum_cardano_lib::{TaggedAmount, Token};
 use spectrum_offchain::data::{Identifier, Stable};
 use spectrum_offchain_cardano::parametrized_validators::apply_params_validator;
-use uplc_pallas_codec::utils::PlutusBytes;
 
 use crate::assets::Splash;
 use crate::constants::WP_FACTORY_SCRIPT;
 use crate::entities::onchain::smart_farm::FarmId;
 use crate::entities::onchain::weighting_poll::WeightingPoll;
+use crate::protocol_config::{GovWitnessScriptHash, WPAuthPolicy};
 use crate::routines::inflation::PollFactorySnapshot;
+use crate::state_projection::ProjectionKey;
 use crate::time::ProtocolEpoch;
 
 use super::weighting_poll::WeightingPollStableId;
 
-#[derive(Copy, Clone, PartialEq, Eq, Ord, PartialOrd)]
+#[derive(Copy, Clone, PartialEq, Eq, Ord, PartialOrd, From)]
 pub struct PollFactoryId(Token);
 
 impl Identifier for PollFactoryId {
     type For = PollFactorySnapshot;
 }
 
+impl ProjectionKey for PollFactoryId {
+    fn projection_key(&self) -> Vec<u8> {
+        self.0.projection_key()
+    }
+}
+
+impl<Ctx> TryFromLedger<TransactionOutput, Ctx> for PollFactoryId
+where
+    Ctx: Has<PollFactoryId>,
+{
+    fn try_from_ledger(repr: &TransactionOutput, ctx: &Ctx) -> Option<Self> {
+        let id = ctx.select::<PollFactoryId>();
+        if repr.value().amount_of(AssetClass::Token(id.0)).unwrap_or(0) > 0 {
+            return Some(id);
+        }
+        None
+    }
+}
+
 pub struct PollFactory {
     pub last_poll_epoch: ProtocolEpoch,
     pub active_farms: Vec<FarmId>,
@@ -75,6 +95,26 @@ impl std::fmt::Display for PollFactoryStableId {
     }
 }
 
+impl<Ctx> TryFromLedger<TransactionOutput, Ctx> for PollFactory
+where
+    Ctx: Has<PollFactoryId> + Has<WPAuthPolicy> + Has<GovWitnessScriptHash>,
+{
+    fn try_from_ledger(repr: &TransactionOutput, ctx: &Ctx) -> Option<Self> {
+        PollFactoryId::try_from_ledger(repr, ctx)?;
+        let mut cpd = repr.clone().into_datum()?.into_pd()?.into_constr_pd()?;
+        let last_poll_epoch = cpd.take_field(0)?.into_u64()? as ProtocolEpoch;
+        let active_farms = cpd.take_field(1)?.into_vec_pd(|pd| pd.into_u64().map(FarmId))?;
+        Some(PollFactory {
+            last_poll_epoch,
+            active_farms,
+            stable_id: PollFactoryStableId {
+                wp_auth_policy: ctx.select::<WPAuthPolicy>().0,
+                gov_witness_script_hash: ctx.select::<GovWitnessScriptHash>().0,
+            },
+        })
+    }
+}
+
 impl Stable for PollFactory {
     type StableId = PollFactoryStableId;
     fn stable_id(&self) -> Self::StableId {
@@ -151,9 +191,71 @@ pub fn compute_wp_factory_script_hash(
     wp_auth_policy: PolicyId,
     gov_witness_script_hash: ScriptHash,
 ) -> ScriptHash {
-    let params_pd = uplc::PlutusData::Array(vec![
-        uplc::PlutusData::BoundedBytes(PlutusBytes::from(wp_auth_policy.to_raw_bytes().to_vec())),
-        uplc::PlutusData::BoundedBytes(PlutusBytes::from(gov_witness_script_hash.to_raw_bytes().to_vec())),
+    let params_pd = PlutusData::new_list(vec![
+        PlutusData::new_bytes(wp_auth_policy.to_raw_bytes().to_vec()),
+        PlutusData::new_bytes(gov_witness_script_hash.to_raw_bytes().to_vec()),
     ]);
     apply_params_validator(params_pd, WP_FACTORY_SCRIPT)
 }
+
+#[cfg(test)]
+mod tests {
+    use cml_chain::plutus::{ConstrPlutusData, PlutusData};
+    use type_equalities::IsEqual;
+
+    use spectrum_cardano_lib::AssetName;
+    use spectrum_offchain::data::Has;
+    use spectrum_offchain::ledger::TryFromLedger;
+
+    use crate::entities::onchain::poll_factory::{PollFactory, PollFactoryId};
+    use crate::entities::onchain::smart_farm::FarmId;
+    use crate::entities::onchain::tests::{dummy_policy_id, output_with};
+    use crate::protocol_config::{GovWitnessScriptHash, WPAuthPolicy};
+
+    struct Context;
+
+    impl Has<PollFactoryId> for Context {
+        fn select<U: IsEqual<PollFactoryId>>(&self) -> PollFactoryId {
+            PollFactoryId::from((dummy_policy_id(1), AssetName::utf8_unsafe("factory".to_string())))
+        }
+    }
+
+    impl Has<WPAuthPolicy> for Context {
+        fn select<U: IsEqual<WPAuthPolicy>>(&self) -> WPAuthPolicy {
+            WPAuthPolicy(dummy_policy_id(2))
+        }
+    }
+
+    impl Has<GovWitnessScriptHash> for Context {
+        fn select<U: IsEqual<GovWitnessScriptHash>>(&self) -> GovWitnessScriptHash {
+            GovWitnessScriptHash(dummy_policy_id(3))
+        }
+    }
+
+    #[test]
+    fn parse_poll_factory() {
+        let datum = PlutusData::ConstrPlutusData(ConstrPlutusData::new(
+            0,
+            vec![
+                PlutusData::new_integer(4u64.into()),
+                PlutusData::new_list(vec![
+                    PlutusData::new_integer(0u64.into()),
+                    PlutusData::new_integer(1u64.into()),
+                ]),
+            ],
+        ));
+        let out = output_with(&[(dummy_policy_id(1), b"factory".to_vec(), 1)], Some(datum));
+        let factory = PollFactory::try_from_ledger(&out, &Context).unwrap();
+        assert_eq!(factory.last_poll_epoch, 4);
+        assert_eq!(factory.active_farms, vec![FarmId(0), FarmId(1)]);
+    }
+
+    #[test]
+    fn poll_factory_with_malformed_datum_is_rejected() {
+        let out = output_with(
+            &[(dummy_policy_id(1), b"factory".to_vec(), 1)],
+            Some(PlutusData::new_integer(4u64.into())),
+        );
+        assert!(PollFactory::try_from_ledger(&out, &Context).is_none());
+    }
+}
@@ -1,3 +1,4 @@
+use cml_chain::transaction::TransactionOutput;
 use cml_chain::utils::BigInteger;
 use cml_chain::{
     plutus::{ConstrPlutusData, ExUnits, PlutusData},
@@ -5,9 +6,14 @@ use cml_chain::{
 };
 use cml_crypto::RawBytesEncoding;
 use spectrum_cardano_lib::plutus_data::{ConstrPlutusDataExtension, IntoPlutusData};
-use spectrum_offchain::data::{Identifier, Stable};
+use spectrum_cardano_lib::transaction::TransactionOutputExtension;
+use spectrum_offchain::data::{Has, Identifier, Stable};
+use spectrum_offchain::ledger::TryFromLedger;
 use spectrum_offchain_cardano::parametrized_validators::apply_params_validator;
 
+use crate::entities::onchain::find_token;
+use crate::protocol_config::FarmAuthPolicy;
+use crate::state_projection::ProjectionKey;
 use crate::{constants::MINT_FARM_AUTH_TOKEN_SCRIPT, routines::inflation::SmartFarmSnapshot};
 
 #[derive(Copy, Clone, PartialEq, Eq, Ord, PartialOrd, Debug, Hash, derive_more::Display)]
@@ -17,6 +23,29 @@ impl Identifier for FarmId {
     type For = SmartFarmSnapshot;
 }
 
+impl ProjectionKey for FarmId {
+    fn projection_key(&self) -> Vec<u8> {
+        self.0.to_be_bytes().to_vec()
+    }
+}
+
+/// Farm auth token name is the farm id encoded as a big-endian integer.
+impl<Ctx> TryFromLedger<TransactionOutput, Ctx> for FarmId
+where
+    Ctx: Has<FarmAuthPolicy>,
+{
+    fn try_from_ledger(repr: &TransactionOutput, ctx: &Ctx) -> Option<Self> {
+        let (_, name) = find_token(repr.value(), ctx.select::<FarmAuthPolicy>().0)?;
+        let name = cml_chain::assets::AssetName::from(name).inner;
+        if name.len() > 8 {
+            return None;
+        }
+        Some(FarmId(
+            name.into_iter().fold(0u64, |acc, b| (acc << 8) | b as u64),
+        ))
+    }
+}
+
 impl IntoPlutusData for FarmId {
     fn into_pd(self) -> cml_chain::plutus::PlutusData {
         cml_chain::plutus::PlutusData::new_integer(BigInteger::from(self.0))
@@ -28,6 +57,15 @@ pub struct SmartFarm {
     pub farm_id: FarmId,
 }
 
+impl<Ctx> TryFromLedger<TransactionOutput, Ctx> for SmartFarm
+where
+    Ctx: Has<FarmAuthPolicy>,
+{
+    fn try_from_ledger(repr: &TransactionOutput, ctx: &Ctx) -> Option<Self> {
+        FarmId::try_from_ledger(repr, ctx).map(|farm_id| SmartFarm { farm_id })
+    }
+}
+
 impl Stable for SmartFarm {
     type StableId = FarmId;
     fn stable_id(&self) -> Self::StableId {
@@ -77,13 +115,9 @@ pub fn compute_mint_farm_auth_token_policy_id(
     splash_policy: PolicyId,
     factory_auth_policy: PolicyId,
 ) -> PolicyId {
-    let params_pd = uplc::PlutusData::Array(vec![
-        uplc::PlutusData::BoundedBytes(uplc_pallas_codec::utils::PlutusBytes::from(
-            splash_policy.to_raw_bytes().to_vec(),
-        )),
-        uplc::PlutusData::BoundedBytes(uplc_pallas_codec::utils::PlutusBytes::from(
-            factory_auth_policy.to_raw_bytes().to_vec(),
-        )),
+    let params_pd = PlutusData::new_list(vec![
+        PlutusData::new_bytes(splash_policy.to_raw_bytes().to_vec()),
+        PlutusData::new_bytes(factory_auth_policy.to_raw_bytes().to_vec()),
     ]);
     apply_params_validator(params_pd, MINT_FARM_AUTH_TOKEN_SCRIPT)
 }
@@ -93,3 +127,41 @@ pub const FARM_EX_UNITS: ExUnits = ExUnits {
     steps: 200_000_000,
     encodings: None,
 };
+
+#[cfg(test)]
+mod tests {
+    use type_equalities::IsEqual;
+
+    use spectrum_offchain::data::Has;
+    use spectrum_offchain::ledger::TryFromLedger;
+
+    use crate::entities::onchain::smart_farm::FarmId;
+    use crate::entities::onchain::tests::{dummy_policy_id, output_with};
+    use crate::protocol_config::FarmAuthPolicy;
+
+    struct Context;
+
+    impl Has<FarmAuthPolicy> for Context {
+        fn select<U: IsEqual<FarmAuthPolicy>>(&self) -> FarmAuthPolicy {
+            FarmAuthPolicy(dummy_policy_id(1))
+        }
+    }
+
+    #[test]
+    fn parse_farm_id_from_auth_token_name() {
+        let out = output_with(&[(dummy_policy_id(1), vec![0x01, 0x02], 1)], None);
+        assert_eq!(FarmId::try_from_ledger(&out, &Context), Some(FarmId(258)));
+    }
+
+    #[test]
+    fn farm_id_overflowing_u64_is_rejected() {
+        let out = output_with(&[(dummy_policy_id(1), vec![0x01; 9], 1)], None);
+        assert_eq!(FarmId::try_from_ledger(&out, &Context), None);
+    }
+
+    #[test]
+    fn token_of_foreign_policy_is_not_farm() {
+        let out = output_with(&[(dummy_policy_id(2), vec![0x01], 1)], None);
+        assert_eq!(FarmId::try_from_ledger(&out, &Context), None);
+    }
+}
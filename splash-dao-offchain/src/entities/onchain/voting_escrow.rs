@@ -9,32 +9,54 @@ use cml_chain::{
     PolicyId, Value,
 };
 use cml_crypto::{PublicKey, RawBytesEncoding, ScriptHash};
-use uplc_pallas_codec::utils::{Int, PlutusBytes};
 
+use derive_more::From;
 use spectrum_cardano_lib::{
-    plutus_data::{ConstrPlutusDataExtension, IntoPlutusData, PlutusDataExtension},
-    Token,
+    plutus_data::{ConstrPlutusDataExtension, DatumExtension, IntoPlutusData, PlutusDataExtension},
+    transaction::TransactionOutputExtension,
+    types::TryFromPData,
+    value::ValueExtension,
+    AssetClass, AssetName, Token,
 };
 use spectrum_offchain::{
     data::{Has, Identifier, Stable},
-    ledger::IntoLedger,
+    ledger::{IntoLedger, TryFromLedger},
 };
 use spectrum_offchain_cardano::parametrized_validators::apply_params_validator;
 
 use crate::{
-    constants::{MAX_LOCK_TIME_SECONDS, MINT_WEIGHTING_POWER_SCRIPT, VOTING_ESCROW_SCRIPT},
-    protocol_config::{NodeMagic, OperatorCreds, VEFactoryAuthPolicy},
+    constants::{GT_NAME, MAX_LOCK_TIME_SECONDS, MINT_WEIGHTING_POWER_SCRIPT, VOTING_ESCROW_SCRIPT},
+    entities::onchain::find_token,
+    protocol_config::{GTAuthPolicy, NodeMagic, OperatorCreds, VEFactoryAuthPolicy},
     routines::inflation::VotingEscrowSnapshot,
+    state_projection::ProjectionKey,
     time::{NetworkTime, ProtocolEpoch},
 };
 
-#[derive(Copy, Clone, PartialEq, Eq, Ord, PartialOrd, Hash, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, Ord, PartialOrd, Hash, Debug, From)]
 pub struct VotingEscrowId(Token);
 
 impl Identifier for VotingEscrowId {
     type For = VotingEscrowSnapshot;
 }
 
+impl ProjectionKey for VotingEscrowId {
+    fn projection_key(&self) -> Vec<u8> {
+        self.0.projection_key()
+    }
+}
+
+/// Voting escrow is identified by the token issued under the voting escrow policy.
+impl<Ctx> TryFromLedger<TransactionOutput, Ctx> for VotingEscrowId
+where
+    Ctx: Has<VEFactoryAuthPolicy>,
+{
+    fn try_from_ledger(repr: &TransactionOutput, ctx: &Ctx) -> Option<Self> {
+        let voting_escrow_policy = compute_voting_escrow_policy_id(ctx.select::<VEFactoryAuthPolicy>().0);
+        find_token(repr.value(), voting_escrow_policy).map(VotingEscrowId)
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 pub struct VotingEscrow {
     pub gov_token_amount: u64,
@@ -91,6 +113,31 @@ where
     }
 }
 
+impl<Ctx> TryFromLedger<TransactionOutput, Ctx> for VotingEscrow
+where
+    Ctx: Has<VEFactoryAuthPolicy> + Has<GTAuthPolicy>,
+{
+    fn try_from_ledger(repr: &TransactionOutput, ctx: &Ctx) -> Option<Self> {
+        VotingEscrowId::try_from_ledger(repr, ctx)?;
+        let gt_policy = ctx.select::<GTAuthPolicy>().0;
+        let gt = AssetClass::Token((gt_policy, AssetName::try_from(vec![GT_NAME]).ok()?));
+        let mut cpd = repr.clone().into_datum()?.into_pd()?.into_constr_pd()?;
+        let locked_until = Lock::try_from_pd(cpd.take_field(0)?)?;
+        let max_ex_fee = cpd.take_field(2)?.into_u64()? as u32;
+        let version = cpd.take_field(3)?.into_u64()? as u32;
+        Some(VotingEscrow {
+            gov_token_amount: repr.value().amount_of(gt).unwrap_or(0),
+            gt_policy,
+            locked_until,
+            stable_id: VotingEscrowStableId {
+                ve_factory_auth_policy: ctx.select::<VEFactoryAuthPolicy>().0,
+            },
+            max_ex_fee,
+            version,
+        })
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub struct VotingEscrowStableId {
     ve_factory_auth_policy: PolicyId,
@@ -121,6 +168,18 @@ pub enum Lock {
     Indef(Duration),
 }
 
+impl TryFromPData for Lock {
+    fn try_from_pd(data: PlutusData) -> Option<Self> {
+        let mut cpd = data.into_constr_pd()?;
+        let value = cpd.take_field(0)?.into_u64()?;
+        match cpd.alternative {
+            0 => Some(Lock::Def(value)),
+            1 => Some(Lock::Indef(Duration::from_millis(value))),
+            _ => None,
+        }
+    }
+}
+
 impl IntoPlutusData for Lock {
     fn into_pd(self) -> PlutusData {
         match self {
@@ -264,17 +323,53 @@ pub fn compute_mint_weighting_power_policy_id(
     proposal_auth_policy: PolicyId,
     gt_policy: PolicyId,
 ) -> PolicyId {
-    let params_pd = uplc::PlutusData::Array(vec![
-        uplc::PlutusData::BigInt(uplc::BigInt::Int(Int::from(zeroth_epoch_start as i64))),
-        uplc::PlutusData::BoundedBytes(PlutusBytes::from(proposal_auth_policy.to_raw_bytes().to_vec())),
-        uplc::PlutusData::BoundedBytes(PlutusBytes::from(gt_policy.to_raw_bytes().to_vec())),
+    let params_pd = PlutusData::new_list(vec![
+        PlutusData::new_integer(BigInteger::from(zeroth_epoch_start)),
+        PlutusData::new_bytes(proposal_auth_policy.to_raw_bytes().to_vec()),
+        PlutusData::new_bytes(gt_policy.to_raw_bytes().to_vec()),
     ]);
     apply_params_validator(params_pd, MINT_WEIGHTING_POWER_SCRIPT)
 }
 
 pub fn compute_voting_escrow_policy_id(ve_factory_auth_policy: PolicyId) -> PolicyId {
-    let params_pd = uplc::PlutusData::Array(vec![uplc::PlutusData::BoundedBytes(PlutusBytes::from(
+    let params_pd = PlutusData::new_list(vec![PlutusData::new_bytes(
         ve_factory_auth_policy.to_raw_bytes().to_vec(),
-    ))]);
+    )]);
     apply_params_validator(params_pd, VOTING_ESCROW_SCRIPT)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use cml_chain::plutus::{ConstrPlutusData, PlutusData};
+
+    use spectrum_cardano_lib::plutus_data::IntoPlutusData;
+    use spectrum_cardano_lib::types::TryFromPData;
+
+    use crate::entities::onchain::voting_escrow::Lock;
+
+    #[test]
+    fn lock_roundtrip() {
+        for lock in [
+            Lock::Def(1_700_000_000_000),
+            Lock::Indef(Duration::from_millis(86_400_000)),
+        ] {
+            let parsed = Lock::try_from_pd(lock.into_pd()).unwrap();
+            match (lock, parsed) {
+                (Lock::Def(expected), Lock::Def(actual)) => assert_eq!(expected, actual),
+                (Lock::Indef(expected), Lock::Indef(actual)) => assert_eq!(expected, actual),
+                _ => panic!("Lock kind changed"),
+            }
+        }
+    }
+
+    #[test]
+    fn lock_of_unknown_kind_is_rejected() {
+        let pd = PlutusData::ConstrPlutusData(ConstrPlutusData::new(
+            2,
+            vec![PlutusData::new_integer(0u64.into())],
+        ));
+        assert!(Lock::try_from_pd(pd).is_none());
+    }
+}
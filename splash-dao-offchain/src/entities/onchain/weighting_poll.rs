@@ -7,22 +7,26 @@ use cml_chain::plutus::{ConstrPlutusData, ExUnits, PlutusData};
 use cml_chain::transaction::{DatumOption, TransactionOutput};
 use cml_chain::utils::BigInteger;
 use cml_chain::{OrderedHashMap, PolicyId, Value};
-use cml_crypto::RawBytesEncoding;
+use cml_crypto::{blake2b256, RawBytesEncoding};
 use derive_more::From;
-use uplc_pallas_codec::utils::{Int, PlutusBytes};
 
-use spectrum_cardano_lib::plutus_data::{ConstrPlutusDataExtension, IntoPlutusData, PlutusDataExtension};
+use spectrum_cardano_lib::plutus_data::{
+    ConstrPlutusDataExtension, DatumExtension, IntoPlutusData, PlutusDataExtension,
+};
+use spectrum_cardano_lib::transaction::TransactionOutputExtension;
 use spectrum_cardano_lib::{TaggedAmount, Token};
 use spectrum_offchain::data::{Has, Identifier, Stable};
-use spectrum_offchain::ledger::IntoLedger;
+use spectrum_offchain::ledger::{IntoLedger, TryFromLedger};
 use spectrum_offchain_cardano::parametrized_validators::apply_params_validator;
 
 use crate::assets::Splash;
-use crate::constants::{MINT_WP_AUTH_TOKEN_SCRIPT, SPLASH_NAME};
+use crate::constants::{EPOCH_LEN, MINT_WP_AUTH_TOKEN_SCRIPT, SPLASH_NAME};
+use crate::entities::onchain::find_token;
 use crate::entities::onchain::smart_farm::FarmId;
 use crate::entities::onchain::voting_escrow::compute_mint_weighting_power_policy_id;
-use crate::protocol_config::{GTAuthPolicy, NodeMagic, SplashPolicy, WPAuthPolicy};
+use crate::protocol_config::{FarmAuthPolicy, GTAuthPolicy, NodeMagic, SplashPolicy, WPAuthPolicy};
 use crate::routines::inflation::WeightingPollSnapshot;
+use crate::state_projection::ProjectionKey;
 use crate::time::{epoch_end, epoch_start, NetworkTime, ProtocolEpoch};
 use crate::GenesisEpochStartTime;
 
@@ -33,6 +37,29 @@ impl Identifier for WeightingPollId {
     type For = WeightingPollSnapshot;
 }
 
+impl ProjectionKey for WeightingPollId {
+    fn projection_key(&self) -> Vec<u8> {
+        self.0.projection_key()
+    }
+}
+
+impl<Ctx> TryFromLedger<TransactionOutput, Ctx> for WeightingPollId
+where
+    Ctx: Has<WPAuthPolicy>,
+{
+    fn try_from_ledger(repr: &TransactionOutput, ctx: &Ctx) -> Option<Self> {
+        find_token(repr.value(), ctx.select::<WPAuthPolicy>().0).map(WeightingPollId)
+    }
+}
+
+/// Name of the auth token binding weighting poll to the given epoch.
+pub fn compute_epoch_asset_name(epoch: ProtocolEpoch) -> AssetName {
+    let mut buffer = [0u8; 128];
+    minicbor::encode(epoch, buffer.as_mut()).unwrap();
+    let token_name = blake2b256(buffer.as_ref());
+    AssetName::new(token_name.to_vec()).unwrap()
+}
+
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct WeightingPoll {
     pub epoch: ProtocolEpoch,
@@ -80,6 +107,37 @@ where
     }
 }
 
+impl<Ctx> TryFromLedger<TransactionOutput, Ctx> for WeightingPoll
+where
+    Ctx: Has<WPAuthPolicy> + Has<FarmAuthPolicy> + Has<GenesisEpochStartTime>,
+{
+    fn try_from_ledger(repr: &TransactionOutput, ctx: &Ctx) -> Option<Self> {
+        WeightingPollId::try_from_ledger(repr, ctx)?;
+        let mut cpd = repr.clone().into_datum()?.into_pd()?.into_constr_pd()?;
+        let distribution = cpd.take_field(0)?.into_vec_pd(|pd| {
+            let mut farm_weight = pd.into_vec()?;
+            let weight = farm_weight.pop()?.into_u64()?;
+            let farm = farm_weight.pop()?.into_u64()?;
+            Some((FarmId(farm), weight))
+        })?;
+        // Voting deadline is the end of the epoch the poll belongs to.
+        let deadline = cpd.take_field(1)?.into_u64()?;
+        let emission_rate = cpd.take_field(2)?.into_u64()?;
+        let genesis = <u64>::from(ctx.select::<GenesisEpochStartTime>());
+        let epoch = (deadline.checked_sub(genesis)? / EPOCH_LEN).checked_sub(1)?;
+        Some(WeightingPoll {
+            epoch: epoch as ProtocolEpoch,
+            distribution,
+            stable_id: WeightingPollStableId {
+                auth_policy: ctx.select::<WPAuthPolicy>().0,
+                farm_auth_policy: ctx.select::<FarmAuthPolicy>().0,
+            },
+            emission_rate: TaggedAmount::new(emission_rate),
+            weighting_power: None,
+        })
+    }
+}
+
 fn create_datum(
     wpoll: &WeightingPoll,
     genesis_epoch_start_time: GenesisEpochStartTime,
@@ -281,11 +339,11 @@ pub fn compute_mint_wp_auth_token_policy_id(
     factory_auth_policy: PolicyId,
     zeroth_epoch_start: u64,
 ) -> PolicyId {
-    let params_pd = uplc::PlutusData::Array(vec![
-        uplc::PlutusData::BoundedBytes(PlutusBytes::from(splash_policy.to_raw_bytes().to_vec())),
-        uplc::PlutusData::BoundedBytes(PlutusBytes::from(farm_auth_policy.to_raw_bytes().to_vec())),
-        uplc::PlutusData::BoundedBytes(PlutusBytes::from(factory_auth_policy.to_raw_bytes().to_vec())),
-        uplc::PlutusData::BigInt(uplc::BigInt::Int(Int::from(zeroth_epoch_start as i64))),
+    let params_pd = PlutusData::new_list(vec![
+        PlutusData::new_bytes(splash_policy.to_raw_bytes().to_vec()),
+        PlutusData::new_bytes(farm_auth_policy.to_raw_bytes().to_vec()),
+        PlutusData::new_bytes(factory_auth_policy.to_raw_bytes().to_vec()),
+        PlutusData::new_integer(BigInteger::from(zeroth_epoch_start)),
     ]);
     apply_params_validator(params_pd, MINT_WP_AUTH_TOKEN_SCRIPT)
 }
@@ -0,0 +1,37 @@
+use async_trait::async_trait;
+
+use cardano_chain_sync::data::LedgerTxEvent;
+use spectrum_cardano_lib::transaction::TxViewMut;
+use spectrum_offchain::event_sink::event_handler::EventHandler;
+
+use crate::state_projection::LedgerProjection;
+
+/// Applies confirmed transactions to the state projection.
+/// A single transaction may update several projections,
+/// so the event is always passed on to the next handler.
+pub struct ProjectionUpdateHandler<P> {
+    projection: P,
+}
+
+impl<P> ProjectionUpdateHandler<P> {
+    pub fn new(projection: P) -> Self {
+        Self { projection }
+    }
+}
+
+#[async_trait(?Send)]
+impl<P> EventHandler<LedgerTxEvent<TxViewMut>> for ProjectionUpdateHandler<P>
+where
+    P: LedgerProjection,
+{
+    async fn try_handle(&mut self, ev: LedgerTxEvent<TxViewMut>) -> Option<LedgerTxEvent<TxViewMut>> {
+        match &ev {
+            LedgerTxEvent::TxApplied { tx, .. } => self.projection.apply_tx(tx).await,
+            LedgerTxEvent::TxUnapplied(tx) => self.projection.unapply_tx(tx).await,
+            LedgerTxEvent::BlockCompleted(_)
+            | LedgerTxEvent::BlockRolledBack(_)
+            | LedgerTxEvent::RollbackIncomplete(_) => {}
+        }
+        Some(ev)
+    }
+}
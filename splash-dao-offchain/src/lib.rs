@@ -3,10 +3,12 @@ use derive_more::{From, Into};
 use crate::time::NetworkTime;
 
 mod assets;
+pub mod backlog;
 pub mod constants;
 pub mod entities;
-mod protocol_config;
-mod routine;
+pub mod event_sink;
+pub mod protocol_config;
+pub mod routine;
 pub mod routines;
 pub mod state_projection;
 pub mod time;
@@ -1,8 +1,11 @@
-use cml_chain::address::Address;
-use cml_chain::builders::tx_builder::TransactionUnspentOutput;
+use cml_chain::address::{Address, EnterpriseAddress};
+use cml_chain::builders::tx_builder::{TransactionBuilderConfig, TransactionUnspentOutput};
+use cml_chain::certs::StakeCredential;
 use cml_chain::PolicyId;
-use cml_crypto::{Ed25519KeyHash, PrivateKey};
+use cml_crypto::{Ed25519KeyHash, PrivateKey, ScriptHash};
 use spectrum_cardano_lib::collateral::Collateral;
+use spectrum_cardano_lib::protocol_params::SharedTxBuilderConfig;
+use spectrum_cardano_lib::AssetName;
 use spectrum_offchain::data::Has;
 use spectrum_offchain_cardano::creds::operator_creds;
 use type_equalities::IsEqual;
@@ -10,7 +13,7 @@ use type_equalities::IsEqual;
 use crate::entities::onchain::inflation_box::InflationBoxId;
 use crate::entities::onchain::permission_manager::PermManagerId;
 use crate::entities::onchain::poll_factory::PollFactoryId;
-use crate::entities::onchain::weighting_poll::WeightingPollId;
+use crate::entities::onchain::weighting_poll::{compute_epoch_asset_name, WeightingPollId};
 use crate::time::ProtocolEpoch;
 use crate::GenesisEpochStartTime;
 
@@ -19,11 +22,13 @@ pub struct ProtocolConfig {
     pub node_magic: u64,
     pub reward_address: cml_chain::address::RewardAddress,
     pub collateral: Collateral,
+    pub tx_builder_config: SharedTxBuilderConfig,
     pub splash_policy: PolicyId,
     pub inflation_box_id: InflationBoxId,
     pub inflation_box_ref_script: TransactionUnspentOutput,
     pub poll_factory_id: PollFactoryId,
     pub poll_factory_ref_script: TransactionUnspentOutput,
+    pub gov_witness_script_hash: ScriptHash,
     pub wpoll_auth_policy: PolicyId,
     pub wpoll_auth_ref_script: TransactionUnspentOutput,
     pub farm_auth_policy: PolicyId,
@@ -41,8 +46,21 @@ pub struct ProtocolConfig {
 }
 
 impl ProtocolConfig {
+    /// Weighting poll of the given epoch is bound to the auth token named after that epoch.
     pub fn poll_id(&self, epoch: ProtocolEpoch) -> WeightingPollId {
-        todo!("implement binder")
+        WeightingPollId::from((
+            self.wpoll_auth_policy,
+            AssetName::from(compute_epoch_asset_name(epoch)),
+        ))
+    }
+}
+
+impl<'a, T> Has<T> for &'a ProtocolConfig
+where
+    ProtocolConfig: Has<T>,
+{
+    fn select<U: IsEqual<T>>(&self) -> T {
+        (**self).select::<U>()
     }
 }
 
@@ -58,6 +76,9 @@ pub struct SplashPolicy(pub PolicyId);
 #[derive(Debug, Clone)]
 pub struct PollFactoryRefScriptOutput(pub TransactionUnspentOutput);
 
+#[derive(Debug, Clone)]
+pub struct GovWitnessScriptHash(pub ScriptHash);
+
 #[derive(Debug, Clone)]
 pub struct WPAuthPolicy(pub PolicyId);
 
@@ -111,6 +132,12 @@ impl Has<Collateral> for ProtocolConfig {
     }
 }
 
+impl Has<TransactionBuilderConfig> for ProtocolConfig {
+    fn select<U: IsEqual<TransactionBuilderConfig>>(&self) -> TransactionBuilderConfig {
+        self.tx_builder_config.get()
+    }
+}
+
 impl Has<SplashPolicy> for ProtocolConfig {
     fn select<U: IsEqual<SplashPolicy>>(&self) -> SplashPolicy {
         SplashPolicy(self.splash_policy)
@@ -129,6 +156,30 @@ impl Has<PollFactoryRefScriptOutput> for ProtocolConfig {
     }
 }
 
+impl Has<GovWitnessScriptHash> for ProtocolConfig {
+    fn select<U: IsEqual<GovWitnessScriptHash>>(&self) -> GovWitnessScriptHash {
+        GovWitnessScriptHash(self.gov_witness_script_hash)
+    }
+}
+
+impl Has<InflationBoxId> for ProtocolConfig {
+    fn select<U: IsEqual<InflationBoxId>>(&self) -> InflationBoxId {
+        self.inflation_box_id
+    }
+}
+
+impl Has<PollFactoryId> for ProtocolConfig {
+    fn select<U: IsEqual<PollFactoryId>>(&self) -> PollFactoryId {
+        self.poll_factory_id
+    }
+}
+
+impl Has<PermManagerId> for ProtocolConfig {
+    fn select<U: IsEqual<PermManagerId>>(&self) -> PermManagerId {
+        self.perm_manager_box_id
+    }
+}
+
 impl Has<WPAuthPolicy> for ProtocolConfig {
     fn select<U: IsEqual<WPAuthPolicy>>(&self) -> WPAuthPolicy {
         WPAuthPolicy(self.wpoll_auth_policy)
@@ -215,7 +266,11 @@ impl Has<NodeMagic> for ProtocolConfig {
 
 impl Has<OperatorCreds> for ProtocolConfig {
     fn select<U: IsEqual<OperatorCreds>>(&self) -> OperatorCreds {
-        let (operator_sk, operator_pkh, operator_addr) = operator_creds(&self.operator_sk, self.node_magic);
+        let (operator_sk, _, _) = operator_creds(&self.operator_sk);
+        let operator_pkh = operator_sk.to_public().hash();
+        let operator_addr =
+            EnterpriseAddress::new(self.node_magic as u8, StakeCredential::new_pub_key(operator_pkh))
+                .to_address();
         OperatorCreds(operator_sk, operator_pkh, operator_addr)
     }
 }
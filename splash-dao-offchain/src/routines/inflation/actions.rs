@@ -6,21 +6,21 @@ use cml_chain::builders::input_builder::SingleInputBuilder;
 use cml_chain::builders::mint_builder::SingleMintBuilder;
 use cml_chain::builders::output_builder::{SingleOutputBuilderResult, TransactionOutputBuilder};
 use cml_chain::builders::redeemer_builder::RedeemerWitnessKey;
-use cml_chain::builders::tx_builder::{ChangeSelectionAlgo, SignedTxBuilder};
+use cml_chain::builders::tx_builder::{
+    ChangeSelectionAlgo, SignedTxBuilder, TransactionBuilder, TransactionBuilderConfig,
+};
 use cml_chain::builders::withdrawal_builder::SingleWithdrawalBuilder;
 use cml_chain::builders::witness_builder::{PartialPlutusWitness, PlutusScriptWitness};
 use cml_chain::plutus::RedeemerTag;
 use cml_chain::transaction::{TransactionInput, TransactionOutput};
 use cml_chain::utils::BigInteger;
 use cml_chain::OrderedHashMap;
-use cml_crypto::{blake2b256, RawBytesEncoding};
-use uplc_pallas_traverse::ComputeHash;
+use cml_crypto::RawBytesEncoding;
 
 use bloom_offchain::execution_engine::bundled::Bundled;
 use spectrum_cardano_lib::collateral::Collateral;
 use spectrum_cardano_lib::hash::hash_transaction_canonical;
 use spectrum_cardano_lib::plutus_data::IntoPlutusData;
-use spectrum_cardano_lib::protocol_params::constant_tx_builder;
 use spectrum_cardano_lib::transaction::TransactionOutputExtension;
 use spectrum_cardano_lib::{AssetName, OutputRef};
 use spectrum_offchain::data::event::{Predicted, Traced};
@@ -43,8 +43,8 @@ use crate::entities::onchain::voting_escrow::{
     WEIGHTING_POWER_EX_UNITS,
 };
 use crate::entities::onchain::weighting_poll::{
-    self, compute_mint_wp_auth_token_policy_id, unsafe_update_wp_state, MintAction, WeightingPoll,
-    MINT_WP_AUTH_EX_UNITS,
+    self, compute_epoch_asset_name, compute_mint_wp_auth_token_policy_id, unsafe_update_wp_state, MintAction,
+    WeightingPoll, MINT_WP_AUTH_EX_UNITS,
 };
 use crate::entities::Snapshot;
 use crate::protocol_config::{
@@ -104,6 +104,12 @@ pub struct CardanoInflationActions<Ctx> {
     ctx: Ctx,
 }
 
+impl<Ctx> CardanoInflationActions<Ctx> {
+    pub fn new(ctx: Ctx) -> Self {
+        Self { ctx }
+    }
+}
+
 #[async_trait::async_trait]
 impl<Ctx> InflationActions<TransactionOutput> for CardanoInflationActions<Ctx>
 where
@@ -112,6 +118,7 @@ where
         + Copy
         + Has<Reward>
         + Has<Collateral>
+        + Has<TransactionBuilderConfig>
         + Has<SplashPolicy>
         + Has<InflationBoxRefScriptOutput>
         + Has<PollFactoryRefScriptOutput>
@@ -141,7 +148,7 @@ where
         Traced<Predicted<Bundled<PollFactorySnapshot, TransactionOutput>>>,
         Traced<Predicted<Bundled<WeightingPollSnapshot, TransactionOutput>>>,
     ) {
-        let mut tx_builder = constant_tx_builder();
+        let mut tx_builder = TransactionBuilder::new(self.ctx.select::<TransactionBuilderConfig>());
 
         let wpoll_auth_policy = self.ctx.select::<WPAuthPolicy>().0;
         let splash_policy = self.ctx.select::<SplashPolicy>().0;
@@ -364,7 +371,7 @@ where
         &self,
         Bundled(weighting_poll, weighting_poll_in): Bundled<WeightingPollSnapshot, TransactionOutput>,
     ) -> SignedTxBuilder {
-        let mut tx_builder = constant_tx_builder();
+        let mut tx_builder = TransactionBuilder::new(self.ctx.select::<TransactionBuilderConfig>());
 
         let splash_policy = self.ctx.select::<SplashPolicy>().0;
         let genesis_time = self.ctx.select::<GenesisEpochStartTime>().0;
@@ -439,7 +446,7 @@ where
         Traced<Predicted<Bundled<WeightingPollSnapshot, TransactionOutput>>>,
         Traced<Predicted<Bundled<VotingEscrowSnapshot, TransactionOutput>>>,
     ) {
-        let mut tx_builder = constant_tx_builder();
+        let mut tx_builder = TransactionBuilder::new(self.ctx.select::<TransactionBuilderConfig>());
 
         let prev_ve_version = voting_escrow.version();
         let prev_wp_version = weighting_poll.version();
@@ -661,7 +668,7 @@ where
         Traced<Predicted<Bundled<SmartFarmSnapshot, TransactionOutput>>>,
         Traced<Predicted<Bundled<PermManagerSnapshot, TransactionOutput>>>,
     ) {
-        let mut tx_builder = constant_tx_builder();
+        let mut tx_builder = TransactionBuilder::new(self.ctx.select::<TransactionBuilderConfig>());
 
         let genesis_time = self.ctx.select::<GenesisEpochStartTime>().0;
         let farm_auth_policy = self.ctx.select::<FarmAuthPolicy>().0;
@@ -867,13 +874,6 @@ where
     }
 }
 
-fn compute_epoch_asset_name(epoch: u32) -> cml_chain::assets::AssetName {
-    let mut buffer = [0u8; 128];
-    minicbor::encode(epoch, buffer.as_mut()).unwrap();
-    let token_name = blake2b256(buffer.as_ref());
-    cml_chain::assets::AssetName::new(token_name.to_vec()).unwrap()
-}
-
 #[cfg(test)]
 mod tests {
     use cml_crypto::ScriptHash;
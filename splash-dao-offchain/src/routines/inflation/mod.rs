@@ -3,13 +3,15 @@ use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use bloom_offchain::execution_engine::bundled::Bundled;
 use cml_chain::transaction::Transaction;
+use log::warn;
+use spectrum_cardano_lib::transaction::OutboundTransaction;
 use spectrum_cardano_lib::OutputRef;
 use spectrum_offchain::backlog::ResilientBacklog;
 use spectrum_offchain::data::event::{AnyMod, Confirmed};
 use spectrum_offchain::network::Network;
 use spectrum_offchain::tx_prover::TxProver;
 use spectrum_offchain_cardano::prover::operator::OperatorProver;
-use spectrum_offchain_cardano::tx_submission::TxRejected;
+use spectrum_offchain_cardano::tx_submission::RejectReasons;
 
 use crate::entities::offchain::voting_order::VotingOrder;
 use crate::entities::onchain::inflation_box::InflationBox;
@@ -37,12 +39,47 @@ pub struct Behaviour<'a, IB, PF, WP, VE, SF, PM, Backlog, Time, Actions, Bearer,
     backlog: Backlog,
     ntp: Time,
     actions: Actions,
-    conf: ProtocolConfig,
+    conf: &'a ProtocolConfig,
     pd: PhantomData<Bearer>,
     network: Net,
     prover: OperatorProver<'a>,
 }
 
+impl<'a, IB, PF, WP, VE, SF, PM, Backlog, Time, Actions, Bearer, Net>
+    Behaviour<'a, IB, PF, WP, VE, SF, PM, Backlog, Time, Actions, Bearer, Net>
+{
+    pub fn new(
+        inflation_box: IB,
+        poll_factory: PF,
+        weighting_poll: WP,
+        voting_escrow: VE,
+        smart_farm: SF,
+        perm_manager: PM,
+        backlog: Backlog,
+        ntp: Time,
+        actions: Actions,
+        conf: &'a ProtocolConfig,
+        network: Net,
+        prover: OperatorProver<'a>,
+    ) -> Self {
+        Self {
+            inflation_box,
+            poll_factory,
+            weighting_poll,
+            voting_escrow,
+            smart_farm,
+            perm_manager,
+            backlog,
+            ntp,
+            actions,
+            conf,
+            pd: PhantomData,
+            network,
+            prover,
+        }
+    }
+}
+
 const DEF_DELAY: Duration = Duration::new(5, 0);
 
 pub type InflationBoxSnapshot = Snapshot<InflationBox, OutputRef>;
@@ -84,7 +121,10 @@ where
     Time: NetworkTimeProvider + Send + Sync,
     Actions: InflationActions<Bearer> + Send + Sync,
     Bearer: Send + Sync,
-    Net: Network<Transaction, TxRejected> + Clone + std::marker::Sync + std::marker::Send,
+    Net: Network<OutboundTransaction<Transaction>, RejectReasons>
+        + Clone
+        + std::marker::Sync
+        + std::marker::Send,
 {
     async fn attempt(&mut self) -> Option<ToRoutine> {
         match self.read_state().await {
@@ -217,14 +257,17 @@ impl<'a, IB, PF, WP, VE, SF, PM, Backlog, Time, Actions, Bearer, Net>
         PF: StateProjectionWrite<PollFactorySnapshot, Bearer>,
         WP: StateProjectionWrite<WeightingPollSnapshot, Bearer>,
         Actions: InflationActions<Bearer>,
-        Net: Network<Transaction, TxRejected> + Clone + std::marker::Sync,
+        Net: Network<OutboundTransaction<Transaction>, RejectReasons> + Clone + std::marker::Sync,
     {
         if let (AnyMod::Confirmed(inflation_box), AnyMod::Confirmed(factory)) = (inflation_box, poll_factory)
         {
             let (signed_tx, next_inflation_box, next_factory, next_wpoll) =
                 self.actions.create_wpoll(inflation_box.0, factory.0).await;
             let tx = self.prover.prove(signed_tx);
-            self.network.submit_tx(tx).await.unwrap();
+            if let Err(err) = self.network.submit_tx(tx).await {
+                warn!("Failed to submit TX: {}", err);
+                return retry_in(DEF_DELAY);
+            }
             self.inflation_box.write(next_inflation_box).await;
             self.poll_factory.write(next_factory).await;
             self.weighting_poll.write(next_wpoll).await;
@@ -244,7 +287,10 @@ impl<'a, IB, PF, WP, VE, SF, PM, Backlog, Time, Actions, Bearer, Net>
         WP: StateProjectionWrite<WeightingPollSnapshot, Bearer>,
         VE: StateProjectionWrite<VotingEscrowSnapshot, Bearer>,
         Actions: InflationActions<Bearer>,
-        Net: Network<Transaction, TxRejected> + Clone + std::marker::Sync + std::marker::Send,
+        Net: Network<OutboundTransaction<Transaction>, RejectReasons>
+            + Clone
+            + std::marker::Sync
+            + std::marker::Send,
     {
         if let Some(next_order) = next_pending_order {
             let (signed_tx, next_wpoll, next_ve) = self
@@ -252,7 +298,10 @@ impl<'a, IB, PF, WP, VE, SF, PM, Backlog, Time, Actions, Bearer, Net>
                 .execute_order(weighting_poll.erased(), next_order)
                 .await;
             let tx = self.prover.prove(signed_tx);
-            self.network.submit_tx(tx).await.unwrap();
+            if let Err(err) = self.network.submit_tx(tx).await {
+                warn!("Failed to submit TX: {}", err);
+                return retry_in(DEF_DELAY);
+            }
             self.weighting_poll.write(next_wpoll).await;
             self.voting_escrow.write(next_ve).await;
             return None;
@@ -273,7 +322,10 @@ impl<'a, IB, PF, WP, VE, SF, PM, Backlog, Time, Actions, Bearer, Net>
         SF: StateProjectionWrite<SmartFarmSnapshot, Bearer>,
         PM: StateProjectionWrite<PermManagerSnapshot, Bearer>,
         Actions: InflationActions<Bearer>,
-        Net: Network<Transaction, TxRejected> + Clone + std::marker::Sync + std::marker::Send,
+        Net: Network<OutboundTransaction<Transaction>, RejectReasons>
+            + Clone
+            + std::marker::Sync
+            + std::marker::Send,
     {
         let (signed_tx, next_wpoll, next_sf, next_pm) = self
             .actions
@@ -285,7 +337,10 @@ impl<'a, IB, PF, WP, VE, SF, PM, Backlog, Time, Actions, Bearer, Net>
             )
             .await;
         let tx = self.prover.prove(signed_tx);
-        self.network.submit_tx(tx).await.unwrap();
+        if let Err(err) = self.network.submit_tx(tx).await {
+            warn!("Failed to submit TX: {}", err);
+            return;
+        }
         self.weighting_poll.write(next_wpoll).await;
         self.smart_farm.write(next_sf).await;
         self.perm_manager.write(next_pm).await;
@@ -297,12 +352,18 @@ impl<'a, IB, PF, WP, VE, SF, PM, Backlog, Time, Actions, Bearer, Net>
     ) -> Option<ToRoutine>
     where
         Actions: InflationActions<Bearer>,
-        Net: Network<Transaction, TxRejected> + Clone + std::marker::Sync + std::marker::Send,
+        Net: Network<OutboundTransaction<Transaction>, RejectReasons>
+            + Clone
+            + std::marker::Sync
+            + std::marker::Send,
     {
         if let AnyMod::Confirmed(Confirmed(weighting_poll)) = weighting_poll {
             let signed_tx = self.actions.eliminate_wpoll(weighting_poll).await;
             let tx = self.prover.prove(signed_tx);
-            self.network.submit_tx(tx).await.unwrap();
+            if let Err(err) = self.network.submit_tx(tx).await {
+                warn!("Failed to submit TX: {}", err);
+                return retry_in(DEF_DELAY);
+            }
             return None;
         }
         retry_in(DEF_DELAY)
@@ -355,7 +416,7 @@ mod tests {
     use spectrum_offchain::data::event::{AnyMod, Predicted, Traced};
     use spectrum_offchain::data::{EntitySnapshot, Identifier};
 
-    use crate::state_projection::{StateProjectionRead, StateProjectionWrite};
+    use crate::state_projection::{ProjectionKey, StateProjectionRead, StateProjectionWrite};
 
     struct StateProjection<T: EntitySnapshot, B>(Arc<Mutex<Option<AnyMod<Bundled<T, B>>>>>);
     #[async_trait]
@@ -378,7 +439,7 @@ mod tests {
     {
         async fn read<I>(&self, id: I) -> Option<AnyMod<Bundled<T, B>>>
         where
-            I: Identifier<For = T> + Send,
+            I: Identifier<For = T> + ProjectionKey + Send,
         {
             self.0.lock().await.clone()
         }
@@ -1,7 +1,27 @@
+use cml_crypto::RawBytesEncoding;
+
 use bloom_offchain::execution_engine::bundled::Bundled;
+use spectrum_cardano_lib::transaction::TxViewMut;
+use spectrum_cardano_lib::Token;
 use spectrum_offchain::data::event::{AnyMod, Predicted, Traced};
 use spectrum_offchain::data::{EntitySnapshot, Identifier};
 
+pub mod rocksdb;
+
+/// Binary key under which the state of an entity is projected.
+pub trait ProjectionKey {
+    fn projection_key(&self) -> Vec<u8>;
+}
+
+impl ProjectionKey for Token {
+    fn projection_key(&self) -> Vec<u8> {
+        let (policy, name) = self;
+        let mut key = policy.to_raw_bytes().to_vec();
+        key.extend_from_slice(&cml_chain::assets::AssetName::from(*name).inner);
+        key
+    }
+}
+
 /// Projection of [T] state relative to the ledger.
 #[async_trait::async_trait]
 pub trait StateProjectionRead<T, B>
@@ -10,7 +30,7 @@ where
 {
     async fn read<I>(&self, id: I) -> Option<AnyMod<Bundled<T, B>>>
     where
-        I: Identifier<For = T> + Send;
+        I: Identifier<For = T> + ProjectionKey + Send;
 }
 
 #[async_trait::async_trait]
@@ -20,3 +40,10 @@ where
 {
     async fn write(&self, entity: Traced<Predicted<Bundled<T, B>>>);
 }
+
+/// Keeps the projection in sync with the confirmed ledger state.
+#[async_trait::async_trait]
+pub trait LedgerProjection {
+    async fn apply_tx(&self, tx: &TxViewMut);
+    async fn unapply_tx(&self, tx: &TxViewMut);
+}
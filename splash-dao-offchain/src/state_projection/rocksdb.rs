@@ -0,0 +1,457 @@
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use async_std::task::spawn_blocking;
+use async_trait::async_trait;
+use cml_chain::transaction::TransactionOutput;
+use cml_core::serialization::{Deserialize, Serialize};
+use cml_crypto::{RawBytesEncoding, TransactionHash};
+use log::{trace, warn};
+
+use bloom_offchain::execution_engine::bundled::Bundled;
+use spectrum_cardano_lib::transaction::TxViewMut;
+use spectrum_cardano_lib::OutputRef;
+use spectrum_offchain::binary::prefixed_key;
+use spectrum_offchain::data::event::{AnyMod, Confirmed, Predicted, Traced};
+use spectrum_offchain::data::{Identifier, Stable};
+use spectrum_offchain::ledger::TryFromLedger;
+use spectrum_offchain::rocks::RocksConfig;
+
+use crate::entities::Snapshot;
+use crate::state_projection::{LedgerProjection, ProjectionKey, StateProjectionRead, StateProjectionWrite};
+
+const CONFIRMED_PREFIX: &str = "confirmed";
+const PREDICTED_PREFIX: &str = "predicted";
+const VERSION_PREFIX: &str = "version";
+const SPENT_PREFIX: &str = "spent";
+
+/// State of an entity as it is stored in the projection.
+/// The entity itself is re-parsed from the bearer on read.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct StoredState {
+    key: Vec<u8>,
+    version: (Vec<u8>, u64),
+    bearer: Vec<u8>,
+    prev_version: Option<(Vec<u8>, u64)>,
+}
+
+/// Predicted state along with the time (millis since epoch) it was predicted at.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct StoredPrediction {
+    state: StoredState,
+    predicted_at: u64,
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64
+}
+
+fn encode_ref(oref: OutputRef) -> (Vec<u8>, u64) {
+    (oref.tx_hash().to_raw_bytes().to_vec(), oref.index())
+}
+
+fn decode_ref((hash, ix): (Vec<u8>, u64)) -> Option<OutputRef> {
+    TransactionHash::from_raw_bytes(&hash)
+        .ok()
+        .map(|hash| OutputRef::new(hash, ix))
+}
+
+fn ns_key(namespace: &'static str, prefix: &str, key: &[u8]) -> Vec<u8> {
+    prefixed_key(prefix, &(namespace, key))
+}
+
+pub fn open_projection_db(conf: RocksConfig) -> Arc<rocksdb::OptimisticTransactionDB> {
+    Arc::new(rocksdb::OptimisticTransactionDB::open_default(conf.db_path).unwrap())
+}
+
+/// RocksDB-backed projection of entities identified by [I].
+/// Confirmed and predicted states are kept apart so that confirmation of
+/// a predicted state doesn't lose the predictions chained on top of it.
+/// Spent states are retained to allow one to roll the projection back.
+/// Predicted states that aren't confirmed within [prediction_ttl] are discarded,
+/// so that a lost tx doesn't shadow the confirmed state forever.
+pub struct StateProjectionRocksDB<I, Ctx> {
+    db: Arc<rocksdb::OptimisticTransactionDB>,
+    namespace: &'static str,
+    ctx: Ctx,
+    prediction_ttl: Duration,
+    pd: PhantomData<I>,
+}
+
+impl<I, Ctx> StateProjectionRocksDB<I, Ctx> {
+    pub fn new(db: Arc<rocksdb::OptimisticTransactionDB>, namespace: &'static str, ctx: Ctx) -> Self {
+        Self {
+            db,
+            namespace,
+            ctx,
+            prediction_ttl: DEFAULT_PREDICTION_TTL,
+            pd: PhantomData,
+        }
+    }
+
+    pub fn with_prediction_ttl(self, prediction_ttl: Duration) -> Self {
+        Self {
+            prediction_ttl,
+            ..self
+        }
+    }
+}
+
+/// A tx not included within this time is most likely lost.
+pub const DEFAULT_PREDICTION_TTL: Duration = Duration::from_secs(600);
+
+impl<I, Ctx> StateProjectionRocksDB<I, Ctx> {
+    fn restore<T>(
+        &self,
+        state: StoredState,
+    ) -> Option<Traced<Bundled<Snapshot<T, OutputRef>, TransactionOutput>>>
+    where
+        T: Stable + TryFromLedger<TransactionOutput, Ctx>,
+    {
+        let bearer = TransactionOutput::from_cbor_bytes(&state.bearer).ok()?;
+        let entity = T::try_from_ledger(&bearer, &self.ctx)?;
+        let version = decode_ref(state.version)?;
+        Some(Traced::new(
+            Bundled(Snapshot::new(entity, version), bearer),
+            state.prev_version.and_then(decode_ref),
+        ))
+    }
+}
+
+#[async_trait]
+impl<I, T, Ctx> StateProjectionRead<Snapshot<T, OutputRef>, TransactionOutput>
+    for StateProjectionRocksDB<I, Ctx>
+where
+    T: Stable + TryFromLedger<TransactionOutput, Ctx> + Send,
+    I: Send + Sync,
+    Ctx: Send + Sync,
+{
+    async fn read<J>(&self, id: J) -> Option<AnyMod<Bundled<Snapshot<T, OutputRef>, TransactionOutput>>>
+    where
+        J: Identifier<For = Snapshot<T, OutputRef>> + ProjectionKey + Send,
+    {
+        let db = self.db.clone();
+        let namespace = self.namespace;
+        let ttl = self.prediction_ttl.as_millis() as u64;
+        let key = id.projection_key();
+        let predicted_key = ns_key(namespace, PREDICTED_PREFIX, &key);
+        let confirmed_key = ns_key(namespace, CONFIRMED_PREFIX, &key);
+        let (predicted, confirmed) = spawn_blocking(move || {
+            let predicted = db
+                .get(&predicted_key)
+                .unwrap()
+                .and_then(|bytes| decode::<StoredPrediction>(namespace, &bytes))
+                .and_then(|prediction| {
+                    if now_millis().saturating_sub(prediction.predicted_at) >= ttl {
+                        trace!(target: "state_projection", "[{}] predicted state expired", namespace);
+                        db.delete(&predicted_key).unwrap();
+                        None
+                    } else {
+                        Some(prediction.state)
+                    }
+                });
+            let confirmed = db
+                .get(&confirmed_key)
+                .unwrap()
+                .and_then(|bytes| decode::<StoredState>(namespace, &bytes));
+            (predicted, confirmed)
+        })
+        .await;
+        predicted
+            .and_then(|st| self.restore(st))
+            .map(|Traced { state, prev_state_id }| {
+                AnyMod::Predicted(Traced::new(Predicted(state), prev_state_id))
+            })
+            .or_else(|| {
+                confirmed
+                    .and_then(|st| self.restore(st))
+                    .map(|Traced { state, .. }| AnyMod::Confirmed(Confirmed(state)))
+            })
+    }
+}
+
+fn decode<T: serde::de::DeserializeOwned>(namespace: &str, bytes: &[u8]) -> Option<T> {
+    bincode::deserialize(bytes)
+        .map_err(|err| warn!(target: "state_projection", "[{}] corrupted state: {}", namespace, err))
+        .ok()
+}
+
+#[async_trait]
+impl<I, T, Ctx> StateProjectionWrite<Snapshot<T, OutputRef>, TransactionOutput>
+    for StateProjectionRocksDB<I, Ctx>
+where
+    I: TryFromLedger<TransactionOutput, Ctx> + ProjectionKey + Send + Sync,
+    T: Stable + Send,
+    Ctx: Send + Sync,
+{
+    async fn write(&self, entity: Traced<Predicted<Bundled<Snapshot<T, OutputRef>, TransactionOutput>>>) {
+        let Traced {
+            state: Predicted(Bundled(snapshot, bearer)),
+            prev_state_id,
+        } = entity;
+        if let Some(id) = I::try_from_ledger(&bearer, &self.ctx) {
+            let key = id.projection_key();
+            let version = *snapshot.version();
+            trace!(target: "state_projection", "[{}] predicted state {}", self.namespace, version);
+            let prediction = StoredPrediction {
+                state: StoredState {
+                    key: key.clone(),
+                    version: encode_ref(version),
+                    bearer: bearer.to_cbor_bytes(),
+                    prev_version: prev_state_id.map(encode_ref),
+                },
+                predicted_at: now_millis(),
+            };
+            let db = self.db.clone();
+            let predicted_key = ns_key(self.namespace, PREDICTED_PREFIX, &key);
+            spawn_blocking(move || {
+                db.put(predicted_key, bincode::serialize(&prediction).unwrap())
+                    .unwrap()
+            })
+            .await;
+        }
+    }
+}
+
+#[async_trait]
+impl<I, Ctx> LedgerProjection for StateProjectionRocksDB<I, Ctx>
+where
+    I: TryFromLedger<TransactionOutput, Ctx> + ProjectionKey + Send + Sync,
+    Ctx: Send + Sync,
+{
+    async fn apply_tx(&self, tx: &TxViewMut) {
+        let namespace = self.namespace;
+        let consumed = tx
+            .inputs
+            .iter()
+            .map(|i| encode_ref(OutputRef::from(i.clone())))
+            .collect::<Vec<_>>();
+        let produced = tx
+            .outputs
+            .iter()
+            .enumerate()
+            .filter_map(|(ix, out)| {
+                I::try_from_ledger(out, &self.ctx).map(|id| StoredState {
+                    key: id.projection_key(),
+                    version: encode_ref(OutputRef::new(tx.hash, ix as u64)),
+                    bearer: out.to_cbor_bytes(),
+                    prev_version: None,
+                })
+            })
+            .collect::<Vec<_>>();
+        let db = self.db.clone();
+        spawn_blocking(move || {
+            let db_tx = db.transaction();
+            for version in consumed {
+                let version_key = prefixed_key(VERSION_PREFIX, &(namespace, &version));
+                if let Some(key) = db_tx.get(&version_key).unwrap() {
+                    let confirmed_key = ns_key(namespace, CONFIRMED_PREFIX, &key);
+                    if let Some(bytes) = db_tx.get(&confirmed_key).unwrap() {
+                        let state: StoredState = bincode::deserialize(&bytes).unwrap();
+                        if state.version == version {
+                            trace!(target: "state_projection", "[{}] state spent", namespace);
+                            db_tx.delete(&confirmed_key).unwrap();
+                            db_tx
+                                .put(prefixed_key(SPENT_PREFIX, &(namespace, &version)), bytes)
+                                .unwrap();
+                        }
+                    }
+                    db_tx.delete(&version_key).unwrap();
+                }
+            }
+            for state in produced {
+                trace!(target: "state_projection", "[{}] state confirmed", namespace);
+                let predicted_key = ns_key(namespace, PREDICTED_PREFIX, &state.key);
+                if let Some(bytes) = db_tx.get(&predicted_key).unwrap() {
+                    let predicted = decode::<StoredPrediction>(namespace, &bytes);
+                    if predicted.map_or(true, |p| p.state.version == state.version) {
+                        db_tx.delete(&predicted_key).unwrap();
+                    }
+                }
+                db_tx
+                    .put(
+                        prefixed_key(VERSION_PREFIX, &(namespace, &state.version)),
+                        state.key.clone(),
+                    )
+                    .unwrap();
+                db_tx
+                    .put(
+                        ns_key(namespace, CONFIRMED_PREFIX, &state.key),
+                        bincode::serialize(&state).unwrap(),
+                    )
+                    .unwrap();
+            }
+            db_tx.commit().unwrap();
+        })
+        .await
+    }
+
+    async fn unapply_tx(&self, tx: &TxViewMut) {
+        let namespace = self.namespace;
+        let consumed = tx
+            .inputs
+            .iter()
+            .map(|i| encode_ref(OutputRef::from(i.clone())))
+            .collect::<Vec<_>>();
+        let produced = (0..tx.outputs.len())
+            .map(|ix| encode_ref(OutputRef::new(tx.hash, ix as u64)))
+            .collect::<Vec<_>>();
+        let db = self.db.clone();
+        spawn_blocking(move || {
+            let db_tx = db.transaction();
+            for version in produced {
+                let version_key = prefixed_key(VERSION_PREFIX, &(namespace, &version));
+                if let Some(key) = db_tx.get(&version_key).unwrap() {
+                    let confirmed_key = ns_key(namespace, CONFIRMED_PREFIX, &key);
+                    if let Some(bytes) = db_tx.get(&confirmed_key).unwrap() {
+                        let state: StoredState = bincode::deserialize(&bytes).unwrap();
+                        if state.version == version {
+                            trace!(target: "state_projection", "[{}] state unconfirmed", namespace);
+                            db_tx.delete(&confirmed_key).unwrap();
+                        }
+                    }
+                    db_tx.delete(&version_key).unwrap();
+                }
+            }
+            for version in consumed {
+                let spent_key = prefixed_key(SPENT_PREFIX, &(namespace, &version));
+                if let Some(bytes) = db_tx.get(&spent_key).unwrap() {
+                    trace!(target: "state_projection", "[{}] spent state restored", namespace);
+                    let state: StoredState = bincode::deserialize(&bytes).unwrap();
+                    db_tx
+                        .put(
+                            prefixed_key(VERSION_PREFIX, &(namespace, &version)),
+                            state.key.clone(),
+                        )
+                        .unwrap();
+                    db_tx
+                        .put(ns_key(namespace, CONFIRMED_PREFIX, &state.key), bytes)
+                        .unwrap();
+                    db_tx.delete(&spent_key).unwrap();
+                }
+            }
+            db_tx.commit().unwrap();
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use cml_chain::transaction::{TransactionInput, TransactionOutput};
+    use cml_crypto::TransactionHash;
+    use type_equalities::IsEqual;
+
+    use bloom_offchain::execution_engine::bundled::Bundled;
+    use spectrum_cardano_lib::transaction::TxViewMut;
+    use spectrum_cardano_lib::OutputRef;
+    use spectrum_offchain::data::event::{AnyMod, Predicted, Traced};
+    use spectrum_offchain::data::Has;
+
+    use crate::entities::onchain::smart_farm::{FarmId, SmartFarm};
+    use crate::entities::onchain::tests::{dummy_policy_id, output_with};
+    use crate::entities::Snapshot;
+    use crate::protocol_config::FarmAuthPolicy;
+    use crate::state_projection::rocksdb::StateProjectionRocksDB;
+    use crate::state_projection::{LedgerProjection, StateProjectionRead, StateProjectionWrite};
+
+    struct Context;
+
+    impl Has<FarmAuthPolicy> for Context {
+        fn select<U: IsEqual<FarmAuthPolicy>>(&self) -> FarmAuthPolicy {
+            FarmAuthPolicy(dummy_policy_id(1))
+        }
+    }
+
+    fn projection() -> StateProjectionRocksDB<FarmId, Context> {
+        let path = std::env::temp_dir().join(format!("state_projection_{}", rand::random::<u64>()));
+        let db = rocksdb::OptimisticTransactionDB::open_default(path).unwrap();
+        StateProjectionRocksDB::new(Arc::new(db), "smart_farm", Context)
+    }
+
+    fn farm_output() -> TransactionOutput {
+        output_with(&[(dummy_policy_id(1), vec![0x01], 1)], None)
+    }
+
+    fn tx(id: u8, spends: Option<OutputRef>) -> TxViewMut {
+        TxViewMut {
+            hash: TransactionHash::from([id; 32]),
+            inputs: spends
+                .into_iter()
+                .map(|oref| TransactionInput::new(oref.tx_hash(), oref.index()))
+                .collect(),
+            outputs: vec![farm_output()],
+        }
+    }
+
+    fn predict(
+        version: OutputRef,
+        prev: OutputRef,
+    ) -> Traced<Predicted<Bundled<Snapshot<SmartFarm, OutputRef>, TransactionOutput>>> {
+        Traced::new(
+            Predicted(Bundled(
+                Snapshot::new(SmartFarm { farm_id: FarmId(1) }, version),
+                farm_output(),
+            )),
+            Some(prev),
+        )
+    }
+
+    async fn read_version(projection: &StateProjectionRocksDB<FarmId, Context>) -> Option<(bool, OutputRef)> {
+        projection.read(FarmId(1)).await.map(|state| match state {
+            AnyMod::Confirmed(st) => (true, *st.0 .0.version()),
+            AnyMod::Predicted(st) => (false, *st.state.0 .0.version()),
+            AnyMod::Unconfirmed(st) => (false, *st.0 .0.version()),
+        })
+    }
+
+    #[tokio::test]
+    async fn apply_and_unapply_txs() {
+        let projection = projection();
+        let tx_1 = tx(1, None);
+        let v1 = OutputRef::new(tx_1.hash, 0);
+        let tx_2 = tx(2, Some(v1));
+        let v2 = OutputRef::new(tx_2.hash, 0);
+        projection.apply_tx(&tx_1).await;
+        assert_eq!(read_version(&projection).await, Some((true, v1)));
+        projection.apply_tx(&tx_2).await;
+        assert_eq!(read_version(&projection).await, Some((true, v2)));
+        projection.unapply_tx(&tx_2).await;
+        assert_eq!(read_version(&projection).await, Some((true, v1)));
+        projection.unapply_tx(&tx_1).await;
+        assert_eq!(read_version(&projection).await, None);
+    }
+
+    #[tokio::test]
+    async fn prediction_is_superseded_by_confirmation() {
+        let projection = projection();
+        let tx_1 = tx(1, None);
+        let v1 = OutputRef::new(tx_1.hash, 0);
+        let tx_2 = tx(2, Some(v1));
+        let v2 = OutputRef::new(tx_2.hash, 0);
+        projection.apply_tx(&tx_1).await;
+        projection.write(predict(v2, v1)).await;
+        assert_eq!(read_version(&projection).await, Some((false, v2)));
+        projection.apply_tx(&tx_2).await;
+        assert_eq!(read_version(&projection).await, Some((true, v2)));
+    }
+
+    #[tokio::test]
+    async fn expired_prediction_is_discarded() {
+        let projection = projection().with_prediction_ttl(Duration::ZERO);
+        let tx_1 = tx(1, None);
+        let v1 = OutputRef::new(tx_1.hash, 0);
+        projection.apply_tx(&tx_1).await;
+        projection
+            .write(predict(OutputRef::new(TransactionHash::from([2; 32]), 0), v1))
+            .await;
+        assert_eq!(read_version(&projection).await, Some((true, v1)));
+        // The prediction is gone for good, not just hidden.
+        let projection = projection.with_prediction_ttl(Duration::from_secs(600));
+        assert_eq!(read_version(&projection).await, Some((true, v1)));
+    }
+}
@@ -1,3 +1,5 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
 use async_trait::async_trait;
 
 use crate::constants::EPOCH_LEN;
@@ -23,3 +25,17 @@ pub trait NetworkTimeProvider {
 pub trait ProtocolTimeProvider {
     async fn epoch(&self) -> ProtocolEpoch;
 }
+
+/// Network time approximated by the local system clock.
+#[derive(Copy, Clone, Debug)]
+pub struct NetworkTimeSource;
+
+#[async_trait]
+impl NetworkTimeProvider for NetworkTimeSource {
+    async fn network_time(&self) -> NetworkTime {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_millis() as NetworkTime
+    }
+}